@@ -0,0 +1,148 @@
+// Replays an eval dataset through the inference chains and writes a report.
+//
+// cargo run --bin shinkai_eval -- --dataset cases.jsonl --mock mock_responses.json --output report.json
+//
+// Use `--llm-provider provider.json` (a serialized llm provider) instead of `--mock` to evaluate a real model.
+// To evaluate cases with a scope, point `--db-path` and `--vector-fs-path` to the storage of a stopped node.
+use std::sync::Arc;
+
+use clap::App;
+use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::SerializedLLMProvider;
+use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
+use shinkai_message_primitives::shinkai_utils::shinkai_logging::init_default_tracing;
+use shinkai_node::db::ShinkaiDB;
+use shinkai_node::llm_provider::eval::eval_dataset::EvalDataset;
+use shinkai_node::llm_provider::eval::eval_harness::EvalHarness;
+use shinkai_node::llm_provider::providers::mock::MockLLM;
+use shinkai_node::llm_provider::providers::provider_registry::LLMProviderRegistry;
+use shinkai_node::vector_fs::vector_fs::VectorFS;
+use shinkai_vector_resources::embedding_generator::RemoteEmbeddingGenerator;
+
+fn read_file(path: &str) -> String {
+    std::fs::read_to_string(path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path, e))
+}
+
+fn read_llm_provider(path: &str) -> SerializedLLMProvider {
+    serde_json::from_str(&read_file(path)).unwrap_or_else(|e| panic!("Invalid llm provider in {}: {}", path, e))
+}
+
+#[tokio::main]
+pub async fn main() {
+    let matches = App::new("Shinkai Eval")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Replays a dataset of user messages through the inference chains and grades the answers")
+        .arg(
+            clap::Arg::new("dataset")
+                .long("dataset")
+                .takes_value(true)
+                .required(true)
+                .help("JSONL file with one eval case per line"),
+        )
+        .arg(
+            clap::Arg::new("output")
+                .long("output")
+                .takes_value(true)
+                .default_value("eval_report.json")
+                .help("Where the JSON report is written"),
+        )
+        .arg(
+            clap::Arg::new("mock")
+                .long("mock")
+                .takes_value(true)
+                .help("JSON file with the scripted responses of the mock llm"),
+        )
+        .arg(
+            clap::Arg::new("llm_provider")
+                .long("llm-provider")
+                .takes_value(true)
+                .help("JSON file with the llm provider to evaluate"),
+        )
+        .arg(
+            clap::Arg::new("judge")
+                .long("judge")
+                .takes_value(true)
+                .help("JSON file with the llm provider grading the llm_judge graders (the evaluated one by default)"),
+        )
+        .arg(
+            clap::Arg::new("profile")
+                .long("profile")
+                .takes_value(true)
+                .default_value("@@localhost.shinkai/main")
+                .help("Profile the eval jobs belong to"),
+        )
+        .arg(clap::Arg::new("db_path").long("db-path").takes_value(true))
+        .arg(clap::Arg::new("vector_fs_path").long("vector-fs-path").takes_value(true))
+        .arg(
+            clap::Arg::new("min_pass_rate")
+                .long("min-pass-rate")
+                .takes_value(true)
+                .help("Exits with an error if the pass rate (0 to 1) is lower"),
+        )
+        .get_matches();
+
+    init_default_tracing();
+
+    let dataset_path = matches.value_of("dataset").unwrap();
+    let dataset = EvalDataset::load(dataset_path).unwrap_or_else(|e| panic!("{}", e));
+    let profile = ShinkaiName::new(matches.value_of("profile").unwrap().to_string()).expect("Invalid profile name");
+
+    let llm_provider = match (matches.value_of("mock"), matches.value_of("llm_provider")) {
+        (Some(mock_path), None) => {
+            let mock = MockLLM::from_json(&read_file(mock_path)).unwrap_or_else(|e| panic!("{}", e));
+            LLMProviderRegistry::register(Arc::new(mock));
+            MockLLM::serialized_llm_provider("mock", profile.clone())
+        }
+        (None, Some(llm_provider_path)) => read_llm_provider(llm_provider_path),
+        _ => panic!("Pass either --mock or --llm-provider"),
+    };
+    let judge = matches
+        .value_of("judge")
+        .map(read_llm_provider)
+        .unwrap_or_else(|| llm_provider.clone());
+
+    // Fresh storage by default, so the evals don't depend on the state of a node
+    let storage_path = std::env::temp_dir().join(format!("shinkai_eval_{}", uuid::Uuid::new_v4()));
+    let db_path = matches
+        .value_of("db_path")
+        .map(|path| path.to_string())
+        .unwrap_or_else(|| storage_path.join("db").to_string_lossy().to_string());
+    let vector_fs_path = matches
+        .value_of("vector_fs_path")
+        .map(|path| path.to_string())
+        .unwrap_or_else(|| storage_path.join("vector_fs").to_string_lossy().to_string());
+
+    let db = Arc::new(ShinkaiDB::new(&db_path).unwrap_or_else(|e| panic!("Failed to open {}: {}", db_path, e)));
+    let generator = RemoteEmbeddingGenerator::new_default();
+    let vector_fs = VectorFS::new(
+        generator.clone(),
+        vec![generator.model_type.clone()],
+        vec![profile.clone()],
+        &vector_fs_path,
+        profile.extract_node(),
+    )
+    .await
+    .unwrap_or_else(|e| panic!("Failed to open {}: {}", vector_fs_path, e));
+
+    let harness = EvalHarness::new(db, Arc::new(vector_fs), llm_provider, generator, profile).with_judge(judge);
+    let report = harness.run(&dataset).await;
+
+    let output_path = matches.value_of("output").unwrap();
+    std::fs::write(output_path, report.to_json().expect("Failed to serialize the report"))
+        .unwrap_or_else(|e| panic!("Failed to write {}: {}", output_path, e));
+    println!("{}", report.to_markdown());
+    println!("Report written to {}", output_path);
+
+    drop(harness);
+    let _ = std::fs::remove_dir_all(&storage_path);
+
+    if let Some(min_pass_rate) = matches.value_of("min_pass_rate") {
+        let min_pass_rate: f64 = min_pass_rate.parse().expect("Invalid --min-pass-rate");
+        if report.summary.pass_rate < min_pass_rate {
+            eprintln!(
+                "Pass rate {:.3} is lower than the minimum {:.3}",
+                report.summary.pass_rate, min_pass_rate
+            );
+            std::process::exit(1);
+        }
+    }
+}
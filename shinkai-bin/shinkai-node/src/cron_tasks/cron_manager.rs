@@ -33,6 +33,7 @@ use futures::Future;
 use shinkai_message_primitives::{
     schemas::{
         inbox_name::{InboxName, InboxNameError},
+        job_config::{JobConfig, JobPriority},
         shinkai_name::ShinkaiName,
     },
     shinkai_message::shinkai_message_schemas::{JobCreationInfo, JobMessage},
@@ -291,6 +292,7 @@ impl CronManager {
             scope: JobScope::new_default(),
             is_hidden: Some(false),
             associated_ui: None,
+            preset_id: None,
         };
 
         // Create Job
@@ -354,6 +356,13 @@ impl CronManager {
             db_arc.update_smart_inbox_name(inbox_name.to_string().as_str(), cron_job.prompt.as_str())?;
         }
 
+        // Scheduled tasks shouldn't delay the interactive jobs
+        let config = JobConfig {
+            priority: Some(JobPriority::Background),
+            ..db_arc.get_job_config(&job_id)?
+        };
+        db_arc.set_job_config(&job_id, &config)?;
+
         // Add Message to Job Queue
         let job_message = JobMessage {
             job_id: job_id.clone(),
@@ -363,6 +372,8 @@ impl CronManager {
             workflow_code: None,
             workflow_name: None,
             callback: None,
+            inference_params: None,
+            is_delegation_trace: false,
             sheet_job_data: None,
         };
 
@@ -59,6 +59,7 @@ pub enum ShinkaiDBError {
     InvalidToolType(String),
     WorkflowNotFound(String),
     SheetNotFound(String),
+    JobPresetNotFound(String),
 }
 
 impl fmt::Display for ShinkaiDBError {
@@ -126,6 +127,7 @@ impl fmt::Display for ShinkaiDBError {
             ShinkaiDBError::InvalidToolType(e) => write!(f, "Invalid tool type: {}", e),
             ShinkaiDBError::WorkflowNotFound(e) => write!(f, "Workflow not found: {}", e),
             ShinkaiDBError::SheetNotFound(e) => write!(f, "Sheet not found: {}", e),
+            ShinkaiDBError::JobPresetNotFound(e) => write!(f, "Job preset not found: {}", e),
         }
     }
 }
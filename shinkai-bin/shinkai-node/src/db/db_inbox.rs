@@ -61,6 +61,35 @@ impl ShinkaiDB {
         message: &ShinkaiMessage,
         maybe_parent_message_key: Option<String>,
         ws_manager: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> Result<(), ShinkaiDBError> {
+        // If this message has a parent, add this message as a child of the parent
+        let parent_key = match maybe_parent_message_key {
+            Some(key) if !key.is_empty() => Some(key),
+            _ => {
+                let inbox_name = InboxName::from_message(message).map_err(ShinkaiDBError::from)?;
+                // Fetch the most recent message from the inbox (of the active branch)
+                let last_messages = self.get_last_messages_from_inbox(inbox_name.get_value(), 1, None)?;
+                if let Some(first_batch) = last_messages.first() {
+                    first_batch
+                        .first()
+                        .map(|last_message| last_message.calculate_message_hash_for_pagination())
+                } else {
+                    None
+                }
+            }
+        };
+
+        self.unsafe_insert_inbox_message_with_parent(message, parent_key, ws_manager)
+            .await
+    }
+
+    /// Inserts the message as a child of `parent_key`, or as a root message of the inbox if `None`.
+    /// The new message becomes the tip of the inbox's active branch.
+    pub async fn unsafe_insert_inbox_message_with_parent(
+        &self,
+        message: &ShinkaiMessage,
+        parent_key: Option<String>,
+        ws_manager: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
     ) -> Result<(), ShinkaiDBError> {
         let inbox_name_manager = InboxName::from_message(message).map_err(ShinkaiDBError::from)?;
 
@@ -97,22 +126,6 @@ impl ShinkaiDB {
             false => ext_metadata.scheduled_time.clone(),
         };
 
-        // If this message has a parent, add this message as a child of the parent
-        let parent_key = match maybe_parent_message_key {
-            Some(key) if !key.is_empty() => Some(key),
-            _ => {
-                // Fetch the most recent message from the inbox
-                let last_messages = self.get_last_messages_from_inbox(inbox_name.clone(), 1, None)?;
-                if let Some(first_batch) = last_messages.first() {
-                    first_batch
-                        .first()
-                        .map(|last_message| last_message.calculate_message_hash_for_pagination())
-                } else {
-                    None
-                }
-            }
-        };
-
         // Previous code was here
         if let InboxName::JobInbox { .. } = inbox_name_manager {
             if let Some(parent_key) = &parent_key.clone() {
@@ -149,6 +162,9 @@ impl ShinkaiDB {
         // Add the message to the shared column family with a key that includes the inbox name
         batch.put_cf(cf_inbox, composite_key.as_bytes(), &hash_key);
 
+        // The newest message is the tip of the active branch, so a previously selected branch no longer applies
+        batch.delete_cf(cf_inbox, Self::inbox_active_leaf_key(&inbox_name_manager).as_bytes());
+
         // Insert the message
         self.insert_message_to_all(&updated_message.clone())?;
 
@@ -575,6 +591,20 @@ impl ShinkaiDB {
         Ok(smart_inboxes)
     }
 
+    /// Fetches the custom name of an inbox, if it was given one
+    pub fn get_smart_inbox_name(&self, inbox_id: &str) -> Result<Option<String>, ShinkaiDBError> {
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+        let inbox_smart_inbox_name_key = format!("{}_smart_inbox_name", inbox_id);
+        match self.db.get_cf(cf_inbox, inbox_smart_inbox_name_key.as_bytes())? {
+            Some(val) => {
+                Ok(Some(String::from_utf8(val.to_vec()).map_err(|_| {
+                    ShinkaiDBError::SomeError("UTF-8 conversion error".to_string())
+                })?))
+            }
+            None => Ok(None),
+        }
+    }
+
     pub fn update_smart_inbox_name(&self, inbox_id: &str, new_name: &str) -> Result<(), ShinkaiDBError> {
         // Fetch the column family for the Inbox topic
         let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
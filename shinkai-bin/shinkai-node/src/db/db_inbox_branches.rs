@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::{db_errors::ShinkaiDBError, db_main::Topic, ShinkaiDB};
+use shinkai_message_primitives::{schemas::inbox_name::InboxName, shinkai_message::shinkai_message::ShinkaiMessage};
+use tokio::sync::Mutex;
+
+use crate::network::ws_manager::WSUpdateHandler;
+
+/// A message of an inbox along with its position in the conversation tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct InboxMessageTreeNode {
+    pub message: ShinkaiMessage,
+    pub hash: String,
+    pub parent_hash: Option<String>,
+    /// Hashes of the replies to this message, the most recent first
+    pub children: Vec<String>,
+    /// Whether the message is part of the active branch
+    pub is_active: bool,
+}
+
+impl ShinkaiDB {
+    pub(crate) fn inbox_active_leaf_key(inbox_name: &InboxName) -> String {
+        format!("inbox_{}_active_leaf", inbox_name.hash_value_first_half())
+    }
+
+    fn inbox_forked_root_key(inbox_hash: &str, message_hash: &str) -> String {
+        format!("inbox_{}_forked_root_{}", inbox_hash, message_hash)
+    }
+
+    /// Returns the leaf of the branch selected with `set_inbox_active_leaf`, if any.
+    /// Once a new message is added to the inbox, it becomes the tip of the active branch and this is cleared.
+    pub fn get_inbox_active_leaf(&self, inbox_name: &str) -> Result<Option<String>, ShinkaiDBError> {
+        let inbox_name = InboxName::new(inbox_name.to_string())?;
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+
+        match self.db.get_cf(cf_inbox, Self::inbox_active_leaf_key(&inbox_name).as_bytes())? {
+            Some(bytes) => Ok(Some(String::from_utf8(bytes.to_vec())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Makes the branch containing the message the active one. The branch continues from the message through
+    /// its most recent replies. Returns the hash of the leaf of the branch.
+    pub fn set_inbox_active_leaf(&self, inbox_name: &str, message_hash: &str) -> Result<String, ShinkaiDBError> {
+        let inbox = InboxName::new(inbox_name.to_string())?;
+        let (message, _) = self.fetch_message_and_hash(message_hash)?;
+        if InboxName::from_message(&message)?.get_value() != inbox.get_value() {
+            return Err(ShinkaiDBError::MessageNotFound);
+        }
+
+        let inbox_hash = inbox.hash_value_first_half();
+        let mut leaf = message_hash.to_string();
+        while let Some(child) = self.get_inbox_message_children(&inbox_hash, &leaf)?.into_iter().next() {
+            leaf = child;
+        }
+
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+        self.db
+            .put_cf(cf_inbox, Self::inbox_active_leaf_key(&inbox).as_bytes(), leaf.as_bytes())?;
+
+        Ok(leaf)
+    }
+
+    /// Inserts a message as a new branch of the conversation: a reply to `parent_hash`, or a new first message
+    /// of the inbox if `None`. The new branch becomes the active one.
+    pub async fn insert_inbox_message_as_branch(
+        &self,
+        message: &ShinkaiMessage,
+        parent_hash: Option<String>,
+        ws_manager: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> Result<(), ShinkaiDBError> {
+        if parent_hash.is_none() {
+            let inbox_hash = InboxName::from_message(message)?.hash_value_first_half();
+            let message_hash = message.calculate_message_hash_for_pagination();
+            let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+            self.db.put_cf(
+                cf_inbox,
+                Self::inbox_forked_root_key(&inbox_hash, &message_hash).as_bytes(),
+                b"",
+            )?;
+        }
+
+        self.unsafe_insert_inbox_message_with_parent(message, parent_hash, ws_manager)
+            .await
+    }
+
+    /// Whether the message is the first message of a branch created by forking the first message of the inbox
+    pub fn is_inbox_forked_root(&self, inbox_hash: &str, message_hash: &str) -> Result<bool, ShinkaiDBError> {
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+        Ok(self
+            .db
+            .get_cf(cf_inbox, Self::inbox_forked_root_key(inbox_hash, message_hash).as_bytes())?
+            .is_some())
+    }
+
+    fn get_inbox_message_children(&self, inbox_hash: &str, message_hash: &str) -> Result<Vec<String>, ShinkaiDBError> {
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+        let children_key = format!("inbox_{}_children_{}", inbox_hash, message_hash);
+        let children_bytes = self.db.get_cf(cf_inbox, children_key.as_bytes())?.unwrap_or_default();
+
+        Ok(String::from_utf8(children_bytes)?
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect())
+    }
+
+    /// Returns every message of the inbox (in all the branches), from the oldest to the most recent
+    pub fn get_inbox_message_tree(&self, inbox_name: &str) -> Result<Vec<InboxMessageTreeNode>, ShinkaiDBError> {
+        let inbox_hash = InboxName::new(inbox_name.to_string())?.hash_value_first_half();
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+
+        let inbox_key_prefix = format!("inbox_{}_message_", inbox_hash);
+        let mut hashes = Vec::new();
+        for item in self.db.prefix_iterator_cf(cf_inbox, inbox_key_prefix.as_bytes()) {
+            let (_, value) = item?;
+            hashes.push(String::from_utf8(value.to_vec())?);
+        }
+
+        // The active branch goes from the selected leaf (or the most recent message) up to the root
+        let mut active = HashSet::new();
+        let mut current = match self.get_inbox_active_leaf(inbox_name)? {
+            Some(leaf) => Some(leaf),
+            None => hashes.last().cloned(),
+        };
+        while let Some(hash) = current {
+            current = self.get_parent_message_hash(inbox_name, &hash)?;
+            active.insert(hash);
+        }
+
+        let mut nodes = Vec::new();
+        for hash in hashes {
+            let (message, _) = self.fetch_message_and_hash(&hash)?;
+            nodes.push(InboxMessageTreeNode {
+                message,
+                parent_hash: self.get_parent_message_hash(inbox_name, &hash)?,
+                children: self.get_inbox_message_children(&inbox_hash, &hash)?,
+                is_active: active.contains(&hash),
+                hash,
+            });
+        }
+
+        Ok(nodes)
+    }
+}
@@ -127,6 +127,18 @@ impl ShinkaiDB {
             }
         }
 
+        // If a branch was selected, start from its leaf instead of the most recent message
+        if until_offset_hash_key.is_none() {
+            if let Some(active_leaf) = self.get_inbox_active_leaf(&inbox_name)? {
+                if let Some(key) = keys
+                    .iter()
+                    .find(|key| key.rsplit_once(":::").map(|(_, hash_key)| hash_key) == Some(active_leaf.as_str()))
+                {
+                    current_key = Some(key.clone());
+                }
+            }
+        }
+
         // If an until_offset_hash_key is provided, find its position in the keys vector
         if let Some(ref until_hash) = until_offset_hash_key {
             // Iterate over keys to find the key that contains the until_offset_hash_key
@@ -229,6 +241,11 @@ impl ShinkaiDB {
             // We check if no parent was found, which means we reached the root of the path
             // If so, let's check if there is a solitary message if not then break
             if current_key.clone().is_none() {
+                // A root created by forking the first message starts its own branch, older messages aren't part of it
+                if self.is_inbox_forked_root(&inbox_hash, &hash_key)? {
+                    break;
+                }
+
                 // Move the iterator forward until it matches the current key
                 if tree_found {
                     let mut found = false;
@@ -0,0 +1,77 @@
+use super::{db_errors::ShinkaiDBError, db_main::Topic, ShinkaiDB};
+use shinkai_message_primitives::schemas::{job_preset::JobPreset, shinkai_name::ShinkaiName};
+
+impl ShinkaiDB {
+    fn job_preset_key(preset_id: &str, profile: &ShinkaiName) -> String {
+        format!(
+            "userjobpresets_{}_{}",
+            Self::user_profile_to_half_hash(profile.clone()),
+            preset_id
+        )
+    }
+
+    /// Saves a JobPreset of a profile (replacing the one with the same id, if any).
+    pub fn save_job_preset(&self, preset: &JobPreset, profile: &ShinkaiName) -> Result<(), ShinkaiDBError> {
+        let key = Self::job_preset_key(&preset.preset_id, profile);
+        let preset_bytes = serde_json::to_vec(preset)?;
+
+        // Use shared CFs
+        let cf_presets = self.get_cf_handle(Topic::Toolkits).unwrap();
+
+        let mut batch = rocksdb::WriteBatch::default();
+        batch.put_cf(cf_presets, key.as_bytes(), &preset_bytes);
+        self.db.write(batch)?;
+
+        Ok(())
+    }
+
+    /// Removes a JobPreset of a profile. Jobs created from it keep working without it.
+    pub fn remove_job_preset(&self, preset_id: &str, profile: &ShinkaiName) -> Result<(), ShinkaiDBError> {
+        // Make sure the preset exists, so removing an unknown one is reported
+        self.get_job_preset(preset_id, profile)?;
+
+        let key = Self::job_preset_key(preset_id, profile);
+        let cf_presets = self.get_cf_handle(Topic::Toolkits).unwrap();
+
+        let mut batch = rocksdb::WriteBatch::default();
+        batch.delete_cf(cf_presets, key.as_bytes());
+        self.db.write(batch)?;
+
+        Ok(())
+    }
+
+    /// Lists all the JobPresets of a profile.
+    pub fn list_job_presets(&self, profile: &ShinkaiName) -> Result<Vec<JobPreset>, ShinkaiDBError> {
+        let prefix_search_key = format!("userjobpresets_{}_", Self::user_profile_to_half_hash(profile.clone()));
+        let cf_presets = self.get_cf_handle(Topic::Toolkits).unwrap();
+
+        let mut presets = Vec::new();
+        let iterator = self.db.prefix_iterator_cf(cf_presets, prefix_search_key.as_bytes());
+        for item in iterator {
+            let (key, value) = item.map_err(ShinkaiDBError::RocksDBError)?;
+            // The prefix iterator can go past the keys of the prefix
+            if !key.starts_with(prefix_search_key.as_bytes()) {
+                break;
+            }
+            let preset: JobPreset = serde_json::from_slice(&value).map_err(ShinkaiDBError::JsonSerializationError)?;
+            presets.push(preset);
+        }
+
+        Ok(presets)
+    }
+
+    /// Gets a JobPreset of a profile.
+    pub fn get_job_preset(&self, preset_id: &str, profile: &ShinkaiName) -> Result<JobPreset, ShinkaiDBError> {
+        let key = Self::job_preset_key(preset_id, profile);
+        let cf_presets = self.get_cf_handle(Topic::Toolkits).unwrap();
+
+        let preset_bytes = self
+            .db
+            .get_cf(cf_presets, key.as_bytes())?
+            .ok_or_else(|| ShinkaiDBError::JobPresetNotFound(preset_id.to_string()))?;
+        let preset: JobPreset = serde_json::from_slice(&preset_bytes)
+            .map_err(|_| ShinkaiDBError::DeserializationFailed("Failed to deserialize job preset".to_string()))?;
+
+        Ok(preset)
+    }
+}
@@ -6,10 +6,11 @@ use super::{db_errors::ShinkaiDBError, db_main::Topic, ShinkaiDB};
 use crate::llm_provider::execution::prompts::prompts::Prompt;
 use crate::llm_provider::execution::prompts::subprompts::SubPromptType;
 use crate::llm_provider::job::{Job, JobLike, JobStepResult};
+use crate::llm_provider::llm_provider::LLMProviderAttempt;
 use crate::network::ws_manager::WSUpdateHandler;
 
 use rocksdb::WriteBatch;
-use shinkai_message_primitives::schemas::{inbox_name::InboxName, shinkai_time::ShinkaiStringTime};
+use shinkai_message_primitives::schemas::{inbox_name::InboxName, job_config::JobConfig, shinkai_time::ShinkaiStringTime};
 use shinkai_message_primitives::shinkai_message::shinkai_message::ShinkaiMessage;
 use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::AssociatedUI;
 use shinkai_message_primitives::shinkai_utils::job_scope::JobScope;
@@ -181,6 +182,7 @@ impl ShinkaiDB {
             unprocessed_messages,
             execution_context,
             associated_ui,
+            config,
         ) = self.get_job_data(job_id, true)?;
 
         // Construct the job
@@ -196,6 +198,7 @@ impl ShinkaiDB {
             unprocessed_messages,
             execution_context,
             associated_ui,
+            config,
         };
 
         let duration = start.elapsed();
@@ -224,6 +227,7 @@ impl ShinkaiDB {
             unprocessed_messages,
             execution_context,
             associated_ui,
+            config,
         ) = self.get_job_data(job_id, false)?;
 
         // Construct the job
@@ -239,6 +243,7 @@ impl ShinkaiDB {
             unprocessed_messages,
             execution_context,
             associated_ui,
+            config,
         };
 
         let duration = start.elapsed();
@@ -271,6 +276,7 @@ impl ShinkaiDB {
             Vec<String>,
             HashMap<String, String>,
             Option<AssociatedUI>,
+            JobConfig,
         ),
         ShinkaiDBError,
     > {
@@ -329,6 +335,8 @@ impl ShinkaiDB {
             .flatten()
             .and_then(|value| serde_json::from_slice(&value).ok());
 
+        let config = self.get_job_config(job_id)?;
+
         Ok((
             scope,
             is_finished,
@@ -340,9 +348,44 @@ impl ShinkaiDB {
             unprocessed_messages,
             self.get_job_execution_context(job_id)?,
             associated_ui_value,
+            config,
         ))
     }
 
+    /// Fetches the id of the llm provider of a job (without loading the rest of the job)
+    pub fn get_job_llm_provider_id(&self, job_id: &str) -> Result<String, ShinkaiDBError> {
+        let cf_jobs = self.get_cf_handle(Topic::Inbox).unwrap();
+        let value = self
+            .db
+            .get_cf(cf_jobs, format!("jobinbox_{}_agentid", job_id).as_bytes())?
+            .ok_or(ShinkaiDBError::DataNotFound)?;
+        Ok(std::str::from_utf8(&value)?.to_string())
+    }
+
+    /// Fetches the settings of a job (the defaults if they were never set)
+    pub fn get_job_config(&self, job_id: &str) -> Result<JobConfig, ShinkaiDBError> {
+        let cf_jobs = self.get_cf_handle(Topic::Inbox).unwrap();
+        match self.db.get_cf(cf_jobs, format!("jobinbox_{}_config", job_id).as_bytes())? {
+            Some(value) => Ok(serde_json::from_slice(&value)?),
+            None => Ok(JobConfig::default()),
+        }
+    }
+
+    /// Updates the settings of an existing job
+    pub fn set_job_config(&self, job_id: &str, config: &JobConfig) -> Result<(), ShinkaiDBError> {
+        let cf_jobs = self.get_cf_handle(Topic::Inbox).unwrap();
+
+        // Make sure the job exists
+        self.db
+            .get_cf(cf_jobs, format!("jobinbox_{}_scope", job_id).as_bytes())?
+            .ok_or(ShinkaiDBError::DataNotFound)?;
+
+        let value = serde_json::to_vec(config)?;
+        self.db
+            .put_cf(cf_jobs, format!("jobinbox_{}_config", job_id).as_bytes(), value)?;
+        Ok(())
+    }
+
     /// Fetches all jobs
     pub fn get_all_jobs(&self) -> Result<Vec<Box<dyn JobLike>>, ShinkaiDBError> {
         // Use shared CFs
@@ -523,6 +566,62 @@ impl ShinkaiDB {
         Ok(())
     }
 
+    /// Marks the job as paused (or not). Paused jobs keep their queued messages but aren't processed.
+    pub fn set_job_paused(&self, job_id: &str, is_paused: bool) -> Result<(), ShinkaiDBError> {
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+
+        // Make sure the job exists
+        self.db
+            .get_cf(cf_inbox, format!("jobinbox_{}_scope", job_id).as_bytes())?
+            .ok_or(ShinkaiDBError::DataNotFound)?;
+
+        let job_is_paused_key = format!("jobinbox_{}_is_paused", job_id);
+        if is_paused {
+            self.db.put_cf(cf_inbox, job_is_paused_key.as_bytes(), b"true")?;
+        } else {
+            self.db.delete_cf(cf_inbox, job_is_paused_key.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Checks if the job is paused
+    pub fn is_job_paused(&self, job_id: &str) -> Result<bool, ShinkaiDBError> {
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+        let job_is_paused_key = format!("jobinbox_{}_is_paused", job_id);
+        Ok(self.db.get_cf(cf_inbox, job_is_paused_key.as_bytes())?.is_some())
+    }
+
+    /// Records llm provider attempts made while processing the current step of a job.
+    /// They are attached to the next step history entry added for the job.
+    pub fn add_pending_llm_provider_attempts(
+        &self,
+        job_id: &str,
+        attempts: Vec<LLMProviderAttempt>,
+    ) -> Result<(), ShinkaiDBError> {
+        let mut pending_attempts = self.get_pending_llm_provider_attempts(job_id)?;
+        pending_attempts.extend(attempts);
+
+        let json =
+            serde_json::to_string(&pending_attempts).map_err(|e| ShinkaiDBError::DataConversionError(e.to_string()))?;
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+        let key = format!("jobinbox_{}_pending_llm_provider_attempts", job_id);
+        self.db.put_cf(cf_inbox, key.as_bytes(), json.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Fetches the llm provider attempts not yet attached to a step history entry
+    pub fn get_pending_llm_provider_attempts(&self, job_id: &str) -> Result<Vec<LLMProviderAttempt>, ShinkaiDBError> {
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+        let key = format!("jobinbox_{}_pending_llm_provider_attempts", job_id);
+        match self.db.get_cf(cf_inbox, key.as_bytes())? {
+            Some(value) => {
+                serde_json::from_slice(&value).map_err(|e| ShinkaiDBError::DataConversionError(e.to_string()))
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+
     pub fn add_step_history(
         &self,
         job_id: String,
@@ -561,6 +660,7 @@ impl ShinkaiDB {
         prompt.add_content(agent_response, SubPromptType::Assistant, 100);
         let mut job_step_result = JobStepResult::new();
         job_step_result.add_new_step_revision(prompt);
+        job_step_result.llm_provider_attempts = self.get_pending_llm_provider_attempts(&job_id)?;
 
         // Convert to json and save to DB
         let json = job_step_result
@@ -574,7 +674,11 @@ impl ShinkaiDB {
         let unique_key = format!("{}_{}", key, current_time);
         // eprintln!("Adding step history Unique key: {}", unique_key);
 
-        self.db.put_cf(cf_inbox, unique_key.as_bytes(), json.as_bytes())?;
+        let pending_attempts_key = format!("jobinbox_{}_pending_llm_provider_attempts", job_id);
+        let mut batch = WriteBatch::default();
+        batch.put_cf(cf_inbox, unique_key.as_bytes(), json.as_bytes());
+        batch.delete_cf(cf_inbox, pending_attempts_key.as_bytes());
+        self.db.write(batch)?;
 
         Ok(())
     }
@@ -592,9 +696,6 @@ impl ShinkaiDB {
         let mut step_history: Vec<JobStepResult> = Vec::new();
         let mut until_offset_key: Option<String> = None;
 
-        // Use shared CFs
-        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
-
         loop {
             // Note(Nico): changing n to 2 helps a lot to debug potential pagination problems
             let mut messages =
@@ -607,30 +708,10 @@ impl ShinkaiDB {
             messages.reverse();
 
             for message_path in &messages {
-                if let Some(message) = message_path.first() {
+                // Delegation traces of the job's agents aren't part of the conversation
+                if let Some(message) = message_path.first().filter(|message| !message.is_delegation_trace()) {
                     let message_key = message.calculate_message_hash_for_pagination();
-                    let hash_message_key = Self::message_key_to_hash(message_key);
-
-                    let prefix = format!("step_history__{}_", hash_message_key);
-                    let iter = self.db.prefix_iterator_cf(cf_inbox, prefix.as_bytes());
-
-                    for item in iter {
-                        match item {
-                            Ok((_, value)) => {
-                                // let key_str = String::from_utf8(key.to_vec())
-                                //     .map_err(|_| ShinkaiDBError::DataConversionError("UTF-8 conversion error".to_string()))?;
-
-                                let step_json_string = std::str::from_utf8(&value)?.to_string();
-                                match JobStepResult::from_json(&step_json_string) {
-                                    Ok(step_res) => step_history.push(step_res),
-                                    Err(e) => eprintln!("Error converting from JSON: {}", e),
-                                }
-                            }
-                            Err(e) => {
-                                return Err(ShinkaiDBError::RocksDBError(e));
-                            }
-                        }
-                    }
+                    step_history.extend(self.get_message_step_history(&message_key)?);
                 }
             }
 
@@ -650,6 +731,57 @@ impl ShinkaiDB {
         Ok(Some(step_history))
     }
 
+    /// Fetches the step history entries added for a message of a job inbox (by its hash)
+    pub fn get_message_step_history(&self, message_key: &str) -> Result<Vec<JobStepResult>, ShinkaiDBError> {
+        let hash_message_key = Self::message_key_to_hash(message_key.to_string());
+        let prefix = format!("step_history__{}_", hash_message_key);
+
+        // Use shared CFs
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+
+        let mut step_history = Vec::new();
+        let iter = self.db.prefix_iterator_cf(cf_inbox, prefix.as_bytes());
+        for item in iter {
+            let (_, value) = item.map_err(ShinkaiDBError::RocksDBError)?;
+            let step_json_string = std::str::from_utf8(&value)?.to_string();
+            match JobStepResult::from_json(&step_json_string) {
+                Ok(step_res) => step_history.push(step_res),
+                Err(e) => eprintln!("Error converting from JSON: {}", e),
+            }
+        }
+
+        Ok(step_history)
+    }
+
+    /// Adds existing step history entries (e.g. of an imported job) for a message of the job inbox, keeping their order
+    pub fn add_job_step_results(
+        &self,
+        job_id: &str,
+        message_key: String,
+        job_step_results: &[JobStepResult],
+    ) -> Result<(), ShinkaiDBError> {
+        let hash_key = Self::job_id_to_hash(job_id);
+        let hash_message_key = Self::message_key_to_hash(message_key);
+        let current_time = ShinkaiStringTime::generate_time_now();
+
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+        let mut batch = WriteBatch::default();
+        for (index, job_step_result) in job_step_results.iter().enumerate() {
+            // The index keeps the keys unique (and ordered) as they share the same time
+            let unique_key = format!(
+                "step_history__{}_{}_{}_{:05}",
+                hash_message_key, hash_key, current_time, index
+            );
+            let json = job_step_result
+                .to_json()
+                .map_err(|e| ShinkaiDBError::DataConversionError(e.to_string()))?;
+            batch.put_cf(cf_inbox, unique_key.as_bytes(), json.as_bytes());
+        }
+        self.db.write(batch)?;
+
+        Ok(())
+    }
+
     pub fn is_job_inbox_empty(&self, job_id: &str) -> Result<bool, ShinkaiDBError> {
         let hashed_job_id = Self::job_id_to_hash(job_id);
         let conversation_inbox_prefix = format!("inbox_{}_message_", hashed_job_id); // 47 characters so prefix works
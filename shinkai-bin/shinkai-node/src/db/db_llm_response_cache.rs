@@ -0,0 +1,194 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::llm_provider::execution::chains::inference_chain_trait::{LLMInferenceResponse, LLMTokenUsage};
+use crate::llm_provider::providers::shared::openai::FunctionCall;
+
+use super::{db_errors::ShinkaiDBError, ShinkaiDB, Topic};
+
+// Note: the prefix needs to be 47 chars long to match the prefix extractor of the Inbox CF
+const LLM_RESPONSE_CACHE_PREFIX: &str = "llm_response_cache_placeholder_to_fit_prefix___";
+
+/// An llm response stored in the cache, keyed by the hash of the provider, model and rendered prompt
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CachedLLMResponse {
+    pub llm_provider_id: String,
+    pub response_string: String,
+    pub json: JsonValue,
+    pub function_call: Option<FunctionCall>,
+    pub usage: Option<LLMTokenUsage>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl CachedLLMResponse {
+    pub fn new(llm_provider_id: String, response: &LLMInferenceResponse, ttl_secs: u64, now: DateTime<Utc>) -> Self {
+        let ttl = chrono::Duration::seconds(ttl_secs.min(i64::MAX as u64) as i64);
+        Self {
+            llm_provider_id,
+            response_string: response.response_string.clone(),
+            json: response.json.clone(),
+            function_call: response.function_call.clone(),
+            usage: response.usage.clone(),
+            created_at: now,
+            expires_at: now.checked_add_signed(ttl).unwrap_or(DateTime::<Utc>::MAX_UTC),
+        }
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+
+    pub fn to_inference_response(&self) -> LLMInferenceResponse {
+        let response = LLMInferenceResponse::new(
+            self.response_string.clone(),
+            self.json.clone(),
+            self.function_call.clone(),
+        );
+        match &self.usage {
+            Some(usage) => response.with_usage(usage.clone()),
+            None => response,
+        }
+    }
+}
+
+impl ShinkaiDB {
+    /// Returns the cached response for the given key, expired responses are removed and not returned
+    pub fn get_cached_llm_response(
+        &self,
+        cache_key: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<CachedLLMResponse>, ShinkaiDBError> {
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+        let key = format!("{}{}", LLM_RESPONSE_CACHE_PREFIX, cache_key);
+
+        let cached: CachedLLMResponse = match self.db.get_cf(cf_inbox, key.as_bytes())? {
+            Some(value) => serde_json::from_slice(&value)?,
+            None => return Ok(None),
+        };
+        if cached.is_expired(now) {
+            self.db.delete_cf(cf_inbox, key.as_bytes())?;
+            return Ok(None);
+        }
+
+        Ok(Some(cached))
+    }
+
+    /// Caches a response. Expired responses of the same llm provider are removed and, if it still has
+    /// more than `max_entries` responses cached, the oldest ones are evicted.
+    pub fn add_cached_llm_response(
+        &self,
+        cache_key: &str,
+        cached: &CachedLLMResponse,
+        max_entries: u64,
+    ) -> Result<(), ShinkaiDBError> {
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+        let key = format!("{}{}", LLM_RESPONSE_CACHE_PREFIX, cache_key);
+        let value = serde_json::to_vec(cached)?;
+        self.db.put_cf(cf_inbox, key.as_bytes(), value)?;
+
+        let mut entries = Vec::new();
+        let iter = self.db.prefix_iterator_cf(cf_inbox, LLM_RESPONSE_CACHE_PREFIX.as_bytes());
+        for item in iter {
+            let (entry_key, entry_value) = item.map_err(ShinkaiDBError::RocksDBError)?;
+            let entry: CachedLLMResponse = match serde_json::from_slice(&entry_value) {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            if entry.llm_provider_id != cached.llm_provider_id {
+                continue;
+            }
+            if entry.is_expired(cached.created_at) {
+                self.db.delete_cf(cf_inbox, &entry_key)?;
+                continue;
+            }
+            entries.push((entry.created_at, entry_key.to_vec()));
+        }
+
+        let max_entries = max_entries as usize;
+        if entries.len() > max_entries {
+            entries.sort();
+            for (_, entry_key) in &entries[..entries.len() - max_entries] {
+                self.db.delete_cf(cf_inbox, entry_key)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes every cached response of the given llm provider (or of all of them if `None`).
+    /// Returns the number of responses removed.
+    pub fn clear_llm_response_cache(&self, llm_provider_id: Option<&str>) -> Result<usize, ShinkaiDBError> {
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+
+        let mut removed = 0;
+        let iter = self.db.prefix_iterator_cf(cf_inbox, LLM_RESPONSE_CACHE_PREFIX.as_bytes());
+        for item in iter {
+            let (entry_key, entry_value) = item.map_err(ShinkaiDBError::RocksDBError)?;
+            if let Some(llm_provider_id) = llm_provider_id {
+                match serde_json::from_slice::<CachedLLMResponse>(&entry_value) {
+                    Ok(entry) if entry.llm_provider_id != llm_provider_id => continue,
+                    _ => {}
+                }
+            }
+            self.db.delete_cf(cf_inbox, &entry_key)?;
+            removed += 1;
+        }
+
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_vector_resources::utils::hash_string;
+    use std::fs;
+    use std::path::Path;
+
+    fn setup() -> ShinkaiDB {
+        let db_path = format!("db_tests/{}", hash_string("llm response cache"));
+        let _ = fs::remove_dir_all(Path::new(&db_path));
+        ShinkaiDB::new(db_path.as_str()).unwrap()
+    }
+
+    fn cached(llm_provider_id: &str, answer: &str, created_at: &str, ttl_secs: u64) -> CachedLLMResponse {
+        let response = LLMInferenceResponse::new(answer.to_string(), serde_json::json!({}), None)
+            .with_usage(LLMTokenUsage::new(10, 5));
+        let created_at = DateTime::parse_from_rfc3339(created_at).unwrap().with_timezone(&Utc);
+        CachedLLMResponse::new(llm_provider_id.to_string(), &response, ttl_secs, created_at)
+    }
+
+    #[test]
+    fn test_llm_response_cache_ttl_and_eviction() {
+        let db = setup();
+        let now = DateTime::parse_from_rfc3339("2024-09-01T10:00:30Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        db.add_cached_llm_response("a", &cached("gpt_4o", "first", "2024-09-01T10:00:00Z", 60), 2)
+            .unwrap();
+        db.add_cached_llm_response("b", &cached("gpt_4o", "second", "2024-09-01T10:00:01Z", 60), 2)
+            .unwrap();
+        db.add_cached_llm_response("x", &cached("llama3", "other", "2024-09-01T10:00:01Z", 10), 2)
+            .unwrap();
+
+        let hit = db.get_cached_llm_response("a", now).unwrap().unwrap();
+        assert_eq!(hit.to_inference_response().response_string, "first");
+        assert_eq!(hit.usage, Some(LLMTokenUsage::new(10, 5)));
+
+        // Expired responses are not returned
+        assert!(db.get_cached_llm_response("x", now).unwrap().is_none());
+
+        // The oldest response of the llm provider gets evicted once the limit is reached
+        db.add_cached_llm_response("c", &cached("gpt_4o", "third", "2024-09-01T10:00:02Z", 60), 2)
+            .unwrap();
+        assert!(db.get_cached_llm_response("a", now).unwrap().is_none());
+        assert!(db.get_cached_llm_response("b", now).unwrap().is_some());
+        assert!(db.get_cached_llm_response("c", now).unwrap().is_some());
+
+        assert_eq!(db.clear_llm_response_cache(Some("gpt_4o")).unwrap(), 2);
+        assert!(db.get_cached_llm_response("c", now).unwrap().is_none());
+    }
+}
@@ -0,0 +1,428 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, SecondsFormat, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{LLMUsageCounts, LLMUsageQuota};
+
+use super::{db_errors::ShinkaiDBError, ShinkaiDB, Topic};
+
+// Note: the prefix needs to be 47 chars long to match the prefix extractor of the Inbox CF
+const TOKEN_USAGE_PREFIX: &str = "token_usage_placeholder_value_to_fit_prefix____";
+const PROFILE_USAGE_QUOTA_PREFIX: &str = "usage_quota_of_";
+
+/// Tokens consumed by a single inference
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TokenUsageRecord {
+    pub job_id: Option<String>,
+    pub inbox_name: Option<String>,
+    /// Full name of the profile owning the llm provider (e.g. `@@node.shinkai/main`)
+    pub profile: Option<String>,
+    /// The llm provider that answered (it may be a fallback of the one assigned to the job)
+    pub llm_provider_id: String,
+    pub model: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// True if the provider didn't report the counts and they were estimated by the node
+    pub estimated: bool,
+    pub datetime: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TokenUsageTotals {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub estimated_requests: u64,
+}
+
+impl TokenUsageTotals {
+    pub fn add(&mut self, record: &TokenUsageRecord) {
+        self.requests += 1;
+        self.prompt_tokens += record.prompt_tokens;
+        self.completion_tokens += record.completion_tokens;
+        self.total_tokens += record.prompt_tokens + record.completion_tokens;
+        if record.estimated {
+            self.estimated_requests += 1;
+        }
+    }
+}
+
+/// Dimensions the token usage can be grouped by
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenUsageGroupBy {
+    Job,
+    Inbox,
+    Profile,
+    LlmProvider,
+    Model,
+    Day,
+    Month,
+}
+
+impl TokenUsageGroupBy {
+    /// Returns the value of this dimension for the given record
+    pub fn key_for(&self, record: &TokenUsageRecord) -> String {
+        match self {
+            TokenUsageGroupBy::Job => record.job_id.clone().unwrap_or_default(),
+            TokenUsageGroupBy::Inbox => record.inbox_name.clone().unwrap_or_default(),
+            TokenUsageGroupBy::Profile => record.profile.clone().unwrap_or_default(),
+            TokenUsageGroupBy::LlmProvider => record.llm_provider_id.clone(),
+            TokenUsageGroupBy::Model => record.model.clone(),
+            TokenUsageGroupBy::Day => record.datetime.format("%Y-%m-%d").to_string(),
+            TokenUsageGroupBy::Month => record.datetime.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+impl FromStr for TokenUsageGroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "job" => Ok(TokenUsageGroupBy::Job),
+            "inbox" => Ok(TokenUsageGroupBy::Inbox),
+            "profile" => Ok(TokenUsageGroupBy::Profile),
+            "llm_provider" => Ok(TokenUsageGroupBy::LlmProvider),
+            "model" => Ok(TokenUsageGroupBy::Model),
+            "day" => Ok(TokenUsageGroupBy::Day),
+            "month" => Ok(TokenUsageGroupBy::Month),
+            other => Err(format!("Invalid token usage group: {}", other)),
+        }
+    }
+}
+
+/// Only the records matching every field set are returned
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TokenUsageFilter {
+    pub job_id: Option<String>,
+    pub inbox_name: Option<String>,
+    pub profile: Option<String>,
+    pub llm_provider_id: Option<String>,
+}
+
+impl TokenUsageFilter {
+    pub fn matches(&self, record: &TokenUsageRecord) -> bool {
+        let matches_field = |expected: &Option<String>, value: Option<&String>| match expected {
+            Some(expected) => value == Some(expected),
+            None => true,
+        };
+
+        matches_field(&self.job_id, record.job_id.as_ref())
+            && matches_field(&self.inbox_name, record.inbox_name.as_ref())
+            && matches_field(&self.profile, record.profile.as_ref())
+            && matches_field(&self.llm_provider_id, Some(&record.llm_provider_id))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TokenUsageGroup {
+    /// Value of each `group_by` dimension (in the same order)
+    pub keys: Vec<String>,
+    pub totals: TokenUsageTotals,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TokenUsageSummary {
+    pub start_date: Option<DateTime<Utc>>,
+    pub end_date: Option<DateTime<Utc>>,
+    pub group_by: Vec<TokenUsageGroupBy>,
+    pub totals: TokenUsageTotals,
+    pub groups: Vec<TokenUsageGroup>,
+}
+
+impl ShinkaiDB {
+    /// Records the tokens consumed by an inference
+    pub fn add_token_usage(&self, record: &TokenUsageRecord) -> Result<(), ShinkaiDBError> {
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+
+        // Keys are sorted by time, the random suffix avoids collisions between concurrent inferences
+        let key = format!(
+            "{}{}_{}",
+            TOKEN_USAGE_PREFIX,
+            record.datetime.to_rfc3339_opts(SecondsFormat::Millis, true),
+            uuid::Uuid::new_v4()
+        );
+        let value = serde_json::to_vec(record)?;
+        self.db.put_cf(cf_inbox, key.as_bytes(), value)?;
+
+        Ok(())
+    }
+
+    /// Returns the token usage records between start (inclusive) and end (exclusive), oldest first
+    pub fn get_token_usage_records(
+        &self,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        filter: &TokenUsageFilter,
+    ) -> Result<Vec<TokenUsageRecord>, ShinkaiDBError> {
+        let cf_inbox = self.get_cf_handle(Topic::Inbox).unwrap();
+        let start_key = start.map(|start| {
+            format!(
+                "{}{}",
+                TOKEN_USAGE_PREFIX,
+                start.to_rfc3339_opts(SecondsFormat::Millis, true)
+            )
+        });
+        let end_key = end.map(|end| {
+            format!(
+                "{}{}",
+                TOKEN_USAGE_PREFIX,
+                end.to_rfc3339_opts(SecondsFormat::Millis, true)
+            )
+        });
+
+        let mut records = Vec::new();
+        let iter = self.db.prefix_iterator_cf(cf_inbox, TOKEN_USAGE_PREFIX.as_bytes());
+        for item in iter {
+            let (key, value) = item.map_err(ShinkaiDBError::RocksDBError)?;
+            let key_str = String::from_utf8(key.to_vec())?;
+
+            if let Some(start_key) = &start_key {
+                if key_str < *start_key {
+                    continue;
+                }
+            }
+            if let Some(end_key) = &end_key {
+                if key_str >= *end_key {
+                    break;
+                }
+            }
+
+            let record: TokenUsageRecord = serde_json::from_slice(&value)?;
+            if filter.matches(&record) {
+                records.push(record);
+            }
+        }
+
+        Ok(records)
+    }
+
+    /// Returns the token usage totals between start (inclusive) and end (exclusive), grouped by the given dimensions
+    pub fn get_token_usage_summary(
+        &self,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        filter: &TokenUsageFilter,
+        group_by: &[TokenUsageGroupBy],
+    ) -> Result<TokenUsageSummary, ShinkaiDBError> {
+        let records = self.get_token_usage_records(start, end, filter)?;
+
+        let mut totals = TokenUsageTotals::default();
+        let mut groups: BTreeMap<Vec<String>, TokenUsageTotals> = BTreeMap::new();
+        for record in &records {
+            totals.add(record);
+            if !group_by.is_empty() {
+                let keys = group_by.iter().map(|group| group.key_for(record)).collect();
+                groups.entry(keys).or_default().add(record);
+            }
+        }
+
+        Ok(TokenUsageSummary {
+            start_date: start,
+            end_date: end,
+            group_by: group_by.to_vec(),
+            totals,
+            groups: groups
+                .into_iter()
+                .map(|(keys, totals)| TokenUsageGroup { keys, totals })
+                .collect(),
+        })
+    }
+
+    /// Returns the tokens and requests consumed since the start of the current day and month (UTC)
+    pub fn get_token_usage_counts(
+        &self,
+        filter: &TokenUsageFilter,
+        now: DateTime<Utc>,
+    ) -> Result<LLMUsageCounts, ShinkaiDBError> {
+        let start_of_day = Utc
+            .with_ymd_and_hms(now.year(), now.month(), now.day(), 0, 0, 0)
+            .single()
+            .unwrap_or(now);
+        let start_of_month = Utc
+            .with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+            .single()
+            .unwrap_or(start_of_day);
+
+        let mut counts = LLMUsageCounts::default();
+        for record in self.get_token_usage_records(Some(start_of_month), None, filter)? {
+            let tokens = record.prompt_tokens + record.completion_tokens;
+            counts.tokens_this_month += tokens;
+            counts.requests_this_month += 1;
+            if record.datetime >= start_of_day {
+                counts.tokens_today += tokens;
+                counts.requests_today += 1;
+            }
+        }
+
+        Ok(counts)
+    }
+
+    /// Sets the usage quota of a profile (full name e.g. `@@node.shinkai/main`), `None` removes it
+    pub fn set_profile_usage_quota(&self, profile: &str, quota: Option<&LLMUsageQuota>) -> Result<(), ShinkaiDBError> {
+        let cf_node_and_users = self.cf_handle(Topic::NodeAndUsers.as_str())?;
+        let key = format!("{}{}", PROFILE_USAGE_QUOTA_PREFIX, profile);
+
+        match quota {
+            Some(quota) => {
+                let value = serde_json::to_vec(quota)?;
+                self.db.put_cf(cf_node_and_users, key.as_bytes(), value)?;
+            }
+            None => self.db.delete_cf(cf_node_and_users, key.as_bytes())?,
+        }
+
+        Ok(())
+    }
+
+    pub fn get_profile_usage_quota(&self, profile: &str) -> Result<Option<LLMUsageQuota>, ShinkaiDBError> {
+        let cf_node_and_users = self.cf_handle(Topic::NodeAndUsers.as_str())?;
+        let key = format!("{}{}", PROFILE_USAGE_QUOTA_PREFIX, profile);
+
+        match self.db.get_cf(cf_node_and_users, key.as_bytes())? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_vector_resources::utils::hash_string;
+    use std::fs;
+    use std::path::Path;
+
+    fn setup(test_name: &str) -> ShinkaiDB {
+        let node1_db_path = format!("db_tests/{}", hash_string(test_name));
+        let _ = fs::remove_dir_all(Path::new(&node1_db_path));
+        ShinkaiDB::new(node1_db_path.as_str()).unwrap()
+    }
+
+    fn record(profile: &str, model: &str, tokens: u64, datetime: &str) -> TokenUsageRecord {
+        TokenUsageRecord {
+            job_id: Some("job_1".to_string()),
+            inbox_name: Some("job_inbox::job_1::false".to_string()),
+            profile: Some(profile.to_string()),
+            llm_provider_id: model.replace(':', "_"),
+            model: model.to_string(),
+            prompt_tokens: tokens,
+            completion_tokens: tokens / 2,
+            estimated: false,
+            datetime: DateTime::parse_from_rfc3339(datetime).unwrap().with_timezone(&Utc),
+        }
+    }
+
+    fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+        Some(DateTime::parse_from_rfc3339(date).unwrap().with_timezone(&Utc))
+    }
+
+    #[test]
+    fn test_token_usage_summary_by_profile_model_and_month() {
+        let db = setup("token usage summary");
+        let records = vec![
+            record("@@node.shinkai/main", "openai:gpt-4o", 100, "2024-08-31T23:59:59Z"),
+            record("@@node.shinkai/main", "openai:gpt-4o", 200, "2024-09-01T10:00:00Z"),
+            record("@@node.shinkai/main", "ollama:llama3", 40, "2024-09-02T10:00:00Z"),
+            record("@@node.shinkai/finance", "openai:gpt-4o", 10, "2024-09-03T10:00:00Z"),
+            record("@@node.shinkai/main", "openai:gpt-4o", 1000, "2024-10-01T00:00:00Z"),
+        ];
+        for record in &records {
+            db.add_token_usage(record).unwrap();
+        }
+
+        let start = parse_date("2024-09-01T00:00:00Z");
+        let end = parse_date("2024-10-01T00:00:00Z");
+        let group_by = vec![
+            TokenUsageGroupBy::Profile,
+            TokenUsageGroupBy::Model,
+            TokenUsageGroupBy::Month,
+        ];
+        let summary = db
+            .get_token_usage_summary(start, end, &TokenUsageFilter::default(), &group_by)
+            .unwrap();
+
+        assert_eq!(summary.totals.requests, 3);
+        assert_eq!(summary.totals.prompt_tokens, 250);
+        assert_eq!(summary.totals.completion_tokens, 125);
+        assert_eq!(summary.totals.total_tokens, 375);
+        assert_eq!(summary.groups.len(), 3);
+        assert_eq!(
+            summary.groups[0].keys,
+            vec!["@@node.shinkai/finance", "openai:gpt-4o", "2024-09"]
+        );
+        assert_eq!(summary.groups[0].totals.prompt_tokens, 10);
+        assert_eq!(
+            summary.groups[2].keys,
+            vec!["@@node.shinkai/main", "openai:gpt-4o", "2024-09"]
+        );
+        assert_eq!(summary.groups[2].totals.prompt_tokens, 200);
+
+        let filter = TokenUsageFilter {
+            profile: Some("@@node.shinkai/main".to_string()),
+            ..Default::default()
+        };
+        let records = db.get_token_usage_records(None, None, &filter).unwrap();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].prompt_tokens, 100);
+        assert_eq!(records[3].prompt_tokens, 1000);
+    }
+
+    #[test]
+    fn test_token_usage_counts_and_profile_quota() {
+        let db = setup("token usage counts");
+        let records = vec![
+            record("@@node.shinkai/main", "openai:gpt-4o", 100, "2024-08-31T23:59:59Z"),
+            record("@@node.shinkai/main", "openai:gpt-4o", 200, "2024-09-01T10:00:00Z"),
+            record("@@node.shinkai/main", "openai:gpt-4o", 20, "2024-09-05T08:00:00Z"),
+            record("@@node.shinkai/finance", "openai:gpt-4o", 10, "2024-09-05T09:00:00Z"),
+        ];
+        for record in &records {
+            db.add_token_usage(record).unwrap();
+        }
+
+        let filter = TokenUsageFilter {
+            profile: Some("@@node.shinkai/main".to_string()),
+            ..Default::default()
+        };
+        let now = parse_date("2024-09-05T12:00:00Z").unwrap();
+        let counts = db.get_token_usage_counts(&filter, now).unwrap();
+        assert_eq!(
+            counts,
+            LLMUsageCounts {
+                tokens_today: 30,
+                requests_today: 1,
+                tokens_this_month: 330,
+                requests_this_month: 2,
+            }
+        );
+
+        assert_eq!(db.get_profile_usage_quota("@@node.shinkai/main").unwrap(), None);
+        let mut quota = LLMUsageQuota::default();
+        quota.hard_limits.max_tokens_per_month = Some(300);
+        db.set_profile_usage_quota("@@node.shinkai/main", Some(&quota)).unwrap();
+        assert_eq!(
+            db.get_profile_usage_quota("@@node.shinkai/main").unwrap(),
+            Some(quota.clone())
+        );
+        assert_eq!(
+            quota.hard_limits.exceeded_limits(&counts),
+            vec!["300 tokens per month (used 330)"]
+        );
+
+        db.set_profile_usage_quota("@@node.shinkai/main", None).unwrap();
+        assert_eq!(db.get_profile_usage_quota("@@node.shinkai/main").unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_token_usage_group_by() {
+        assert_eq!(
+            TokenUsageGroupBy::from_str("llm_provider"),
+            Ok(TokenUsageGroupBy::LlmProvider)
+        );
+        assert_eq!(TokenUsageGroupBy::from_str(" month"), Ok(TokenUsageGroupBy::Month));
+        assert!(TokenUsageGroupBy::from_str("year").is_err());
+    }
+}
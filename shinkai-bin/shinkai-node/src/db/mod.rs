@@ -9,6 +9,7 @@ pub mod db_identity;
 pub mod db_identity_registration;
 pub mod db_inbox;
 pub mod db_inbox_get_messages;
+pub mod db_inbox_branches;
 pub mod db_job_queue;
 pub mod db_jobs;
 pub mod db_profile_bound;
@@ -21,3 +22,6 @@ pub mod db_settings;
 pub mod db_network_notifications;
 pub mod db_uploaded_files_links;
 pub mod db_sheet;
+pub mod db_job_presets;
+pub mod db_token_usage;
+pub mod db_llm_response_cache;
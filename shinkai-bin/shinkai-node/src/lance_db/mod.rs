@@ -2,4 +2,6 @@ pub mod ollama_embedding_fn;
 pub mod shinkai_lance_db;
 pub mod shinkai_tool_schema;
 pub mod shinkai_lancedb_error;
-pub mod shinkai_lance_version;
\ No newline at end of file
+pub mod shinkai_lance_version;
+pub mod shinkai_memory_schema;
+pub mod shinkai_lance_memory;
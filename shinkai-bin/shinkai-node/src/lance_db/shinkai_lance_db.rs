@@ -29,8 +29,9 @@ pub struct LanceShinkaiDb {
     connection: Connection,
     pub tool_table: Table,
     pub version_table: Table,
-    embedding_model: EmbeddingModelType,
-    embedding_function: OllamaEmbeddingFunction,
+    pub memory_table: Table,
+    pub(crate) embedding_model: EmbeddingModelType,
+    pub(crate) embedding_function: OllamaEmbeddingFunction,
 }
 
 impl LanceShinkaiDb {
@@ -49,6 +50,7 @@ impl LanceShinkaiDb {
         let connection = connect(&db_path).execute().await?;
         let version_table = Self::create_version_table(&connection).await?;
         let tool_table = Self::create_tool_router_table(&connection, &embedding_model).await?;
+        let memory_table = Self::create_memory_table(&connection, &embedding_model).await?;
         let api_url = generator.api_url;
         let embedding_function = OllamaEmbeddingFunction::new(&api_url, embedding_model.clone());
 
@@ -56,6 +58,7 @@ impl LanceShinkaiDb {
             connection,
             tool_table,
             version_table,
+            memory_table,
             embedding_model,
             embedding_function,
         })
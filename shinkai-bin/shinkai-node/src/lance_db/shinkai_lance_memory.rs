@@ -0,0 +1,288 @@
+use super::shinkai_memory_schema::ShinkaiMemorySchema;
+use super::{shinkai_lance_db::LanceShinkaiDb, shinkai_lancedb_error::ShinkaiLanceDBError};
+
+use arrow_array::{Array, FixedSizeListArray, Float32Array, RecordBatch, RecordBatchIterator, StringArray};
+use arrow_schema::{DataType, Field};
+use chrono::Utc;
+use futures::TryStreamExt;
+use lancedb::query::{ExecutableQuery, QueryBase, Select};
+use lancedb::table::AddDataMode;
+use lancedb::{Connection, Error as LanceDbError, Table};
+use serde::{Deserialize, Serialize};
+use shinkai_vector_resources::model_type::EmbeddingModelType;
+use shinkai_vector_resources::utils::hash_string;
+use std::sync::Arc;
+
+/// An exchange of a past job conversation remembered for a profile
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MemoryEntry {
+    pub memory_id: String,
+    pub profile: String,
+    pub job_id: String,
+    pub content: String,
+    pub created_at: String,
+}
+
+/// Escapes a value to be used inside a quoted string of a filter
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+impl LanceShinkaiDb {
+    pub async fn create_memory_table(
+        connection: &Connection,
+        embedding_model: &EmbeddingModelType,
+    ) -> Result<Table, ShinkaiLanceDBError> {
+        let schema = ShinkaiMemorySchema::create_schema(embedding_model)
+            .map_err(|e| ShinkaiLanceDBError::Schema(e.to_string()))?;
+
+        match connection.create_empty_table("job_memory", schema).execute().await {
+            Ok(table) => Ok(table),
+            Err(LanceDbError::TableAlreadyExists { .. }) => connection
+                .open_table("job_memory")
+                .execute()
+                .await
+                .map_err(ShinkaiLanceDBError::from),
+            Err(e) => Err(ShinkaiLanceDBError::from(e)),
+        }
+    }
+
+    /// Remembers the content for the profile. The same content of the same job is only stored once.
+    pub async fn add_memory_entry(
+        &self,
+        profile: &str,
+        job_id: &str,
+        content: &str,
+    ) -> Result<MemoryEntry, ShinkaiLanceDBError> {
+        let entry = MemoryEntry {
+            memory_id: hash_string(&format!("{}:{}:{}", profile, job_id, content)),
+            profile: profile.to_string(),
+            job_id: job_id.to_string(),
+            content: content.to_string(),
+            created_at: Utc::now().to_rfc3339(),
+        };
+
+        let embedding = self
+            .embedding_function
+            .request_embeddings(content)
+            .await
+            .map_err(|e| ShinkaiLanceDBError::Memory(e.to_string()))?;
+
+        self.forget_memory_entry(profile, &entry.memory_id).await?;
+
+        let schema = self.memory_table.schema().await?;
+        let vector_dimensions = self
+            .embedding_model
+            .vector_dimensions()
+            .map_err(|e| ShinkaiLanceDBError::Schema(e.to_string()))?;
+
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![entry.memory_id.clone()])),
+                Arc::new(StringArray::from(vec![entry.profile.clone()])),
+                Arc::new(StringArray::from(vec![entry.job_id.clone()])),
+                Arc::new(
+                    FixedSizeListArray::try_new(
+                        Arc::new(Field::new("item", DataType::Float32, true)),
+                        vector_dimensions.try_into().unwrap(),
+                        Arc::new(Float32Array::from(embedding)),
+                        None,
+                    )
+                    .map_err(|e| ShinkaiLanceDBError::Arrow(e.to_string()))?,
+                ),
+                Arc::new(StringArray::from(vec![entry.content.clone()])),
+                Arc::new(StringArray::from(vec![entry.created_at.clone()])),
+            ],
+        )
+        .map_err(|e| ShinkaiLanceDBError::Arrow(e.to_string()))?;
+
+        let batch_reader = Box::new(RecordBatchIterator::new(vec![Ok(batch)], schema.clone()));
+        self.memory_table
+            .add(batch_reader)
+            .mode(AddDataMode::Append)
+            .execute()
+            .await?;
+
+        Ok(entry)
+    }
+
+    /// Returns the entries of the profile most similar to the query, leaving out the ones of the excluded jobs
+    pub async fn memory_vector_search(
+        &self,
+        profile: &str,
+        query: &str,
+        num_results: u64,
+        excluded_job_ids: &[String],
+    ) -> Result<Vec<MemoryEntry>, ShinkaiLanceDBError> {
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let embedding = self
+            .embedding_function
+            .request_embeddings(query)
+            .await
+            .map_err(|e| ShinkaiLanceDBError::Memory(e.to_string()))?;
+
+        let mut filter = format!("{} = {}", ShinkaiMemorySchema::profile_field(), quote(profile));
+        if !excluded_job_ids.is_empty() {
+            let job_ids: Vec<String> = excluded_job_ids.iter().map(|job_id| quote(job_id)).collect();
+            filter.push_str(&format!(
+                " AND {} NOT IN ({})",
+                ShinkaiMemorySchema::job_id_field(),
+                job_ids.join(", ")
+            ));
+        }
+
+        let results = self
+            .memory_table
+            .query()
+            .select(Self::memory_columns())
+            .only_if(filter)
+            .limit(num_results as usize)
+            .nearest_to(embedding)?
+            .execute()
+            .await?;
+
+        Self::collect_memory_entries(results.try_collect::<Vec<_>>().await?)
+    }
+
+    /// Returns every entry remembered for the profile, the oldest first
+    pub async fn get_memory_entries(&self, profile: &str) -> Result<Vec<MemoryEntry>, ShinkaiLanceDBError> {
+        let results = self
+            .memory_table
+            .query()
+            .select(Self::memory_columns())
+            .only_if(format!("{} = {}", ShinkaiMemorySchema::profile_field(), quote(profile)))
+            .execute()
+            .await?;
+
+        let mut entries = Self::collect_memory_entries(results.try_collect::<Vec<_>>().await?)?;
+        entries.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        Ok(entries)
+    }
+
+    pub async fn forget_memory_entry(&self, profile: &str, memory_id: &str) -> Result<(), ShinkaiLanceDBError> {
+        self.memory_table
+            .delete(&format!(
+                "{} = {} AND {} = {}",
+                ShinkaiMemorySchema::profile_field(),
+                quote(profile),
+                ShinkaiMemorySchema::memory_id_field(),
+                quote(memory_id)
+            ))
+            .await
+            .map_err(ShinkaiLanceDBError::from)
+    }
+
+    /// Forgets every entry of the job remembered for the profile
+    pub async fn forget_job_memory(&self, profile: &str, job_id: &str) -> Result<(), ShinkaiLanceDBError> {
+        self.memory_table
+            .delete(&format!(
+                "{} = {} AND {} = {}",
+                ShinkaiMemorySchema::profile_field(),
+                quote(profile),
+                ShinkaiMemorySchema::job_id_field(),
+                quote(job_id)
+            ))
+            .await
+            .map_err(ShinkaiLanceDBError::from)
+    }
+
+    fn memory_columns() -> Select {
+        Select::columns(&[
+            ShinkaiMemorySchema::memory_id_field(),
+            ShinkaiMemorySchema::profile_field(),
+            ShinkaiMemorySchema::job_id_field(),
+            ShinkaiMemorySchema::content_field(),
+            ShinkaiMemorySchema::created_at_field(),
+        ])
+    }
+
+    fn collect_memory_entries(batches: Vec<RecordBatch>) -> Result<Vec<MemoryEntry>, ShinkaiLanceDBError> {
+        let column = |batch: &RecordBatch, name: &str| -> Result<StringArray, ShinkaiLanceDBError> {
+            batch
+                .column_by_name(name)
+                .and_then(|column| column.as_any().downcast_ref::<StringArray>())
+                .cloned()
+                .ok_or_else(|| ShinkaiLanceDBError::Schema(format!("Missing column {}", name)))
+        };
+
+        let mut entries = Vec::new();
+        for batch in batches {
+            let memory_ids = column(&batch, ShinkaiMemorySchema::memory_id_field())?;
+            let profiles = column(&batch, ShinkaiMemorySchema::profile_field())?;
+            let job_ids = column(&batch, ShinkaiMemorySchema::job_id_field())?;
+            let contents = column(&batch, ShinkaiMemorySchema::content_field())?;
+            let created_ats = column(&batch, ShinkaiMemorySchema::created_at_field())?;
+
+            for i in 0..memory_ids.len() {
+                entries.push(MemoryEntry {
+                    memory_id: memory_ids.value(i).to_string(),
+                    profile: profiles.value(i).to_string(),
+                    job_id: job_ids.value(i).to_string(),
+                    content: contents.value(i).to_string(),
+                    created_at: created_ats.value(i).to_string(),
+                });
+            }
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_message_primitives::shinkai_utils::shinkai_logging::init_default_tracing;
+    use shinkai_vector_resources::embedding_generator::EmbeddingGenerator;
+    use shinkai_vector_resources::embedding_generator::RemoteEmbeddingGenerator;
+    use std::fs;
+    use std::path::Path;
+
+    fn setup() {
+        let path = Path::new("lance_db_tests/");
+        let _ = fs::remove_dir_all(path);
+    }
+
+    #[tokio::test]
+    async fn test_job_memory() -> Result<(), ShinkaiLanceDBError> {
+        init_default_tracing();
+        setup();
+
+        let generator = RemoteEmbeddingGenerator::new_default();
+        let embedding_model = generator.model_type().clone();
+        let db = LanceShinkaiDb::new("lance_db_tests/lancedb", embedding_model.clone(), generator.clone()).await?;
+
+        let pets = db
+            .add_memory_entry("main", "job1", "User: My dog is called Toby\nAssistant: Nice name!")
+            .await?;
+        db.add_memory_entry("main", "job2", "User: I live in Lisbon\nAssistant: Great city.")
+            .await?;
+        db.add_memory_entry("other", "job3", "User: My cat is called Luna\nAssistant: Cute!")
+            .await?;
+        // Remembering the same content again doesn't duplicate it
+        db.add_memory_entry("main", "job1", "User: My dog is called Toby\nAssistant: Nice name!")
+            .await?;
+
+        let entries = db.get_memory_entries("main").await?;
+        assert_eq!(entries.len(), 2);
+
+        let results = db.memory_vector_search("main", "What is my dog's name?", 1, &[]).await?;
+        assert_eq!(results[0].memory_id, pets.memory_id);
+
+        // Entries of other profiles and excluded jobs are left out
+        let results = db
+            .memory_vector_search("main", "What is my dog's name?", 5, &["job1".to_string()])
+            .await?;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].job_id, "job2");
+
+        db.forget_memory_entry("main", &pets.memory_id).await?;
+        db.forget_job_memory("main", "job2").await?;
+        assert!(db.get_memory_entries("main").await?.is_empty());
+        assert_eq!(db.get_memory_entries("other").await?.len(), 1);
+
+        Ok(())
+    }
+}
@@ -8,7 +8,8 @@ pub enum ShinkaiLanceDBError {
     Schema(String),
     Arrow(String),
     ToolError(String),
-    InvalidPath(String)
+    InvalidPath(String),
+    Memory(String),
 }
 
 impl fmt::Display for ShinkaiLanceDBError {
@@ -19,6 +20,7 @@ impl fmt::Display for ShinkaiLanceDBError {
             ShinkaiLanceDBError::Arrow(err) => write!(f, "Arrow error: {}", err),
             ShinkaiLanceDBError::ToolError(err) => write!(f, "Tool error: {}", err),
             ShinkaiLanceDBError::InvalidPath(err) => write!(f, "Invalid path error: {}", err),
+            ShinkaiLanceDBError::Memory(err) => write!(f, "Memory error: {}", err),
         }
     }
 }
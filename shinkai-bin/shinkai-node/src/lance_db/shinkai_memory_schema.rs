@@ -0,0 +1,60 @@
+use arrow_schema::{DataType, Field, Schema};
+use shinkai_vector_resources::{model_type::EmbeddingModelType, resource_errors::VRError};
+use std::sync::Arc;
+
+pub struct ShinkaiMemorySchema;
+
+impl ShinkaiMemorySchema {
+    /// Creates a new Schema for the memory of past job conversations with the following fields:
+    /// - memory_id: UTF-8 string (non-nullable)
+    /// - profile: UTF-8 string (non-nullable)
+    /// - job_id: UTF-8 string (non-nullable)
+    /// - vector: Fixed-size list of 32-bit floats (nullable)
+    /// - content: UTF-8 string (non-nullable)
+    /// - created_at: UTF-8 string (non-nullable)
+    ///
+    /// The vector field's size is determined by the embedding model's dimensions.
+    pub fn create_schema(embedding_model: &EmbeddingModelType) -> Result<Arc<Schema>, VRError> {
+        let vector_dimensions = embedding_model.vector_dimensions()?;
+
+        Ok(Arc::new(Schema::new(vec![
+            Field::new(Self::memory_id_field(), DataType::Utf8, false),
+            Field::new(Self::profile_field(), DataType::Utf8, false),
+            Field::new(Self::job_id_field(), DataType::Utf8, false),
+            Field::new(
+                Self::vector_field(),
+                DataType::FixedSizeList(
+                    Arc::new(Field::new("item", DataType::Float32, true)),
+                    vector_dimensions.try_into().unwrap(),
+                ),
+                true,
+            ),
+            Field::new(Self::content_field(), DataType::Utf8, false),
+            Field::new(Self::created_at_field(), DataType::Utf8, false),
+        ])))
+    }
+
+    pub fn memory_id_field() -> &'static str {
+        "memory_id"
+    }
+
+    pub fn profile_field() -> &'static str {
+        "profile"
+    }
+
+    pub fn job_id_field() -> &'static str {
+        "job_id"
+    }
+
+    pub fn vector_field() -> &'static str {
+        "vector"
+    }
+
+    pub fn content_field() -> &'static str {
+        "content"
+    }
+
+    pub fn created_at_field() -> &'static str {
+        "created_at"
+    }
+}
@@ -78,7 +78,14 @@ pub enum LLMProviderError {
     CallbackManagerNotFound,
     SheetManagerError(String),
     InputProcessingError(String),
-    ToolRouterNotFound
+    ToolRouterNotFound,
+    ProviderBackendNotRegistered(String),
+    UsageQuotaExceeded(String),
+    InvalidStructuredOutput(String),
+    JobCancelled(String),
+    InvalidJobArchive(String),
+    InvalidEvalDataset(String),
+    AgentExecutionTimeout(String),
 }
 
 impl fmt::Display for LLMProviderError {
@@ -164,14 +171,23 @@ impl fmt::Display for LLMProviderError {
             LLMProviderError::SheetManagerError(s) => write!(f, "{}", s),
             LLMProviderError::InputProcessingError(s) => write!(f, "{}", s),
             LLMProviderError::ToolRouterNotFound => write!(f, "Tool Router not found"),
+            LLMProviderError::ProviderBackendNotRegistered(s) => {
+                write!(f, "No LLM provider backend registered for: {}", s)
+            }
+            LLMProviderError::UsageQuotaExceeded(s) => write!(f, "Usage quota exceeded: {}", s),
+            LLMProviderError::InvalidStructuredOutput(s) => write!(f, "Invalid structured output: {}", s),
+            LLMProviderError::JobCancelled(s) => write!(f, "Job cancelled: {}", s),
+            LLMProviderError::InvalidJobArchive(s) => write!(f, "Invalid job archive: {}", s),
+            LLMProviderError::InvalidEvalDataset(s) => write!(f, "Invalid eval dataset: {}", s),
+            LLMProviderError::AgentExecutionTimeout(s) => write!(f, "Agent execution timed out: {}", s),
         }
     }
 }
 
 impl LLMProviderError {
-    /// Encodes the error as a JSON string that is easily parsable by frontends
-    pub fn to_error_json(&self) -> String {
-        let error_name = match self {
+    /// Returns the name of the error variant (used by frontends and by retry policies)
+    pub fn error_name(&self) -> &'static str {
+        match self {
             LLMProviderError::UrlNotSet => "UrlNotSet",
             LLMProviderError::ApiKeyNotSet => "ApiKeyNotSet",
             LLMProviderError::ReqwestError(_) => "ReqwestError",
@@ -239,8 +255,19 @@ impl LLMProviderError {
             LLMProviderError::SheetManagerError(_) => "SheetManagerError",
             LLMProviderError::InputProcessingError(_) => "InputProcessingError",
             LLMProviderError::ToolRouterNotFound => "ToolRouterNotFound",
-        };
+            LLMProviderError::ProviderBackendNotRegistered(_) => "ProviderBackendNotRegistered",
+            LLMProviderError::UsageQuotaExceeded(_) => "UsageQuotaExceeded",
+            LLMProviderError::InvalidStructuredOutput(_) => "InvalidStructuredOutput",
+            LLMProviderError::JobCancelled(_) => "JobCancelled",
+            LLMProviderError::InvalidJobArchive(_) => "InvalidJobArchive",
+            LLMProviderError::InvalidEvalDataset(_) => "InvalidEvalDataset",
+            LLMProviderError::AgentExecutionTimeout(_) => "AgentExecutionTimeout",
+        }
+    }
 
+    /// Encodes the error as a JSON string that is easily parsable by frontends
+    pub fn to_error_json(&self) -> String {
+        let error_name = self.error_name();
         let error_message = format!("{}", self);
 
         serde_json::json!({
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
+use shinkai_message_primitives::shinkai_utils::job_scope::JobScope;
+
+use super::eval_graders::GraderConfig;
+use crate::llm_provider::error::LLMProviderError;
+
+/// A user message replayed through the inference chains, with the graders scoring its answer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalCase {
+    pub id: String,
+    pub message: String,
+    #[serde(default = "JobScope::new_default")]
+    pub scope: JobScope,
+    #[serde(default)]
+    pub inference_params: Option<InferenceParams>,
+    #[serde(default)]
+    pub graders: Vec<GraderConfig>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvalDataset {
+    pub cases: Vec<EvalCase>,
+}
+
+impl EvalDataset {
+    /// Parses a dataset with one case (JSON object) per line. Empty lines and lines starting with `#` are skipped.
+    pub fn from_jsonl(jsonl: &str) -> Result<Self, LLMProviderError> {
+        let mut cases: Vec<EvalCase> = Vec::new();
+        for (index, line) in jsonl.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let case: EvalCase = serde_json::from_str(line)
+                .map_err(|e| LLMProviderError::InvalidEvalDataset(format!("line {}: {}", index + 1, e)))?;
+            if cases.iter().any(|other| other.id == case.id) {
+                return Err(LLMProviderError::InvalidEvalDataset(format!(
+                    "line {}: duplicated case id {}",
+                    index + 1,
+                    case.id
+                )));
+            }
+            cases.push(case);
+        }
+        Ok(Self { cases })
+    }
+
+    pub fn load(path: &str) -> Result<Self, LLMProviderError> {
+        let jsonl = std::fs::read_to_string(path)
+            .map_err(|e| LLMProviderError::InvalidEvalDataset(format!("failed to read {}: {}", path, e)))?;
+        Self::from_jsonl(&jsonl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dataset_from_jsonl() {
+        let jsonl = r#"
+# Capitals
+{"id": "france", "message": "What is the capital of France?", "graders": [{"type": "exact_match", "expected": "Paris"}]}
+{"id": "peru", "message": "What is the capital of Peru?", "inference_params": {"temperature": 0.0}}
+"#;
+        let dataset = EvalDataset::from_jsonl(jsonl).unwrap();
+        assert_eq!(dataset.cases.len(), 2);
+        assert_eq!(dataset.cases[0].id, "france");
+        assert_eq!(dataset.cases[0].graders.len(), 1);
+        assert!(dataset.cases[0].scope.is_empty());
+        assert_eq!(
+            dataset.cases[1].inference_params.as_ref().unwrap().temperature,
+            Some(0.0)
+        );
+
+        let duplicated = r#"{"id": "a", "message": "1"}
+{"id": "a", "message": "2"}"#;
+        assert!(matches!(
+            EvalDataset::from_jsonl(duplicated),
+            Err(LLMProviderError::InvalidEvalDataset(_))
+        ));
+        assert!(EvalDataset::from_jsonl(r#"{"id": "a"}"#).is_err());
+    }
+}
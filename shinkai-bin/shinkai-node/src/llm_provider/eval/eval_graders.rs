@@ -0,0 +1,296 @@
+use async_trait::async_trait;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
+use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::SerializedLLMProvider;
+
+use super::eval_dataset::EvalCase;
+use crate::llm_provider::error::LLMProviderError;
+use crate::llm_provider::execution::prompts::prompts::Prompt;
+use crate::llm_provider::execution::prompts::subprompts::SubPromptType;
+use crate::llm_provider::execution::structured_output::{extract_json_value, validate_json_schema, ResponseSchema};
+use crate::llm_provider::job_manager::JobManager;
+
+fn default_min_score() -> f64 {
+    7.0
+}
+
+/// Score given by a grader to the answer of a case
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalGrade {
+    pub grader: String,
+    pub passed: bool,
+    /// Between 0 and 1
+    pub score: f64,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+impl EvalGrade {
+    fn new(grader: String, passed: bool, reason: Option<String>) -> Self {
+        Self {
+            grader,
+            passed,
+            score: if passed { 1.0 } else { 0.0 },
+            reason,
+        }
+    }
+}
+
+/// Scores the answer given to an eval case. Implement it to add graders on top of the built-in ones.
+#[async_trait]
+pub trait EvalGrader: Send + Sync {
+    /// Name of the grader in the report
+    fn name(&self) -> String;
+
+    async fn grade(&self, case: &EvalCase, output: &str) -> Result<EvalGrade, LLMProviderError>;
+}
+
+/// Built-in graders, as written in the dataset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GraderConfig {
+    ExactMatch {
+        expected: String,
+        #[serde(default)]
+        ignore_case: bool,
+    },
+    Regex {
+        pattern: String,
+    },
+    JsonSchema {
+        schema: JsonValue,
+    },
+    /// Asks the judge llm provider to score the answer from 0 to 10 following the criteria
+    LlmJudge {
+        criteria: String,
+        #[serde(default = "default_min_score")]
+        min_score: f64,
+    },
+}
+
+impl GraderConfig {
+    /// Creates the grader. LLM judges need the llm provider acting as judge.
+    pub fn build(&self, judge: Option<&SerializedLLMProvider>) -> Result<Box<dyn EvalGrader>, LLMProviderError> {
+        match self {
+            GraderConfig::ExactMatch { expected, ignore_case } => Ok(Box::new(ExactMatchGrader {
+                expected: expected.clone(),
+                ignore_case: *ignore_case,
+            })),
+            GraderConfig::Regex { pattern } => {
+                let regex = Regex::new(pattern)
+                    .map_err(|e| LLMProviderError::InvalidEvalDataset(format!("invalid regex {}: {}", pattern, e)))?;
+                Ok(Box::new(RegexGrader { regex }))
+            }
+            GraderConfig::JsonSchema { schema } => Ok(Box::new(JsonSchemaGrader { schema: schema.clone() })),
+            GraderConfig::LlmJudge { criteria, min_score } => {
+                let judge = judge.ok_or_else(|| {
+                    LLMProviderError::InvalidEvalDataset("llm_judge graders need a judge llm provider".to_string())
+                })?;
+                Ok(Box::new(LLMJudgeGrader {
+                    judge: judge.clone(),
+                    criteria: criteria.clone(),
+                    min_score: *min_score,
+                }))
+            }
+        }
+    }
+}
+
+/// Passes if the answer (trimmed) is the expected text
+pub struct ExactMatchGrader {
+    pub expected: String,
+    pub ignore_case: bool,
+}
+
+#[async_trait]
+impl EvalGrader for ExactMatchGrader {
+    fn name(&self) -> String {
+        "exact_match".to_string()
+    }
+
+    async fn grade(&self, _case: &EvalCase, output: &str) -> Result<EvalGrade, LLMProviderError> {
+        let (output, expected) = (output.trim(), self.expected.trim());
+        let passed = if self.ignore_case {
+            output.to_lowercase() == expected.to_lowercase()
+        } else {
+            output == expected
+        };
+        let reason = (!passed).then(|| format!("expected {:?}", expected));
+        Ok(EvalGrade::new(self.name(), passed, reason))
+    }
+}
+
+/// Passes if the regex matches somewhere in the answer
+pub struct RegexGrader {
+    pub regex: Regex,
+}
+
+#[async_trait]
+impl EvalGrader for RegexGrader {
+    fn name(&self) -> String {
+        "regex".to_string()
+    }
+
+    async fn grade(&self, _case: &EvalCase, output: &str) -> Result<EvalGrade, LLMProviderError> {
+        let passed = self.regex.is_match(output);
+        let reason = (!passed).then(|| format!("no match for {}", self.regex.as_str()));
+        Ok(EvalGrade::new(self.name(), passed, reason))
+    }
+}
+
+/// Passes if the answer contains a JSON value following the schema
+pub struct JsonSchemaGrader {
+    pub schema: JsonValue,
+}
+
+#[async_trait]
+impl EvalGrader for JsonSchemaGrader {
+    fn name(&self) -> String {
+        "json_schema".to_string()
+    }
+
+    async fn grade(&self, _case: &EvalCase, output: &str) -> Result<EvalGrade, LLMProviderError> {
+        let errors = match extract_json_value(output) {
+            Some(value) => validate_json_schema(&value, &self.schema),
+            None => vec!["the answer doesn't contain a JSON value".to_string()],
+        };
+        let reason = (!errors.is_empty()).then(|| errors.join("; "));
+        Ok(EvalGrade::new(self.name(), errors.is_empty(), reason))
+    }
+}
+
+/// Asks an llm provider to score the answer following the criteria
+pub struct LLMJudgeGrader {
+    pub judge: SerializedLLMProvider,
+    pub criteria: String,
+    /// Minimum score (out of 10) to pass
+    pub min_score: f64,
+}
+
+impl LLMJudgeGrader {
+    fn judge_prompt(&self, case: &EvalCase, output: &str) -> Prompt {
+        let mut prompt = Prompt::new();
+        prompt.add_content(
+            "You are grading the answer given by an AI assistant to a user. Be strict and impartial.".to_string(),
+            SubPromptType::System,
+            100,
+        );
+        prompt.add_content(
+            format!(
+                "Grading criteria: {}\n\nUser message:\n{}\n\nAnswer to grade:\n{}\n\nScore the answer from 0 (fails the criteria) to 10 (fully meets them) and explain the score in one sentence.",
+                self.criteria, case.message, output
+            ),
+            SubPromptType::User,
+            100,
+        );
+        prompt.set_response_schema(Some(ResponseSchema::new(json!({
+            "type": "object",
+            "properties": {
+                "score": { "type": "number", "minimum": 0, "maximum": 10 },
+                "reason": { "type": "string" }
+            },
+            "required": ["score"]
+        }))));
+        prompt
+    }
+}
+
+#[async_trait]
+impl EvalGrader for LLMJudgeGrader {
+    fn name(&self) -> String {
+        "llm_judge".to_string()
+    }
+
+    async fn grade(&self, case: &EvalCase, output: &str) -> Result<EvalGrade, LLMProviderError> {
+        let response = JobManager::inference_with_llm_provider(
+            self.judge.clone(),
+            self.judge_prompt(case, output),
+            InferenceParams::default(),
+            None,
+            None,
+            None,
+        )
+        .await?;
+
+        let score = response.json["score"].as_f64().unwrap_or_default();
+        Ok(EvalGrade {
+            grader: self.name(),
+            passed: score >= self.min_score,
+            score: (score / 10.0).clamp(0.0, 1.0),
+            reason: response.json["reason"].as_str().map(|reason| reason.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_message_primitives::shinkai_utils::job_scope::JobScope;
+
+    fn case() -> EvalCase {
+        EvalCase {
+            id: "case".to_string(),
+            message: "Give me the capital of France as JSON".to_string(),
+            scope: JobScope::new_default(),
+            inference_params: None,
+            graders: vec![],
+        }
+    }
+
+    async fn grade(config: GraderConfig, output: &str) -> EvalGrade {
+        config.build(None).unwrap().grade(&case(), output).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_built_in_graders() {
+        let exact = GraderConfig::ExactMatch {
+            expected: "Paris".to_string(),
+            ignore_case: true,
+        };
+        assert!(grade(exact.clone(), " paris\n").await.passed);
+        assert!(!grade(exact, "Lyon").await.passed);
+
+        let regex = GraderConfig::Regex {
+            pattern: r"(?i)\bparis\b".to_string(),
+        };
+        assert!(grade(regex.clone(), "It's Paris.").await.passed);
+        let failed = grade(regex, "It's Lyon.").await;
+        assert!(!failed.passed);
+        assert_eq!(failed.score, 0.0);
+
+        let json_schema = GraderConfig::JsonSchema {
+            schema: json!({
+                "type": "object",
+                "properties": { "capital": { "type": "string" } },
+                "required": ["capital"]
+            }),
+        };
+        assert!(
+            grade(json_schema.clone(), "```json\n{\"capital\": \"Paris\"}\n```")
+                .await
+                .passed
+        );
+        assert!(!grade(json_schema.clone(), "{\"city\": \"Paris\"}").await.passed);
+        assert!(!grade(json_schema, "Paris").await.passed);
+    }
+
+    #[test]
+    fn test_invalid_grader_configs() {
+        let config: GraderConfig = serde_json::from_str(r#"{"type": "llm_judge", "criteria": "Is correct"}"#).unwrap();
+        assert_eq!(
+            config,
+            GraderConfig::LlmJudge {
+                criteria: "Is correct".to_string(),
+                min_score: 7.0
+            }
+        );
+        assert!(config.build(None).is_err());
+
+        let regex = GraderConfig::Regex {
+            pattern: "(".to_string(),
+        };
+        assert!(regex.build(None).is_err());
+    }
+}
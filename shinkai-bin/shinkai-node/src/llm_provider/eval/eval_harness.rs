@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::SerializedLLMProvider;
+use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
+use shinkai_message_primitives::schemas::shinkai_time::ShinkaiStringTime;
+use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::JobMessage;
+use shinkai_vector_resources::embedding_generator::RemoteEmbeddingGenerator;
+
+use super::eval_dataset::{EvalCase, EvalDataset};
+use super::eval_graders::{EvalGrade, EvalGrader};
+use super::eval_report::{EvalCaseResult, EvalReport};
+use crate::db::ShinkaiDB;
+use crate::llm_provider::error::LLMProviderError;
+use crate::llm_provider::job_manager::JobManager;
+use crate::vector_fs::vector_fs::VectorFS;
+
+/// Replays the cases of a dataset through the inference chain router and grades the answers.
+/// Each case runs in a new (hidden) job, so the cases don't see each other's messages.
+pub struct EvalHarness {
+    db: Arc<ShinkaiDB>,
+    vector_fs: Arc<VectorFS>,
+    llm_provider: SerializedLLMProvider,
+    generator: RemoteEmbeddingGenerator,
+    user_profile: ShinkaiName,
+    judge: Option<SerializedLLMProvider>,
+    graders: Vec<Arc<dyn EvalGrader>>,
+}
+
+impl EvalHarness {
+    pub fn new(
+        db: Arc<ShinkaiDB>,
+        vector_fs: Arc<VectorFS>,
+        llm_provider: SerializedLLMProvider,
+        generator: RemoteEmbeddingGenerator,
+        user_profile: ShinkaiName,
+    ) -> Self {
+        Self {
+            db,
+            vector_fs,
+            llm_provider,
+            generator,
+            user_profile,
+            judge: None,
+            graders: vec![],
+        }
+    }
+
+    /// Sets the llm provider scoring the answers of the `llm_judge` graders
+    pub fn with_judge(mut self, judge: SerializedLLMProvider) -> Self {
+        self.judge = Some(judge);
+        self
+    }
+
+    /// Adds a grader applied to every case (on top of the graders of each case)
+    pub fn with_grader(mut self, grader: Arc<dyn EvalGrader>) -> Self {
+        self.graders.push(grader);
+        self
+    }
+
+    /// Runs every case of the dataset, in order
+    pub async fn run(&self, dataset: &EvalDataset) -> EvalReport {
+        let started_at = ShinkaiStringTime::generate_time_now();
+        let start = Instant::now();
+
+        let mut results = Vec::new();
+        for case in &dataset.cases {
+            results.push(self.run_case(case).await);
+        }
+
+        EvalReport::new(
+            self.llm_provider.id.clone(),
+            format!(
+                "{}:{}",
+                self.llm_provider.model.provider_prefix(),
+                self.llm_provider.model.model_type()
+            ),
+            started_at,
+            start.elapsed().as_millis() as u64,
+            results,
+        )
+    }
+
+    pub async fn run_case(&self, case: &EvalCase) -> EvalCaseResult {
+        let start = Instant::now();
+        let (output, error) = match self.infer(case).await {
+            Ok(output) => (Some(output), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+
+        let mut grades = Vec::new();
+        if let Some(output) = &output {
+            for config in &case.graders {
+                let grade = match config.build(self.judge.as_ref()) {
+                    Ok(grader) => Self::grade(grader.as_ref(), case, output).await,
+                    Err(e) => EvalGrade {
+                        grader: "invalid_grader".to_string(),
+                        passed: false,
+                        score: 0.0,
+                        reason: Some(e.to_string()),
+                    },
+                };
+                grades.push(grade);
+            }
+            for grader in &self.graders {
+                grades.push(Self::grade(grader.as_ref(), case, output).await);
+            }
+        }
+
+        EvalCaseResult {
+            case_id: case.id.clone(),
+            output,
+            error,
+            grades,
+            duration_ms: start.elapsed().as_millis() as u64,
+        }
+    }
+
+    /// Graders that fail to run (e.g. the judge is unreachable) fail the case
+    async fn grade(grader: &dyn EvalGrader, case: &EvalCase, output: &str) -> EvalGrade {
+        match grader.grade(case, output).await {
+            Ok(grade) => grade,
+            Err(e) => EvalGrade {
+                grader: grader.name(),
+                passed: false,
+                score: 0.0,
+                reason: Some(format!("Grader failed: {}", e)),
+            },
+        }
+    }
+
+    /// Sends the message of the case to a new job and returns the answer of the inference chain
+    async fn infer(&self, case: &EvalCase) -> Result<String, LLMProviderError> {
+        let job_id = format!("jobid_{}", uuid::Uuid::new_v4());
+        self.db.create_new_job(
+            job_id.clone(),
+            self.llm_provider.id.clone(),
+            case.scope.clone(),
+            true,
+            None,
+        )?;
+        let full_job = self.db.get_job(&job_id)?;
+
+        let job_message = JobMessage {
+            job_id,
+            content: case.message.clone(),
+            files_inbox: "".to_string(),
+            parent: None,
+            workflow_code: None,
+            workflow_name: None,
+            sheet_job_data: None,
+            callback: None,
+            inference_params: case.inference_params.clone(),
+            is_delegation_trace: false,
+        };
+
+        let result = JobManager::inference_chain_router(
+            self.db.clone(),
+            self.vector_fs.clone(),
+            Some(self.llm_provider.clone()),
+            full_job,
+            job_message,
+            HashMap::new(),
+            self.generator.clone(),
+            self.user_profile.clone(),
+            None,
+            None,
+            None,
+        )
+        .await?;
+        Ok(result.response)
+    }
+}
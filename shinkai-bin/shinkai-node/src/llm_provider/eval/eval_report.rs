@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::eval_graders::EvalGrade;
+
+/// Outcome of a single eval case
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalCaseResult {
+    pub case_id: String,
+    /// Answer of the inference chain (None if it failed)
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub grades: Vec<EvalGrade>,
+    pub duration_ms: u64,
+}
+
+impl EvalCaseResult {
+    /// A case passes if the chain answered and every grader passed
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.grades.iter().all(|grade| grade.passed)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EvalGraderSummary {
+    pub passed: usize,
+    pub total: usize,
+    pub mean_score: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EvalSummary {
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    /// Cases where the inference chain failed (counted in `failed` too)
+    pub errored: usize,
+    pub pass_rate: f64,
+    pub graders: BTreeMap<String, EvalGraderSummary>,
+}
+
+impl EvalSummary {
+    pub fn from_results(results: &[EvalCaseResult]) -> Self {
+        let mut graders: BTreeMap<String, EvalGraderSummary> = BTreeMap::new();
+        for grade in results.iter().flat_map(|result| result.grades.iter()) {
+            let summary = graders.entry(grade.grader.clone()).or_default();
+            summary.total += 1;
+            summary.passed += grade.passed as usize;
+            summary.mean_score += grade.score;
+        }
+        for summary in graders.values_mut() {
+            summary.mean_score /= summary.total as f64;
+        }
+
+        let total = results.len();
+        let passed = results.iter().filter(|result| result.passed()).count();
+        Self {
+            total,
+            passed,
+            failed: total - passed,
+            errored: results.iter().filter(|result| result.error.is_some()).count(),
+            pass_rate: if total == 0 { 0.0 } else { passed as f64 / total as f64 },
+            graders,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalReport {
+    pub llm_provider_id: String,
+    /// `provider:model` of the evaluated llm provider
+    pub model: String,
+    pub started_at: String,
+    pub duration_ms: u64,
+    pub summary: EvalSummary,
+    pub cases: Vec<EvalCaseResult>,
+}
+
+impl EvalReport {
+    pub fn new(
+        llm_provider_id: String,
+        model: String,
+        started_at: String,
+        duration_ms: u64,
+        cases: Vec<EvalCaseResult>,
+    ) -> Self {
+        Self {
+            llm_provider_id,
+            model,
+            started_at,
+            duration_ms,
+            summary: EvalSummary::from_results(&cases),
+            cases,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Human readable version of the report, listing the cases that failed
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!(
+            "# Eval report\n\n- LLM provider: {} ({})\n- Started at: {}\n- Duration: {} ms\n- Passed: {}/{} ({:.1}%)\n- Errored: {}\n",
+            self.llm_provider_id,
+            self.model,
+            self.started_at,
+            self.duration_ms,
+            self.summary.passed,
+            self.summary.total,
+            self.summary.pass_rate * 100.0,
+            self.summary.errored
+        );
+
+        if !self.summary.graders.is_empty() {
+            markdown.push_str("\n| Grader | Passed | Mean score |\n|---|---|---|\n");
+            for (grader, summary) in &self.summary.graders {
+                markdown.push_str(&format!(
+                    "| {} | {}/{} | {:.2} |\n",
+                    grader, summary.passed, summary.total, summary.mean_score
+                ));
+            }
+        }
+
+        let failed: Vec<&EvalCaseResult> = self.cases.iter().filter(|case| !case.passed()).collect();
+        if !failed.is_empty() {
+            markdown.push_str("\n## Failed cases\n");
+            for case in failed {
+                markdown.push_str(&format!("\n### {}\n", case.case_id));
+                if let Some(error) = &case.error {
+                    markdown.push_str(&format!("- Error: {}\n", error));
+                }
+                for grade in case.grades.iter().filter(|grade| !grade.passed) {
+                    markdown.push_str(&format!(
+                        "- {}: {}\n",
+                        grade.grader,
+                        grade.reason.as_deref().unwrap_or("failed")
+                    ));
+                }
+            }
+        }
+
+        markdown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grade(grader: &str, passed: bool) -> EvalGrade {
+        EvalGrade {
+            grader: grader.to_string(),
+            passed,
+            score: if passed { 1.0 } else { 0.0 },
+            reason: (!passed).then(|| "wrong answer".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_eval_report_summary() {
+        let cases = vec![
+            EvalCaseResult {
+                case_id: "a".to_string(),
+                output: Some("Paris".to_string()),
+                error: None,
+                grades: vec![grade("exact_match", true), grade("regex", true)],
+                duration_ms: 10,
+            },
+            EvalCaseResult {
+                case_id: "b".to_string(),
+                output: Some("Lyon".to_string()),
+                error: None,
+                grades: vec![grade("exact_match", false), grade("regex", true)],
+                duration_ms: 10,
+            },
+            EvalCaseResult {
+                case_id: "c".to_string(),
+                output: None,
+                error: Some("Inference failed".to_string()),
+                grades: vec![],
+                duration_ms: 10,
+            },
+        ];
+
+        let report = EvalReport::new(
+            "mock".to_string(),
+            "mock:scripted".to_string(),
+            "2024-01-01T00:00:00Z".to_string(),
+            30,
+            cases,
+        );
+        assert_eq!(report.summary.total, 3);
+        assert_eq!(report.summary.passed, 1);
+        assert_eq!(report.summary.failed, 2);
+        assert_eq!(report.summary.errored, 1);
+        assert_eq!(report.summary.graders["exact_match"].passed, 1);
+        assert_eq!(report.summary.graders["exact_match"].mean_score, 0.5);
+        assert_eq!(report.summary.graders["regex"].passed, 2);
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("Passed: 1/3"));
+        assert!(markdown.contains("### b\n- exact_match: wrong answer"));
+        assert!(markdown.contains("### c\n- Error: Inference failed"));
+        assert!(!markdown.contains("### a"));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod eval_dataset;
+pub mod eval_graders;
+pub mod eval_harness;
+pub mod eval_report;
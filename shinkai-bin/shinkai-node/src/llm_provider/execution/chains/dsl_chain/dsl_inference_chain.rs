@@ -7,11 +7,13 @@ use crate::{
     },
     managers::model_capabilities_manager::ModelCapabilitiesManager,
     tools::{shinkai_tool::ShinkaiTool, workflow_tool::WorkflowTool},
-    workflows::sm_executor::{AsyncFunction, FunctionMap, WorkflowEngine, WorkflowError},
+    workflows::sm_executor::{AsyncFunction, FunctionMap, WorkflowEngine, WorkflowError, RESULT_REGISTER},
 };
 use async_trait::async_trait;
 use dashmap::DashMap;
+use serde_json::Value;
 use shinkai_dsl::dsl_schemas::Workflow;
+use shinkai_dsl::register_value::to_register_string;
 use shinkai_message_primitives::{
     schemas::inbox_name::InboxName,
     shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption},
@@ -23,6 +25,7 @@ use crate::llm_provider::{
     execution::{
         chains::inference_chain_trait::{InferenceChain, InferenceChainResult},
         prompts::prompts::JobPromptGenerator,
+        structured_output::ResponseSchema,
     },
     job_manager::JobManager,
 };
@@ -65,7 +68,7 @@ impl<'a> InferenceChain for DslChain<'a> {
         // Inject user_message into $R0
         final_registers.insert(
             "$INPUT".to_string(),
-            self.context.user_message().clone().original_user_message_string,
+            Value::String(self.context.user_message().clone().original_user_message_string),
         );
         let executor = engine.iter(
             &self.workflow_tool.workflow,
@@ -87,8 +90,8 @@ impl<'a> InferenceChain for DslChain<'a> {
         }
 
         let response_register = final_registers
-            .get("$RESULT")
-            .map(|r| r.clone())
+            .get(RESULT_REGISTER)
+            .map(|r| to_register_string(&r))
             .unwrap_or_else(String::new);
         let new_contenxt = HashMap::new();
 
@@ -135,6 +138,15 @@ impl<'a> DslChain<'a> {
         );
     }
 
+    pub fn add_structured_inference_function(&mut self) {
+        self.functions.insert(
+            "structured_inference".to_string(),
+            Box::new(StructuredInferenceFunction {
+                context: self.context.clone_box(),
+            }),
+        );
+    }
+
     pub fn add_opinionated_inference_function(&mut self) {
         self.functions.insert(
             "opinionated_inference".to_string(),
@@ -296,12 +308,14 @@ impl AsyncFunction for InferenceFunction {
         let response = JobManager::inference_with_llm_provider(
             llm_provider.clone(),
             filled_prompt.clone(),
+            self.context.inference_params().clone(),
             inbox_name,
             if self.use_ws_manager {
                 self.context.ws_manager_trait()
             } else {
                 None
             },
+            Some(self.context.db()),
         )
         .await
         .map_err(|e| WorkflowError::ExecutionError(e.to_string()))?;
@@ -318,6 +332,67 @@ impl AsyncFunction for InferenceFunction {
     }
 }
 
+/// Inference whose answer follows the JSON schema of the second argument. The optional third argument is the
+/// number of attempts to fix an answer not following it. Returns the validated JSON value serialized as a string.
+#[derive(Clone)]
+struct StructuredInferenceFunction {
+    context: Box<dyn InferenceChainContextTrait>,
+}
+
+#[async_trait]
+impl AsyncFunction for StructuredInferenceFunction {
+    async fn call(&self, args: Vec<Box<dyn Any + Send>>) -> Result<Box<dyn Any + Send>, WorkflowError> {
+        let user_message = args
+            .first()
+            .and_then(|arg| arg.downcast_ref::<String>())
+            .ok_or_else(|| WorkflowError::InvalidArgument("Invalid argument".to_string()))?
+            .clone();
+        let schema = args
+            .get(1)
+            .and_then(|arg| arg.downcast_ref::<String>())
+            .ok_or_else(|| WorkflowError::InvalidArgument("Missing JSON schema argument".to_string()))?;
+        let schema: serde_json::Value = serde_json::from_str(schema)
+            .map_err(|e| WorkflowError::InvalidArgument(format!("Invalid JSON schema: {}", e)))?;
+        let mut response_schema = ResponseSchema::new(schema);
+        if let Some(max_retries) = args.get(2).and_then(|arg| arg.downcast_ref::<String>()) {
+            let max_retries = max_retries
+                .trim()
+                .parse()
+                .map_err(|_| WorkflowError::InvalidArgument(format!("Invalid number of retries: {}", max_retries)))?;
+            response_schema = response_schema.with_max_retries(max_retries);
+        }
+
+        let full_job = self.context.full_job();
+        let llm_provider = self.context.agent();
+
+        let mut filled_prompt = JobPromptGenerator::generic_inference_prompt(
+            None,
+            None,
+            user_message,
+            vec![],
+            None,
+            None,
+            vec![],
+            None,
+        );
+        filled_prompt.set_response_schema(Some(response_schema));
+
+        let inbox_name = InboxName::get_job_inbox_name_from_params(full_job.job_id.clone()).ok();
+        let response = JobManager::inference_with_llm_provider(
+            llm_provider.clone(),
+            filled_prompt,
+            self.context.inference_params().clone(),
+            inbox_name,
+            None,
+            Some(self.context.db()),
+        )
+        .await
+        .map_err(|e| WorkflowError::ExecutionError(e.to_string()))?;
+
+        Ok(Box::new(response.json.to_string()))
+    }
+}
+
 #[derive(Clone)]
 struct OpinionatedInferenceFunction {
     context: Box<dyn InferenceChainContextTrait>,
@@ -360,7 +435,7 @@ impl AsyncFunction for OpinionatedInferenceFunction {
         let mut summary_node_text = None;
         if !scope_is_empty {
             // TODO: this should also be a generic fn
-            let (ret, summary) = JobManager::keyword_chained_job_scope_vector_search(
+            let (ret, summary) = JobManager::job_scope_search(
                 db.clone(),
                 vector_fs.clone(),
                 full_job.scope(),
@@ -369,6 +444,7 @@ impl AsyncFunction for OpinionatedInferenceFunction {
                 generator.clone(),
                 20,
                 max_tokens_in_prompt,
+                full_job.config.retrieval_mode,
             )
             .await
             .map_err(|e| WorkflowError::ExecutionError(e.to_string()))?;
@@ -395,12 +471,14 @@ impl AsyncFunction for OpinionatedInferenceFunction {
         let response = JobManager::inference_with_llm_provider(
             llm_provider.clone(),
             filled_prompt.clone(),
+            self.context.inference_params().clone(),
             inbox_name,
             if self.use_ws_manager {
                 self.context.ws_manager_trait()
             } else {
                 None
             },
+            Some(self.context.db()),
         )
         .await
         .map_err(|e| WorkflowError::ExecutionError(e.to_string()))?;
@@ -443,9 +443,10 @@ pub fn search_embeddings_in_job_scope(
                 let vector_fs = context.vector_fs();
                 let user_profile = context.user_profile();
                 let job_scope = context.full_job().scope.clone();
+                let retrieval_mode = context.full_job().config.retrieval_mode;
                 let generator = context.generator();
 
-                let result = JobManager::keyword_chained_job_scope_vector_search(
+                let result = JobManager::job_scope_search(
                     db,
                     vector_fs,
                     &job_scope,
@@ -454,6 +455,7 @@ pub fn search_embeddings_in_job_scope(
                     generator.clone(),
                     num_of_top_results,
                     max_tokens_in_prompt,
+                    retrieval_mode,
                 )
                 .await;
 
@@ -862,6 +864,10 @@ mod tests {
             toolkit_permissions: vec![],
             storage_bucket_permissions: vec![],
             allowed_message_senders: vec![],
+            retry_policy: Default::default(),
+            fallback_llm_providers: vec![],
+            usage_quota: None,
+            response_cache: None,
         };
 
         // Create a full InferenceChainContext with the generated embeddings
@@ -992,6 +998,10 @@ mod tests {
             toolkit_permissions: vec![],
             storage_bucket_permissions: vec![],
             allowed_message_senders: vec![],
+            retry_policy: Default::default(),
+            fallback_llm_providers: vec![],
+            usage_quota: None,
+            response_cache: None,
         };
 
         // Create a full InferenceChainContext with the generated embeddings
@@ -3,10 +3,12 @@ use crate::llm_provider::error::LLMProviderError;
 use crate::llm_provider::execution::chains::inference_chain_trait::{
     InferenceChain, InferenceChainContext, InferenceChainContextTrait, InferenceChainResult,
 };
+use crate::llm_provider::execution::job_memory::memory_step;
 use crate::llm_provider::execution::prompts::prompts::JobPromptGenerator;
 use crate::llm_provider::execution::user_message_parser::ParsedUserMessage;
 use crate::llm_provider::job::{Job, JobLike};
 use crate::llm_provider::job_manager::JobManager;
+use crate::managers::model_capabilities_manager::ModelCapabilitiesManager;
 use crate::managers::sheet_manager::SheetManager;
 use crate::network::ws_manager::WSUpdateHandler;
 use crate::tools::tool_router::ToolRouter;
@@ -14,9 +16,8 @@ use crate::vector_fs::vector_fs::VectorFS;
 use async_recursion::async_recursion;
 use async_trait::async_trait;
 use shinkai_message_primitives::schemas::inbox_name::InboxName;
-use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{
-    LLMProviderInterface, SerializedLLMProvider,
-};
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
+use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::SerializedLLMProvider;
 use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
 use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
 use shinkai_vector_resources::embedding_generator::RemoteEmbeddingGenerator;
@@ -69,6 +70,7 @@ impl InferenceChain for GenericInferenceChain {
             self.context.user_profile.clone(),
             self.context.max_iterations,
             self.context.max_tokens_in_prompt,
+            self.context.inference_params.clone(),
             self.ws_manager_trait.clone(),
             self.context.tool_router.clone(),
             self.context.sheet_manager.clone(),
@@ -104,6 +106,7 @@ impl GenericInferenceChain {
         user_profile: ShinkaiName,
         max_iterations: u64,
         max_tokens_in_prompt: usize,
+        inference_params: InferenceParams,
         ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
         tool_router: Option<Arc<Mutex<ToolRouter>>>,
         sheet_manager: Option<Arc<Mutex<SheetManager>>>,
@@ -130,12 +133,24 @@ impl GenericInferenceChain {
         Note: we need to handle errors and retry
         */
 
+        // The preset the job was created from (if it was removed the job goes on without it)
+        let preset = full_job
+            .config
+            .preset_id
+            .as_ref()
+            .and_then(|preset_id| db.get_job_preset(preset_id, &user_profile).ok());
+        let system_prompt = preset.as_ref().and_then(|preset| preset.system_prompt.clone());
+        let max_iterations = preset
+            .as_ref()
+            .and_then(|preset| preset.max_iterations)
+            .unwrap_or(max_iterations);
+
         // 1) Vector search for knowledge if the scope isn't empty
         let scope_is_empty = full_job.scope().is_empty();
         let mut ret_nodes: Vec<RetrievedNode> = vec![];
         let mut summary_node_text = None;
         if !scope_is_empty {
-            let (ret, summary) = JobManager::keyword_chained_job_scope_vector_search(
+            let (ret, summary) = JobManager::job_scope_search(
                 db.clone(),
                 vector_fs.clone(),
                 full_job.scope(),
@@ -144,6 +159,7 @@ impl GenericInferenceChain {
                 generator.clone(),
                 20,
                 max_tokens_in_prompt,
+                full_job.config.retrieval_mode,
             )
             .await?;
             ret_nodes = ret;
@@ -151,9 +167,9 @@ impl GenericInferenceChain {
         }
 
         // 2) Vector search for tooling / workflows if the workflow / tooling scope isn't empty
-        // Only for OpenAI right now
+        // Only for the providers with native tool calling
         let mut tools = vec![];
-        if let LLMProviderInterface::OpenAI(_openai) = &llm_provider.model.clone() {
+        if ModelCapabilitiesManager::supports_tool_calls(&llm_provider.model) {
             if let Some(tool_router) = &tool_router {
                 let tool_router = tool_router.lock().await;
 
@@ -163,27 +179,49 @@ impl GenericInferenceChain {
                 //     tools.extend(default_tools);
                 // }
 
-                // Search in JS Tools
-                let results = tool_router
-                    .vector_search_enabled_tools(&user_message.clone(), 3)
-                    .await
-                    .unwrap();
-                for result in results {
-                    if let Some(tool) = tool_router.get_tool_by_name(&result.tool_router_key).await.unwrap() {
-                        tools.push(tool);
+                match preset.as_ref().filter(|preset| !preset.tools.is_empty()) {
+                    // The preset picks the tools
+                    Some(preset) => {
+                        for tool_name in &preset.tools {
+                            if let Ok(Some(tool)) = tool_router.get_tool_by_name(tool_name).await {
+                                tools.push(tool);
+                            }
+                        }
+                    }
+                    // Search in JS Tools
+                    None => {
+                        let results = tool_router
+                            .vector_search_enabled_tools(&user_message.clone(), 3)
+                            .await
+                            .unwrap();
+                        for result in results {
+                            if let Some(tool) = tool_router.get_tool_by_name(&result.tool_router_key).await.unwrap() {
+                                tools.push(tool);
+                            }
+                        }
                     }
                 }
             }
         }
 
+        // Search the profile's memory of past conversations (added right before the new message)
+        let mut step_history = full_job.step_history.clone();
+        if full_job.config.use_memory {
+            if let (Some(tool_router), Some(profile)) = (&tool_router, user_profile.get_profile_name_string()) {
+                let entries =
+                    JobManager::recall_job_memory(tool_router, &profile, &full_job.job_id, &user_message).await;
+                step_history.extend(memory_step(&entries));
+            }
+        }
+
         // 3) Generate Prompt
         let mut filled_prompt = JobPromptGenerator::generic_inference_prompt(
-            None, // TODO: connect later on
+            system_prompt.clone(),
             None, // TODO: connect later on
             user_message.clone(),
             ret_nodes.clone(),
             summary_node_text.clone(),
-            Some(full_job.step_history.clone()),
+            Some(step_history.clone()),
             tools.clone(),
             None,
         );
@@ -206,8 +244,10 @@ impl GenericInferenceChain {
             let response_res = JobManager::inference_with_llm_provider(
                 llm_provider.clone(),
                 filled_prompt.clone(),
+                inference_params.clone(),
                 inbox_name,
                 ws_manager_trait.clone(),
+                Some(db.clone()),
             )
             .await;
 
@@ -223,7 +263,7 @@ impl GenericInferenceChain {
             // 5) Check response if it requires a function call
             if let Some(function_call) = response.function_call {
                 let parsed_message = ParsedUserMessage::new(user_message.clone());
-                let context = InferenceChainContext::new(
+                let mut context = InferenceChainContext::new(
                     db.clone(),
                     vector_fs.clone(),
                     full_job.clone(),
@@ -239,6 +279,7 @@ impl GenericInferenceChain {
                     tool_router.clone(),
                     sheet_manager.clone(),
                 );
+                context.update_inference_params(inference_params.clone());
 
                 // 6) Call workflow or tooling
                 // Find the ShinkaiTool that has a tool with the function name
@@ -267,12 +308,12 @@ impl GenericInferenceChain {
 
                 // 7) Call LLM again with the response (for formatting)
                 filled_prompt = JobPromptGenerator::generic_inference_prompt(
-                    None, // TODO: connect later on
+                    system_prompt.clone(),
                     None, // TODO: connect later on
                     user_message.clone(),
                     ret_nodes.clone(),
                     summary_node_text.clone(),
-                    Some(full_job.step_history.clone()),
+                    Some(step_history.clone()),
                     tools.clone(),
                     Some(function_response),
                 );
@@ -29,11 +29,11 @@ pub struct CronExecutionState {
 impl JobManager {
     #[async_recursion]
     pub async fn image_analysis_chain(
-        _db: Arc<ShinkaiDB>,
+        db: Arc<ShinkaiDB>,
         full_job: Job,
         agent_found: Option<SerializedLLMProvider>,
         _execution_context: HashMap<String, String>,
-        _user_profile: Option<ShinkaiName>,
+        user_profile: Option<ShinkaiName>,
         task: String,
         image: String,
         iteration_count: u64,
@@ -56,8 +56,19 @@ impl JobManager {
             Ok(name) => Some(name),
             Err(_) => None,
         };
-        let response_json =
-            JobManager::inference_with_llm_provider(agent.clone(), image_prompt, inbox_name, ws_manager_trait).await?;
+        let inference_params = match &user_profile {
+            Some(user_profile) => JobManager::job_inference_params(&db, &full_job, user_profile, None),
+            None => full_job.config.inference_params.clone(),
+        };
+        let response_json = JobManager::inference_with_llm_provider(
+            agent.clone(),
+            image_prompt,
+            inference_params,
+            inbox_name,
+            ws_manager_trait,
+            Some(db),
+        )
+        .await?;
         let mut new_execution_context = HashMap::new();
 
         new_execution_context.insert(
@@ -1,5 +1,6 @@
 use super::generic_chain::generic_inference_chain::GenericInferenceChain;
 use super::inference_chain_trait::{InferenceChain, InferenceChainContext, InferenceChainResult};
+use super::multi_agent_chain::multi_agent_inference_chain::MultiAgentInferenceChain;
 use super::sheet_ui_chain::sheet_ui_inference_chain::SheetUIInferenceChain;
 use crate::db::ShinkaiDB;
 use crate::llm_provider::error::LLMProviderError;
@@ -43,8 +44,15 @@ impl JobManager {
         let max_tokens_in_prompt = ModelCapabilitiesManager::get_max_input_tokens(&llm_provider.model);
         let parsed_user_message = ParsedUserMessage::new(job_message.content.to_string());
 
+        let inference_params = Self::job_inference_params(
+            &db,
+            &full_job,
+            &user_profile,
+            job_message.inference_params.as_ref(),
+        );
+
         // Create the inference chain context
-        let chain_context = InferenceChainContext::new(
+        let mut chain_context = InferenceChainContext::new(
             db,
             vector_fs,
             full_job.clone(),
@@ -60,11 +68,15 @@ impl JobManager {
             tool_router.clone(),
             sheet_manager.clone(),
         );
+        chain_context.update_inference_params(inference_params);
 
         // Check for associated_ui and choose the appropriate chain
         if let Some(AssociatedUI::Sheet(sheet_string)) = &full_job.associated_ui {
             let mut sheet_ui_chain = SheetUIInferenceChain::new(chain_context, ws_manager_trait, sheet_string.clone());
             sheet_ui_chain.run_chain().await
+        } else if !full_job.config.agents.is_empty() {
+            let mut multi_agent_chain = MultiAgentInferenceChain::new(chain_context, ws_manager_trait);
+            multi_agent_chain.run_chain().await
         } else {
             let mut generic_chain = GenericInferenceChain::new(chain_context, ws_manager_trait);
             generic_chain.run_chain().await
@@ -1,4 +1,5 @@
 use crate::db::ShinkaiDB;
+use crate::llm_provider::execution::chains::multi_agent_chain::multi_agent_inference_chain::DelegationStep;
 use crate::llm_provider::execution::user_message_parser::ParsedUserMessage;
 use crate::llm_provider::providers::shared::openai::FunctionCall;
 use crate::llm_provider::{error::LLMProviderError, job::Job};
@@ -8,7 +9,9 @@ use crate::tools::tool_router::ToolRouter;
 use crate::vector_fs;
 use crate::vector_fs::vector_fs::VectorFS;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
 use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::SerializedLLMProvider;
 use shinkai_message_primitives::schemas::sheet;
 use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
@@ -65,6 +68,7 @@ pub trait InferenceChainContextTrait: Send + Sync {
     fn max_tokens_in_prompt(&self) -> usize;
     fn score_results(&self) -> &HashMap<String, ScoreResult>;
     fn raw_files(&self) -> &RawFiles;
+    fn inference_params(&self) -> &InferenceParams;
     fn ws_manager_trait(&self) -> Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>;
     fn tool_router(&self) -> Option<Arc<Mutex<ToolRouter>>>;
     fn sheet_manager(&self) -> Option<Arc<Mutex<SheetManager>>>;
@@ -143,6 +147,10 @@ impl InferenceChainContextTrait for InferenceChainContext {
         &self.raw_files
     }
 
+    fn inference_params(&self) -> &InferenceParams {
+        &self.inference_params
+    }
+
     fn ws_manager_trait(&self) -> Option<Arc<Mutex<dyn WSUpdateHandler + Send>>> {
         self.ws_manager_trait.clone()
     }
@@ -178,6 +186,8 @@ pub struct InferenceChainContext {
     pub max_tokens_in_prompt: usize,
     pub score_results: HashMap<String, ScoreResult>,
     pub raw_files: RawFiles,
+    /// Sampling of the inferences: the job's params with the overrides of the message being processed
+    pub inference_params: InferenceParams,
     pub ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
     pub tool_router: Option<Arc<Mutex<ToolRouter>>>,
     pub sheet_manager: Option<Arc<Mutex<SheetManager>>>,
@@ -215,6 +225,7 @@ impl InferenceChainContext {
             max_tokens_in_prompt,
             score_results,
             raw_files: None,
+            inference_params: InferenceParams::default(),
             ws_manager_trait,
             tool_router,
             sheet_manager,
@@ -230,6 +241,11 @@ impl InferenceChainContext {
     pub fn update_raw_files(&mut self, new_raw_files: RawFiles) {
         self.raw_files = new_raw_files;
     }
+
+    /// Updates the inference params used by the chain
+    pub fn update_inference_params(&mut self, new_inference_params: InferenceParams) {
+        self.inference_params = new_inference_params;
+    }
 }
 
 impl fmt::Debug for InferenceChainContext {
@@ -248,6 +264,7 @@ impl fmt::Debug for InferenceChainContext {
             .field("max_tokens_in_prompt", &self.max_tokens_in_prompt)
             .field("score_results", &self.score_results)
             .field("raw_files", &self.raw_files)
+            .field("inference_params", &self.inference_params)
             .field("ws_manager_trait", &self.ws_manager_trait.is_some())
             .field("tool_router", &self.tool_router.is_some())
             .field("sheet_manager", &self.sheet_manager.is_some())
@@ -259,6 +276,8 @@ impl fmt::Debug for InferenceChainContext {
 pub struct InferenceChainResult {
     pub response: String,
     pub new_job_execution_context: HashMap<String, String>,
+    /// Sub-tasks the agents handed to each other while answering (only for multi-agent jobs)
+    pub delegation_trace: Vec<DelegationStep>,
 }
 
 impl InferenceChainResult {
@@ -266,9 +285,15 @@ impl InferenceChainResult {
         Self {
             response,
             new_job_execution_context,
+            delegation_trace: vec![],
         }
     }
 
+    pub fn with_delegation_trace(mut self, delegation_trace: Vec<DelegationStep>) -> Self {
+        self.delegation_trace = delegation_trace;
+        self
+    }
+
     pub fn new_empty_execution_context(response: String) -> Self {
         Self::new(response, HashMap::new())
     }
@@ -299,6 +324,9 @@ pub struct LLMInferenceResponse {
     pub response_string: String,
     pub function_call: Option<FunctionCall>,
     pub json: JsonValue,
+    /// Tokens consumed by the inference. Set by the backend when the provider reports it,
+    /// otherwise estimated by `LLMProvider::inference`.
+    pub usage: Option<LLMTokenUsage>,
 }
 
 impl LLMInferenceResponse {
@@ -307,8 +335,37 @@ impl LLMInferenceResponse {
             response_string: original_response_string,
             json,
             function_call,
+            usage: None,
         }
     }
+
+    pub fn with_usage(mut self, usage: LLMTokenUsage) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+}
+
+/// Prompt and completion token counts of an inference
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LLMTokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// True if the provider didn't report the counts and they were estimated locally
+    pub estimated: bool,
+}
+
+impl LLMTokenUsage {
+    pub fn new(prompt_tokens: u64, completion_tokens: u64) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            estimated: false,
+        }
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
 }
 
 impl InferenceChainContextTrait for Box<dyn InferenceChainContextTrait> {
@@ -376,6 +433,10 @@ impl InferenceChainContextTrait for Box<dyn InferenceChainContextTrait> {
         (**self).raw_files()
     }
 
+    fn inference_params(&self) -> &InferenceParams {
+        (**self).inference_params()
+    }
+
     fn ws_manager_trait(&self) -> Option<Arc<Mutex<dyn WSUpdateHandler + Send>>> {
         (**self).ws_manager_trait()
     }
@@ -403,6 +464,7 @@ pub struct MockInferenceChainContext {
     pub max_tokens_in_prompt: usize,
     pub score_results: HashMap<String, ScoreResult>,
     pub raw_files: RawFiles,
+    pub inference_params: InferenceParams,
     pub db: Option<Arc<ShinkaiDB>>,
     pub vector_fs: Option<Arc<VectorFS>>,
 }
@@ -431,6 +493,7 @@ impl MockInferenceChainContext {
             max_tokens_in_prompt,
             score_results,
             raw_files,
+            inference_params: InferenceParams::default(),
             db,
             vector_fs,
         }
@@ -453,6 +516,7 @@ impl Default for MockInferenceChainContext {
             max_tokens_in_prompt: 1000,
             score_results: HashMap::new(),
             raw_files: None,
+            inference_params: InferenceParams::default(),
             db: None,
             vector_fs: None,
         }
@@ -524,6 +588,10 @@ impl InferenceChainContextTrait for MockInferenceChainContext {
         &self.raw_files
     }
 
+    fn inference_params(&self) -> &InferenceParams {
+        &self.inference_params
+    }
+
     fn ws_manager_trait(&self) -> Option<Arc<Mutex<dyn WSUpdateHandler + Send>>> {
         None
     }
@@ -552,6 +620,7 @@ impl Clone for MockInferenceChainContext {
             max_tokens_in_prompt: self.max_tokens_in_prompt,
             score_results: self.score_results.clone(),
             raw_files: self.raw_files.clone(),
+            inference_params: self.inference_params.clone(),
             db: self.db.clone(),
             vector_fs: self.vector_fs.clone(),
         }
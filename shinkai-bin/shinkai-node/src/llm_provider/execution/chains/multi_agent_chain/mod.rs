@@ -0,0 +1,2 @@
+pub mod multi_agent_inference_chain;
+pub mod multi_agent_prompts;
@@ -0,0 +1,548 @@
+use crate::llm_provider::error::LLMProviderError;
+use crate::llm_provider::execution::chains::inference_chain_trait::{
+    InferenceChain, InferenceChainContext, InferenceChainContextTrait, InferenceChainResult,
+};
+use crate::llm_provider::execution::prompts::prompts::JobPromptGenerator;
+use crate::llm_provider::job::JobStepResult;
+use crate::llm_provider::job_manager::JobManager;
+use crate::llm_provider::providers::shared::openai::{FunctionCall, FunctionCallResponse};
+use crate::managers::model_capabilities_manager::ModelCapabilitiesManager;
+use crate::network::ws_manager::WSUpdateHandler;
+use crate::tools::shinkai_tool::ShinkaiTool;
+use async_recursion::async_recursion;
+use async_trait::async_trait;
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use lazy_static::lazy_static;
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use shinkai_message_primitives::schemas::inbox_name::InboxName;
+use shinkai_message_primitives::schemas::llm_providers::customized_agent::CustomizedAgent;
+use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::SerializedLLMProvider;
+use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
+use std::collections::HashMap;
+use std::fmt;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Max number of llm calls of an agent for a task, if the agent doesn't set it
+pub const DEFAULT_AGENT_MAX_ITER: u32 = 10;
+/// How deep delegations can go (the lead delegating to a coworker is depth 1)
+pub const MAX_DELEGATION_DEPTH: usize = 3;
+/// Max number of agents whose rate limiter is kept in memory
+const MAX_AGENT_RATE_LIMITERS: usize = 1024;
+
+lazy_static! {
+    /// Rate limiters of the agents with a `max_rpm`, by job and agent (see `JobManager::agent_rate_limiter`),
+    /// so the limit holds across the messages of a job and not only within one
+    static ref AGENT_RATE_LIMITERS: std::sync::Mutex<LruCache<String, Arc<DefaultDirectRateLimiter>>> =
+        std::sync::Mutex::new(LruCache::new(MAX_AGENT_RATE_LIMITERS));
+}
+
+/// A sub-task delegated by an agent to a coworker, with the delegations the coworker made in turn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DelegationStep {
+    /// Role of the coworker doing the sub-task
+    pub agent: String,
+    /// Role of the agent that delegated it
+    pub delegated_by: String,
+    pub task: String,
+    pub answer: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    pub steps: Vec<DelegationStep>,
+}
+
+impl DelegationStep {
+    /// Job inbox messages showing a delegation trace, in the order the delegations were made.
+    /// Nested delegations are quoted one level deeper than the delegation they're part of.
+    pub fn inbox_messages(trace: &[DelegationStep]) -> Vec<String> {
+        let mut messages = Vec::new();
+        Self::add_inbox_messages(trace, 1, &mut messages);
+        messages
+    }
+
+    fn add_inbox_messages(trace: &[DelegationStep], depth: usize, messages: &mut Vec<String>) {
+        for step in trace {
+            let outcome = match (&step.answer, &step.error) {
+                (Some(answer), _) => answer.clone(),
+                (None, Some(error)) => format!("Failed: {}", error),
+                (None, None) => String::new(),
+            };
+            let message = format!(
+                "**{}** delegated to **{}**: {}\n\n{}",
+                step.delegated_by, step.agent, step.task, outcome
+            );
+            let quote = "> ".repeat(depth);
+            messages.push(
+                message
+                    .lines()
+                    .map(|line| format!("{}{}", quote, line).trim_end().to_string())
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            );
+            Self::add_inbox_messages(&step.steps, depth + 1, messages);
+        }
+    }
+
+    /// What the delegating agent reads back
+    fn result(&self) -> String {
+        match (&self.answer, &self.error) {
+            (Some(answer), _) => answer.clone(),
+            (None, Some(error)) => format!("(failed to complete the task: {})", error),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// What an agent did so far for its current task, replayed in its prompt
+#[derive(Debug, Clone)]
+pub enum AgentScratchpadEntry {
+    Delegation {
+        /// The raw decision of the agent
+        decision: String,
+        coworker: String,
+        result: String,
+    },
+    ToolCall(FunctionCallResponse),
+}
+
+/// Structured answer of an agent that has coworkers
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum AgentDecision {
+    Delegate { coworker: String, task: String },
+    FinalAnswer { answer: String },
+}
+
+/// Answers the messages of jobs configured with agents (see `JobConfig::agents`).
+/// The first agent leads: it gets the user message and the job's history, and it can delegate
+/// sub-tasks to the other agents (if it's allowed to), who can delegate in turn.
+pub struct MultiAgentInferenceChain {
+    pub context: InferenceChainContext,
+    pub ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+}
+
+impl fmt::Debug for MultiAgentInferenceChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiAgentInferenceChain")
+            .field("context", &self.context)
+            .field("ws_manager_trait", &self.ws_manager_trait.is_some())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl InferenceChain for MultiAgentInferenceChain {
+    fn chain_id() -> String {
+        "multi_agent_inference_chain".to_string()
+    }
+
+    fn chain_context(&mut self) -> &mut dyn InferenceChainContextTrait {
+        &mut self.context
+    }
+
+    async fn run_chain(&mut self) -> Result<InferenceChainResult, LLMProviderError> {
+        if self.context.full_job.config.agents.is_empty() {
+            return Err(LLMProviderError::LLMProviderMissingCapabilities(
+                "The job has no agents".to_string(),
+            ));
+        }
+
+        let team = AgentTeam::new(self.context.clone());
+        let (answer, trace) = team
+            .run_agent(
+                0,
+                self.context.user_message.original_user_message_string.to_string(),
+                vec![0],
+                Some(self.context.full_job.step_history.clone()),
+            )
+            .await?;
+        let job_execution_context = self.context.execution_context.clone();
+        Ok(InferenceChainResult::new(answer, job_execution_context).with_delegation_trace(trace))
+    }
+}
+
+impl MultiAgentInferenceChain {
+    pub fn new(
+        context: InferenceChainContext,
+        ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> Self {
+        Self {
+            context,
+            ws_manager_trait,
+        }
+    }
+}
+
+/// The agents of a job working on a message
+struct AgentTeam {
+    context: InferenceChainContext,
+    agents: Vec<CustomizedAgent>,
+    /// By agent index, for the agents with a `max_rpm`
+    rate_limiters: HashMap<usize, Arc<DefaultDirectRateLimiter>>,
+}
+
+impl AgentTeam {
+    fn new(context: InferenceChainContext) -> Self {
+        let agents = context.full_job.config.agents.clone();
+        let rate_limiters = agents
+            .iter()
+            .enumerate()
+            .filter_map(|(index, agent)| {
+                let rate_limiter = JobManager::agent_rate_limiter(&context.full_job.job_id, index, agent)?;
+                Some((index, rate_limiter))
+            })
+            .collect();
+        Self {
+            context,
+            agents,
+            rate_limiters,
+        }
+    }
+
+    /// Runs an agent on a task, within its max execution time.
+    /// The path holds the agents (indexes) the task went through, the agent included.
+    /// Returns the answer of the agent and the delegations it made.
+    #[async_recursion]
+    async fn run_agent(
+        &self,
+        index: usize,
+        task: String,
+        path: Vec<usize>,
+        job_step_history: Option<Vec<JobStepResult>>,
+    ) -> Result<(String, Vec<DelegationStep>), LLMProviderError> {
+        let agent = &self.agents[index];
+        match agent.max_execution_time {
+            Some(max_execution_time) => tokio::time::timeout(
+                Duration::from_secs(max_execution_time as u64),
+                self.run_agent_steps(index, task, path, job_step_history),
+            )
+            .await
+            .map_err(|_| {
+                LLMProviderError::AgentExecutionTimeout(format!(
+                    "{} didn't complete its task in {} seconds",
+                    agent.role, max_execution_time
+                ))
+            })?,
+            None => self.run_agent_steps(index, task, path, job_step_history).await,
+        }
+    }
+
+    async fn run_agent_steps(
+        &self,
+        index: usize,
+        task: String,
+        path: Vec<usize>,
+        job_step_history: Option<Vec<JobStepResult>>,
+    ) -> Result<(String, Vec<DelegationStep>), LLMProviderError> {
+        let agent = &self.agents[index];
+        let llm_provider = match &agent.llm_provider_id {
+            Some(llm_provider_id) => self
+                .context
+                .db
+                .get_llm_provider(llm_provider_id, &self.context.user_profile)?
+                .ok_or(LLMProviderError::LLMProviderNotFound)?,
+            None => self.context.llm_provider.clone(),
+        };
+        let max_iter = agent.max_iter.unwrap_or(DEFAULT_AGENT_MAX_ITER).max(1);
+        let tools = self.agent_tools(agent, &llm_provider).await;
+        let coworkers = self.coworkers(index, &path);
+        let inbox_name = InboxName::get_job_inbox_name_from_params(self.context.full_job.job_id.clone()).ok();
+
+        let mut scratchpad = Vec::new();
+        let mut trace = Vec::new();
+        for iteration in 1..=max_iter {
+            // The last llm call has to answer: no coworkers nor tools to turn to
+            let last_iteration = iteration == max_iter;
+            let (iteration_coworkers, iteration_tools) = if last_iteration {
+                (vec![], vec![])
+            } else {
+                (coworkers.clone(), tools.clone())
+            };
+            let prompt = JobPromptGenerator::agent_prompt(
+                agent,
+                &iteration_coworkers
+                    .iter()
+                    .map(|coworker| &self.agents[*coworker])
+                    .collect::<Vec<&CustomizedAgent>>(),
+                task.clone(),
+                job_step_history.clone(),
+                &scratchpad,
+                iteration_tools.clone(),
+            );
+
+            if let Some(rate_limiter) = self.rate_limiters.get(&index) {
+                rate_limiter.until_ready().await;
+            }
+            Self::log(
+                agent,
+                &format!(
+                    "{} (iteration {}/{}) working on: {}",
+                    agent.role, iteration, max_iter, task
+                ),
+            );
+            let response = JobManager::inference_with_llm_provider(
+                llm_provider.clone(),
+                prompt,
+                self.context.inference_params.clone(),
+                inbox_name.clone(),
+                None,
+                Some(self.context.db.clone()),
+            )
+            .await?;
+
+            if let Some(function_call) = response.function_call {
+                Self::log(agent, &format!("{} calls the tool {}", agent.role, function_call.name));
+                let function_response = self.call_tool(function_call, &iteration_tools, &llm_provider).await?;
+                scratchpad.push(AgentScratchpadEntry::ToolCall(function_response));
+                continue;
+            }
+
+            let decision = if iteration_coworkers.is_empty() {
+                None
+            } else {
+                serde_json::from_value::<AgentDecision>(response.json.clone()).ok()
+            };
+            match decision {
+                Some(AgentDecision::Delegate {
+                    coworker,
+                    task: sub_task,
+                }) => {
+                    Self::log(
+                        agent,
+                        &format!("{} delegates to {}: {}", agent.role, coworker, sub_task),
+                    );
+                    let step = match iteration_coworkers
+                        .iter()
+                        .find(|coworker_index| self.agents[**coworker_index].role.eq_ignore_ascii_case(coworker.trim()))
+                    {
+                        Some(coworker_index) => self.delegate(index, *coworker_index, sub_task, &path).await,
+                        None => DelegationStep {
+                            agent: coworker.clone(),
+                            delegated_by: agent.role.clone(),
+                            task: sub_task,
+                            answer: None,
+                            error: Some(format!("There is no coworker with the role {}", coworker)),
+                            duration_ms: 0,
+                            steps: vec![],
+                        },
+                    };
+                    scratchpad.push(AgentScratchpadEntry::Delegation {
+                        decision: response.response_string,
+                        coworker: step.agent.clone(),
+                        result: step.result(),
+                    });
+                    trace.push(step);
+                }
+                Some(AgentDecision::FinalAnswer { answer }) => return Ok((answer, trace)),
+                // Answers that aren't a decision are taken as the final answer
+                None => return Ok((response.response_string, trace)),
+            }
+        }
+
+        Err(LLMProviderError::MaxIterationsReached(format!(
+            "{} reached its maximum number of iterations ({})",
+            agent.role, max_iter
+        )))
+    }
+
+    /// Runs a coworker on a sub-task. Failures are recorded in the step so the delegating agent can go on.
+    async fn delegate(&self, from: usize, to: usize, task: String, path: &[usize]) -> DelegationStep {
+        let start = Instant::now();
+        let mut path = path.to_vec();
+        path.push(to);
+        let (answer, error, steps) = match self.run_agent(to, task.clone(), path, None).await {
+            Ok((answer, steps)) => (Some(answer), None, steps),
+            Err(e) => (None, Some(e.to_string()), vec![]),
+        };
+        DelegationStep {
+            agent: self.agents[to].role.clone(),
+            delegated_by: self.agents[from].role.clone(),
+            task,
+            answer,
+            error,
+            duration_ms: start.elapsed().as_millis() as u64,
+            steps,
+        }
+    }
+
+    /// Agents an agent can delegate to: the ones the task didn't go through yet, while the max depth isn't reached
+    fn coworkers(&self, index: usize, path: &[usize]) -> Vec<usize> {
+        if !self.agents[index].allow_delegation || path.len() > MAX_DELEGATION_DEPTH {
+            return vec![];
+        }
+        (0..self.agents.len()).filter(|agent| !path.contains(agent)).collect()
+    }
+
+    /// Tools of the agent, if its llm provider supports tool calls
+    async fn agent_tools(&self, agent: &CustomizedAgent, llm_provider: &SerializedLLMProvider) -> Vec<ShinkaiTool> {
+        let mut tools = vec![];
+        if agent.tools.is_empty() || !ModelCapabilitiesManager::supports_tool_calls(&llm_provider.model) {
+            return tools;
+        }
+        if let Some(tool_router) = &self.context.tool_router {
+            let tool_router = tool_router.lock().await;
+            for tool_name in &agent.tools {
+                if let Ok(Some(tool)) = tool_router.get_tool_by_name(tool_name).await {
+                    tools.push(tool);
+                }
+            }
+        }
+        tools
+    }
+
+    async fn call_tool(
+        &self,
+        function_call: FunctionCall,
+        tools: &[ShinkaiTool],
+        llm_provider: &SerializedLLMProvider,
+    ) -> Result<FunctionCallResponse, LLMProviderError> {
+        let tool = tools
+            .iter()
+            .find(|tool| tool.name() == function_call.name)
+            .ok_or_else(|| LLMProviderError::FunctionNotFound(function_call.name.clone()))?;
+        let tool_router = self
+            .context
+            .tool_router
+            .as_ref()
+            .ok_or(LLMProviderError::ToolRouterNotFound)?;
+
+        let mut context = self.context.clone();
+        context.llm_provider = llm_provider.clone();
+        tool_router
+            .lock()
+            .await
+            .call_function(function_call, &context, tool)
+            .await
+    }
+
+    fn log(agent: &CustomizedAgent, message: &str) {
+        let level = if agent.verbose {
+            ShinkaiLogLevel::Info
+        } else {
+            ShinkaiLogLevel::Debug
+        };
+        shinkai_log(ShinkaiLogOption::JobExecution, level, message);
+    }
+}
+
+impl JobManager {
+    /// Rate limiter of an agent of the job, shared by the messages of the job (None if the agent has no `max_rpm`).
+    /// Changing the role or the `max_rpm` of the agent starts a new limiter.
+    fn agent_rate_limiter(
+        job_id: &str,
+        index: usize,
+        agent: &CustomizedAgent,
+    ) -> Option<Arc<DefaultDirectRateLimiter>> {
+        let max_rpm = NonZeroU32::new(agent.max_rpm?)?;
+        let key = format!("{}:{}:{}:{}", job_id, index, agent.role, max_rpm);
+        let mut rate_limiters = AGENT_RATE_LIMITERS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(rate_limiter) = rate_limiters.get(&key) {
+            return Some(rate_limiter.clone());
+        }
+        let rate_limiter = Arc::new(RateLimiter::direct(Quota::per_minute(max_rpm)));
+        rate_limiters.put(key, rate_limiter.clone());
+        Some(rate_limiter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(agent: &str, delegated_by: &str, answer: Option<&str>, steps: Vec<DelegationStep>) -> DelegationStep {
+        DelegationStep {
+            agent: agent.to_string(),
+            delegated_by: delegated_by.to_string(),
+            task: format!("Task for {}", agent),
+            answer: answer.map(|answer| answer.to_string()),
+            error: answer.is_none().then(|| "timed out".to_string()),
+            duration_ms: 10,
+            steps,
+        }
+    }
+
+    #[test]
+    fn test_agent_decisions() {
+        let decision: AgentDecision =
+            serde_json::from_str(r#"{"action": "delegate", "coworker": "Researcher", "task": "Find the capital"}"#)
+                .unwrap();
+        assert_eq!(
+            decision,
+            AgentDecision::Delegate {
+                coworker: "Researcher".to_string(),
+                task: "Find the capital".to_string()
+            }
+        );
+
+        let decision: AgentDecision = serde_json::from_str(r#"{"action": "final_answer", "answer": "Lima"}"#).unwrap();
+        assert_eq!(
+            decision,
+            AgentDecision::FinalAnswer {
+                answer: "Lima".to_string()
+            }
+        );
+
+        assert!(serde_json::from_str::<AgentDecision>(r#"{"action": "delegate", "task": "No coworker"}"#).is_err());
+    }
+
+    #[test]
+    fn test_delegation_trace_inbox_messages() {
+        let trace = vec![
+            step(
+                "Researcher",
+                "Writer",
+                Some("Lima is the capital\nof Peru"),
+                vec![step("Fact Checker", "Researcher", None, vec![])],
+            ),
+            step("Editor", "Writer", Some("Looks good"), vec![]),
+        ];
+
+        assert_eq!(
+            DelegationStep::inbox_messages(&trace),
+            vec![
+                "> **Writer** delegated to **Researcher**: Task for Researcher\n>\n> Lima is the capital\n> of Peru"
+                    .to_string(),
+                "> > **Researcher** delegated to **Fact Checker**: Task for Fact Checker\n> >\n> > Failed: timed out"
+                    .to_string(),
+                "> **Writer** delegated to **Editor**: Task for Editor\n>\n> Looks good".to_string(),
+            ]
+        );
+        assert_eq!(trace[0].steps[0].result(), "(failed to complete the task: timed out)");
+    }
+
+    #[test]
+    fn test_agent_rate_limiters_are_kept_per_job_and_agent() {
+        let agent = |max_rpm| {
+            CustomizedAgent::new(
+                "Researcher".to_string(),
+                "Research".to_string(),
+                "".to_string(),
+                None,
+                vec![],
+                None,
+                None,
+                max_rpm,
+                None,
+                false,
+                false,
+                None,
+            )
+        };
+
+        assert!(JobManager::agent_rate_limiter("job_rpm_1", 0, &agent(None)).is_none());
+        let rate_limiter = JobManager::agent_rate_limiter("job_rpm_1", 0, &agent(Some(2))).unwrap();
+        // The next message of the job gets the same limiter
+        assert!(Arc::ptr_eq(
+            &rate_limiter,
+            &JobManager::agent_rate_limiter("job_rpm_1", 0, &agent(Some(2))).unwrap()
+        ));
+        // Other jobs, agents or limits don't share it
+        for (job_id, index, max_rpm) in [("job_rpm_2", 0, 2), ("job_rpm_1", 1, 2), ("job_rpm_1", 0, 3)] {
+            let other = JobManager::agent_rate_limiter(job_id, index, &agent(Some(max_rpm))).unwrap();
+            assert!(!Arc::ptr_eq(&rate_limiter, &other));
+        }
+    }
+}
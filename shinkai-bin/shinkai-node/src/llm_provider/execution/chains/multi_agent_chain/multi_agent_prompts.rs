@@ -0,0 +1,86 @@
+use super::super::super::prompts::prompts::{JobPromptGenerator, Prompt};
+use super::multi_agent_inference_chain::AgentScratchpadEntry;
+use crate::llm_provider::execution::structured_output::ResponseSchema;
+use crate::llm_provider::{execution::prompts::subprompts::SubPromptType, job::JobStepResult};
+use crate::tools::shinkai_tool::ShinkaiTool;
+use serde_json::json;
+use shinkai_message_primitives::schemas::llm_providers::customized_agent::CustomizedAgent;
+
+impl JobPromptGenerator {
+    /// Prompt of an agent working on a task. Agents with coworkers answer with a JSON decision:
+    /// either delegating a sub-task to a coworker or giving their final answer.
+    /// The scratchpad holds what the agent did so far for this task (delegations and tool calls).
+    pub fn agent_prompt(
+        agent: &CustomizedAgent,
+        coworkers: &[&CustomizedAgent],
+        task: String,
+        job_step_history: Option<Vec<JobStepResult>>,
+        scratchpad: &[AgentScratchpadEntry],
+        tools: Vec<ShinkaiTool>,
+    ) -> Prompt {
+        let mut prompt = Prompt::new();
+
+        prompt.add_content(
+            format!("You are {}. {}\nYour goal: {}", agent.role, agent.backstory, agent.goal),
+            SubPromptType::System,
+            98,
+        );
+
+        if !coworkers.is_empty() {
+            let coworkers_list = coworkers
+                .iter()
+                .map(|coworker| format!("- {}: {}", coworker.role, coworker.goal))
+                .collect::<Vec<String>>()
+                .join("\n");
+            prompt.add_content(
+                format!(
+                    "You work with these coworkers:\n{}\nWhen part of the task fits the role of a coworker better than yours, delegate it to them, explaining everything they need to know as they don't see your conversation. Answer with {{\"action\": \"delegate\", \"coworker\": <role of the coworker>, \"task\": <the sub-task>}} to delegate, or with {{\"action\": \"final_answer\", \"answer\": <your complete answer>}} once you can answer.",
+                    coworkers_list
+                ),
+                SubPromptType::System,
+                98,
+            );
+            prompt.set_response_schema(Some(ResponseSchema::new(json!({
+                "type": "object",
+                "properties": {
+                    "action": { "type": "string", "enum": ["delegate", "final_answer"] },
+                    "coworker": { "type": "string" },
+                    "task": { "type": "string" },
+                    "answer": { "type": "string" }
+                },
+                "required": ["action"]
+            }))));
+        }
+
+        if let Some(step_history) = job_step_history {
+            prompt.add_step_history(step_history, 97);
+        }
+
+        for tool in &tools {
+            if let Ok(tool_content) = tool.json_function_call_format() {
+                prompt.add_tool(tool_content, SubPromptType::AvailableTool, 98);
+            }
+        }
+
+        prompt.add_content(task, SubPromptType::User, 100);
+
+        for entry in scratchpad {
+            match entry {
+                AgentScratchpadEntry::Delegation {
+                    decision,
+                    coworker,
+                    result,
+                } => {
+                    prompt.add_content(decision.clone(), SubPromptType::Assistant, 100);
+                    prompt.add_content(format!("{} answered:\n{}", coworker, result), SubPromptType::User, 100);
+                }
+                AgentScratchpadEntry::ToolCall(function_response) => {
+                    prompt.add_function_call(function_response.function_call.clone(), 100);
+                    prompt.add_function_call_response(function_response.clone(), 100);
+                }
+            }
+        }
+
+        prompt
+    }
+}
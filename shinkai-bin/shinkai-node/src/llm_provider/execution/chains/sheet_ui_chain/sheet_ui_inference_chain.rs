@@ -9,15 +9,15 @@ use crate::llm_provider::execution::user_message_parser::ParsedUserMessage;
 use crate::llm_provider::job::{Job, JobLike};
 use crate::llm_provider::job_manager::JobManager;
 use crate::llm_provider::providers::shared::openai::FunctionCallResponse;
+use crate::managers::model_capabilities_manager::ModelCapabilitiesManager;
 use crate::managers::sheet_manager::SheetManager;
 use crate::network::ws_manager::WSUpdateHandler;
 use crate::tools::tool_router::ToolRouter;
 use crate::vector_fs::vector_fs::VectorFS;
 use async_trait::async_trait;
 use shinkai_message_primitives::schemas::inbox_name::InboxName;
-use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{
-    LLMProviderInterface, SerializedLLMProvider,
-};
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
+use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::SerializedLLMProvider;
 use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
 use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
 use shinkai_vector_resources::embedding_generator::RemoteEmbeddingGenerator;
@@ -27,7 +27,6 @@ use std::fmt;
 use std::result::Result::Ok;
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::Mutex;
-use tokio::task;
 use tracing::instrument;
 
 #[derive(Clone)]
@@ -68,6 +67,7 @@ impl InferenceChain for SheetUIInferenceChain {
             self.context.user_profile.clone(),
             self.context.max_iterations,
             self.context.max_tokens_in_prompt,
+            self.context.inference_params.clone(),
             self.ws_manager_trait.clone(),
             self.context.tool_router.clone(),
             self.context.sheet_manager.clone(),
@@ -107,6 +107,7 @@ impl SheetUIInferenceChain {
         user_profile: ShinkaiName,
         max_iterations: u64,
         max_tokens_in_prompt: usize,
+        inference_params: InferenceParams,
         ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
         tool_router: Option<Arc<Mutex<ToolRouter>>>,
         sheet_manager: Option<Arc<Mutex<SheetManager>>>,
@@ -139,7 +140,7 @@ impl SheetUIInferenceChain {
         let mut ret_nodes: Vec<RetrievedNode> = vec![];
         let mut summary_node_text = None;
         if !scope_is_empty {
-            let (ret, summary) = JobManager::keyword_chained_job_scope_vector_search(
+            let (ret, summary) = JobManager::job_scope_search(
                 db.clone(),
                 vector_fs.clone(),
                 full_job.scope(),
@@ -148,6 +149,7 @@ impl SheetUIInferenceChain {
                 generator.clone(),
                 20,
                 max_tokens_in_prompt,
+                full_job.config.retrieval_mode,
             )
             .await?;
             ret_nodes = ret;
@@ -155,9 +157,9 @@ impl SheetUIInferenceChain {
         }
 
         // 2) Vector search for tooling / workflows if the workflow / tooling scope isn't empty
-        // Only for OpenAI right now
+        // Only for the providers with native tool calling
         let mut tools = vec![];
-        if let LLMProviderInterface::OpenAI(_openai) = &llm_provider.model.clone() {
+        if ModelCapabilitiesManager::supports_tool_calls(&llm_provider.model) {
             tools.extend(SheetRustFunctions::sheet_rust_fn());
 
             if let Some(tool_router) = &tool_router {
@@ -212,8 +214,10 @@ impl SheetUIInferenceChain {
             let response_res = JobManager::inference_with_llm_provider(
                 llm_provider.clone(),
                 filled_prompt.clone(),
+                inference_params.clone(),
                 inbox_name,
                 ws_manager_trait.clone(),
+                Some(db.clone()),
             )
             .await;
 
@@ -263,18 +267,13 @@ impl SheetUIInferenceChain {
                         ));
                     }
 
-                    let handle = task::spawn(async move { function(sheet_manager_clone, sheet_id_clone, args).await });
-
-                    let response = match handle.await {
-                        Ok(Ok(response)) => response,
-                        Ok(Err(e)) => {
+                    // Awaited in place (not spawned) so it stops if the job gets interrupted
+                    let response = match function(sheet_manager_clone, sheet_id_clone, args).await {
+                        Ok(response) => response,
+                        Err(e) => {
                             eprintln!("Error calling function: {:?}", e);
                             return Err(LLMProviderError::FunctionExecutionError(e));
                         }
-                        Err(e) => {
-                            eprintln!("Task join error: {:?}", e);
-                            return Err(LLMProviderError::FunctionExecutionError(e.to_string()));
-                        }
                     };
 
                     FunctionCallResponse {
@@ -283,7 +282,7 @@ impl SheetUIInferenceChain {
                     }
                 } else {
                     let parsed_message = ParsedUserMessage::new(user_message.clone());
-                    let context = InferenceChainContext::new(
+                    let mut context = InferenceChainContext::new(
                         db.clone(),
                         vector_fs.clone(),
                         full_job.clone(),
@@ -299,6 +298,7 @@ impl SheetUIInferenceChain {
                         tool_router.clone(),
                         sheet_manager.clone(),
                     );
+                    context.update_inference_params(inference_params.clone());
                     
                     // JS or workflow tool
                     match tool_router
@@ -0,0 +1,293 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use shinkai_message_primitives::schemas::job_config::{HistoryStrategy, JobConfig};
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
+use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::SerializedLLMProvider;
+use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
+use shinkai_vector_resources::embedding_generator::{EmbeddingGenerator, RemoteEmbeddingGenerator};
+
+use crate::db::ShinkaiDB;
+use crate::llm_provider::error::LLMProviderError;
+use crate::llm_provider::job::JobStepResult;
+use crate::llm_provider::job_manager::JobManager;
+use crate::managers::model_capabilities_manager::ModelCapabilitiesManager;
+
+use super::prompts::prompts::Prompt;
+use super::prompts::subprompts::{SubPrompt, SubPromptType};
+
+/// Execution context key holding the summary of the oldest steps of the history
+pub const HISTORY_SUMMARY_KEY: &str = "history_summary";
+/// Execution context key holding how many steps (from the start of the history) the summary covers
+pub const HISTORY_SUMMARY_STEPS_KEY: &str = "history_summary_steps";
+
+/// Number of most recent steps that are always kept as they are
+const MIN_RECENT_STEPS: usize = 2;
+
+/// Estimated number of tokens the step takes in a prompt
+pub fn step_tokens(step: &JobStepResult) -> usize {
+    step.get_result_prompt()
+        .map(|prompt| {
+            prompt
+                .sub_prompts
+                .iter()
+                .map(|sub_prompt| sub_prompt.count_tokens_as_completion_message())
+                .sum()
+        })
+        .unwrap_or_default()
+}
+
+fn step_text(step: &JobStepResult) -> String {
+    format!(
+        "User: {}\nAssistant: {}",
+        step.get_latest_user_message_string().unwrap_or_default(),
+        step.get_latest_assistant_message_string().unwrap_or_default()
+    )
+}
+
+fn summary_sub_prompt(summary: &str) -> SubPrompt {
+    SubPrompt::Content(
+        SubPromptType::ExtraContext,
+        format!("Summary of the earlier conversation: {}", summary),
+        97,
+    )
+}
+
+/// Step holding the summary of the earlier conversation, added to the prompt (as extra context) in place of the
+/// summarized steps
+pub fn summary_step(summary: &str) -> JobStepResult {
+    let mut prompt = Prompt::new();
+    prompt.add_sub_prompt(summary_sub_prompt(summary));
+    let mut step = JobStepResult::new();
+    step.add_new_step_revision(prompt);
+    step
+}
+
+/// Returns the summary stored in the execution context and the number of steps it covers
+pub fn stored_history_summary(
+    execution_context: &HashMap<String, String>,
+    history_len: usize,
+) -> (Option<String>, usize) {
+    match (
+        execution_context.get(HISTORY_SUMMARY_KEY),
+        execution_context
+            .get(HISTORY_SUMMARY_STEPS_KEY)
+            .and_then(|steps| steps.parse::<usize>().ok()),
+    ) {
+        (Some(summary), Some(steps)) => (Some(summary.clone()), steps.min(history_len)),
+        _ => (None, 0),
+    }
+}
+
+/// Number of oldest steps to summarize so the history fits in the budget (always keeping the most recent ones)
+pub fn steps_to_summarize(step_tokens: &[usize], summary_tokens: usize, budget: usize) -> usize {
+    let max_steps = step_tokens.len().saturating_sub(MIN_RECENT_STEPS);
+    let mut total = summary_tokens + step_tokens.iter().sum::<usize>();
+    let mut steps = 0;
+    while total > budget && steps < max_steps {
+        total -= step_tokens[steps];
+        steps += 1;
+    }
+    steps
+}
+
+/// Indices (in order) of the steps to keep within the budget: the most recent ones, up to half of the budget,
+/// and then the older ones with the highest relevance scores
+pub fn select_relevant_steps(step_tokens: &[usize], scores: &[f32], budget: usize) -> Vec<usize> {
+    let count = step_tokens.len();
+    let mut selected = vec![false; count];
+    let mut used = 0;
+
+    for index in (0..count).rev() {
+        if count - index > MIN_RECENT_STEPS && used + step_tokens[index] > budget / 2 {
+            break;
+        }
+        selected[index] = true;
+        used += step_tokens[index];
+    }
+
+    let mut older: Vec<usize> = (0..count).filter(|index| !selected[*index]).collect();
+    older.sort_by(|a, b| scores[*b].partial_cmp(&scores[*a]).unwrap_or(Ordering::Equal));
+    for index in older {
+        if used + step_tokens[index] <= budget {
+            selected[index] = true;
+            used += step_tokens[index];
+        }
+    }
+
+    (0..count).filter(|index| selected[*index]).collect()
+}
+
+impl JobManager {
+    /// Keeps the step history of a job within the share of the context window set in its config.
+    /// With the summarize strategy, the new summary is stored in the execution context.
+    /// If the strategy fails, the history is returned as it is (and gets truncated when generating the prompt).
+    #[allow(clippy::too_many_arguments)]
+    pub async fn manage_conversation_history(
+        db: Arc<ShinkaiDB>,
+        llm_provider: &SerializedLLMProvider,
+        config: &JobConfig,
+        step_history: Vec<JobStepResult>,
+        execution_context: &mut HashMap<String, String>,
+        user_message: &str,
+        generator: &RemoteEmbeddingGenerator,
+    ) -> Vec<JobStepResult> {
+        let max_input_tokens = ModelCapabilitiesManager::get_max_input_tokens(&llm_provider.model);
+        let budget = (max_input_tokens as f32 * config.history_threshold.clamp(0.0, 1.0)) as usize;
+
+        match config.history_strategy {
+            HistoryStrategy::Truncate => step_history,
+            HistoryStrategy::Summarize => {
+                let (mut summary, mut summarized_steps) =
+                    stored_history_summary(execution_context, step_history.len());
+                let tokens: Vec<usize> = step_history[summarized_steps..].iter().map(step_tokens).collect();
+                let summary_tokens = summary
+                    .as_deref()
+                    .map(|summary| summary_sub_prompt(summary).count_tokens_as_completion_message())
+                    .unwrap_or_default();
+
+                let new_steps = steps_to_summarize(&tokens, summary_tokens, budget);
+                if new_steps > 0 {
+                    let steps = &step_history[summarized_steps..summarized_steps + new_steps];
+                    match Self::summarize_history(db, llm_provider, summary.as_deref(), steps).await {
+                        Ok(new_summary) => {
+                            summarized_steps += new_steps;
+                            execution_context.insert(HISTORY_SUMMARY_KEY.to_string(), new_summary.clone());
+                            execution_context
+                                .insert(HISTORY_SUMMARY_STEPS_KEY.to_string(), summarized_steps.to_string());
+                            summary = Some(new_summary);
+                        }
+                        Err(e) => shinkai_log(
+                            ShinkaiLogOption::JobExecution,
+                            ShinkaiLogLevel::Error,
+                            &format!("Failed to summarize the conversation history: {}", e),
+                        ),
+                    }
+                }
+
+                let mut history = Vec::new();
+                if let Some(summary) = summary {
+                    history.push(summary_step(&summary));
+                }
+                history.extend(step_history.into_iter().skip(summarized_steps));
+                history
+            }
+            HistoryStrategy::Retrieval => {
+                let tokens: Vec<usize> = step_history.iter().map(step_tokens).collect();
+                if tokens.iter().sum::<usize>() <= budget {
+                    return step_history;
+                }
+
+                let mut texts = vec![user_message.to_string()];
+                texts.extend(step_history.iter().map(step_text));
+                let embeddings = match generator.generate_embeddings_default(&texts).await {
+                    Ok(embeddings) if embeddings.len() == texts.len() => embeddings,
+                    Ok(_) => return step_history,
+                    Err(e) => {
+                        shinkai_log(
+                            ShinkaiLogOption::JobExecution,
+                            ShinkaiLogLevel::Error,
+                            &format!("Failed to embed the conversation history: {}", e),
+                        );
+                        return step_history;
+                    }
+                };
+                let scores: Vec<f32> = embeddings[1..]
+                    .iter()
+                    .map(|embedding| embeddings[0].score_similarity(embedding))
+                    .collect();
+
+                let selected = select_relevant_steps(&tokens, &scores, budget);
+                step_history
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(index, _)| selected.contains(index))
+                    .map(|(_, step)| step)
+                    .collect()
+            }
+        }
+    }
+
+    /// Asks the llm provider to summarize the steps, extending the previous summary if any
+    async fn summarize_history(
+        db: Arc<ShinkaiDB>,
+        llm_provider: &SerializedLLMProvider,
+        previous_summary: Option<&str>,
+        steps: &[JobStepResult],
+    ) -> Result<String, LLMProviderError> {
+        let mut prompt = Prompt::new();
+        prompt.add_content(
+            "You summarize conversations between a user and an assistant. Keep every fact, name, number, preference and decision that could be needed later on, and leave out greetings and filler. Answer only with the summary.".to_string(),
+            SubPromptType::System,
+            100,
+        );
+
+        let mut content = String::new();
+        if let Some(previous_summary) = previous_summary {
+            content.push_str(&format!("Summary of the conversation so far:\n{}\n\n", previous_summary));
+        }
+        content.push_str("Conversation to summarize:\n");
+        for step in steps {
+            content.push_str(&step_text(step));
+            content.push_str("\n\n");
+        }
+        prompt.add_content(content, SubPromptType::User, 100);
+
+        // Summaries don't follow the sampling of the job
+        let response = Self::inference_with_llm_provider(
+            llm_provider.clone(),
+            prompt,
+            InferenceParams::default(),
+            None,
+            None,
+            Some(db),
+        )
+        .await?;
+        Ok(response.response_string.trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps_to_summarize() {
+        // Fits in the budget
+        assert_eq!(steps_to_summarize(&[10, 10, 10], 0, 30), 0);
+        // The oldest steps are summarized until it fits
+        assert_eq!(steps_to_summarize(&[10, 10, 10, 10], 5, 30), 2);
+        // The most recent steps are always kept
+        assert_eq!(steps_to_summarize(&[10, 10, 100, 100], 0, 30), 2);
+        assert_eq!(steps_to_summarize(&[100], 0, 30), 0);
+    }
+
+    #[test]
+    fn test_select_relevant_steps() {
+        let tokens = [10, 10, 10, 10, 10, 10];
+        let scores = [0.9, 0.1, 0.2, 0.8, 0.5, 0.5];
+        // The 2 most recent steps take half the budget, the most relevant older ones fill the rest
+        assert_eq!(select_relevant_steps(&tokens, &scores, 40), vec![0, 3, 4, 5]);
+        // Under budget everything is kept
+        assert_eq!(select_relevant_steps(&tokens, &scores, 60), vec![0, 1, 2, 3, 4, 5]);
+        // The most recent steps are kept even if they don't fit
+        assert_eq!(select_relevant_steps(&[50, 50], &[0.0, 0.0], 20), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_stored_history_summary() {
+        let mut execution_context = HashMap::new();
+        assert_eq!(stored_history_summary(&execution_context, 3), (None, 0));
+
+        execution_context.insert(HISTORY_SUMMARY_KEY.to_string(), "summary".to_string());
+        execution_context.insert(HISTORY_SUMMARY_STEPS_KEY.to_string(), "5".to_string());
+        assert_eq!(
+            stored_history_summary(&execution_context, 3),
+            (Some("summary".to_string()), 3)
+        );
+
+        let step = summary_step("summary");
+        assert_eq!(step.get_result_prompt().unwrap().sub_prompts, vec![summary_sub_prompt("summary")]);
+    }
+}
@@ -14,6 +14,7 @@ use crate::vector_fs::vector_fs::VectorFS;
 use ed25519_dalek::SigningKey;
 use shinkai_dsl::dsl_schemas::Workflow;
 use shinkai_dsl::parser::parse_workflow;
+use shinkai_message_primitives::schemas::inbox_name::InboxName;
 use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::SerializedLLMProvider;
 use shinkai_message_primitives::schemas::sheet::{self, WorkflowSheetJobData};
 use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::CallbackAction;
@@ -41,6 +42,9 @@ use tracing::instrument;
 
 use super::chains::dsl_chain::dsl_inference_chain::DslChain;
 use super::chains::inference_chain_trait::{InferenceChainContext, InferenceChainResult};
+use super::chains::multi_agent_chain::multi_agent_inference_chain::DelegationStep;
+use super::prompts::prompts::JobPromptGenerator;
+use super::structured_output::ResponseSchema;
 use super::user_message_parser::ParsedUserMessage;
 
 impl JobManager {
@@ -109,6 +113,19 @@ impl JobManager {
         )
         .unwrap();
 
+        // Stop here if the profile or the llm provider went over their usage quota
+        let quota_result = JobManager::enforce_usage_quotas(
+            db.clone(),
+            &job_id,
+            &user_profile,
+            llm_provider_found.as_ref(),
+            ws_manager.clone(),
+        )
+        .await;
+        if let Err(e) = quota_result {
+            return Self::handle_error(&db, Some(user_profile), &job_id, &identity_secret_key, e, ws_manager).await;
+        }
+
         // Note: remove later on. This code is for the meantime only while we add embeddings to tools so they can get added at the first Shinkai start
         {
             if let Some(tool_router) = tool_router.clone() {
@@ -298,7 +315,24 @@ impl JobManager {
         );
 
         // Setup initial data to get ready to call a specific inference chain
-        let prev_execution_context = full_job.execution_context.clone();
+        let mut full_job = full_job;
+        let mut prev_execution_context = full_job.execution_context.clone();
+        let exclude_from_memory = full_job.config.exclude_from_memory;
+
+        // Keep the conversation history within the context window of the llm provider
+        if let Some(llm_provider) = &llm_provider_found {
+            full_job.step_history = JobManager::manage_conversation_history(
+                db.clone(),
+                llm_provider,
+                &full_job.config,
+                std::mem::take(&mut full_job.step_history),
+                &mut prev_execution_context,
+                &job_message.content,
+                &generator,
+            )
+            .await;
+        }
+
         shinkai_log(
             ShinkaiLogOption::JobExecution,
             ShinkaiLogLevel::Debug,
@@ -349,6 +383,19 @@ impl JobManager {
             format!("process_inference_chain> shinkai_message: {:?}", shinkai_message).as_str(),
         );
 
+        // Remember the exchange in the profile's memory of past conversations
+        if let Some(tool_router) = &tool_router {
+            if !exclude_from_memory {
+                JobManager::remember_job_exchange(
+                    tool_router.clone(),
+                    profile_name.clone(),
+                    job_id.clone(),
+                    job_message.content.clone(),
+                    inference_response_content.to_string(),
+                );
+            }
+        }
+
         // Save response data to DB
         db.add_step_history(
             job_message.job_id.clone(),
@@ -356,6 +403,20 @@ impl JobManager {
             inference_response_content.to_string(),
             None,
         )?;
+        // The delegations between the job's agents show up in the inbox before the answer
+        // (marked as traces, so they aren't replayed as the history of the job)
+        for trace_message in DelegationStep::inbox_messages(&inference_response.delegation_trace) {
+            let trace_message = ShinkaiMessageBuilder::job_delegation_trace_from_llm_provider(
+                job_id.to_string(),
+                trace_message,
+                clone_signature_secret_key(&identity_secret_key),
+                user_profile.node_name.clone(),
+                user_profile.node_name.clone(),
+            )
+            .map_err(|e| LLMProviderError::ShinkaiMessageBuilderError(e.to_string()))?;
+            db.add_message_to_job_inbox(&job_message.job_id.clone(), &trace_message, None, ws_manager.clone())
+                .await?;
+        }
         db.add_message_to_job_inbox(&job_message.job_id.clone(), &shinkai_message, None, ws_manager)
             .await?;
         db.set_job_execution_context(job_message.job_id.clone(), new_execution_context, None)?;
@@ -489,6 +550,12 @@ impl JobManager {
         let parsed_user_message = ParsedUserMessage::new(message_content);
         let full_execution_context = full_job.execution_context.clone();
 
+        let inference_params = Self::job_inference_params(
+            &db,
+            &full_job,
+            &user_profile,
+            job_message.inference_params.as_ref(),
+        );
         let mut chain_context = InferenceChainContext::new(
             db.clone(),
             vector_fs.clone(),
@@ -505,6 +572,7 @@ impl JobManager {
             tool_router.clone(),
             sheet_manager.clone(),
         );
+        chain_context.update_inference_params(inference_params);
 
         // Process files
         {
@@ -533,6 +601,7 @@ impl JobManager {
 
         dsl_inference.add_inference_function();
         dsl_inference.add_inference_no_ws_function();
+        dsl_inference.add_structured_inference_function();
         dsl_inference.add_opinionated_inference_function();
         dsl_inference.add_opinionated_inference_no_ws_function();
         dsl_inference.add_multi_inference_function();
@@ -599,6 +668,21 @@ impl JobManager {
                 None
             };
 
+            let response_schema = match &sheet_job_data.col_definition.behavior {
+                sheet::ColumnBehavior::LLMCall {
+                    response_schema: Some(schema),
+                    response_schema_max_retries,
+                    ..
+                } => {
+                    let response_schema = ResponseSchema::new(schema.clone());
+                    Some(match response_schema_max_retries {
+                        Some(max_retries) => response_schema.with_max_retries(*max_retries),
+                        None => response_schema,
+                    })
+                }
+                _ => None,
+            };
+
             // Process the sheet job
             let inference_result = if let Some(workflow) = workflow {
                 Self::execute_workflow(
@@ -616,6 +700,38 @@ impl JobManager {
                     workflow,
                 )
                 .await?
+            } else if let Some(response_schema) = response_schema {
+                // Structured cells are answered directly, with the validated JSON as the cell value
+                let llm_provider = llm_provider_found.ok_or(LLMProviderError::LLMProviderNotFound)?;
+                let mut filled_prompt = JobPromptGenerator::generic_inference_prompt(
+                    None,
+                    None,
+                    input_string,
+                    vec![],
+                    None,
+                    None,
+                    vec![],
+                    None,
+                );
+                filled_prompt.set_response_schema(Some(response_schema));
+
+                let inference_params = Self::job_inference_params(
+                    &db,
+                    &full_job,
+                    &user_profile,
+                    job_message.inference_params.as_ref(),
+                );
+                let inbox_name = InboxName::get_job_inbox_name_from_params(full_job.job_id.clone()).ok();
+                let response = JobManager::inference_with_llm_provider(
+                    llm_provider,
+                    filled_prompt,
+                    inference_params,
+                    inbox_name,
+                    ws_manager.clone(),
+                    Some(db.clone()),
+                )
+                .await?;
+                InferenceChainResult::new_empty_execution_context(response.json.to_string())
             } else {
                 let mut job_message = job_message.clone();
                 job_message.content = input_string;
@@ -1,13 +1,18 @@
 use super::chains::inference_chain_trait::LLMInferenceResponse;
 use super::prompts::prompts::Prompt;
+use super::prompts::subprompts::SubPromptType;
 use crate::db::db_errors::ShinkaiDBError;
+use crate::db::db_token_usage::TokenUsageRecord;
 use crate::db::ShinkaiDB;
 use crate::llm_provider::error::LLMProviderError;
 use crate::llm_provider::job::Job;
 use crate::llm_provider::job_manager::JobManager;
-use crate::llm_provider::llm_provider::LLMProvider;
+use crate::llm_provider::llm_provider::{LLMProvider, LLMProviderAttempt};
+use crate::managers::model_capabilities_manager::ModelCapabilitiesManager;
 use crate::network::ws_manager::WSUpdateHandler;
+use chrono::Utc;
 use shinkai_message_primitives::schemas::inbox_name::InboxName;
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
 use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::SerializedLLMProvider;
 use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
 use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
@@ -17,22 +22,141 @@ use std::sync::Arc;
 
 impl JobManager {
     /// Inferences the Agent's LLM with the given prompt.
+    /// If a db is provided, the llm provider's fallbacks are resolved from it, the tokens used are recorded
+    /// and the attempts made are stored so they end up in the job's step history.
+    /// The db also backs the llm provider's response cache, unless the job is configured to bypass it.
+    /// If the prompt has a response schema, the response is validated against it (asking the LLM to fix
+    /// it up to the schema's `max_retries` times) and the validated value is returned as its `json`.
+    /// The inference params (e.g. the ones of the job) are used by the fallbacks too.
     pub async fn inference_with_llm_provider(
         llm_provider: SerializedLLMProvider,
         filled_prompt: Prompt,
+        inference_params: InferenceParams,
         inbox_name: Option<InboxName>,
         ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+        db: Option<Arc<ShinkaiDB>>,
     ) -> Result<LLMInferenceResponse, LLMProviderError> {
-        let llm_provider_cloned = llm_provider.clone();
-        let prompt_cloned = filled_prompt.clone();
+        let response_schema = match filled_prompt.response_schema.clone() {
+            Some(response_schema) => response_schema,
+            None => {
+                return Self::inference_with_llm_provider_once(
+                    llm_provider,
+                    filled_prompt,
+                    inference_params,
+                    inbox_name,
+                    ws_manager_trait,
+                    db,
+                )
+                .await
+            }
+        };
+
+        let mut prompt = filled_prompt;
+        if !ModelCapabilitiesManager::supports_response_schema(&llm_provider.model) {
+            prompt.add_content(response_schema.instructions(), SubPromptType::User, 100);
+        }
+
+        let mut ws_manager_trait = ws_manager_trait;
+        let mut retries = 0;
+        loop {
+            let mut response = Self::inference_with_llm_provider_once(
+                llm_provider.clone(),
+                prompt.clone(),
+                inference_params.clone(),
+                inbox_name.clone(),
+                ws_manager_trait.take(),
+                db.clone(),
+            )
+            .await?;
+            // Tool calls are answered before the final (structured) response
+            if response.function_call.is_some() {
+                return Ok(response);
+            }
+
+            let error = match response_schema.parse_response(&response.response_string) {
+                Ok(value) => {
+                    response.json = value;
+                    return Ok(response);
+                }
+                Err(error) => error,
+            };
+            if retries >= response_schema.max_retries {
+                return Err(LLMProviderError::InvalidStructuredOutput(error));
+            }
+            retries += 1;
+            shinkai_log(
+                ShinkaiLogOption::JobExecution,
+                ShinkaiLogLevel::Info,
+                format!("Invalid structured output ({}), asking the llm provider to fix it", error).as_str(),
+            );
 
-        let task_response = tokio::spawn(async move {
-            let llm_provider = LLMProvider::from_serialized_llm_provider(llm_provider_cloned);
-            llm_provider.inference(prompt_cloned, inbox_name, ws_manager_trait).await
-        })
-        .await;
+            // The repair attempts don't stream to the ws, as the client already received the invalid answer
+            prompt.add_content(response.response_string, SubPromptType::Assistant, 100);
+            prompt.add_content(response_schema.repair_instructions(&error), SubPromptType::User, 100);
+        }
+    }
 
-        let response = task_response?;
+    async fn inference_with_llm_provider_once(
+        llm_provider: SerializedLLMProvider,
+        filled_prompt: Prompt,
+        inference_params: InferenceParams,
+        inbox_name: Option<InboxName>,
+        ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+        db: Option<Arc<ShinkaiDB>>,
+    ) -> Result<LLMInferenceResponse, LLMProviderError> {
+        let fallbacks = match &db {
+            Some(db) => Self::fetch_fallback_llm_providers(db.clone(), &llm_provider),
+            None => vec![],
+        };
+        let job_id = match &inbox_name {
+            Some(InboxName::JobInbox { unique_id, .. }) => Some(unique_id.clone()),
+            _ => None,
+        };
+        let response_cache_db = match (&db, &job_id) {
+            (Some(db), Some(job_id)) => match db.get_job_config(job_id) {
+                Ok(config) if config.bypass_response_cache => None,
+                _ => Some(db.clone()),
+            },
+            (Some(db), None) => Some(db.clone()),
+            _ => None,
+        };
+        let inbox_name_string = inbox_name.as_ref().map(|inbox_name| inbox_name.to_string());
+
+        // Awaited in place (not spawned) so interrupting the job, which drops this future, also stops the
+        // request to the provider and its streaming to the ws
+        let (response, attempts) = LLMProvider::from_serialized_llm_provider(llm_provider.clone())
+            .with_fallbacks(fallbacks)
+            .with_response_cache_db(response_cache_db)
+            .with_inference_params(inference_params)
+            .inference_with_attempts(filled_prompt, inbox_name, ws_manager_trait)
+            .await;
+        // Cached responses didn't consume any token
+        let from_cache = attempts.last().map_or(false, |attempt| attempt.cached);
+        if let (Some(db), Ok(inference_response), false) = (&db, &response, from_cache) {
+            let record = Self::token_usage_record(
+                &llm_provider,
+                job_id.clone(),
+                inbox_name_string,
+                inference_response,
+                &attempts,
+            );
+            if let Err(e) = db.add_token_usage(&record) {
+                shinkai_log(
+                    ShinkaiLogOption::JobExecution,
+                    ShinkaiLogLevel::Error,
+                    format!("Failed to record token usage: {}", e).as_str(),
+                );
+            }
+        }
+        if let (Some(db), Some(job_id)) = (db, job_id) {
+            if let Err(e) = db.add_pending_llm_provider_attempts(&job_id, attempts) {
+                shinkai_log(
+                    ShinkaiLogOption::JobExecution,
+                    ShinkaiLogLevel::Error,
+                    format!("Failed to record llm provider attempts for job {}: {}", job_id, e).as_str(),
+                );
+            }
+        }
         shinkai_log(
             ShinkaiLogOption::JobExecution,
             ShinkaiLogLevel::Debug,
@@ -42,6 +166,93 @@ impl JobManager {
         response
     }
 
+    /// Builds the token usage record of an inference, attributed to the llm provider that answered
+    fn token_usage_record(
+        llm_provider: &SerializedLLMProvider,
+        job_id: Option<String>,
+        inbox_name: Option<String>,
+        response: &LLMInferenceResponse,
+        attempts: &[LLMProviderAttempt],
+    ) -> TokenUsageRecord {
+        let usage = response.usage.clone().unwrap_or_default();
+        let (llm_provider_id, model) = match attempts.iter().rev().find(|attempt| attempt.error.is_none()) {
+            Some(attempt) => (attempt.llm_provider_id.clone(), attempt.model.clone()),
+            None => (
+                llm_provider.id.clone(),
+                format!("{}:{}", llm_provider.model.provider_prefix(), llm_provider.model.model_type()),
+            ),
+        };
+
+        TokenUsageRecord {
+            job_id,
+            inbox_name,
+            profile: llm_provider
+                .full_identity_name
+                .extract_profile()
+                .ok()
+                .map(|profile| profile.full_name),
+            llm_provider_id,
+            model,
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            estimated: usage.estimated,
+            datetime: Utc::now(),
+        }
+    }
+
+    /// Resolves the fallback llm providers (in order) of the given llm provider.
+    /// Fallbacks that can't be found (or that point to the llm provider itself) are skipped.
+    fn fetch_fallback_llm_providers(db: Arc<ShinkaiDB>, llm_provider: &SerializedLLMProvider) -> Vec<LLMProvider> {
+        let profile = match llm_provider.full_identity_name.extract_profile() {
+            Ok(profile) => profile,
+            Err(_) => return vec![],
+        };
+
+        llm_provider
+            .fallback_llm_providers
+            .iter()
+            .filter(|fallback_id| **fallback_id != llm_provider.id)
+            .filter_map(|fallback_id| match db.get_llm_provider(fallback_id, &profile) {
+                Ok(Some(fallback)) => Some(LLMProvider::from_serialized_llm_provider(fallback)),
+                _ => {
+                    shinkai_log(
+                        ShinkaiLogOption::JobExecution,
+                        ShinkaiLogLevel::Error,
+                        format!("Fallback llm provider {} not found", fallback_id).as_str(),
+                    );
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Sampling of the inferences of a job: the params of the preset it was created from, overridden by the
+    /// job's ones, overridden by the ones of the message being processed (if any)
+    pub fn job_inference_params(
+        db: &ShinkaiDB,
+        full_job: &Job,
+        user_profile: &ShinkaiName,
+        message_inference_params: Option<&InferenceParams>,
+    ) -> InferenceParams {
+        let preset_inference_params = full_job
+            .config
+            .preset_id
+            .as_ref()
+            .and_then(|preset_id| db.get_job_preset(preset_id, user_profile).ok())
+            .map(|preset| InferenceParams {
+                temperature: preset.temperature,
+                max_tokens: preset.max_tokens,
+                ..Default::default()
+            })
+            .unwrap_or_default();
+
+        let inference_params = preset_inference_params.merged_with(&full_job.config.inference_params);
+        match message_inference_params {
+            Some(message_inference_params) => inference_params.merged_with(message_inference_params),
+            None => inference_params,
+        }
+    }
+
     /// Fetches boilerplate/relevant data required for a job to process a step
     /// it may return an outdated node_name
     pub async fn fetch_relevant_job_data(
@@ -73,3 +284,142 @@ impl JobManager {
         db.get_all_llm_providers()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm_provider::providers::mock::MockLLM;
+    use crate::llm_provider::providers::provider_registry::{LLMProviderBackend, LLMProviderRegistry};
+    use crate::managers::model_capabilities_manager::{ModelCapabilitiesManagerError, PromptResult};
+    use crate::network::ws_manager::{WSMessageType, WSMetadata};
+    use async_trait::async_trait;
+    use reqwest::Client;
+    use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{
+        CustomProvider, LLMProviderInterface,
+    };
+    use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::WSTopic;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Streams a chunk to the ws every 10ms, for a second
+    struct SlowStreamingBackend;
+
+    #[async_trait]
+    impl LLMProviderBackend for SlowStreamingBackend {
+        fn provider_prefix(&self) -> &str {
+            "slow_streaming"
+        }
+
+        async fn call_api(
+            &self,
+            _client: &Client,
+            _url: Option<&String>,
+            _api_key: Option<&String>,
+            _prompt: Prompt,
+            _inference_params: InferenceParams,
+            _model: LLMProviderInterface,
+            inbox_name: Option<InboxName>,
+            ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+        ) -> Result<LLMInferenceResponse, LLMProviderError> {
+            for index in 0..100 {
+                if let (Some(ws_manager), Some(inbox_name)) = (&ws_manager_trait, &inbox_name) {
+                    let metadata = WSMetadata {
+                        id: Some("slow".to_string()),
+                        is_done: false,
+                        done_reason: None,
+                        total_duration: None,
+                        eval_count: None,
+                    };
+                    ws_manager
+                        .lock()
+                        .await
+                        .queue_message(
+                            WSTopic::Inbox,
+                            inbox_name.to_string(),
+                            format!("chunk {}", index),
+                            WSMessageType::Metadata(metadata),
+                            true,
+                        )
+                        .await;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            Ok(LLMInferenceResponse::new(
+                "done".to_string(),
+                serde_json::json!({}),
+                None,
+            ))
+        }
+
+        fn prepare_messages(
+            &self,
+            _model: &LLMProviderInterface,
+            _prompt: Prompt,
+        ) -> Result<PromptResult, ModelCapabilitiesManagerError> {
+            Err(ModelCapabilitiesManagerError::NotImplemented(
+                "slow_streaming".to_string(),
+            ))
+        }
+    }
+
+    struct CountingWSManager {
+        chunks: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl WSUpdateHandler for CountingWSManager {
+        async fn queue_message(
+            &self,
+            _topic: WSTopic,
+            _subtopic: String,
+            _update: String,
+            _metadata: WSMessageType,
+            _is_stream: bool,
+        ) {
+            self.chunks.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_inference_stops_streaming() {
+        LLMProviderRegistry::register(Arc::new(SlowStreamingBackend));
+
+        let mut llm_provider = MockLLM::serialized_llm_provider(
+            "slow",
+            ShinkaiName::new("@@node.shinkai/main/agent/slow".to_string()).unwrap(),
+        );
+        llm_provider.model = LLMProviderInterface::Custom(CustomProvider {
+            provider: "slow_streaming".to_string(),
+            model_type: "test-model".to_string(),
+        });
+        let chunks = Arc::new(AtomicUsize::new(0));
+        let ws_manager: Arc<Mutex<dyn WSUpdateHandler + Send>> =
+            Arc::new(Mutex::new(CountingWSManager { chunks: chunks.clone() }));
+        let inbox_name = InboxName::get_job_inbox_name_from_params("job_slow".to_string()).unwrap();
+        let mut prompt = Prompt::new();
+        prompt.add_content("hi".to_string(), SubPromptType::User, 100);
+
+        // Interrupted the way the job queue does it: the processing future gets dropped
+        let (interrupt, interruption) = tokio::sync::oneshot::channel::<()>();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let _ = interrupt.send(());
+        });
+        tokio::select! {
+            _ = JobManager::inference_with_llm_provider(
+                llm_provider,
+                prompt,
+                InferenceParams::default(),
+                Some(inbox_name),
+                Some(ws_manager),
+                None,
+            ) => panic!("The inference should have been interrupted"),
+            _ = interruption => {}
+        }
+
+        let streamed = chunks.load(Ordering::SeqCst);
+        assert!(streamed > 0);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(chunks.load(Ordering::SeqCst), streamed);
+    }
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
+use tokio::sync::Mutex;
+
+use crate::lance_db::shinkai_lance_memory::MemoryEntry;
+use crate::llm_provider::job::JobStepResult;
+use crate::llm_provider::job_manager::JobManager;
+use crate::tools::tool_router::ToolRouter;
+
+use super::prompts::prompts::Prompt;
+use super::prompts::subprompts::SubPromptType;
+
+/// Number of memory entries added to the prompt
+pub const MEMORY_SEARCH_RESULTS: u64 = 5;
+
+/// Text remembered for an exchange of a job conversation
+pub fn memory_content(user_message: &str, response: &str) -> String {
+    format!("User: {}\nAssistant: {}", user_message, response)
+}
+
+/// Step holding the memory entries, added to the prompt (as extra context) right before the new message
+pub fn memory_step(entries: &[MemoryEntry]) -> Option<JobStepResult> {
+    if entries.is_empty() {
+        return None;
+    }
+
+    let mut content = "Relevant parts of previous conversations with the user:".to_string();
+    for entry in entries {
+        content.push_str(&format!("\n\n{}", entry.content));
+    }
+
+    let mut prompt = Prompt::new();
+    prompt.add_content(content, SubPromptType::ExtraContext, 97);
+    let mut step = JobStepResult::new();
+    step.add_new_step_revision(prompt);
+    Some(step)
+}
+
+impl JobManager {
+    /// Remembers an exchange of the job for the profile. It runs in the background and errors are only logged,
+    /// so a failure never affects the job.
+    pub fn remember_job_exchange(
+        tool_router: Arc<Mutex<ToolRouter>>,
+        profile: String,
+        job_id: String,
+        user_message: String,
+        response: String,
+    ) {
+        tokio::spawn(async move {
+            let lance_db = tool_router.lock().await.lance_db.clone();
+            let content = memory_content(&user_message, &response);
+            let result = lance_db.lock().await.add_memory_entry(&profile, &job_id, &content).await;
+            if let Err(e) = result {
+                shinkai_log(
+                    ShinkaiLogOption::JobExecution,
+                    ShinkaiLogLevel::Error,
+                    &format!("Failed to remember the message of job {}: {}", job_id, e),
+                );
+            }
+        });
+    }
+
+    /// Returns the entries of the profile's memory most relevant to the message, leaving out the ones of the job
+    pub async fn recall_job_memory(
+        tool_router: &Arc<Mutex<ToolRouter>>,
+        profile: &str,
+        job_id: &str,
+        user_message: &str,
+    ) -> Vec<MemoryEntry> {
+        let lance_db = tool_router.lock().await.lance_db.clone();
+        let result = lance_db
+            .lock()
+            .await
+            .memory_vector_search(profile, user_message, MEMORY_SEARCH_RESULTS, &[job_id.to_string()])
+            .await;
+
+        result.unwrap_or_else(|e| {
+            shinkai_log(
+                ShinkaiLogOption::JobExecution,
+                ShinkaiLogLevel::Error,
+                &format!("Failed to search the memory for job {}: {}", job_id, e),
+            );
+            Vec::new()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_step() {
+        assert!(memory_step(&[]).is_none());
+
+        let entry = MemoryEntry {
+            memory_id: "id".to_string(),
+            profile: "main".to_string(),
+            job_id: "job".to_string(),
+            content: memory_content("My dog is called Toby", "Nice name!"),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+        };
+        let step = memory_step(&[entry]).unwrap();
+        let prompt = step.get_result_prompt().unwrap();
+        assert_eq!(prompt.sub_prompts.len(), 1);
+        assert!(prompt.sub_prompts[0]
+            .get_content()
+            .ends_with("User: My dog is called Toby\nAssistant: Nice name!"));
+    }
+}
@@ -0,0 +1,217 @@
+use crate::db::db_token_usage::TokenUsageFilter;
+use crate::db::ShinkaiDB;
+use crate::llm_provider::error::LLMProviderError;
+use crate::llm_provider::job_manager::JobManager;
+use crate::network::ws_manager::{WSMessageType, WSUpdateHandler};
+use chrono::{DateTime, Utc};
+use shinkai_message_primitives::schemas::inbox_name::InboxName;
+use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{
+    LLMUsageQuota, SerializedLLMProvider,
+};
+use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
+use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::WSTopic;
+use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Limits reached by the usage of a profile or an llm provider
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageQuotaStatus {
+    pub soft_limits_reached: Vec<String>,
+    pub hard_limits_reached: Vec<String>,
+}
+
+impl JobManager {
+    /// Checks the usage quotas of the profile and of the llm provider before a job step is dispatched.
+    /// Reaching a soft limit sends a notification to the job inbox, reaching a hard limit fails the step.
+    pub async fn enforce_usage_quotas(
+        db: Arc<ShinkaiDB>,
+        job_id: &str,
+        user_profile: &ShinkaiName,
+        llm_provider: Option<&SerializedLLMProvider>,
+        ws_manager: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> Result<(), LLMProviderError> {
+        let status = Self::check_usage_quotas(db, user_profile, llm_provider, Utc::now())?;
+
+        if !status.hard_limits_reached.is_empty() {
+            return Err(LLMProviderError::UsageQuotaExceeded(
+                status.hard_limits_reached.join("; "),
+            ));
+        }
+
+        if !status.soft_limits_reached.is_empty() {
+            let message = status.soft_limits_reached.join("; ");
+            shinkai_log(
+                ShinkaiLogOption::JobExecution,
+                ShinkaiLogLevel::Info,
+                &format!("Soft usage limit reached for job {}: {}", job_id, message),
+            );
+
+            if let Some(ws_manager) = ws_manager {
+                let inbox_name = InboxName::get_job_inbox_name_from_params(job_id.to_string())?.to_string();
+                let notification = serde_json::json!({
+                    "notification": "UsageQuotaSoftLimitReached",
+                    "message": message,
+                });
+                ws_manager
+                    .lock()
+                    .await
+                    .queue_message(
+                        WSTopic::Inbox,
+                        inbox_name,
+                        notification.to_string(),
+                        WSMessageType::Notification,
+                        false,
+                    )
+                    .await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compares the usage of the current day and month against the quotas of the profile and the llm provider
+    pub fn check_usage_quotas(
+        db: Arc<ShinkaiDB>,
+        user_profile: &ShinkaiName,
+        llm_provider: Option<&SerializedLLMProvider>,
+        now: DateTime<Utc>,
+    ) -> Result<UsageQuotaStatus, LLMProviderError> {
+        let mut quotas: Vec<(String, LLMUsageQuota, TokenUsageFilter)> = Vec::new();
+
+        let profile = user_profile.full_name.clone();
+        if let Some(quota) = db.get_profile_usage_quota(&profile)? {
+            let filter = TokenUsageFilter {
+                profile: Some(profile.clone()),
+                ..Default::default()
+            };
+            quotas.push((format!("profile {}", profile), quota, filter));
+        }
+
+        if let Some(llm_provider) = llm_provider {
+            if let Some(quota) = llm_provider.usage_quota.clone() {
+                let filter = TokenUsageFilter {
+                    llm_provider_id: Some(llm_provider.id.clone()),
+                    ..Default::default()
+                };
+                quotas.push((format!("llm provider {}", llm_provider.id), quota, filter));
+            }
+        }
+
+        let mut status = UsageQuotaStatus::default();
+        for (scope, quota, filter) in quotas {
+            let usage = db.get_token_usage_counts(&filter, now)?;
+
+            let hard_limits = quota.hard_limits.exceeded_limits(&usage);
+            if !hard_limits.is_empty() {
+                status
+                    .hard_limits_reached
+                    .push(format!("{} reached {}", scope, hard_limits.join(", ")));
+            }
+
+            let soft_limits = quota.soft_limits.exceeded_limits(&usage);
+            if !soft_limits.is_empty() {
+                status
+                    .soft_limits_reached
+                    .push(format!("{} reached {}", scope, soft_limits.join(", ")));
+            }
+        }
+
+        Ok(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::db_token_usage::TokenUsageRecord;
+    use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{
+        LLMProviderInterface, LLMUsageLimits, OpenAI,
+    };
+    use shinkai_vector_resources::utils::hash_string;
+    use std::fs;
+    use std::path::Path;
+
+    fn setup() -> Arc<ShinkaiDB> {
+        let db_path = format!("db_tests/{}", hash_string("usage quota"));
+        let _ = fs::remove_dir_all(Path::new(&db_path));
+        Arc::new(ShinkaiDB::new(db_path.as_str()).unwrap())
+    }
+
+    fn llm_provider(usage_quota: Option<LLMUsageQuota>) -> SerializedLLMProvider {
+        SerializedLLMProvider {
+            id: "gpt_4o".to_string(),
+            full_identity_name: ShinkaiName::new("@@node.shinkai/main/agent/gpt_4o".to_string()).unwrap(),
+            perform_locally: false,
+            external_url: Some("https://api.openai.com".to_string()),
+            api_key: None,
+            model: LLMProviderInterface::OpenAI(OpenAI {
+                model_type: "gpt-4o".to_string(),
+            }),
+            toolkit_permissions: vec![],
+            storage_bucket_permissions: vec![],
+            allowed_message_senders: vec![],
+            retry_policy: Default::default(),
+            fallback_llm_providers: vec![],
+            usage_quota,
+            response_cache: None,
+        }
+    }
+
+    #[test]
+    fn test_check_usage_quotas() {
+        let db = setup();
+        let now = Utc::now();
+        for tokens in [400, 300] {
+            db.add_token_usage(&TokenUsageRecord {
+                job_id: Some("job_1".to_string()),
+                inbox_name: None,
+                profile: Some("@@node.shinkai/main".to_string()),
+                llm_provider_id: "gpt_4o".to_string(),
+                model: "openai:gpt-4o".to_string(),
+                prompt_tokens: tokens,
+                completion_tokens: 0,
+                estimated: false,
+                datetime: now,
+            })
+            .unwrap();
+        }
+        let profile = ShinkaiName::new("@@node.shinkai/main".to_string()).unwrap();
+
+        // No quotas at all
+        let status = JobManager::check_usage_quotas(db.clone(), &profile, Some(&llm_provider(None)), now).unwrap();
+        assert_eq!(status, UsageQuotaStatus::default());
+
+        // The profile went over its soft limit and the llm provider over its hard limit
+        let profile_quota = LLMUsageQuota {
+            soft_limits: LLMUsageLimits {
+                max_tokens_per_day: Some(500),
+                ..Default::default()
+            },
+            hard_limits: LLMUsageLimits {
+                max_tokens_per_day: Some(1000),
+                ..Default::default()
+            },
+        };
+        db.set_profile_usage_quota("@@node.shinkai/main", Some(&profile_quota))
+            .unwrap();
+        let provider_quota = LLMUsageQuota {
+            hard_limits: LLMUsageLimits {
+                max_requests_per_month: Some(2),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let status =
+            JobManager::check_usage_quotas(db.clone(), &profile, Some(&llm_provider(Some(provider_quota))), now)
+                .unwrap();
+        assert_eq!(
+            status.soft_limits_reached,
+            vec!["profile @@node.shinkai/main reached 500 tokens per day (used 700)"]
+        );
+        assert_eq!(
+            status.hard_limits_reached,
+            vec!["llm provider gpt_4o reached 2 requests per month (used 2)"]
+        );
+    }
+}
@@ -2,21 +2,293 @@ use crate::db::db_errors::ShinkaiDBError;
 use crate::db::ShinkaiDB;
 use crate::llm_provider::job_manager::JobManager;
 use crate::vector_fs::vector_fs::VectorFS;
+use chrono::{DateTime, Utc};
 use keyphrases::KeyPhraseExtractor;
+use lazy_static::lazy_static;
+use lru::LruCache;
+use shinkai_message_primitives::schemas::job_config::RetrievalMode;
 use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
 use shinkai_message_primitives::shinkai_utils::job_scope::JobScope;
 use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
 use shinkai_vector_resources::embedding_generator::{EmbeddingGenerator, RemoteEmbeddingGenerator};
 use shinkai_vector_resources::embeddings::Embedding;
+use shinkai_vector_resources::keyword_index::KeywordIndex;
 use shinkai_vector_resources::vector_resource::{
     deep_search_scores_average_out, BaseVectorResource, Node, ResultsMode, RetrievedNode, ScoringMode, TraversalMethod,
-    TraversalOption,
+    TraversalOption, VectorResource,
 };
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::result::Result::Ok;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+
+/// Max number of resources whose keyword index is kept in memory
+const MAX_CACHED_KEYWORD_INDEXES: usize = 256;
+
+lazy_static! {
+    /// Keyword indexes of the resources of job scopes, by resource and the time it was last saved
+    /// (see `keyword_index_cache_key`), so each resource is only read and tokenized once.
+    static ref KEYWORD_INDEX_CACHE: Mutex<LruCache<String, Arc<KeywordIndex>>> =
+        Mutex::new(LruCache::new(MAX_CACHED_KEYWORD_INDEXES));
+}
 
 impl JobManager {
+    /// Searches the job scope following the retrieval mode of the job.
+    /// Returns the search results and the description/summary text of the VR the highest scored node of the vector
+    /// search is from (none in keyword mode).
+    #[allow(clippy::too_many_arguments)]
+    pub async fn job_scope_search(
+        db: Arc<ShinkaiDB>,
+        vector_fs: Arc<VectorFS>,
+        job_scope: &JobScope,
+        query_text: String,
+        user_profile: &ShinkaiName,
+        generator: RemoteEmbeddingGenerator,
+        num_of_top_results: u64,
+        max_tokens_in_prompt: usize,
+        retrieval_mode: RetrievalMode,
+    ) -> Result<(Vec<RetrievedNode>, Option<String>), ShinkaiDBError> {
+        match retrieval_mode {
+            RetrievalMode::Vector => {
+                Self::keyword_chained_job_scope_vector_search(
+                    db,
+                    vector_fs,
+                    job_scope,
+                    query_text,
+                    user_profile,
+                    generator,
+                    num_of_top_results,
+                    max_tokens_in_prompt,
+                )
+                .await
+            }
+            RetrievalMode::Keyword => {
+                let keyword_indexes = Self::job_scope_keyword_indexes(vector_fs, job_scope, user_profile).await?;
+                let keyword_indexes: Vec<&KeywordIndex> = keyword_indexes.iter().map(|index| index.as_ref()).collect();
+                Ok((
+                    KeywordIndex::search_all(&keyword_indexes, &query_text, num_of_top_results),
+                    None,
+                ))
+            }
+            RetrievalMode::Hybrid {
+                vector_weight,
+                keyword_weight,
+            } => {
+                let query = generator.generate_embedding_default(&query_text).await?;
+                let (ret_groups, intro_hashmap) = JobManager::internal_job_scope_vector_search_groups(
+                    db,
+                    vector_fs.clone(),
+                    job_scope,
+                    query,
+                    query_text.clone(),
+                    num_of_top_results,
+                    user_profile,
+                    true,
+                    generator,
+                    max_tokens_in_prompt,
+                )
+                .await?;
+                let keyword_indexes = Self::job_scope_keyword_indexes(vector_fs, job_scope, user_profile).await?;
+                let keyword_indexes: Vec<&KeywordIndex> = keyword_indexes.iter().map(|index| index.as_ref()).collect();
+                let keyword_nodes = KeywordIndex::search_all(&keyword_indexes, &query_text, num_of_top_results);
+
+                let fused_groups = Self::fuse_hybrid_results(
+                    ret_groups,
+                    keyword_nodes,
+                    vector_weight,
+                    keyword_weight,
+                    num_of_top_results,
+                );
+                Ok(Self::with_intro_nodes(
+                    &fused_groups,
+                    &intro_hashmap,
+                    max_tokens_in_prompt,
+                ))
+            }
+        }
+    }
+
+    /// Merges the vector search groups with the keyword search hits using reciprocal rank fusion, keeping the top
+    /// `num_of_top_results` hits. A group is ranked by its highest scored node, a keyword hit inside a group counts
+    /// for that group. Keyword hits outside of every group are returned as groups of their own.
+    fn fuse_hybrid_results(
+        ret_groups: Vec<Vec<RetrievedNode>>,
+        keyword_nodes: Vec<RetrievedNode>,
+        vector_weight: f32,
+        keyword_weight: f32,
+        num_of_top_results: u64,
+    ) -> Vec<Vec<RetrievedNode>> {
+        let ret_groups: Vec<Vec<RetrievedNode>> = ret_groups.into_iter().filter(|group| !group.is_empty()).collect();
+
+        // The highest scored node of each group is the vector search hit the group was built around
+        let mut vector_hits: Vec<(usize, RetrievedNode)> = ret_groups
+            .iter()
+            .enumerate()
+            .filter_map(|(index, group)| {
+                group
+                    .iter()
+                    .max_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(Ordering::Equal))
+                    .map(|node| (index, node.clone()))
+            })
+            .collect();
+        vector_hits.sort_by(|(_, a), (_, b)| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+
+        let mut group_of_key: HashMap<String, usize> = HashMap::new();
+        for (index, group) in ret_groups.iter().enumerate() {
+            for node in group {
+                group_of_key.entry(KeywordIndex::node_key(node)).or_insert(index);
+            }
+        }
+        let hit_of_group: HashMap<usize, RetrievedNode> = vector_hits.iter().cloned().collect();
+
+        // Keyword hits inside a group stand for the group's vector hit, so both rankings count for the same group
+        let mut seen_keys = HashSet::new();
+        let keyword_hits: Vec<RetrievedNode> = keyword_nodes
+            .into_iter()
+            .map(|node| match group_of_key.get(&KeywordIndex::node_key(&node)) {
+                Some(index) => hit_of_group[index].clone(),
+                None => node,
+            })
+            .filter(|node| seen_keys.insert(KeywordIndex::node_key(node)))
+            .collect();
+
+        let vector_hits: Vec<RetrievedNode> = vector_hits.into_iter().map(|(_, node)| node).collect();
+        let fused_hits =
+            KeywordIndex::reciprocal_rank_fusion(vec![(vector_hits, vector_weight), (keyword_hits, keyword_weight)]);
+
+        // Re-attach the groups of the top hits
+        let mut fused_groups = Vec::new();
+        let mut added_groups = HashSet::new();
+        for hit in fused_hits.into_iter().take(num_of_top_results as usize) {
+            match group_of_key.get(&KeywordIndex::node_key(&hit)) {
+                Some(index) => {
+                    if added_groups.insert(*index) {
+                        fused_groups.push(ret_groups[*index].clone());
+                    }
+                }
+                None => fused_groups.push(vec![hit]),
+            }
+        }
+        fused_groups
+    }
+
+    /// Returns the keyword indexes over the text nodes of every Vector Resource in the job scope,
+    /// including the ones held in its VRPacks and (at any depth) in its folders.
+    /// Indexes are cached per resource and only rebuilt once the resource has been saved again.
+    pub async fn job_scope_keyword_indexes(
+        vector_fs: Arc<VectorFS>,
+        job_scope: &JobScope,
+        profile: &ShinkaiName,
+    ) -> Result<Vec<Arc<KeywordIndex>>, ShinkaiDBError> {
+        let mut keyword_indexes = Vec::new();
+
+        for entry in &job_scope.local_vrkai {
+            let resource = entry.vrkai.resource.as_trait_object();
+            let cache_key =
+                Self::keyword_index_cache_key(None, resource.reference_string(), resource.last_written_datetime());
+            let keyword_index = match Self::cached_keyword_index(&cache_key) {
+                Some(keyword_index) => keyword_index,
+                None => {
+                    let mut keyword_index = KeywordIndex::new();
+                    keyword_index.add_resource(&entry.vrkai.resource);
+                    Self::cache_keyword_index(cache_key, keyword_index)
+                }
+            };
+            keyword_indexes.push(keyword_index);
+        }
+
+        for entry in &job_scope.local_vrpack {
+            let resource = entry.vrpack.resource.as_trait_object();
+            let cache_key =
+                Self::keyword_index_cache_key(None, resource.reference_string(), resource.last_written_datetime());
+            let keyword_index = match Self::cached_keyword_index(&cache_key) {
+                Some(keyword_index) => keyword_index,
+                None => {
+                    let mut keyword_index = KeywordIndex::new();
+                    for (vrkai, _) in entry.vrpack.unpack_all_vrkais()? {
+                        keyword_index.add_resource(&vrkai.resource);
+                    }
+                    Self::cache_keyword_index(cache_key, keyword_index)
+                }
+            };
+            keyword_indexes.push(keyword_index);
+        }
+
+        let mut item_readers = Vec::new();
+        for folder in &job_scope.vector_fs_folders {
+            let reader = vector_fs
+                .new_reader(profile.clone(), folder.path.clone(), profile.clone())
+                .await?;
+            for item_path in vector_fs
+                .retrieve_all_item_paths_underneath_folder(reader.clone())
+                .await?
+            {
+                item_readers.push(reader.new_reader_copied_data(item_path, &vector_fs).await?);
+            }
+        }
+        for fs_item in &job_scope.vector_fs_items {
+            item_readers.push(
+                vector_fs
+                    .new_reader(profile.clone(), fs_item.path.clone(), profile.clone())
+                    .await?,
+            );
+        }
+
+        for item_reader in item_readers {
+            // The FSItem tells when its resource was last saved without loading the resource itself
+            let fs_item = vector_fs.retrieve_fs_entry(&item_reader).await?.as_item()?;
+            let cache_key = Self::keyword_index_cache_key(
+                Some(profile),
+                fs_item.vr_header.reference_string(),
+                fs_item.vr_last_saved_datetime,
+            );
+            let keyword_index = match Self::cached_keyword_index(&cache_key) {
+                Some(keyword_index) => keyword_index,
+                None => {
+                    let resource = vector_fs.retrieve_vector_resource(&item_reader).await?;
+                    let mut keyword_index = KeywordIndex::new();
+                    keyword_index.add_resource(&resource);
+                    Self::cache_keyword_index(cache_key, keyword_index)
+                }
+            };
+            keyword_indexes.push(keyword_index);
+        }
+
+        shinkai_log(
+            ShinkaiLogOption::JobExecution,
+            ShinkaiLogLevel::Info,
+            &format!(
+                "Num of nodes in the keyword indexes: {}",
+                keyword_indexes.iter().map(|index| index.len()).sum::<usize>()
+            ),
+        );
+        Ok(keyword_indexes)
+    }
+
+    /// Key of the keyword index of a resource in the cache. VectorFS resources are keyed per profile.
+    fn keyword_index_cache_key(
+        profile: Option<&ShinkaiName>,
+        reference_string: String,
+        saved_datetime: DateTime<Utc>,
+    ) -> String {
+        let profile = profile.map(|profile| profile.full_name.clone()).unwrap_or_default();
+        format!("{}::{}::{}", profile, reference_string, saved_datetime.to_rfc3339())
+    }
+
+    /// Fetches a keyword index from the cache
+    fn cached_keyword_index(cache_key: &str) -> Option<Arc<KeywordIndex>> {
+        let mut cache = KEYWORD_INDEX_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        cache.get(cache_key).cloned()
+    }
+
+    /// Adds a keyword index to the cache, evicting the least recently used one if full
+    fn cache_keyword_index(cache_key: String, keyword_index: KeywordIndex) -> Arc<KeywordIndex> {
+        let keyword_index = Arc::new(keyword_index);
+        let mut cache = KEYWORD_INDEX_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        cache.put(cache_key, keyword_index.clone());
+        keyword_index
+    }
+
     /// Performs multiple proximity vector searches within the job scope based on extracting keywords from the query text.
     /// Attempts to take at least 1 proximity group per keyword that is from a VR different than the highest scored node, to encourage wider diversity in results.
     /// Returns the search results and the description/summary text of the VR the highest scored retrieved node is from.
@@ -126,6 +398,20 @@ impl JobManager {
             }
         }
 
+        Ok(Self::with_intro_nodes(
+            &ret_groups,
+            &master_intro_hashmap,
+            max_tokens_in_prompt,
+        ))
+    }
+
+    /// Flattens the groups into the final list of nodes, with the intros of the VRs of the top groups at the front.
+    /// Returns the nodes and the intro text of the VR of the first group.
+    fn with_intro_nodes(
+        ret_groups: &[Vec<RetrievedNode>],
+        intro_hashmap: &HashMap<String, Vec<RetrievedNode>>,
+        max_tokens_in_prompt: usize,
+    ) -> (Vec<RetrievedNode>, Option<String>) {
         // For the top N groups, fetch their VRs' intros and include them at the front of the list
         // We do this by iterating in reverse order (ex. 5th, 4th, 3rd, 2nd, 1st), so highest scored VR intro will be at the top.
         let num_groups = Self::determine_num_groups_for_intro_fetch(max_tokens_in_prompt);
@@ -137,8 +423,7 @@ impl JobManager {
             // Take the first 5 groups and reverse the order
             if let Some(first_node) = group.first() {
                 // Take the first node of the group
-                if let Some(intro_text_nodes) = master_intro_hashmap.get(&first_node.resource_header.reference_string())
-                {
+                if let Some(intro_text_nodes) = intro_hashmap.get(&first_node.resource_header.reference_string()) {
                     if !added_intros.contains_key(&first_node.resource_header.reference_string()) {
                         // Add the intro nodes, and the ref string to added_intros
                         for intro_node in intro_text_nodes.iter() {
@@ -170,16 +455,7 @@ impl JobManager {
             }
         }
 
-        // println!(
-        //     "\n\n\nDone Vector Searching: {}\n------------------------------------------------",
-        //     query_text
-        // );
-
-        // for node in &final_nodes {
-        //     eprintln!("{:?} - {:?}\n", node.score as f32, node.format_for_prompt(3500));
-        // }
-
-        Ok((final_nodes, first_intro_text))
+        (final_nodes, first_intro_text)
     }
 
     /// Determines the number of grouped proximity retrieved nodes to check for intro fetching
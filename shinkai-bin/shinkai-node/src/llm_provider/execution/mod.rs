@@ -1,8 +1,12 @@
 pub mod chains;
+pub mod conversation_history;
 pub mod job_execution_core;
 pub mod job_execution_handlers;
 pub mod job_execution_helpers;
+pub mod job_memory;
 pub mod job_scope_helpers;
+pub mod job_usage_quota;
 pub mod job_vector_search;
 pub mod prompts;
+pub mod structured_output;
 pub mod user_message_parser;
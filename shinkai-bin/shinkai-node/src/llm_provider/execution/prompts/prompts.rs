@@ -1,6 +1,7 @@
 use crate::{
     llm_provider::{
         error::LLMProviderError,
+        execution::structured_output::ResponseSchema,
         job::JobStepResult,
         providers::shared::{
             llm_message::{DetailedFunctionCall, LlmMessage},
@@ -25,6 +26,9 @@ pub struct Prompt {
     pub lowest_priority: u8,
     /// The highest priority value held in sub_prompts. TODO: Make this a hashmap to make it more efficient for updating priorities.
     pub highest_priority: u8,
+    /// JSON Schema the response must follow, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_schema: Option<ResponseSchema>,
 }
 
 impl Default for Prompt {
@@ -39,9 +43,15 @@ impl Prompt {
             sub_prompts: Vec::new(),
             lowest_priority: 100,
             highest_priority: 0,
+            response_schema: None,
         }
     }
 
+    /// Sets the JSON Schema the response of the inference must follow
+    pub fn set_response_schema(&mut self, response_schema: Option<ResponseSchema>) {
+        self.response_schema = response_schema;
+    }
+
     pub fn to_json(&self) -> Result<String, LLMProviderError> {
         Ok(serde_json::to_string(self)?)
     }
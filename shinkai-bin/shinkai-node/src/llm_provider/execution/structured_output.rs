@@ -0,0 +1,298 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// Upper bound of the attempts to fix an invalid response, whatever the caller asks for
+pub const MAX_RESPONSE_SCHEMA_RETRIES: u32 = 10;
+
+fn default_max_retries() -> u32 {
+    2
+}
+
+/// JSON Schema the response of an inference must follow.
+/// Providers supporting it natively receive the schema, every response is also validated locally and,
+/// if invalid, the LLM is asked to fix it up to `max_retries` times.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResponseSchema {
+    pub schema: JsonValue,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+}
+
+impl ResponseSchema {
+    pub fn new(schema: JsonValue) -> Self {
+        Self {
+            schema,
+            max_retries: default_max_retries(),
+        }
+    }
+
+    /// Sets the attempts to fix an invalid response, capped at `MAX_RESPONSE_SCHEMA_RETRIES`
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries.min(MAX_RESPONSE_SCHEMA_RETRIES);
+        self
+    }
+
+    /// Instructions added to the prompt for the providers without native support
+    pub fn instructions(&self) -> String {
+        format!(
+            "Answer only with a JSON value (no markdown and no other text) that follows this JSON schema: {}",
+            self.schema
+        )
+    }
+
+    /// Instructions asking the LLM to fix its previous answer
+    pub fn repair_instructions(&self, error: &str) -> String {
+        format!(
+            "Your previous answer is not valid: {}. Answer again only with a JSON value (no markdown and no other text) that follows this JSON schema: {}",
+            error, self.schema
+        )
+    }
+
+    /// Extracts the JSON value from the response and validates it against the schema
+    pub fn parse_response(&self, response: &str) -> Result<JsonValue, String> {
+        let value = extract_json_value(response).ok_or_else(|| "it doesn't contain a JSON value".to_string())?;
+        let errors = validate_json_schema(&value, &self.schema);
+        if errors.is_empty() {
+            Ok(value)
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+}
+
+/// Parses the JSON value in a response, which may be wrapped in a markdown code block or surrounded by text
+pub fn extract_json_value(response: &str) -> Option<JsonValue> {
+    let response = response.trim();
+    if let Ok(value) = serde_json::from_str(response) {
+        return Some(value);
+    }
+
+    // Markdown code block
+    if let Some(start) = response.find("```") {
+        let block = &response[start + 3..];
+        let block = block.split_once('\n').map_or("", |(_, rest)| rest);
+        if let Some(end) = block.find("```") {
+            if let Ok(value) = serde_json::from_str(block[..end].trim()) {
+                return Some(value);
+            }
+        }
+    }
+
+    // Text around the JSON object or array
+    let start = response.find(['{', '['])?;
+    let closing = if response[start..].starts_with('{') { '}' } else { ']' };
+    let end = response.rfind(closing)?;
+    if end <= start {
+        return None;
+    }
+    serde_json::from_str(&response[start..=end]).ok()
+}
+
+/// Validates a value against a JSON Schema. Returns the errors found (empty if the value is valid).
+/// Supports the keywords used to describe structured outputs: `type`, `enum`, `const`, `properties`,
+/// `required`, `additionalProperties`, `items`, `minItems`, `maxItems`, `minLength`, `maxLength`,
+/// `minimum`, `maximum`, `anyOf`, `oneOf` and `allOf`. Other keywords are ignored.
+pub fn validate_json_schema(value: &JsonValue, schema: &JsonValue) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at(value, schema, "$", &mut errors);
+    errors
+}
+
+fn validate_at(value: &JsonValue, schema: &JsonValue, path: &str, errors: &mut Vec<String>) {
+    let schema = match schema {
+        JsonValue::Bool(true) => return,
+        JsonValue::Bool(false) => {
+            errors.push(format!("{} is not allowed", path));
+            return;
+        }
+        JsonValue::Object(schema) => schema,
+        _ => return,
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            JsonValue::String(t) => vec![t.as_str()],
+            JsonValue::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+            _ => vec![],
+        };
+        if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+            errors.push(format!("{} should be of type {}", path, types.join(" or ")));
+            return;
+        }
+    }
+
+    if let Some(JsonValue::Array(options)) = schema.get("enum") {
+        if !options.contains(value) {
+            errors.push(format!("{} should be one of {}", path, JsonValue::Array(options.clone())));
+        }
+    }
+    if let Some(constant) = schema.get("const") {
+        if constant != value {
+            errors.push(format!("{} should be {}", path, constant));
+        }
+    }
+
+    match value {
+        JsonValue::Object(object) => {
+            let properties = schema.get("properties").and_then(|p| p.as_object());
+            if let Some(JsonValue::Array(required)) = schema.get("required") {
+                for name in required.iter().filter_map(|r| r.as_str()) {
+                    if !object.contains_key(name) {
+                        errors.push(format!("{} is missing the required property {}", path, name));
+                    }
+                }
+            }
+            for (name, property_value) in object {
+                let property_path = format!("{}.{}", path, name);
+                match properties.and_then(|p| p.get(name)) {
+                    Some(property_schema) => validate_at(property_value, property_schema, &property_path, errors),
+                    None => {
+                        if let Some(additional) = schema.get("additionalProperties") {
+                            validate_at(property_value, additional, &property_path, errors);
+                        }
+                    }
+                }
+            }
+        }
+        JsonValue::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(|m| m.as_u64()) {
+                if (items.len() as u64) < min {
+                    errors.push(format!("{} should have at least {} items", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(|m| m.as_u64()) {
+                if (items.len() as u64) > max {
+                    errors.push(format!("{} should have at most {} items", path, max));
+                }
+            }
+            if let Some(items_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate_at(item, items_schema, &format!("{}[{}]", path, index), errors);
+                }
+            }
+        }
+        JsonValue::String(text) => {
+            let length = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(|m| m.as_u64()) {
+                if length < min {
+                    errors.push(format!("{} should have at least {} characters", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(|m| m.as_u64()) {
+                if length > max {
+                    errors.push(format!("{} should have at most {} characters", path, max));
+                }
+            }
+        }
+        JsonValue::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(|m| m.as_f64()) {
+                if number < min {
+                    errors.push(format!("{} should be at least {}", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(|m| m.as_f64()) {
+                if number > max {
+                    errors.push(format!("{} should be at most {}", path, max));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    if let Some(JsonValue::Array(all_of)) = schema.get("allOf") {
+        for sub_schema in all_of {
+            validate_at(value, sub_schema, path, errors);
+        }
+    }
+    if let Some(JsonValue::Array(any_of)) = schema.get("anyOf") {
+        if !any_of.iter().any(|s| validate_json_schema(value, s).is_empty()) {
+            errors.push(format!("{} doesn't match any of the allowed schemas", path));
+        }
+    }
+    if let Some(JsonValue::Array(one_of)) = schema.get("oneOf") {
+        let matches = one_of.iter().filter(|s| validate_json_schema(value, s).is_empty()).count();
+        if matches != 1 {
+            errors.push(format!("{} should match exactly one of the allowed schemas", path));
+        }
+    }
+}
+
+fn has_type(value: &JsonValue, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn person_schema() -> ResponseSchema {
+        ResponseSchema::new(json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "minLength": 1 },
+                "age": { "type": "integer", "minimum": 0 },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "role": { "enum": ["admin", "user"] }
+            },
+            "required": ["name", "age"],
+            "additionalProperties": false
+        }))
+    }
+
+    #[test]
+    fn test_parse_valid_responses() {
+        let schema = person_schema();
+        assert_eq!(
+            schema.parse_response(r#"{"name": "Alice", "age": 30}"#).unwrap(),
+            json!({"name": "Alice", "age": 30})
+        );
+
+        let wrapped = "Sure! Here it is:\n```json\n{\"name\": \"Bob\", \"age\": 4, \"tags\": [\"a\"]}\n```";
+        assert_eq!(schema.parse_response(wrapped).unwrap()["name"], "Bob");
+
+        let surrounded = "The answer is {\"name\": \"Carol\", \"age\": 5, \"role\": \"user\"} as requested";
+        assert_eq!(schema.parse_response(surrounded).unwrap()["role"], "user");
+    }
+
+    #[test]
+    fn test_max_retries() {
+        assert_eq!(person_schema().max_retries, 2);
+        assert_eq!(person_schema().with_max_retries(0).max_retries, 0);
+        assert_eq!(
+            person_schema().with_max_retries(u32::MAX).max_retries,
+            MAX_RESPONSE_SCHEMA_RETRIES
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_responses() {
+        let schema = person_schema();
+        assert!(schema.parse_response("I don't know").is_err());
+
+        let error = schema
+            .parse_response(r#"{"name": "", "age": -1, "tags": [1], "role": "root", "extra": true}"#)
+            .unwrap_err();
+        assert!(error.contains("$.name should have at least 1 characters"));
+        assert!(error.contains("$.age should be at least 0"));
+        assert!(error.contains("$.tags[0] should be of type string"));
+        assert!(error.contains("$.role should be one of"));
+        assert!(error.contains("$.extra is not allowed"));
+
+        let error = schema.parse_response(r#"{"name": "Dan", "age": 1.5}"#).unwrap_err();
+        assert_eq!(error, "$.age should be of type integer");
+
+        let error = schema.parse_response(r#"{"name": "Dan"}"#).unwrap_err();
+        assert_eq!(error, "$ is missing the required property age");
+    }
+}
@@ -1,6 +1,7 @@
 use super::execution::{prompts::{prompts::Prompt, subprompts::{SubPrompt, SubPromptType}}, user_message_parser::ParsedUserMessage};
+use super::llm_provider::LLMProviderAttempt;
 use serde::{Deserialize, Serialize};
-use shinkai_message_primitives::{schemas::inbox_name::InboxName, shinkai_message::shinkai_message_schemas::AssociatedUI, shinkai_utils::job_scope::JobScope};
+use shinkai_message_primitives::{schemas::{inbox_name::InboxName, job_config::JobConfig}, shinkai_message::shinkai_message_schemas::AssociatedUI, shinkai_utils::job_scope::JobScope};
 use std::collections::HashMap;
 
 pub trait JobLike: Send + Sync {
@@ -15,7 +16,7 @@ pub trait JobLike: Send + Sync {
 }
 
 // Todo: Add a persistent_context: String
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Job {
     /// Based on uuid
     pub job_id: String,
@@ -43,6 +44,18 @@ pub struct Job {
     pub execution_context: HashMap<String, String>,
     /// A link to the UI where the user can view the job e.g. Sheet UI
     pub associated_ui: Option<AssociatedUI>,
+    /// Per job settings (e.g. skipping the llm response cache)
+    pub config: JobConfig,
+}
+
+impl Job {
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
 }
 
 impl JobLike for Job {
@@ -90,6 +103,10 @@ pub struct JobStepResult {
     /// single step, meaning that if this list has more than one prompt, later ones denote
     /// edits which were made off of the original message.
     pub step_revisions: Vec<Prompt>,
+    /// Calls made to the llm providers while processing this step (retries and fallbacks included).
+    /// The last successful one is the provider that actually answered.
+    #[serde(default)]
+    pub llm_provider_attempts: Vec<LLMProviderAttempt>,
 }
 
 impl Default for JobStepResult {
@@ -104,6 +121,7 @@ impl JobStepResult {
         Self {
             initial_message_datetime: String::new(),
             step_revisions: Vec::new(),
+            llm_provider_attempts: Vec::new(),
         }
     }
 
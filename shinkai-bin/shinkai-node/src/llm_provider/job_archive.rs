@@ -0,0 +1,516 @@
+use std::collections::HashMap;
+
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use shinkai_message_primitives::{
+    schemas::{
+        inbox_name::InboxName, job_archive::SignedJobArchive, shinkai_name::ShinkaiName,
+        shinkai_time::ShinkaiStringTime,
+    },
+    shinkai_message::{
+        shinkai_message::{MessageBody, MessageData, ShinkaiMessage},
+        shinkai_message_schemas::{JobMessage, MessageSchemaType},
+    },
+    shinkai_utils::job_scope::JobScope,
+};
+
+use super::{
+    error::LLMProviderError,
+    job::{Job, JobStepResult},
+};
+use crate::{
+    db::{db_errors::ShinkaiDBError, ShinkaiDB},
+    vector_fs::vector_fs::VectorFS,
+};
+
+/// Version of the archive format. Archives of newer versions are rejected.
+pub const JOB_ARCHIVE_VERSION: u32 = 1;
+
+/// Everything needed to recreate a job on another node: the job (scope, execution context and config),
+/// the messages of its inbox with their step history and the files attached to them.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JobArchive {
+    pub version: u32,
+    /// Node the job was exported from
+    pub node_name: String,
+    pub exported_at: String,
+    /// The step history of the job is stored with the messages it belongs to
+    pub job: Job,
+    /// Custom name of the job inbox
+    #[serde(default)]
+    pub custom_name: Option<String>,
+    /// Messages of the active branch of the job inbox, oldest first
+    pub messages: Vec<JobArchiveMessage>,
+    #[serde(default)]
+    pub files_inboxes: Vec<JobArchiveFilesInbox>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JobArchiveMessage {
+    pub message: ShinkaiMessage,
+    #[serde(default)]
+    pub step_history: Vec<JobStepResult>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JobArchiveFilesInbox {
+    pub files_inbox: String,
+    pub files: Vec<JobArchiveFile>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JobArchiveFile {
+    pub name: String,
+    /// Base64 encoded content of the file
+    pub content: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JobImportResult {
+    pub job_id: String,
+    /// Paths of the VectorFS items and folders in the scope of the job that don't exist on this node
+    pub missing_scope_items: Vec<String>,
+}
+
+impl JobArchive {
+    /// Bundles a job of this node. Only the active branch of the conversation is exported.
+    pub fn export(
+        db: &ShinkaiDB,
+        vector_fs: &VectorFS,
+        job_id: &str,
+        node_name: &ShinkaiName,
+    ) -> Result<Self, LLMProviderError> {
+        let mut job = db.get_job(job_id)?;
+        job.step_history = Vec::new();
+        let inbox_name = job.conversation_inbox_name.to_string();
+        let custom_name = db.get_smart_inbox_name(&inbox_name)?;
+
+        let mut messages = Vec::new();
+        let mut files_inboxes: Vec<JobArchiveFilesInbox> = Vec::new();
+        // The first message of each path is the one of the active branch
+        for message in db
+            .get_last_messages_from_inbox(inbox_name, usize::MAX, None)?
+            .into_iter()
+            .filter_map(|path| path.into_iter().next())
+        {
+            let message_key = message.calculate_message_hash_for_pagination();
+            let step_history = db.get_message_step_history(&message_key)?;
+
+            let files_inbox = Self::job_message(&message)
+                .map(|job_message| job_message.files_inbox)
+                .unwrap_or_default();
+            if !files_inbox.is_empty() && !files_inboxes.iter().any(|inbox| inbox.files_inbox == files_inbox) {
+                let files = vector_fs
+                    .db
+                    .get_all_files_from_inbox(files_inbox.clone())?
+                    .into_iter()
+                    .map(|(name, content)| JobArchiveFile {
+                        name,
+                        content: base64::encode(content),
+                    })
+                    .collect();
+                files_inboxes.push(JobArchiveFilesInbox { files_inbox, files });
+            }
+
+            messages.push(JobArchiveMessage { message, step_history });
+        }
+
+        Ok(Self {
+            version: JOB_ARCHIVE_VERSION,
+            node_name: node_name.node_name.clone(),
+            exported_at: ShinkaiStringTime::generate_time_now(),
+            job,
+            custom_name,
+            messages,
+            files_inboxes,
+        })
+    }
+
+    /// Signs the archive with the identity key of the node
+    pub fn sign(&self, signing_key: &SigningKey) -> Result<SignedJobArchive, LLMProviderError> {
+        let archive = serde_json::to_string(self)?;
+        Ok(SignedJobArchive::sign(archive, signing_key))
+    }
+
+    /// Returns the name of the node that exported a signed archive, without checking the signature.
+    /// Used to find the key the archive must be signed with.
+    pub fn exporting_node_name(signed_archive: &SignedJobArchive) -> Result<String, LLMProviderError> {
+        #[derive(Deserialize)]
+        struct ArchiveOrigin {
+            node_name: String,
+        }
+
+        serde_json::from_str::<ArchiveOrigin>(&signed_archive.archive)
+            .map(|origin| origin.node_name)
+            .map_err(|e| LLMProviderError::InvalidJobArchive(e.to_string()))
+    }
+
+    /// Reads a signed archive, checking its version and that it's signed by `expected_signer`,
+    /// the identity key of the node that exported it
+    pub fn from_signed(
+        signed_archive: &SignedJobArchive,
+        expected_signer: &VerifyingKey,
+    ) -> Result<Self, LLMProviderError> {
+        signed_archive
+            .verify(expected_signer)
+            .map_err(|e| LLMProviderError::InvalidJobArchive(e.to_string()))?;
+        let archive: JobArchive = serde_json::from_str(&signed_archive.archive)
+            .map_err(|e| LLMProviderError::InvalidJobArchive(e.to_string()))?;
+        if archive.version > JOB_ARCHIVE_VERSION {
+            return Err(LLMProviderError::InvalidJobArchive(format!(
+                "Unsupported archive version {}",
+                archive.version
+            )));
+        }
+        Ok(archive)
+    }
+
+    /// Recreates the job (with a new id) and its inbox on this node. The messages are moved to the new inbox,
+    /// their node and profile names are remapped and they are signed again by this node.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn import(
+        &self,
+        db: &ShinkaiDB,
+        vector_fs: &VectorFS,
+        node_name: &ShinkaiName,
+        profile: &ShinkaiName,
+        llm_provider_id: &str,
+        profile_mapping: &HashMap<String, String>,
+        signing_key: &SigningKey,
+    ) -> Result<JobImportResult, LLMProviderError> {
+        let job_id = format!("jobid_{}", uuid::Uuid::new_v4());
+        let inbox_name = InboxName::get_job_inbox_name_from_params(job_id.clone())?;
+
+        db.create_new_job(
+            job_id.clone(),
+            llm_provider_id.to_string(),
+            self.job.scope.clone(),
+            self.job.is_hidden,
+            self.job.associated_ui.clone(),
+        )?;
+        db.set_job_config(&job_id, &self.job.config)?;
+
+        let mut files_inboxes = HashMap::new();
+        for files_inbox in &self.files_inboxes {
+            let new_files_inbox = uuid::Uuid::new_v4().to_string();
+            db.create_files_message_inbox(new_files_inbox.clone())
+                .map_err(ShinkaiDBError::RocksDBError)?;
+            for file in &files_inbox.files {
+                let content = base64::decode(&file.content)
+                    .map_err(|e| LLMProviderError::InvalidJobArchive(format!("Invalid file {}: {}", file.name, e)))?;
+                vector_fs
+                    .db
+                    .add_file_to_files_message_inbox(new_files_inbox.clone(), file.name.clone(), content)?;
+            }
+            files_inboxes.insert(files_inbox.files_inbox.clone(), new_files_inbox);
+        }
+
+        let mut message_hashes: HashMap<String, String> = HashMap::new();
+        let mut parent_hash: Option<String> = None;
+        for archived_message in &self.messages {
+            let message = self.remap_message(
+                &archived_message.message,
+                &job_id,
+                &inbox_name,
+                &node_name.node_name,
+                llm_provider_id,
+                profile_mapping,
+                &files_inboxes,
+                &message_hashes,
+                signing_key,
+            )?;
+            db.unsafe_insert_inbox_message_with_parent(&message, parent_hash.clone(), None)
+                .await?;
+
+            let message_hash = message.calculate_message_hash_for_pagination();
+            db.add_job_step_results(&job_id, message_hash.clone(), &archived_message.step_history)?;
+            message_hashes.insert(
+                archived_message.message.calculate_message_hash_for_pagination(),
+                message_hash.clone(),
+            );
+            parent_hash = Some(message_hash);
+        }
+
+        if let Some(last_message_hash) = parent_hash {
+            if !self.job.execution_context.is_empty() {
+                db.set_job_execution_context(
+                    job_id.clone(),
+                    self.job.execution_context.clone(),
+                    Some(last_message_hash),
+                )?;
+            }
+        }
+        if let Some(custom_name) = &self.custom_name {
+            db.update_smart_inbox_name(&inbox_name.to_string(), custom_name)?;
+        }
+        if self.job.is_finished {
+            db.update_job_to_finished(&job_id)?;
+        }
+
+        let missing_scope_items = Self::missing_scope_items(&self.job.scope, vector_fs, profile).await;
+        Ok(JobImportResult {
+            job_id,
+            missing_scope_items,
+        })
+    }
+
+    /// Returns the paths of the VectorFS items and folders of the scope that don't exist for the profile
+    pub async fn missing_scope_items(scope: &JobScope, vector_fs: &VectorFS, profile: &ShinkaiName) -> Vec<String> {
+        let mut missing_scope_items = Vec::new();
+        for item in &scope.vector_fs_items {
+            if vector_fs
+                .validate_path_points_to_item(item.path.clone(), profile)
+                .await
+                .is_err()
+            {
+                missing_scope_items.push(item.path.to_string());
+            }
+        }
+        for folder in &scope.vector_fs_folders {
+            if vector_fs
+                .validate_path_points_to_folder(folder.path.clone(), profile)
+                .await
+                .is_err()
+            {
+                missing_scope_items.push(folder.path.to_string());
+            }
+        }
+        missing_scope_items
+    }
+
+    fn job_message(message: &ShinkaiMessage) -> Option<JobMessage> {
+        match message.get_message_content_schema() {
+            Ok(MessageSchemaType::JobMessageSchema) => serde_json::from_str(&message.get_message_content().ok()?).ok(),
+            _ => None,
+        }
+    }
+
+    /// Moves an archived message to the inbox of the imported job and signs it again
+    #[allow(clippy::too_many_arguments)]
+    fn remap_message(
+        &self,
+        message: &ShinkaiMessage,
+        job_id: &str,
+        inbox_name: &InboxName,
+        node_name: &str,
+        llm_provider_id: &str,
+        profile_mapping: &HashMap<String, String>,
+        files_inboxes: &HashMap<String, String>,
+        message_hashes: &HashMap<String, String>,
+        signing_key: &SigningKey,
+    ) -> Result<ShinkaiMessage, LLMProviderError> {
+        let mut message = message.clone();
+        let remap_subidentity =
+            |subidentity: &str| self.remap_subidentity(subidentity, llm_provider_id, profile_mapping);
+
+        let body = match &mut message.body {
+            MessageBody::Unencrypted(body) => body,
+            _ => {
+                return Err(LLMProviderError::InvalidJobArchive(
+                    "Encrypted messages can't be imported".to_string(),
+                ))
+            }
+        };
+        let data = match &mut body.message_data {
+            MessageData::Unencrypted(data) => data,
+            _ => {
+                return Err(LLMProviderError::InvalidJobArchive(
+                    "Encrypted messages can't be imported".to_string(),
+                ))
+            }
+        };
+        if data.message_content_schema == MessageSchemaType::JobMessageSchema {
+            let mut job_message: JobMessage = serde_json::from_str(&data.message_raw_content)?;
+            job_message.job_id = job_id.to_string();
+            if let Some(files_inbox) = files_inboxes.get(&job_message.files_inbox) {
+                job_message.files_inbox = files_inbox.clone();
+            }
+            job_message.parent = job_message
+                .parent
+                .and_then(|parent| message_hashes.get(&parent).cloned());
+            data.message_raw_content = serde_json::to_string(&job_message)?;
+        }
+
+        body.internal_metadata.inbox = inbox_name.to_string();
+        body.internal_metadata.sender_subidentity = remap_subidentity(&body.internal_metadata.sender_subidentity);
+        body.internal_metadata.recipient_subidentity = remap_subidentity(&body.internal_metadata.recipient_subidentity);
+        body.internal_metadata.node_api_data = None;
+
+        for name in [
+            &mut message.external_metadata.sender,
+            &mut message.external_metadata.recipient,
+        ] {
+            if *name == self.node_name {
+                *name = node_name.to_string();
+            }
+        }
+
+        message.sign_inner_layer(signing_key)?;
+        Ok(message.sign_outer_layer(signing_key)?)
+    }
+
+    /// Renames the profile of a subidentity (e.g. `main/agent/my_gpt`) and points the llm provider ones of the job
+    /// to its new llm provider
+    fn remap_subidentity(
+        &self,
+        subidentity: &str,
+        llm_provider_id: &str,
+        profile_mapping: &HashMap<String, String>,
+    ) -> String {
+        let mut parts: Vec<String> = subidentity.split('/').map(str::to_string).collect();
+        if let Some(profile) = parts.first_mut() {
+            if let Some(new_profile) = profile_mapping.get(profile.as_str()) {
+                *profile = new_profile.clone();
+            }
+        }
+        if parts.len() == 3 && parts[1] == "agent" && parts[2] == self.job.parent_llm_provider_id {
+            parts[2] = llm_provider_id.to_string();
+        }
+        parts.join("/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_message_primitives::{
+        schemas::job_config::JobConfig,
+        shinkai_utils::{
+            encryption::{unsafe_deterministic_encryption_keypair, EncryptionMethod},
+            shinkai_message_builder::ShinkaiMessageBuilder,
+            signatures::unsafe_deterministic_signature_keypair,
+        },
+    };
+
+    fn archive() -> JobArchive {
+        let job = Job {
+            job_id: "jobid_1".to_string(),
+            is_hidden: false,
+            datetime_created: "2024-01-01T00:00:00.000Z".to_string(),
+            is_finished: false,
+            parent_llm_provider_id: "my_gpt".to_string(),
+            scope: JobScope::new_default(),
+            conversation_inbox_name: InboxName::get_job_inbox_name_from_params("jobid_1".to_string()).unwrap(),
+            step_history: Vec::new(),
+            unprocessed_messages: Vec::new(),
+            execution_context: HashMap::new(),
+            associated_ui: None,
+            config: JobConfig::default(),
+        };
+        JobArchive {
+            version: JOB_ARCHIVE_VERSION,
+            node_name: "@@node1.shinkai".to_string(),
+            exported_at: "2024-01-02T00:00:00.000Z".to_string(),
+            job,
+            custom_name: Some("Research".to_string()),
+            messages: Vec::new(),
+            files_inboxes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_signed_job_archive_roundtrip() {
+        let (signing_key, verifying_key) = unsafe_deterministic_signature_keypair(0);
+        let signed = archive().sign(&signing_key).unwrap();
+        assert_eq!(JobArchive::exporting_node_name(&signed).unwrap(), "@@node1.shinkai");
+        let read = JobArchive::from_signed(&signed, &verifying_key).unwrap();
+        assert_eq!(read.job.job_id, "jobid_1");
+        assert_eq!(read.custom_name, Some("Research".to_string()));
+
+        let tampered = SignedJobArchive {
+            archive: signed.archive.replace("Research", "Other"),
+            ..signed.clone()
+        };
+        assert!(matches!(
+            JobArchive::from_signed(&tampered, &verifying_key),
+            Err(LLMProviderError::InvalidJobArchive(_))
+        ));
+
+        // Edited and re-signed by someone else: the signature is valid, but not made by the exporting node
+        let (other_signing_key, _) = unsafe_deterministic_signature_keypair(1);
+        let resigned = SignedJobArchive::sign(signed.archive.replace("Research", "Other"), &other_signing_key);
+        assert!(matches!(
+            JobArchive::from_signed(&resigned, &verifying_key),
+            Err(LLMProviderError::InvalidJobArchive(_))
+        ));
+
+        let mut newer = archive();
+        newer.version = JOB_ARCHIVE_VERSION + 1;
+        assert!(JobArchive::from_signed(&newer.sign(&signing_key).unwrap(), &verifying_key).is_err());
+    }
+
+    #[test]
+    fn test_remap_message() {
+        let archive = archive();
+        let (old_node_sk, _) = unsafe_deterministic_signature_keypair(0);
+        let (new_node_sk, new_node_pk) = unsafe_deterministic_signature_keypair(1);
+        let (encryption_sk, encryption_pk) = unsafe_deterministic_encryption_keypair(0);
+
+        let job_message = JobMessage {
+            job_id: "jobid_1".to_string(),
+            content: "Hello".to_string(),
+            files_inbox: "old_files".to_string(),
+            parent: Some("old_parent".to_string()),
+            workflow_code: None,
+            workflow_name: None,
+            sheet_job_data: None,
+            callback: None,
+            inference_params: None,
+            is_delegation_trace: false,
+        };
+        let message = ShinkaiMessageBuilder::new(encryption_sk, old_node_sk, encryption_pk)
+            .message_raw_content(serde_json::to_string(&job_message).unwrap())
+            .body_encryption(EncryptionMethod::None)
+            .message_schema_type(MessageSchemaType::JobMessageSchema)
+            .internal_metadata_with_inbox(
+                "work".to_string(),
+                "work/agent/my_gpt".to_string(),
+                archive.job.conversation_inbox_name.to_string(),
+                EncryptionMethod::None,
+                None,
+            )
+            .external_metadata_with_schedule(
+                "@@node1.shinkai".to_string(),
+                "@@node1.shinkai".to_string(),
+                "2024-01-01T00:00:01.000Z".to_string(),
+            )
+            .build()
+            .unwrap();
+
+        let new_inbox = InboxName::get_job_inbox_name_from_params("jobid_2".to_string()).unwrap();
+        let profile_mapping = HashMap::from([("work".to_string(), "main".to_string())]);
+        let files_inboxes = HashMap::from([("old_files".to_string(), "new_files".to_string())]);
+        let message_hashes = HashMap::from([("old_parent".to_string(), "new_parent".to_string())]);
+        let remapped = archive
+            .remap_message(
+                &message,
+                "jobid_2",
+                &new_inbox,
+                "@@node2.shinkai",
+                "my_ollama",
+                &profile_mapping,
+                &files_inboxes,
+                &message_hashes,
+                &new_node_sk,
+            )
+            .unwrap();
+
+        assert_eq!(remapped.get_message_inbox().unwrap(), new_inbox.to_string());
+        assert_eq!(remapped.get_sender_subidentity(), Some("main".to_string()));
+        assert_eq!(
+            remapped.get_recipient_subidentity(),
+            Some("main/agent/my_ollama".to_string())
+        );
+        assert_eq!(remapped.external_metadata.sender, "@@node2.shinkai");
+        assert_eq!(remapped.external_metadata.recipient, "@@node2.shinkai");
+
+        let remapped_job_message = JobArchive::job_message(&remapped).unwrap();
+        assert_eq!(remapped_job_message.job_id, "jobid_2");
+        assert_eq!(remapped_job_message.files_inbox, "new_files");
+        assert_eq!(remapped_job_message.parent, Some("new_parent".to_string()));
+
+        // Signed again by the importing node
+        assert!(remapped.verify_outer_layer_signature(&new_node_pk).unwrap());
+        assert!(remapped.verify_inner_layer_signature(&new_node_pk).unwrap());
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::{HashMap, HashSet};
+
+use tokio::sync::oneshot;
+
+/// Why the processing of a job message was stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobInterruption {
+    /// The job was cancelled: its queued messages are dropped
+    Cancelled,
+    /// The job was paused: the message stays queued and is processed again once the job is resumed
+    Paused,
+}
+
+impl JobInterruption {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobInterruption::Cancelled => "cancelled",
+            JobInterruption::Paused => "paused",
+        }
+    }
+}
+
+/// Keeps track of the jobs being processed so they can be interrupted, and of the paused jobs
+/// (which are skipped by the job queue until they get resumed)
+#[derive(Debug, Default)]
+pub struct JobControl {
+    running: HashMap<String, oneshot::Sender<JobInterruption>>,
+    paused: HashSet<String>,
+}
+
+impl JobControl {
+    pub fn new(paused_jobs: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            running: HashMap::new(),
+            paused: paused_jobs.into_iter().collect(),
+        }
+    }
+
+    /// Registers the processing of a job message. Returns the receiver notified if the processing gets
+    /// interrupted, or `None` if the job is paused and shouldn't be processed.
+    pub fn start(&mut self, job_id: &str) -> Option<oneshot::Receiver<JobInterruption>> {
+        if self.paused.contains(job_id) {
+            return None;
+        }
+        let (sender, receiver) = oneshot::channel();
+        self.running.insert(job_id.to_string(), sender);
+        Some(receiver)
+    }
+
+    /// Unregisters the processing of a job message
+    pub fn finish(&mut self, job_id: &str) {
+        self.running.remove(job_id);
+    }
+
+    pub fn is_running(&self, job_id: &str) -> bool {
+        self.running.contains_key(job_id)
+    }
+
+    pub fn is_paused(&self, job_id: &str) -> bool {
+        self.paused.contains(job_id)
+    }
+
+    /// Interrupts the processing of the job, if any. Returns true if the job was being processed.
+    pub fn interrupt(&mut self, job_id: &str, interruption: JobInterruption) -> bool {
+        match self.running.remove(job_id) {
+            Some(sender) => sender.send(interruption).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Pauses the job, interrupting the message being processed. Returns true if it was being processed.
+    pub fn pause(&mut self, job_id: &str) -> bool {
+        self.paused.insert(job_id.to_string());
+        self.interrupt(job_id, JobInterruption::Paused)
+    }
+
+    /// Resumes the job. Returns false if it wasn't paused.
+    pub fn resume(&mut self, job_id: &str) -> bool {
+        self.paused.remove(job_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_job_control_interrupt_and_pause() {
+        let mut job_control = JobControl::default();
+
+        let receiver = job_control.start("job1").unwrap();
+        assert!(job_control.is_running("job1"));
+        assert!(job_control.interrupt("job1", JobInterruption::Cancelled));
+        assert_eq!(receiver.await.unwrap(), JobInterruption::Cancelled);
+        assert!(!job_control.is_running("job1"));
+        assert!(!job_control.interrupt("job1", JobInterruption::Cancelled));
+
+        let receiver = job_control.start("job2").unwrap();
+        assert!(job_control.pause("job2"));
+        assert_eq!(receiver.await.unwrap(), JobInterruption::Paused);
+        assert!(job_control.start("job2").is_none());
+
+        assert!(job_control.resume("job2"));
+        assert!(!job_control.resume("job2"));
+        assert!(job_control.start("job2").is_some());
+        job_control.finish("job2");
+        assert!(!job_control.is_running("job2"));
+    }
+}
@@ -1,12 +1,14 @@
 use super::error::LLMProviderError;
 use super::job_callback_manager::JobCallbackManager;
+use super::job_control::{JobControl, JobInterruption};
 use super::queue::job_queue_manager::{JobForProcessing, JobQueueManager};
+use super::queue::job_scheduler::{select_jobs_to_process, JobSchedulerLimits, QueuedJob, RunningJob};
 use crate::db::{ShinkaiDB, Topic};
 use crate::llm_provider::job::JobLike;
 use crate::llm_provider::llm_provider::LLMProvider;
 use crate::managers::sheet_manager::SheetManager;
 use crate::managers::IdentityManager;
-use crate::network::ws_manager::WSUpdateHandler;
+use crate::network::ws_manager::{WSMessageType, WSMetadata, WSUpdateHandler};
 use crate::tools::tool_router::ToolRouter;
 use crate::vector_fs::vector_fs::VectorFS;
 use ed25519_dalek::SigningKey;
@@ -17,19 +19,18 @@ use shinkai_message_primitives::{
     schemas::shinkai_name::ShinkaiName,
     shinkai_message::{
         shinkai_message::{MessageBody, MessageData, ShinkaiMessage},
-        shinkai_message_schemas::{JobCreationInfo, JobMessage, MessageSchemaType},
+        shinkai_message_schemas::{JobCreationInfo, JobMessage, MessageSchemaType, WSTopic},
     },
-    shinkai_utils::signatures::clone_signature_secret_key,
+    shinkai_utils::{shinkai_message_builder::ShinkaiMessageBuilder, signatures::clone_signature_secret_key},
 };
 use shinkai_vector_resources::embedding_generator::RemoteEmbeddingGenerator;
 use shinkai_vector_resources::file_parser::unstructured_api::UnstructuredAPI;
-use std::collections::HashSet;
 use std::env;
 use std::pin::Pin;
 use std::result::Result::Ok;
 use std::sync::Weak;
 use std::{collections::HashMap, sync::Arc};
-use tokio::sync::{Mutex, Semaphore};
+use tokio::sync::{Mutex, Notify};
 
 const NUM_THREADS: usize = 4;
 
@@ -70,6 +71,8 @@ pub struct JobManager {
     pub sheet_manager: Arc<Mutex<SheetManager>>,
     // Job callback manager for handling job callbacks
     pub callback_manager: Arc<Mutex<JobCallbackManager>>,
+    // Running and paused jobs, used to cancel, pause and resume them
+    pub job_control: Arc<Mutex<JobControl>>,
 }
 
 impl JobManager {
@@ -88,14 +91,19 @@ impl JobManager {
         callback_manager: Arc<Mutex<JobCallbackManager>>,
     ) -> Self {
         let jobs_map = Arc::new(Mutex::new(HashMap::new()));
+        let mut paused_jobs = Vec::new();
         {
             let db_arc = db.upgrade().ok_or("Failed to upgrade shinkai_db").unwrap();
             let all_jobs = db_arc.get_all_jobs().unwrap();
             let mut jobs = jobs_map.lock().await;
             for job in all_jobs {
+                if db_arc.is_job_paused(job.job_id()).unwrap_or(false) {
+                    paused_jobs.push(job.job_id().to_string());
+                }
                 jobs.insert(job.job_id().to_string(), job);
             }
         }
+        let job_control = Arc::new(Mutex::new(JobControl::new(paused_jobs)));
 
         // Get all serialized_llm_providers and convert them to LLM Providers
         let mut llm_providers: Vec<Arc<Mutex<LLMProvider>>> = Vec::new();
@@ -129,7 +137,7 @@ impl JobManager {
             db.clone(),
             vector_fs.clone(),
             node_profile_name.clone(),
-            thread_number,
+            JobSchedulerLimits::from_env(thread_number),
             clone_signature_secret_key(&identity_secret_key),
             embedding_generator.clone(),
             unstructured_api.clone(),
@@ -137,6 +145,7 @@ impl JobManager {
             tool_router.clone(),
             sheet_manager.clone(),
             callback_manager.clone(),
+            job_control.clone(),
             |job,
              db,
              vector_fs,
@@ -183,6 +192,7 @@ impl JobManager {
             tool_router,
             sheet_manager,
             callback_manager,
+            job_control,
         }
     }
 
@@ -192,7 +202,7 @@ impl JobManager {
         db: Weak<ShinkaiDB>,
         vector_fs: Weak<VectorFS>,
         node_profile_name: ShinkaiName,
-        max_parallel_jobs: usize,
+        limits: JobSchedulerLimits,
         identity_sk: SigningKey,
         generator: RemoteEmbeddingGenerator,
         unstructured_api: UnstructuredAPI,
@@ -200,6 +210,7 @@ impl JobManager {
         tool_router: Option<Arc<Mutex<ToolRouter>>>,
         sheet_manager: Arc<Mutex<SheetManager>>,
         callback_manager: Arc<Mutex<JobCallbackManager>>,
+        job_control: Arc<Mutex<JobControl>>,
         job_processing_fn: impl Fn(
                 JobForProcessing,
                 Weak<ShinkaiDB>,
@@ -225,8 +236,9 @@ impl JobManager {
         let identity_sk = clone_signature_secret_key(&identity_sk);
         let job_processing_fn = Arc::new(job_processing_fn);
 
-        let processing_jobs = Arc::new(Mutex::new(HashSet::new()));
-        let semaphore = Arc::new(Semaphore::new(max_parallel_jobs));
+        // Jobs with a message being processed, along with what's needed to schedule the rest
+        let processing_jobs: Arc<Mutex<HashMap<String, RunningJob>>> = Arc::new(Mutex::new(HashMap::new()));
+        let job_finished = Arc::new(Notify::new());
 
         return tokio::spawn(async move {
             shinkai_log(
@@ -235,46 +247,59 @@ impl JobManager {
                 "Starting job queue processing loop",
             );
 
-            let mut handles = Vec::new();
             loop {
-                let mut continue_immediately = false;
-
-                // Scope for acquiring and releasing the lock quickly
+                // Scope for acquiring and releasing the locks quickly
                 let job_ids_to_process: Vec<String> = {
                     let mut processing_jobs_lock = processing_jobs.lock().await;
-                    let job_queue_manager_lock = job_queue_manager.lock().await;
-                    let all_jobs = job_queue_manager_lock
-                        .get_all_elements_interleave()
+                    let queue_heads = job_queue_manager
+                        .lock()
                         .await
-                        .unwrap_or(Vec::new());
-                    std::mem::drop(job_queue_manager_lock);
+                        .get_all_queue_heads()
+                        .await
+                        .unwrap_or_default();
+                    let job_control_lock = job_control.lock().await;
+                    let db_arc = db_clone.upgrade();
 
-                    let filtered_jobs = all_jobs
+                    // Paused jobs keep their messages queued until they get resumed
+                    let queued_jobs = queue_heads
                         .into_iter()
-                        .filter_map(|job| {
-                            let job_id = job.job_message.job_id.clone().to_string();
-                            if !processing_jobs_lock.contains(&job_id) {
-                                processing_jobs_lock.insert(job_id.clone());
-                                Some(job_id)
-                            } else {
-                                None
+                        .filter(|(job_id, _)| !job_control_lock.is_paused(job_id))
+                        .map(|(job_id, job)| {
+                            let (priority_override, llm_provider_id) = match &db_arc {
+                                Some(db) => (
+                                    db.get_job_config(&job_id).ok().and_then(|config| config.priority),
+                                    db.get_job_llm_provider_id(&job_id).ok(),
+                                ),
+                                None => (None, None),
+                            };
+                            QueuedJob {
+                                job_id,
+                                job,
+                                priority_override,
+                                llm_provider_id,
                             }
                         })
-                        .take(max_parallel_jobs)
                         .collect::<Vec<_>>();
-
-                    // Check if the number of jobs to process is equal to max_parallel_jobs
-                    continue_immediately = filtered_jobs.len() == max_parallel_jobs;
-
-                    std::mem::drop(processing_jobs_lock);
-                    filtered_jobs
+                    std::mem::drop(job_control_lock);
+
+                    let selected_jobs = select_jobs_to_process(queued_jobs, &processing_jobs_lock, &limits);
+                    for job in selected_jobs.iter() {
+                        processing_jobs_lock.insert(
+                            job.job_id.clone(),
+                            RunningJob {
+                                profile: job.profile(),
+                                llm_provider_id: job.llm_provider_id.clone(),
+                            },
+                        );
+                    }
+                    selected_jobs.into_iter().map(|job| job.job_id).collect()
                 };
 
                 // Spawn tasks based on filtered job IDs
                 for job_id in job_ids_to_process {
                     let job_queue_manager = Arc::clone(&job_queue_manager);
                     let processing_jobs = Arc::clone(&processing_jobs);
-                    let semaphore = Arc::clone(&semaphore);
+                    let job_finished = Arc::clone(&job_finished);
                     let db_clone_2 = db_clone.clone();
                     let vector_fs_clone_2 = vector_fs_clone.clone();
                     let identity_sk_clone = clone_signature_secret_key(&identity_sk);
@@ -286,40 +311,59 @@ impl JobManager {
                     let tool_router = tool_router.clone();
                     let sheet_manager = sheet_manager.clone();
                     let callback_manager = callback_manager.clone();
-
-                    let handle = tokio::spawn(async move {
-                        let _permit = semaphore.acquire().await.unwrap();
-
-                        // Acquire the lock, dequeue the job, and immediately release the lock
-                        let job = {
-                            let job_queue_manager = job_queue_manager.lock().await;
-
-                            job_queue_manager.peek(&job_id).await
+                    let job_control = job_control.clone();
+
+                    tokio::spawn(async move {
+                        // Register the processing and peek the job while holding the job control lock, so a
+                        // cancellation can't clear the queue in between. Paused jobs are skipped.
+                        let (interruption, job) = {
+                            let mut job_control = job_control.lock().await;
+                            match job_control.start(&job_id) {
+                                Some(interruption) => {
+                                    let job_queue_manager = job_queue_manager.lock().await;
+                                    (Some(interruption), job_queue_manager.peek(&job_id).await)
+                                }
+                                None => (None, Ok(None)),
+                            }
                         };
 
-                        match job {
-                            Ok(Some(job)) => {
-                                // Acquire the lock, process the job, and immediately release the lock
-                                let result = {
-                                    let result = job_processing_fn(
-                                        job,
-                                        db_clone_2,
-                                        vector_fs_clone_2,
-                                        node_profile_name,
-                                        identity_sk_clone,
-                                        cloned_generator,
-                                        cloned_unstructured_api,
-                                        ws_manager,
-                                        tool_router,
-                                        sheet_manager,
-                                        callback_manager,
-                                        job_queue_manager.clone(),
-                                    )
-                                    .await;
-                                    if let Ok(Some(_)) = job_queue_manager.lock().await.dequeue(&job_id.clone()).await {
-                                        result
-                                    } else {
-                                        Err(LLMProviderError::JobDequeueFailed(job_id.clone()))
+                        match (interruption, job) {
+                            (Some(interruption), Ok(Some(job))) => {
+                                let processing = job_processing_fn(
+                                    job,
+                                    db_clone_2,
+                                    vector_fs_clone_2,
+                                    node_profile_name,
+                                    identity_sk_clone,
+                                    cloned_generator,
+                                    cloned_unstructured_api,
+                                    ws_manager,
+                                    tool_router,
+                                    sheet_manager,
+                                    callback_manager,
+                                    job_queue_manager.clone(),
+                                );
+                                // Interrupting drops the processing future, which stops its in-flight llm
+                                // provider calls (awaited in place, so their requests and ws streaming stop
+                                // too). A JS tool blocks its thread until done, so it completes first.
+                                // The queue is handled by whoever interrupted the job (cleared if cancelled,
+                                // kept if paused).
+                                let result = tokio::select! {
+                                    result = processing => {
+                                        let dequeued = job_queue_manager.lock().await.dequeue(&job_id.clone()).await;
+                                        if let Ok(Some(_)) = dequeued {
+                                            result
+                                        } else {
+                                            Err(LLMProviderError::JobDequeueFailed(job_id.clone()))
+                                        }
+                                    }
+                                    Ok(interruption) = interruption => {
+                                        shinkai_log(
+                                            ShinkaiLogOption::JobExecution,
+                                            ShinkaiLogLevel::Info,
+                                            format!("Job {} {}", job_id, interruption.as_str()).as_str(),
+                                        );
+                                        Err(LLMProviderError::JobCancelled(job_id.clone()))
                                     }
                                 };
 
@@ -331,34 +375,29 @@ impl JobManager {
                                     );
                                 }
                             }
-                            Ok(None) => {}
-                            Err(_) => {
+                            (_, Err(_)) => {
                                 // Log the error
                             }
+                            _ => {}
                         }
-                        drop(_permit);
+                        job_control.lock().await.finish(&job_id);
                         processing_jobs.lock().await.remove(&job_id);
+                        job_finished.notify_one();
                     });
-                    handles.push(handle);
                 }
 
-                let handles_to_join = std::mem::take(&mut handles);
-                futures::future::join_all(handles_to_join).await;
-                handles.clear();
-
-                // If job_ids_to_process was equal to max_parallel_jobs, loop again immediately
-                // without waiting for a new job from receiver.recv().await
-                if continue_immediately {
-                    continue;
-                }
-
-                // Receive new jobs
-                if let Some(new_job) = receiver.recv().await {
-                    shinkai_log(
-                        ShinkaiLogOption::JobExecution,
-                        ShinkaiLogLevel::Info,
-                        format!("Received new job {:?}", new_job.job_message.job_id).as_str(),
-                    );
+                // Schedule again once a new job arrives or a running one finishes (freeing its slot)
+                tokio::select! {
+                    new_job = receiver.recv() => {
+                        if let Some(new_job) = new_job {
+                            shinkai_log(
+                                ShinkaiLogOption::JobExecution,
+                                ShinkaiLogLevel::Info,
+                                format!("Received new job {:?}", new_job.job_message.job_id).as_str(),
+                            );
+                        }
+                    }
+                    _ = job_finished.notified() => {}
                 }
             }
         });
@@ -432,10 +471,22 @@ impl JobManager {
         {
             let db_arc = self.db.upgrade().ok_or("Failed to upgrade shinkai_db").unwrap();
             let is_hidden = job_creation.is_hidden.unwrap_or(false);
+
+            // Jobs created from a preset get its scope if they don't have one
+            let mut scope = job_creation.scope;
+            if let Some(preset_id) = &job_creation.preset_id {
+                let preset = db_arc
+                    .get_job_preset(preset_id, profile)
+                    .map_err(LLMProviderError::ShinkaiDB)?;
+                if scope.is_empty() {
+                    scope = preset.scope;
+                }
+            }
+
             match db_arc.create_new_job(
                 job_id.clone(),
                 llm_provider_id.clone(),
-                job_creation.scope,
+                scope,
                 is_hidden,
                 job_creation.associated_ui,
             ) {
@@ -443,6 +494,14 @@ impl JobManager {
                 Err(err) => return Err(LLMProviderError::ShinkaiDB(err)),
             };
 
+            if job_creation.preset_id.is_some() {
+                let mut config = db_arc.get_job_config(&job_id).map_err(LLMProviderError::ShinkaiDB)?;
+                config.preset_id = job_creation.preset_id;
+                db_arc
+                    .set_job_config(&job_id, &config)
+                    .map_err(LLMProviderError::ShinkaiDB)?;
+            }
+
             match db_arc.get_job(&job_id) {
                 Ok(job) => {
                     std::mem::drop(db_arc); // require to avoid deadlock
@@ -526,6 +585,28 @@ impl JobManager {
         Ok(job_message.job_id.clone().to_string())
     }
 
+    /// Adds the message to the job inbox as a new branch of the conversation (a reply to `job_message.parent`,
+    /// or a new first message if `None`) and queues it so the job answers it in that branch.
+    pub async fn add_branch_to_job_processing_queue(
+        &mut self,
+        message: ShinkaiMessage,
+        job_message: JobMessage,
+    ) -> Result<String, LLMProviderError> {
+        let sender_subidentity = ShinkaiName::from_shinkai_message_using_sender_subidentity(&message)
+            .map_err(LLMProviderError::InvalidSubidentity)?;
+        let profile = sender_subidentity
+            .extract_profile()
+            .map_err(|e| LLMProviderError::InvalidProfileSubidentity(e.to_string()))?;
+
+        let db_arc = self.db.upgrade().ok_or("Failed to upgrade shinkai_db").unwrap();
+        db_arc
+            .insert_inbox_message_as_branch(&message, job_message.parent.clone(), self.ws_manager.clone())
+            .await?;
+        std::mem::drop(db_arc);
+
+        self.add_job_message_to_job_queue(&job_message, &profile).await
+    }
+
     pub async fn add_job_message_to_job_queue(
         &mut self,
         job_message: &JobMessage,
@@ -538,6 +619,103 @@ impl JobManager {
 
         Ok(job_message.job_id.clone().to_string())
     }
+
+    /// Cancels a job: the message being processed is interrupted (aborting its in-flight llm provider calls
+    /// and tool executions) and the queued messages are removed. A cancellation message is added to the job
+    /// inbox and streaming clients receive a termination event.
+    /// Returns false if there was nothing to cancel.
+    pub async fn cancel_job(&self, job_id: &str) -> Result<bool, LLMProviderError> {
+        let db_arc = self.db.upgrade().ok_or("Failed to upgrade shinkai_db").unwrap();
+        db_arc.get_job(job_id)?;
+
+        let (was_running, removed_messages) = {
+            let mut job_control = self.job_control.lock().await;
+            let was_running = job_control.interrupt(job_id, JobInterruption::Cancelled);
+            job_control.resume(job_id);
+            let mut job_queue_manager = self.job_queue_manager.lock().await;
+            (was_running, job_queue_manager.clear(job_id).await?)
+        };
+        db_arc.set_job_paused(job_id, false)?;
+
+        if !was_running && removed_messages.is_empty() {
+            return Ok(false);
+        }
+
+        let cancellation = LLMProviderError::JobCancelled(format!(
+            "{} queued message(s) were not processed",
+            removed_messages.len()
+        ));
+        let shinkai_message = ShinkaiMessageBuilder::job_message_from_llm_provider(
+            job_id.to_string(),
+            cancellation.to_error_json(),
+            "".to_string(),
+            clone_signature_secret_key(&self.identity_secret_key),
+            self.node_profile_name.node_name.clone(),
+            self.node_profile_name.node_name.clone(),
+        )
+        .map_err(|e| LLMProviderError::ShinkaiMessageBuilderError(e.to_string()))?;
+        db_arc
+            .add_message_to_job_inbox(job_id, &shinkai_message, None, self.ws_manager.clone())
+            .await?;
+
+        self.send_job_interruption_ws_event(job_id, JobInterruption::Cancelled).await?;
+        Ok(true)
+    }
+
+    /// Pauses a job: the message being processed is interrupted but kept in the queue, together with the
+    /// rest of the job's messages, and processed again once the job gets resumed
+    pub async fn pause_job(&self, job_id: &str) -> Result<(), LLMProviderError> {
+        let db_arc = self.db.upgrade().ok_or("Failed to upgrade shinkai_db").unwrap();
+        db_arc.set_job_paused(job_id, true)?;
+
+        let was_running = self.job_control.lock().await.pause(job_id);
+        if was_running {
+            self.send_job_interruption_ws_event(job_id, JobInterruption::Paused).await?;
+        }
+        Ok(())
+    }
+
+    /// Resumes a paused job, processing its queued messages. Returns false if the job wasn't paused.
+    pub async fn resume_job(&self, job_id: &str) -> Result<bool, LLMProviderError> {
+        let db_arc = self.db.upgrade().ok_or("Failed to upgrade shinkai_db").unwrap();
+        db_arc.set_job_paused(job_id, false)?;
+
+        if !self.job_control.lock().await.resume(job_id) {
+            return Ok(false);
+        }
+        self.job_queue_manager.lock().await.notify(job_id).await?;
+        Ok(true)
+    }
+
+    /// Lets the clients streaming the job's answer know that it was interrupted
+    async fn send_job_interruption_ws_event(
+        &self,
+        job_id: &str,
+        interruption: JobInterruption,
+    ) -> Result<(), LLMProviderError> {
+        if let Some(ws_manager) = &self.ws_manager {
+            let inbox_name = InboxName::get_job_inbox_name_from_params(job_id.to_string())?;
+            let metadata = WSMetadata {
+                id: None,
+                is_done: true,
+                done_reason: Some(interruption.as_str().to_string()),
+                total_duration: None,
+                eval_count: None,
+            };
+            ws_manager
+                .lock()
+                .await
+                .queue_message(
+                    WSTopic::Inbox,
+                    inbox_name.to_string(),
+                    "".to_string(),
+                    WSMessageType::Metadata(metadata),
+                    true,
+                )
+                .await;
+        }
+        Ok(())
+    }
 }
 
 impl JobManagerTrait for JobManager {
@@ -1,19 +1,35 @@
 use std::sync::Arc;
 
-use crate::network::ws_manager::WSUpdateHandler;
+use crate::db::db_llm_response_cache::CachedLLMResponse;
+use crate::db::ShinkaiDB;
+use crate::network::ws_manager::{WSMessageType, WSMetadata, WSUpdateHandler};
 
 use super::error::LLMProviderError;
-use super::execution::chains::inference_chain_trait::LLMInferenceResponse;
+use super::execution::chains::inference_chain_trait::{LLMInferenceResponse, LLMTokenUsage};
 use super::execution::prompts::prompts::Prompt;
-use super::providers::LLMService;
+use super::execution::prompts::subprompts::{SubPrompt, SubPromptAssetType};
+use super::providers::provider_registry::LLMProviderRegistry;
+use super::providers::shared::llm_message::LlmMessage;
+use crate::managers::model_capabilities_manager::{ModelCapabilitiesManager, ModelCapability};
+use chrono::Utc;
 use reqwest::Client;
-use serde_json::{Map, Value as JsonValue};
+use serde::{Deserialize, Serialize};
 use shinkai_message_primitives::schemas::inbox_name::InboxName;
+use shinkai_message_primitives::schemas::shinkai_time::ShinkaiStringTime;
 use shinkai_message_primitives::schemas::{
-    llm_providers::serialized_llm_provider::{LLMProviderInterface, SerializedLLMProvider},
+    llm_providers::{
+        inference_params::InferenceParams,
+        serialized_llm_provider::{
+            LLMProviderInterface, LLMProviderRetryPolicy, LLMResponseCacheConfig, LLMUsageQuota, SerializedLLMProvider,
+        },
+    },
     shinkai_name::ShinkaiName,
 };
+use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::WSTopic;
+use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
+use shinkai_vector_resources::utils::hash_string;
 use tokio::sync::Mutex;
+use uuid::Uuid;
 
 #[derive(Debug, Clone)]
 pub struct LLMProvider {
@@ -27,6 +43,50 @@ pub struct LLMProvider {
     pub toolkit_permissions: Vec<String>,        // Todo: remove as not used
     pub storage_bucket_permissions: Vec<String>, // Todo: remove as not used
     pub allowed_message_senders: Vec<String>,    // list of sub-identities allowed to message the llm provider
+    pub retry_policy: LLMProviderRetryPolicy,
+    pub fallbacks: Vec<LLMProvider>, // tried in order when this provider keeps failing
+    pub usage_quota: Option<LLMUsageQuota>,
+    pub response_cache: Option<LLMResponseCacheConfig>,
+    pub response_cache_db: Option<Arc<ShinkaiDB>>, // responses are only cached when set (and enabled in response_cache)
+    pub inference_params: InferenceParams,         // sent as is to the fallbacks too
+}
+
+/// A single call made to an llm provider while answering a prompt (including the retries and fallbacks)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LLMProviderAttempt {
+    pub llm_provider_id: String,
+    pub model: String,
+    pub attempt: u32,
+    /// None if this attempt is the one that answered
+    pub error: Option<String>,
+    /// True if the response came from the response cache (no inference was made)
+    #[serde(default)]
+    pub cached: bool,
+    pub datetime: String,
+}
+
+impl LLMProviderAttempt {
+    fn new(llm_provider: &LLMProvider, attempt: u32, error: Option<String>) -> Self {
+        Self {
+            llm_provider_id: llm_provider.id.clone(),
+            model: format!(
+                "{}:{}",
+                llm_provider.model.provider_prefix(),
+                llm_provider.model.model_type()
+            ),
+            attempt,
+            error,
+            cached: false,
+            datetime: ShinkaiStringTime::generate_time_now(),
+        }
+    }
+
+    fn cache_hit(llm_provider: &LLMProvider) -> Self {
+        Self {
+            cached: true,
+            ..Self::new(llm_provider, 0, None)
+        }
+    }
 }
 
 impl LLMProvider {
@@ -57,134 +117,308 @@ impl LLMProvider {
             toolkit_permissions,
             storage_bucket_permissions,
             allowed_message_senders,
+            retry_policy: LLMProviderRetryPolicy::default(),
+            fallbacks: vec![],
+            usage_quota: None,
+            response_cache: None,
+            response_cache_db: None,
+            inference_params: InferenceParams::default(),
         }
     }
 
-    /// Inferences an LLM locally based on info held in the LLM Provider
-    /// TODO: For now just mocked, eventually get around to this, and create a struct that implements the Provider trait to unify local with remote interface.
-    async fn inference_locally(&self, content: String) -> Result<LLMInferenceResponse, LLMProviderError> {
-        // Here we run our GPU-intensive task on a separate thread
-        let handle = tokio::task::spawn_blocking(move || {
-            let mut map = Map::new();
-            map.insert(
-                "answer".to_string(),
-                JsonValue::String("\n\nHello there, how may I assist you today?".to_string()),
-            );
-            JsonValue::Object(map)
-        });
+    pub fn with_retry_policy(mut self, retry_policy: LLMProviderRetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
 
-        match handle.await {
-            Ok(response) => Ok(LLMInferenceResponse::new(content, response, None)),
-            Err(_e) => Err(LLMProviderError::InferenceFailed),
-        }
+    pub fn with_usage_quota(mut self, usage_quota: Option<LLMUsageQuota>) -> Self {
+        self.usage_quota = usage_quota;
+        self
+    }
+
+    pub fn with_response_cache(mut self, response_cache: Option<LLMResponseCacheConfig>) -> Self {
+        self.response_cache = response_cache;
+        self
     }
 
+    /// Sets where the responses are cached. Pass `None` to skip the cache (e.g. for jobs bypassing it).
+    pub fn with_response_cache_db(mut self, db: Option<Arc<ShinkaiDB>>) -> Self {
+        self.response_cache_db = db;
+        self
+    }
+
+    /// Sets the sampling of the inferences (e.g. the ones of the job), checked against the limits of each model
+    pub fn with_inference_params(mut self, inference_params: InferenceParams) -> Self {
+        self.inference_params = inference_params;
+        self
+    }
+
+    pub fn with_fallbacks(mut self, fallbacks: Vec<LLMProvider>) -> Self {
+        self.fallbacks = fallbacks;
+        self
+    }
+
+    /// Runs the inference, retrying and falling back to other providers as configured
     pub async fn inference(
         &self,
         prompt: Prompt,
         inbox_name: Option<InboxName>,
         ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
     ) -> Result<LLMInferenceResponse, LLMProviderError> {
-        let response = match &self.model {
-            LLMProviderInterface::OpenAI(openai) => {
-                openai
-                    .call_api(
-                        &self.client,
-                        self.external_url.as_ref(),
-                        self.api_key.as_ref(),
-                        prompt.clone(),
-                        self.model.clone(),
-                        inbox_name,
-                        ws_manager_trait,
-                    )
-                    .await
-            }
-            LLMProviderInterface::GenericAPI(genericapi) => {
-                genericapi
-                    .call_api(
-                        &self.client,
-                        self.external_url.as_ref(),
-                        self.api_key.as_ref(),
-                        prompt.clone(),
-                        self.model.clone(),
-                        inbox_name,
-                        ws_manager_trait,
-                    )
-                    .await
+        self.inference_with_attempts(prompt, inbox_name, ws_manager_trait)
+            .await
+            .0
+    }
+
+    /// Same as `inference` but also returns every attempt made, in order.
+    /// If the response cache is enabled, a cached response for the same prompt is returned without inferencing
+    /// (and streamed to the ws like a live one). Only the answers of this provider are cached, not of its fallbacks.
+    /// Otherwise each provider is retried following its own retry policy, then the fallbacks are tried in order
+    /// (skipping the ones missing a capability required by the prompt).
+    pub async fn inference_with_attempts(
+        &self,
+        prompt: Prompt,
+        inbox_name: Option<InboxName>,
+        ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> (Result<LLMInferenceResponse, LLMProviderError>, Vec<LLMProviderAttempt>) {
+        let cache_key = self.response_cache_key(&prompt);
+        if let Some(cached) = self.get_cached_response(cache_key.as_deref()) {
+            Self::send_cached_response_to_ws(&cached, inbox_name, ws_manager_trait).await;
+            return (Ok(cached), vec![LLMProviderAttempt::cache_hit(self)]);
+        }
+
+        let (response, attempts) = self
+            .inference_with_fallbacks(prompt, inbox_name, ws_manager_trait)
+            .await;
+        let answered_by_self = attempts
+            .last()
+            .is_some_and(|attempt| attempt.error.is_none() && attempt.llm_provider_id == self.id);
+        if let (Some(cache_key), Ok(response), true) = (cache_key.as_deref(), &response, answered_by_self) {
+            self.cache_response(cache_key, response);
+        }
+
+        (response, attempts)
+    }
+
+    async fn inference_with_fallbacks(
+        &self,
+        prompt: Prompt,
+        inbox_name: Option<InboxName>,
+        ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> (Result<LLMInferenceResponse, LLMProviderError>, Vec<LLMProviderAttempt>) {
+        let required_capabilities = Self::required_capabilities(&prompt);
+        let mut attempts = Vec::new();
+        let mut last_error = None;
+
+        for (index, llm_provider) in std::iter::once(self).chain(self.fallbacks.iter()).enumerate() {
+            if index > 0 {
+                let capabilities = ModelCapabilitiesManager::get_llm_provider_capabilities(&llm_provider.model);
+                if let Some(missing) = required_capabilities.iter().find(|c| !capabilities.contains(c)) {
+                    let error = format!("Skipped: missing capability {:?}", missing);
+                    attempts.push(LLMProviderAttempt::new(llm_provider, 0, Some(error)));
+                    continue;
+                }
             }
-            LLMProviderInterface::Ollama(ollama) => {
-                ollama
-                    .call_api(
-                        &self.client,
-                        self.external_url.as_ref(),
-                        self.api_key.as_ref(),
+
+            let max_attempts = llm_provider.retry_policy.max_attempts.max(1);
+            for attempt in 1..=max_attempts {
+                match llm_provider
+                    .inference_once(
                         prompt.clone(),
-                        self.model.clone(),
-                        inbox_name,
-                        ws_manager_trait,
+                        &self.inference_params,
+                        inbox_name.clone(),
+                        ws_manager_trait.clone(),
                     )
                     .await
+                {
+                    Ok(response) => {
+                        attempts.push(LLMProviderAttempt::new(llm_provider, attempt, None));
+                        return (Ok(response), attempts);
+                    }
+                    Err(e) => {
+                        shinkai_log(
+                            ShinkaiLogOption::JobExecution,
+                            ShinkaiLogLevel::Error,
+                            &format!(
+                                "LLM provider {} failed (attempt {}/{}): {}",
+                                llm_provider.id, attempt, max_attempts, e
+                            ),
+                        );
+                        attempts.push(LLMProviderAttempt::new(llm_provider, attempt, Some(e.to_error_json())));
+                        let retryable = llm_provider.retry_policy.is_retryable(e.error_name());
+                        last_error = Some(e);
+                        if !retryable || attempt == max_attempts {
+                            break;
+                        }
+                        let backoff = llm_provider.retry_policy.backoff_ms(attempt);
+                        tokio::time::sleep(std::time::Duration::from_millis(backoff)).await;
+                    }
+                }
             }
-            LLMProviderInterface::Exo(exo) => {
-                exo.call_api(
-                    &self.client,
-                    self.external_url.as_ref(),
-                    self.api_key.as_ref(),
-                    prompt.clone(),
-                    self.model.clone(),
-                    inbox_name,
-                    ws_manager_trait,
-                )
-                .await
-            }
-            LLMProviderInterface::ShinkaiBackend(shinkai_backend) => {
-                shinkai_backend
-                    .call_api(
-                        &self.client,
-                        self.external_url.as_ref(),
-                        self.api_key.as_ref(),
-                        prompt.clone(),
-                        self.model.clone(),
-                        inbox_name,
-                        ws_manager_trait,
-                    )
-                    .await
+        }
+
+        (Err(last_error.unwrap_or(LLMProviderError::InferenceFailed)), attempts)
+    }
+
+    /// Content-addressed key of the prompt in the response cache (None if caching is disabled)
+    fn response_cache_key(&self, prompt: &Prompt) -> Option<String> {
+        if self.response_cache.is_none() || self.response_cache_db.is_none() {
+            return None;
+        }
+        let rendered_prompt = serde_json::to_string(prompt).ok()?;
+        let rendered_params = serde_json::to_string(&self.inference_params).ok()?;
+        Some(hash_string(&format!(
+            "{}:{}:{}:{}:{}",
+            self.id,
+            self.model.provider_prefix(),
+            self.model.model_type(),
+            rendered_params,
+            rendered_prompt
+        )))
+    }
+
+    fn get_cached_response(&self, cache_key: Option<&str>) -> Option<LLMInferenceResponse> {
+        let (cache_key, db) = (cache_key?, self.response_cache_db.as_ref()?);
+        match db.get_cached_llm_response(cache_key, Utc::now()) {
+            Ok(cached) => cached.map(|cached| cached.to_inference_response()),
+            Err(e) => {
+                shinkai_log(
+                    ShinkaiLogOption::JobExecution,
+                    ShinkaiLogLevel::Error,
+                    &format!("Failed to read the response cache of {}: {}", self.id, e),
+                );
+                None
             }
-            LLMProviderInterface::Groq(groq) => {
-                groq.call_api(
-                    &self.client,
-                    self.external_url.as_ref(),
-                    self.api_key.as_ref(),
-                    prompt.clone(),
-                    self.model.clone(),
-                    inbox_name,
-                    ws_manager_trait,
+        }
+    }
+
+    fn cache_response(&self, cache_key: &str, response: &LLMInferenceResponse) {
+        let (Some(config), Some(db)) = (&self.response_cache, &self.response_cache_db) else {
+            return;
+        };
+        let cached = CachedLLMResponse::new(self.id.clone(), response, config.ttl_secs, Utc::now());
+        if let Err(e) = db.add_cached_llm_response(cache_key, &cached, config.max_entries) {
+            shinkai_log(
+                ShinkaiLogOption::JobExecution,
+                ShinkaiLogLevel::Error,
+                &format!("Failed to cache the response of {}: {}", self.id, e),
+            );
+        }
+    }
+
+    /// Streams a cached response like the providers do: the text as one chunk, then the end of the stream
+    async fn send_cached_response_to_ws(
+        response: &LLMInferenceResponse,
+        inbox_name: Option<InboxName>,
+        ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) {
+        let (Some(ws_manager), Some(inbox_name)) = (ws_manager_trait, inbox_name) else {
+            return;
+        };
+        let session_id = Uuid::new_v4().to_string();
+        let ws_manager = ws_manager.lock().await;
+        for (content, is_done) in [(response.response_string.clone(), false), ("".to_string(), true)] {
+            let metadata = WSMetadata {
+                id: Some(session_id.clone()),
+                is_done,
+                done_reason: is_done.then(|| "stop".to_string()),
+                total_duration: None,
+                eval_count: None,
+            };
+            ws_manager
+                .queue_message(
+                    WSTopic::Inbox,
+                    inbox_name.to_string(),
+                    content,
+                    WSMessageType::Metadata(metadata),
+                    true,
                 )
-                .await
-            }
-            LLMProviderInterface::Gemini(gemini) => {
-                gemini
-                    .call_api(
-                        &self.client,
-                        self.external_url.as_ref(),
-                        self.api_key.as_ref(),
-                        prompt.clone(),
-                        self.model.clone(),
-                        inbox_name,
-                        ws_manager_trait,
-                    )
-                    .await
-            }
-            LLMProviderInterface::LocalLLM(_local_llm) => {
-                self.inference_locally(prompt.generate_single_output_string()?).await
-            }
-        }?;
-        Ok(response)
+                .await;
+        }
+    }
+
+    /// Capabilities a fallback provider needs to be able to answer the prompt
+    fn required_capabilities(prompt: &Prompt) -> Vec<ModelCapability> {
+        let has_image = prompt
+            .sub_prompts
+            .iter()
+            .any(|sub_prompt| matches!(sub_prompt, SubPrompt::Asset(_, SubPromptAssetType::Image, _, _, _)));
+        if has_image {
+            vec![ModelCapability::ImageAnalysis]
+        } else {
+            vec![ModelCapability::TextInference]
+        }
+    }
+
+    /// Runs the inference through the backend registered for the model's provider.
+    /// Params out of the model's limits fail the attempt without calling the provider.
+    async fn inference_once(
+        &self,
+        prompt: Prompt,
+        inference_params: &InferenceParams,
+        inbox_name: Option<InboxName>,
+        ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> Result<LLMInferenceResponse, LLMProviderError> {
+        ModelCapabilitiesManager::validate_inference_params(&self.model, inference_params)?;
+
+        let backend = LLMProviderRegistry::get(&self.model)
+            .ok_or_else(|| LLMProviderError::ProviderBackendNotRegistered(self.model.provider_prefix()))?;
+
+        let response = backend
+            .call_api(
+                &self.client,
+                self.external_url.as_ref(),
+                self.api_key.as_ref(),
+                prompt.clone(),
+                inference_params.clone(),
+                self.model.clone(),
+                inbox_name,
+                ws_manager_trait,
+            )
+            .await?;
+
+        if response.usage.is_some() {
+            return Ok(response);
+        }
+        let usage = Self::estimate_usage(&prompt, &response);
+        Ok(response.with_usage(usage))
+    }
+
+    /// Estimates the tokens used by an inference (for backends that don't report them)
+    fn estimate_usage(prompt: &Prompt, response: &LLMInferenceResponse) -> LLMTokenUsage {
+        // Images are not counted as tokens (same as when preparing the messages)
+        let prompt_messages: Vec<LlmMessage> = prompt
+            .generate_openai_messages(None)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|message| message.name.as_deref() != Some("image"))
+            .collect();
+
+        let mut completion = response.response_string.clone();
+        if let Some(function_call) = &response.function_call {
+            completion.push_str(&function_call.name);
+            completion.push_str(&function_call.arguments.to_string());
+        }
+        let completion_message = LlmMessage {
+            role: Some("assistant".to_string()),
+            content: Some(completion),
+            ..Default::default()
+        };
+
+        LLMTokenUsage {
+            prompt_tokens: ModelCapabilitiesManager::num_tokens_from_messages(&prompt_messages) as u64,
+            completion_tokens: ModelCapabilitiesManager::num_tokens_from_messages(&[completion_message]) as u64,
+            estimated: true,
+        }
     }
 }
 
 impl LLMProvider {
+    /// Note: fallbacks are stored as ids, they need to be resolved and added with `with_fallbacks`
     pub fn from_serialized_llm_provider(serialized_llm_provider: SerializedLLMProvider) -> Self {
+        let retry_policy = serialized_llm_provider.retry_policy.clone();
+        let usage_quota = serialized_llm_provider.usage_quota.clone();
+        let response_cache = serialized_llm_provider.response_cache.clone();
         Self::new(
             serialized_llm_provider.id,
             serialized_llm_provider.full_identity_name,
@@ -196,5 +430,378 @@ impl LLMProvider {
             serialized_llm_provider.storage_bucket_permissions,
             serialized_llm_provider.allowed_message_senders,
         )
+        .with_retry_policy(retry_policy)
+        .with_usage_quota(usage_quota)
+        .with_response_cache(response_cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm_provider::execution::prompts::subprompts::SubPromptType;
+    use crate::llm_provider::providers::provider_registry::LLMProviderBackend;
+    use crate::managers::model_capabilities_manager::{ModelCapabilitiesManagerError, PromptResult};
+    use async_trait::async_trait;
+    use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::CustomProvider;
+
+    struct UnreachableBackend;
+
+    #[async_trait]
+    impl LLMProviderBackend for UnreachableBackend {
+        fn provider_prefix(&self) -> &str {
+            "unreachable"
+        }
+
+        async fn call_api(
+            &self,
+            _client: &Client,
+            _url: Option<&String>,
+            _api_key: Option<&String>,
+            _prompt: Prompt,
+            _inference_params: InferenceParams,
+            _model: LLMProviderInterface,
+            _inbox_name: Option<InboxName>,
+            _ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+        ) -> Result<LLMInferenceResponse, LLMProviderError> {
+            Err(LLMProviderError::NetworkError("connection refused".to_string()))
+        }
+
+        fn prepare_messages(
+            &self,
+            _model: &LLMProviderInterface,
+            _prompt: Prompt,
+        ) -> Result<PromptResult, ModelCapabilitiesManagerError> {
+            Err(ModelCapabilitiesManagerError::NotImplemented("unreachable".to_string()))
+        }
+    }
+
+    struct AnsweringBackend;
+
+    #[async_trait]
+    impl LLMProviderBackend for AnsweringBackend {
+        fn provider_prefix(&self) -> &str {
+            "answering"
+        }
+
+        async fn call_api(
+            &self,
+            _client: &Client,
+            _url: Option<&String>,
+            _api_key: Option<&String>,
+            _prompt: Prompt,
+            _inference_params: InferenceParams,
+            _model: LLMProviderInterface,
+            _inbox_name: Option<InboxName>,
+            _ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+        ) -> Result<LLMInferenceResponse, LLMProviderError> {
+            Ok(LLMInferenceResponse::new(
+                "hello".to_string(),
+                serde_json::json!({}),
+                None,
+            ))
+        }
+
+        fn prepare_messages(
+            &self,
+            _model: &LLMProviderInterface,
+            _prompt: Prompt,
+        ) -> Result<PromptResult, ModelCapabilitiesManagerError> {
+            Err(ModelCapabilitiesManagerError::NotImplemented("answering".to_string()))
+        }
+    }
+
+    fn custom_llm_provider(id: &str, provider: &str) -> LLMProvider {
+        LLMProvider::new(
+            id.to_string(),
+            ShinkaiName::new(format!("@@node.shinkai/main/agent/{}", id)).unwrap(),
+            false,
+            None,
+            None,
+            LLMProviderInterface::Custom(CustomProvider {
+                provider: provider.to_string(),
+                model_type: "test-model".to_string(),
+            }),
+            vec![],
+            vec![],
+            vec![],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_inference_retries_then_falls_back() {
+        LLMProviderRegistry::register(Arc::new(UnreachableBackend));
+        LLMProviderRegistry::register(Arc::new(AnsweringBackend));
+
+        let retry_policy = LLMProviderRetryPolicy {
+            max_attempts: 3,
+            initial_backoff_ms: 1,
+            ..Default::default()
+        };
+        let llm_provider = custom_llm_provider("primary", "unreachable")
+            .with_retry_policy(retry_policy)
+            .with_fallbacks(vec![custom_llm_provider("backup", "answering")]);
+
+        let mut prompt = Prompt::new();
+        prompt.add_content("hi".to_string(), SubPromptType::User, 100);
+        let (response, attempts) = llm_provider.inference_with_attempts(prompt, None, None).await;
+
+        assert_eq!(response.unwrap().response_string, "hello");
+        assert_eq!(attempts.len(), 4);
+        assert!(attempts[..3]
+            .iter()
+            .all(|a| a.llm_provider_id == "primary" && a.error.is_some()));
+        assert_eq!(attempts[2].attempt, 3);
+        assert_eq!(attempts[3].llm_provider_id, "backup");
+        assert_eq!(attempts[3].model, "answering:test-model");
+        assert_eq!(attempts[3].error, None);
+
+        // Image prompts skip fallbacks that can't analyze images
+        let mut prompt = Prompt::new();
+        prompt.add_asset(
+            SubPromptAssetType::Image,
+            "base64image".to_string(),
+            "auto".to_string(),
+            SubPromptType::User,
+            100,
+        );
+        let llm_provider = custom_llm_provider("primary", "unreachable")
+            .with_fallbacks(vec![custom_llm_provider("backup", "answering")]);
+        let (response, attempts) = llm_provider.inference_with_attempts(prompt, None, None).await;
+
+        assert!(matches!(response, Err(LLMProviderError::NetworkError(_))));
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[1].attempt, 0);
+        assert!(attempts[1].error.as_ref().unwrap().contains("ImageAnalysis"));
+    }
+
+    static COUNTING_BACKEND_CALLS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    struct CountingBackend;
+
+    #[async_trait]
+    impl LLMProviderBackend for CountingBackend {
+        fn provider_prefix(&self) -> &str {
+            "counting"
+        }
+
+        async fn call_api(
+            &self,
+            _client: &Client,
+            _url: Option<&String>,
+            _api_key: Option<&String>,
+            _prompt: Prompt,
+            _inference_params: InferenceParams,
+            _model: LLMProviderInterface,
+            _inbox_name: Option<InboxName>,
+            _ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+        ) -> Result<LLMInferenceResponse, LLMProviderError> {
+            let calls = COUNTING_BACKEND_CALLS.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            Ok(LLMInferenceResponse::new(
+                format!("call {}", calls),
+                serde_json::json!({}),
+                None,
+            ))
+        }
+
+        fn prepare_messages(
+            &self,
+            _model: &LLMProviderInterface,
+            _prompt: Prompt,
+        ) -> Result<PromptResult, ModelCapabilitiesManagerError> {
+            Err(ModelCapabilitiesManagerError::NotImplemented("counting".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inference_uses_response_cache() {
+        LLMProviderRegistry::register(Arc::new(CountingBackend));
+
+        let db_path = format!("db_tests/{}", hash_string("llm provider response cache"));
+        let _ = std::fs::remove_dir_all(std::path::Path::new(&db_path));
+        let db = Arc::new(ShinkaiDB::new(db_path.as_str()).unwrap());
+
+        let mut prompt = Prompt::new();
+        prompt.add_content("hi".to_string(), SubPromptType::User, 100);
+        let llm_provider = custom_llm_provider("cached", "counting")
+            .with_response_cache(Some(LLMResponseCacheConfig::default()))
+            .with_response_cache_db(Some(db.clone()));
+
+        let (response, attempts) = llm_provider.inference_with_attempts(prompt.clone(), None, None).await;
+        assert_eq!(response.unwrap().response_string, "call 1");
+        assert!(!attempts[0].cached);
+
+        // Same provider, model and prompt: answered from the cache
+        let (response, attempts) = llm_provider.inference_with_attempts(prompt.clone(), None, None).await;
+        assert_eq!(response.unwrap().response_string, "call 1");
+        assert_eq!(attempts.len(), 1);
+        assert!(attempts[0].cached);
+
+        // A different prompt or a provider without a cache db (e.g. the job bypasses the cache) calls the backend
+        let mut other_prompt = prompt.clone();
+        other_prompt.add_content("again".to_string(), SubPromptType::User, 100);
+        let (response, _) = llm_provider.inference_with_attempts(other_prompt, None, None).await;
+        assert_eq!(response.unwrap().response_string, "call 2");
+
+        let bypassing = llm_provider.with_response_cache_db(None);
+        let (response, attempts) = bypassing.inference_with_attempts(prompt, None, None).await;
+        assert_eq!(response.unwrap().response_string, "call 3");
+        assert!(!attempts[0].cached);
+    }
+
+    struct RecordingWSManager {
+        messages: Arc<std::sync::Mutex<Vec<(String, WSMessageType)>>>,
+    }
+
+    #[async_trait]
+    impl WSUpdateHandler for RecordingWSManager {
+        async fn queue_message(
+            &self,
+            _topic: WSTopic,
+            _subtopic: String,
+            update: String,
+            metadata: WSMessageType,
+            _is_stream: bool,
+        ) {
+            self.messages.lock().unwrap().push((update, metadata));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cached_response_is_streamed_and_fallback_answers_are_not_cached() {
+        LLMProviderRegistry::register(Arc::new(UnreachableBackend));
+        LLMProviderRegistry::register(Arc::new(AnsweringBackend));
+
+        let db_path = format!("db_tests/{}", hash_string("llm provider response cache ws"));
+        let _ = std::fs::remove_dir_all(std::path::Path::new(&db_path));
+        let db = Arc::new(ShinkaiDB::new(db_path.as_str()).unwrap());
+        let inbox_name = InboxName::get_job_inbox_name_from_params("job_cached".to_string()).unwrap();
+        let messages = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let ws_manager: Arc<Mutex<dyn WSUpdateHandler + Send>> = Arc::new(Mutex::new(RecordingWSManager {
+            messages: messages.clone(),
+        }));
+
+        let mut prompt = Prompt::new();
+        prompt.add_content("hi".to_string(), SubPromptType::User, 100);
+        let llm_provider = custom_llm_provider("cached_ws", "answering")
+            .with_response_cache(Some(LLMResponseCacheConfig::default()))
+            .with_response_cache_db(Some(db.clone()));
+        llm_provider
+            .inference_with_attempts(prompt.clone(), None, None)
+            .await
+            .0
+            .unwrap();
+
+        // The cache hit is streamed as one chunk followed by the end of the stream
+        let (response, attempts) = llm_provider
+            .inference_with_attempts(prompt.clone(), Some(inbox_name), Some(ws_manager))
+            .await;
+        assert_eq!(response.unwrap().response_string, "hello");
+        assert!(attempts[0].cached);
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages.len(), 2);
+        let (WSMessageType::Metadata(chunk), WSMessageType::Metadata(done)) = (&messages[0].1, &messages[1].1) else {
+            panic!("Expected metadata messages, got {:?}", messages);
+        };
+        assert_eq!(messages[0].0, "hello");
+        assert!(!chunk.is_done);
+        assert!(done.is_done);
+        assert_eq!(done.done_reason.as_deref(), Some("stop"));
+        assert_eq!(chunk.id, done.id);
+        drop(messages);
+
+        // An answer of a fallback is not cached under the key of the primary provider
+        let retry_policy = LLMProviderRetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        };
+        let llm_provider = custom_llm_provider("cached_primary", "unreachable")
+            .with_retry_policy(retry_policy)
+            .with_fallbacks(vec![custom_llm_provider("backup", "answering")])
+            .with_response_cache(Some(LLMResponseCacheConfig::default()))
+            .with_response_cache_db(Some(db));
+        for _ in 0..2 {
+            let (response, attempts) = llm_provider.inference_with_attempts(prompt.clone(), None, None).await;
+            assert_eq!(response.unwrap().response_string, "hello");
+            assert_eq!(attempts.len(), 2);
+            assert!(attempts.iter().all(|a| !a.cached));
+        }
+    }
+
+    struct SamplingBackend;
+
+    #[async_trait]
+    impl LLMProviderBackend for SamplingBackend {
+        fn provider_prefix(&self) -> &str {
+            "sampling"
+        }
+
+        async fn call_api(
+            &self,
+            _client: &Client,
+            _url: Option<&String>,
+            _api_key: Option<&String>,
+            _prompt: Prompt,
+            inference_params: InferenceParams,
+            _model: LLMProviderInterface,
+            _inbox_name: Option<InboxName>,
+            _ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+        ) -> Result<LLMInferenceResponse, LLMProviderError> {
+            Ok(LLMInferenceResponse::new(
+                format!("{:?} {:?}", inference_params.temperature, inference_params.seed),
+                serde_json::json!({}),
+                None,
+            ))
+        }
+
+        fn prepare_messages(
+            &self,
+            _model: &LLMProviderInterface,
+            _prompt: Prompt,
+        ) -> Result<PromptResult, ModelCapabilitiesManagerError> {
+            Err(ModelCapabilitiesManagerError::NotImplemented("sampling".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inference_params_are_validated_and_sent() {
+        LLMProviderRegistry::register(Arc::new(SamplingBackend));
+
+        let mut prompt = Prompt::new();
+        prompt.add_content("hi".to_string(), SubPromptType::User, 100);
+
+        let llm_provider = custom_llm_provider("sampling", "sampling").with_inference_params(InferenceParams {
+            temperature: Some(0.3),
+            seed: Some(7),
+            ..Default::default()
+        });
+        let response = llm_provider.inference(prompt.clone(), None, None).await.unwrap();
+        assert_eq!(response.response_string, "Some(0.3) Some(7)");
+
+        // Params out of the model's limits never reach the backend
+        for inference_params in [
+            InferenceParams {
+                temperature: Some(2.5),
+                ..Default::default()
+            },
+            InferenceParams {
+                top_p: Some(1.5),
+                ..Default::default()
+            },
+            InferenceParams {
+                max_tokens: Some(100_000),
+                ..Default::default()
+            },
+        ] {
+            let llm_provider = custom_llm_provider("sampling", "sampling").with_inference_params(inference_params);
+            let (response, attempts) = llm_provider.inference_with_attempts(prompt.clone(), None, None).await;
+            assert!(matches!(
+                response,
+                Err(LLMProviderError::LLMProviderCapabilitiesManagerError(
+                    ModelCapabilitiesManagerError::InvalidInferenceParams(_)
+                ))
+            ));
+            assert_eq!(attempts.len(), 1);
+        }
     }
 }
@@ -14,6 +14,10 @@ impl From<LLMProvider> for SerializedLLMProvider {
             toolkit_permissions: agent.toolkit_permissions,
             storage_bucket_permissions: agent.storage_bucket_permissions,
             allowed_message_senders: agent.allowed_message_senders,
+            retry_policy: agent.retry_policy,
+            fallback_llm_providers: agent.fallbacks.into_iter().map(|fallback| fallback.id).collect(),
+            usage_quota: agent.usage_quota,
+            response_cache: agent.response_cache,
         }
     }
 }
@@ -2,8 +2,11 @@
 pub mod llm_provider;
 pub mod llm_provider_to_serialization;
 pub mod error;
+pub mod eval;
 pub mod execution;
 pub mod job;
+pub mod job_archive;
+pub mod job_control;
 pub mod job_manager;
 pub mod parsing_helper;
 pub mod providers;
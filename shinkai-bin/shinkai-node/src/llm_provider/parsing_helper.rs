@@ -3,6 +3,7 @@ use super::execution::prompts::prompts::JobPromptGenerator;
 use super::execution::user_message_parser::{JobTaskElement, ParsedUserMessage};
 use super::job_manager::JobManager;
 use regex::Regex;
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
 use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::SerializedLLMProvider;
 use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
 use shinkai_vector_resources::embedding_generator::EmbeddingGenerator;
@@ -27,13 +28,21 @@ impl ParsingHelper {
 
         let mut extracted_answer: Option<String> = None;
         for _ in 0..5 {
-            let response_json =
-                match JobManager::inference_with_llm_provider(agent.clone(), prompt.clone(), None, None).await {
-                    Ok(json) => json,
-                    Err(_e) => {
-                        continue; // Continue to the next iteration on error
-                    }
-                };
+            let response_json = match JobManager::inference_with_llm_provider(
+                agent.clone(),
+                prompt.clone(),
+                InferenceParams::default(),
+                None,
+                None,
+                None,
+            )
+            .await
+            {
+                Ok(json) => json,
+                Err(_e) => {
+                    continue; // Continue to the next iteration on error
+                }
+            };
             extracted_answer = Some(response_json.response_string);
             break; // Exit the loop if successful
         }
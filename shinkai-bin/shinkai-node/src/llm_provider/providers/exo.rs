@@ -2,11 +2,16 @@ use crate::llm_provider::execution::chains::inference_chain_trait::LLMInferenceR
 use crate::llm_provider::providers::shared::ollama::{
     ollama_conversation_prepare_messages, OllamaAPIStreamingResponse,
 };
-use crate::managers::model_capabilities_manager::PromptResultEnum;
+use crate::managers::model_capabilities_manager::{
+    ModelCapabilitiesManager, ModelCapabilitiesManagerError, ModelCapability, ModelCost, ModelPrivacy, PromptResult,
+    PromptResultEnum,
+};
 use crate::network::ws_manager::{WSMessageType, WSMetadata, WSUpdateHandler};
 
 use super::super::{error::LLMProviderError, execution::prompts::prompts::Prompt};
 use super::ollama::truncate_image_content_in_payload;
+use super::provider_registry::LLMProviderBackend;
+use super::shared::shared_model_logic::llama_prepare_messages;
 use super::LLMService;
 use async_trait::async_trait;
 use futures::StreamExt;
@@ -16,6 +21,7 @@ use serde_json;
 use serde_json::json;
 use serde_json::Value as JsonValue;
 use shinkai_message_primitives::schemas::inbox_name::InboxName;
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
 use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{Exo, LLMProviderInterface, Ollama};
 use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::WSTopic;
 use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
@@ -64,6 +70,7 @@ impl LLMService for Exo {
         url: Option<&String>,
         _api_key: Option<&String>, // Note: not required
         prompt: Prompt,
+        inference_params: InferenceParams,
         model: LLMProviderInterface,
         inbox_name: Option<InboxName>,
         ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
@@ -102,7 +109,7 @@ impl LLMService for Exo {
             });
 
             // Modify payload to add options if needed
-            add_options_to_payload(&mut payload);
+            add_options_to_payload(&mut payload, &inference_params);
 
             let mut payload_log = payload.clone();
             truncate_image_content_in_payload(&mut payload_log);
@@ -201,7 +208,7 @@ impl LLMService for Exo {
     }
 }
 
-fn add_options_to_payload(payload: &mut serde_json::Value) {
+fn add_options_to_payload(payload: &mut serde_json::Value, inference_params: &InferenceParams) {
     let mut options = serde_json::Map::new();
 
     // Helper function to read and parse environment variables
@@ -298,8 +305,91 @@ fn add_options_to_payload(payload: &mut serde_json::Value) {
         options.insert("num_thread".to_string(), serde_json::json!(num_thread));
     }
 
+    if let Some(temperature) = inference_params.temperature {
+        options.insert("temperature".to_string(), serde_json::json!(temperature));
+    }
+    if let Some(top_p) = inference_params.top_p {
+        options.insert("top_p".to_string(), serde_json::json!(top_p));
+    }
+    if let Some(max_tokens) = inference_params.max_tokens {
+        options.insert("num_predict".to_string(), serde_json::json!(max_tokens));
+    }
+    if let Some(stop) = &inference_params.stop {
+        options.insert("stop".to_string(), serde_json::json!(stop));
+    }
+    if let Some(seed) = inference_params.seed {
+        options.insert("seed".to_string(), serde_json::json!(seed));
+    }
+
     // Add options to payload if not empty
     if !options.is_empty() {
         payload["options"] = serde_json::Value::Object(options);
     }
 }
+
+/// Registry backend for `exo:` models
+pub struct ExoBackend;
+
+#[async_trait]
+impl LLMProviderBackend for ExoBackend {
+    fn provider_prefix(&self) -> &str {
+        "exo"
+    }
+
+    async fn call_api(
+        &self,
+        client: &Client,
+        url: Option<&String>,
+        api_key: Option<&String>,
+        prompt: Prompt,
+        inference_params: InferenceParams,
+        model: LLMProviderInterface,
+        inbox_name: Option<InboxName>,
+        ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> Result<LLMInferenceResponse, LLMProviderError> {
+        match &model {
+            LLMProviderInterface::Exo(exo) => {
+                exo.call_api(
+                    client,
+                    url,
+                    api_key,
+                    prompt,
+                    inference_params,
+                    model.clone(),
+                    inbox_name,
+                    ws_manager_trait,
+                )
+                .await
+            }
+            _ => Err(LLMProviderError::InvalidModelType(format!(
+                "exo backend can't handle {:?}",
+                model
+            ))),
+        }
+    }
+
+    fn prepare_messages(
+        &self,
+        model: &LLMProviderInterface,
+        prompt: Prompt,
+    ) -> Result<PromptResult, ModelCapabilitiesManagerError> {
+        let total_tokens = self.max_tokens(model);
+        Ok(llama_prepare_messages(model, model.model_type(), prompt, total_tokens)?)
+    }
+
+    fn capabilities(&self, model: &LLMProviderInterface) -> Vec<ModelCapability> {
+        ModelCapabilitiesManager::get_capabilities_for_model_type(&model.model_type())
+    }
+
+    fn cost(&self, _model: &LLMProviderInterface) -> ModelCost {
+        ModelCost::Cheap
+    }
+
+    fn privacy(&self, _model: &LLMProviderInterface) -> ModelPrivacy {
+        ModelPrivacy::Local
+    }
+
+    fn max_tokens(&self, model: &LLMProviderInterface) -> usize {
+        ModelCapabilitiesManager::get_max_tokens_for_model_type(&model.model_type())
+    }
+}
@@ -1,10 +1,15 @@
 use std::sync::Arc;
 
 use super::super::{error::LLMProviderError, execution::prompts::prompts::Prompt};
-use super::shared::openai::openai_prepare_messages;
+use super::shared::openai::{openai_prepare_messages, FunctionCall};
+use super::provider_registry::LLMProviderBackend;
+use super::shared::shared_model_logic::llama_prepare_messages;
 use super::LLMService;
 use crate::llm_provider::execution::chains::inference_chain_trait::LLMInferenceResponse;
-use crate::managers::model_capabilities_manager::PromptResultEnum;
+use crate::managers::model_capabilities_manager::{
+    InferenceParamLimits, ModelCapabilitiesManagerError, ModelCapability, ModelCost, ModelPrivacy, PromptResult,
+    PromptResultEnum,
+};
 use crate::network::ws_manager::{WSMessageType, WSMetadata, WSUpdateHandler};
 use async_trait::async_trait;
 use futures::StreamExt;
@@ -13,6 +18,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use serde_json::Value as JsonValue;
 use shinkai_message_primitives::schemas::inbox_name::InboxName;
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
 use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{Gemini, LLMProviderInterface};
 use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::WSTopic;
 use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
@@ -41,7 +47,17 @@ struct StreamingContent {
 
 #[derive(Debug, Deserialize)]
 struct StreamingPart {
+    #[serde(default)]
     text: String,
+    #[serde(rename = "functionCall")]
+    function_call: Option<GeminiFunctionCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiFunctionCall {
+    name: String,
+    #[serde(default)]
+    args: JsonValue,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -96,6 +112,7 @@ impl LLMService for Gemini {
         url: Option<&String>,
         api_key: Option<&String>,
         prompt: Prompt,
+        inference_params: InferenceParams,
         model: LLMProviderInterface,
         inbox_name: Option<InboxName>,
         ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
@@ -122,29 +139,12 @@ impl LLMService for Gemini {
                 };
 
                 // Convert OpenAI-style messages to Gemini format
-                let contents: Vec<serde_json::Value> = messages
-                    .as_array()
-                    .ok_or_else(|| {
-                        LLMProviderError::UnexpectedPromptResultVariant("Expected array of messages".to_string())
-                    })?
-                    .iter()
-                    .map(|msg| {
-                        let role = match msg["role"].as_str() {
-                            Some("system") => "user", // Gemini doesn't have a system role, so we'll use user
-                            Some("assistant") => "model",
-                            Some("user") => "user",
-                            _ => "user",
-                        };
-                        json!({
-                            "role": role,
-                            "parts": [{
-                                "text": msg["content"]
-                            }]
-                        })
-                    })
-                    .collect();
+                let contents = gemini_contents_from_messages(messages.as_array().ok_or_else(|| {
+                    LLMProviderError::UnexpectedPromptResultVariant("Expected array of messages".to_string())
+                })?);
+                let function_declarations = gemini_function_declarations(result.functions.unwrap_or_default());
 
-                let payload = json!({
+                let mut payload = json!({
                     "contents": contents,
                     "generationConfig": {
                         "temperature": 0.9,
@@ -172,6 +172,12 @@ impl LLMService for Gemini {
                     ]
                 });
 
+                add_inference_params_to_payload(&mut payload, &inference_params);
+
+                if !function_declarations.is_empty() {
+                    payload["tools"] = json!([{ "functionDeclarations": function_declarations }]);
+                }
+
                 // Print payload as a pretty JSON string only if IS_TESTING is true
                 if std::env::var("IS_TESTING").unwrap_or_default() == "true" {
                     match serde_json::to_string_pretty(&payload) {
@@ -197,6 +203,7 @@ impl LLMService for Gemini {
                 let mut buffer = String::new();
                 let mut is_done = false;
                 let mut finish_reason = None;
+                let mut function_call = None;
 
                 while let Some(item) = stream.next().await {
                     match item {
@@ -205,6 +212,7 @@ impl LLMService for Gemini {
                                 &chunk,
                                 &mut buffer,
                                 &mut response_text,
+                                &mut function_call,
                                 &session_id,
                                 &ws_manager_trait,
                                 &inbox_name,
@@ -224,7 +232,7 @@ impl LLMService for Gemini {
                         }
                     }
                 }
-                Ok(LLMInferenceResponse::new(response_text, json!({}), None))
+                Ok(LLMInferenceResponse::new(response_text, json!({}), function_call))
             } else {
                 Err(LLMProviderError::ApiKeyNotSet)
             }
@@ -234,11 +242,95 @@ impl LLMService for Gemini {
     }
 }
 
+/// Converts the OpenAI-style messages into Gemini contents, including the function calls and responses
+fn gemini_contents_from_messages(messages: &[JsonValue]) -> Vec<JsonValue> {
+    messages
+        .iter()
+        .map(|msg| {
+            if let Some(function_call) = msg.get("function_call") {
+                let args = function_call["arguments"]
+                    .as_str()
+                    .and_then(|args| serde_json::from_str::<JsonValue>(args).ok())
+                    .unwrap_or_else(|| json!({}));
+                return json!({
+                    "role": "model",
+                    "parts": [{
+                        "functionCall": { "name": function_call["name"], "args": args }
+                    }]
+                });
+            }
+
+            let role = match msg["role"].as_str() {
+                Some("system") => "user", // Gemini doesn't have a system role, so we'll use user
+                Some("assistant") => "model",
+                Some("user") => "user",
+                Some("function") => {
+                    return json!({
+                        "role": "function",
+                        "parts": [{
+                            "functionResponse": {
+                                "name": msg["name"],
+                                "response": { "name": msg["name"], "content": msg["content"] }
+                            }
+                        }]
+                    })
+                }
+                _ => "user",
+            };
+            json!({
+                "role": role,
+                "parts": [{
+                    "text": msg["content"]
+                }]
+            })
+        })
+        .collect()
+}
+
+/// Gemini rejects object parameters without properties, so those functions are declared without parameters
+fn gemini_function_declarations(functions: Vec<JsonValue>) -> Vec<JsonValue> {
+    functions
+        .into_iter()
+        .map(|mut function| {
+            let has_properties = function["parameters"]["properties"]
+                .as_object()
+                .is_some_and(|properties| !properties.is_empty());
+            if !has_properties {
+                if let Some(function) = function.as_object_mut() {
+                    function.remove("parameters");
+                }
+            }
+            function
+        })
+        .collect()
+}
+
+/// Sets the inference params of the job in the generationConfig of the payload
+fn add_inference_params_to_payload(payload: &mut JsonValue, inference_params: &InferenceParams) {
+    let generation_config = &mut payload["generationConfig"];
+    if let Some(temperature) = inference_params.temperature {
+        generation_config["temperature"] = json!(temperature);
+    }
+    if let Some(top_p) = inference_params.top_p {
+        generation_config["topP"] = json!(top_p);
+    }
+    if let Some(max_tokens) = inference_params.max_tokens {
+        generation_config["maxOutputTokens"] = json!(max_tokens);
+    }
+    if let Some(stop) = &inference_params.stop {
+        generation_config["stopSequences"] = json!(stop);
+    }
+    if let Some(seed) = inference_params.seed {
+        generation_config["seed"] = json!(seed);
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn process_chunk(
     chunk: &[u8],
     buffer: &mut String,
     response_text: &mut String,
+    function_call: &mut Option<FunctionCall>,
     session_id: &str,
     ws_manager_trait: &Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
     inbox_name: &Option<InboxName>,
@@ -269,6 +361,7 @@ async fn process_chunk(
                 process_gemini_response(
                     value,
                     response_text,
+                    function_call,
                     session_id,
                     ws_manager_trait,
                     inbox_name,
@@ -317,9 +410,11 @@ async fn process_chunk(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn process_gemini_response(
     value: JsonValue,
     response_text: &mut String,
+    function_call: &mut Option<FunctionCall>,
     session_id: &str,
     ws_manager_trait: &Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
     inbox_name: &Option<InboxName>,
@@ -329,9 +424,19 @@ async fn process_gemini_response(
     if let Ok(response) = serde_json::from_value::<GeminiStreamingResponse>(value) {
         for candidate in &response.candidates {
             for part in &candidate.content.parts {
+                finish_reason.clone_from(&candidate.finish_reason);
+                if let Some(call) = &part.function_call {
+                    if function_call.is_none() {
+                        *function_call = Some(FunctionCall {
+                            name: call.name.clone(),
+                            arguments: call.args.clone(),
+                        });
+                    }
+                    continue;
+                }
+
                 let content = &part.text;
                 response_text.push_str(content);
-                finish_reason.clone_from(&candidate.finish_reason);
 
                 if let Some(ref manager) = ws_manager_trait {
                     if let Some(ref inbox_name) = inbox_name {
@@ -365,6 +470,85 @@ async fn process_gemini_response(
     Ok(())
 }
 
+/// Registry backend for `gemini:` models
+pub struct GeminiBackend;
+
+#[async_trait]
+impl LLMProviderBackend for GeminiBackend {
+    fn provider_prefix(&self) -> &str {
+        "gemini"
+    }
+
+    async fn call_api(
+        &self,
+        client: &Client,
+        url: Option<&String>,
+        api_key: Option<&String>,
+        prompt: Prompt,
+        inference_params: InferenceParams,
+        model: LLMProviderInterface,
+        inbox_name: Option<InboxName>,
+        ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> Result<LLMInferenceResponse, LLMProviderError> {
+        match &model {
+            LLMProviderInterface::Gemini(gemini) => {
+                gemini
+                    .call_api(
+                        client,
+                        url,
+                        api_key,
+                        prompt,
+                        inference_params,
+                        model.clone(),
+                        inbox_name,
+                        ws_manager_trait,
+                    )
+                    .await
+            }
+            _ => Err(LLMProviderError::InvalidModelType(format!(
+                "gemini backend can't handle {:?}",
+                model
+            ))),
+        }
+    }
+
+    fn prepare_messages(
+        &self,
+        model: &LLMProviderInterface,
+        prompt: Prompt,
+    ) -> Result<PromptResult, ModelCapabilitiesManagerError> {
+        let total_tokens = self.max_tokens(model);
+        Ok(llama_prepare_messages(model, model.model_type(), prompt, total_tokens)?)
+    }
+
+    fn capabilities(&self, _model: &LLMProviderInterface) -> Vec<ModelCapability> {
+        vec![ModelCapability::TextInference, ModelCapability::ImageAnalysis]
+    }
+
+    fn cost(&self, _model: &LLMProviderInterface) -> ModelCost {
+        ModelCost::Cheap
+    }
+
+    fn inference_param_limits(&self, _model: &LLMProviderInterface) -> InferenceParamLimits {
+        InferenceParamLimits {
+            max_temperature: 2.0,
+            max_stop_sequences: Some(5),
+        }
+    }
+
+    fn privacy(&self, _model: &LLMProviderInterface) -> ModelPrivacy {
+        ModelPrivacy::RemoteGreedy
+    }
+
+    fn supports_tool_calls(&self, _model: &LLMProviderInterface) -> bool {
+        true
+    }
+
+    fn max_tokens(&self, _model: &LLMProviderInterface) -> usize {
+        1_000_000
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -402,11 +586,13 @@ mod tests {
         let inbox_name: Option<InboxName> = None;
         let mut is_done = false;
         let mut finish_reason = None;
+        let mut function_call = None;
 
         process_chunk(
             chunk,
             &mut buffer,
             &mut response_text,
+            &mut function_call,
             session_id,
             &ws_manager_trait,
             &inbox_name,
@@ -471,11 +657,13 @@ mod tests {
         let inbox_name: Option<InboxName> = None;
         let mut is_done = false;
         let mut finish_reason = None;
+        let mut function_call = None;
 
         process_chunk(
             chunk,
             &mut buffer,
             &mut response_text,
+            &mut function_call,
             session_id,
             &ws_manager_trait,
             &inbox_name,
@@ -543,11 +731,13 @@ mod tests {
         let inbox_name: Option<InboxName> = None;
         let mut is_done = false;
         let mut finish_reason = None;
+        let mut function_call = None;
 
         process_chunk(
             chunk,
             &mut buffer,
             &mut response_text,
+            &mut function_call,
             session_id,
             &ws_manager_trait,
             &inbox_name,
@@ -561,4 +751,90 @@ mod tests {
         assert!(is_done);
         assert_eq!(finish_reason, Some("STOP".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_process_function_call_chunk() {
+        let chunk = b"[{
+            \"candidates\": [
+                {
+                    \"content\": {
+                        \"parts\": [
+                            {
+                                \"functionCall\": {
+                                    \"name\": \"concat_strings\",
+                                    \"args\": { \"first_string\": \"hola\", \"second_string\": \"chao\" }
+                                }
+                            }
+                        ],
+                        \"role\": \"model\"
+                    },
+                    \"finishReason\": \"STOP\",
+                    \"index\": 0
+                }
+            ]
+        }]";
+
+        let mut buffer = String::new();
+        let mut response_text = String::new();
+        let session_id = "test_session_id";
+        let ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>> = None;
+        let inbox_name: Option<InboxName> = None;
+        let mut is_done = false;
+        let mut finish_reason = None;
+        let mut function_call = None;
+
+        process_chunk(
+            chunk,
+            &mut buffer,
+            &mut response_text,
+            &mut function_call,
+            session_id,
+            &ws_manager_trait,
+            &inbox_name,
+            &mut is_done,
+            &mut finish_reason,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response_text, "");
+        let function_call = function_call.unwrap();
+        assert_eq!(function_call.name, "concat_strings");
+        assert_eq!(
+            function_call.arguments,
+            json!({"first_string": "hola", "second_string": "chao"})
+        );
+    }
+
+    #[test]
+    fn test_gemini_contents_from_messages_with_function_call() {
+        let messages = vec![
+            json!({ "role": "user", "content": "concatenate hola and chao" }),
+            json!({
+                "role": "assistant",
+                "function_call": { "name": "concat_strings", "arguments": "{\"first_string\":\"hola\"}" }
+            }),
+            json!({ "role": "function", "name": "concat_strings", "content": "holachao" }),
+        ];
+
+        let contents = gemini_contents_from_messages(&messages);
+        assert_eq!(
+            contents[0],
+            json!({ "role": "user", "parts": [{ "text": "concatenate hola and chao" }] })
+        );
+        assert_eq!(
+            contents[1]["parts"][0]["functionCall"],
+            json!({ "name": "concat_strings", "args": { "first_string": "hola" } })
+        );
+        assert_eq!(contents[2]["role"], "function");
+        assert_eq!(
+            contents[2]["parts"][0]["functionResponse"]["response"]["content"],
+            "holachao"
+        );
+
+        let declarations = gemini_function_declarations(vec![
+            json!({ "name": "now", "description": "Current time", "parameters": { "type": "object", "properties": {}, "required": [] } }),
+        ]);
+        assert!(declarations[0].get("parameters").is_none());
+    }
 }
@@ -1,11 +1,16 @@
 use std::sync::Arc;
 
 use crate::llm_provider::execution::chains::inference_chain_trait::LLMInferenceResponse;
-use crate::managers::model_capabilities_manager::ModelCapabilitiesManager;
+use crate::managers::model_capabilities_manager::{
+    ModelCapabilitiesManager, ModelCapabilitiesManagerError, ModelCapability, ModelCost, ModelPrivacy, PromptResult,
+};
 use crate::network::ws_manager::WSUpdateHandler;
 
 use super::super::{error::LLMProviderError, execution::prompts::prompts::Prompt};
+use super::shared::openai::openai_add_inference_params;
 use super::shared::togetherai::TogetherAPIResponse;
+use super::provider_registry::LLMProviderBackend;
+use super::shared::shared_model_logic::llama_prepare_messages;
 use super::LLMService;
 use async_trait::async_trait;
 use reqwest::Client;
@@ -13,6 +18,7 @@ use reqwest::Client;
 use serde_json;
 use serde_json::json;
 use shinkai_message_primitives::schemas::inbox_name::InboxName;
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
 use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{GenericAPI, LLMProviderInterface};
 use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
 use tokio::sync::Mutex;
@@ -25,6 +31,7 @@ impl LLMService for GenericAPI {
         url: Option<&String>,
         api_key: Option<&String>,
         prompt: Prompt,
+        inference_params: InferenceParams,
         model: LLMProviderInterface,
         inbox_name: Option<InboxName>,
         _ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
@@ -44,7 +51,7 @@ impl LLMService for GenericAPI {
                     format!("Messages JSON: {:?}", messages_string).as_str(),
                 );
 
-                let payload = json!({
+                let mut payload = json!({
                     "model": self.model_type,
                     "max_tokens": max_output_tokens,
                     "prompt": messages_string,
@@ -65,6 +72,15 @@ impl LLMService for GenericAPI {
                     "repetitive_penalty": 1,
                 });
 
+                // The stop sequences of the job are added to the end of turn markers of the models
+                let default_stop = payload["stop"].clone();
+                openai_add_inference_params(&mut payload, &inference_params);
+                if let (Some(default_stop), Some(stop)) = (default_stop.as_array(), &inference_params.stop) {
+                    let mut stop_sequences = default_stop.clone();
+                    stop_sequences.extend(stop.iter().map(|sequence| json!(sequence)));
+                    payload["stop"] = serde_json::Value::Array(stop_sequences);
+                }
+
                 shinkai_log(
                     ShinkaiLogOption::JobExecution,
                     ShinkaiLogLevel::Debug,
@@ -123,3 +139,105 @@ impl LLMService for GenericAPI {
         }
     }
 }
+
+/// Registry backend for `genericapi:` models
+pub struct GenericAPIBackend;
+
+#[async_trait]
+impl LLMProviderBackend for GenericAPIBackend {
+    fn provider_prefix(&self) -> &str {
+        "genericapi"
+    }
+
+    async fn call_api(
+        &self,
+        client: &Client,
+        url: Option<&String>,
+        api_key: Option<&String>,
+        prompt: Prompt,
+        inference_params: InferenceParams,
+        model: LLMProviderInterface,
+        inbox_name: Option<InboxName>,
+        ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> Result<LLMInferenceResponse, LLMProviderError> {
+        match &model {
+            LLMProviderInterface::GenericAPI(genericapi) => {
+                genericapi
+                    .call_api(
+                        client,
+                        url,
+                        api_key,
+                        prompt,
+                        inference_params,
+                        model.clone(),
+                        inbox_name,
+                        ws_manager_trait,
+                    )
+                    .await
+            }
+            _ => Err(LLMProviderError::InvalidModelType(format!(
+                "genericapi backend can't handle {:?}",
+                model
+            ))),
+        }
+    }
+
+    fn prepare_messages(
+        &self,
+        model: &LLMProviderInterface,
+        prompt: Prompt,
+    ) -> Result<PromptResult, ModelCapabilitiesManagerError> {
+        let model_type = model.model_type();
+        if model_type.starts_with("togethercomputer/llama-2") || model_type.starts_with("meta-llama/Llama-3") {
+            let total_tokens = self.max_tokens(model);
+            Ok(llama_prepare_messages(model, model_type, prompt, total_tokens)?)
+        } else {
+            Err(ModelCapabilitiesManagerError::NotImplemented(model_type))
+        }
+    }
+
+    fn capabilities(&self, model: &LLMProviderInterface) -> Vec<ModelCapability> {
+        match model.model_type().as_str() {
+            "togethercomputer/llama-2-70b-chat" => vec![ModelCapability::TextInference],
+            "yorickvp/llava-13b" => vec![ModelCapability::ImageAnalysis],
+            model_type if model_type.starts_with("togethercomputer/llama-2") => vec![ModelCapability::TextInference],
+            _ => vec![],
+        }
+    }
+
+    fn cost(&self, model: &LLMProviderInterface) -> ModelCost {
+        match model.model_type().as_str() {
+            "togethercomputer/llama-2-70b-chat" => ModelCost::Cheap,
+            "togethercomputer/llama3" => ModelCost::Cheap,
+            "yorickvp/llava-13b" => ModelCost::Expensive,
+            _ => ModelCost::Unknown,
+        }
+    }
+
+    fn privacy(&self, _model: &LLMProviderInterface) -> ModelPrivacy {
+        ModelPrivacy::RemoteGreedy
+    }
+
+    fn max_tokens(&self, model: &LLMProviderInterface) -> usize {
+        let model_type = model.model_type();
+        if model_type == "mistralai/Mixtral-8x7B-Instruct-v0.1" {
+            32_000
+        } else if model_type.starts_with("mistralai/Mistral-7B-Instruct-v0.2") {
+            16_000
+        } else if model_type.starts_with("meta-llama/Llama-3") {
+            8_000
+        } else if model_type.starts_with("mistralai/Mixtral-8x22B") {
+            65_000
+        } else {
+            4096
+        }
+    }
+
+    fn max_output_tokens(&self, model: &LLMProviderInterface) -> usize {
+        if self.max_tokens(model) <= 8000 {
+            2800
+        } else {
+            4096
+        }
+    }
+}
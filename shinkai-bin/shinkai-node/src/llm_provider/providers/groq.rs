@@ -1,10 +1,18 @@
 use std::sync::Arc;
 
 use super::super::{error::LLMProviderError, execution::prompts::prompts::Prompt};
-use super::shared::openai::{openai_prepare_messages, MessageContent, OpenAIResponse};
+use super::shared::openai::{
+    openai_add_inference_params, openai_messages_with_tool_calls, openai_prepare_messages, openai_tools_from_functions,
+    MessageContent, OpenAIResponse,
+};
+use super::provider_registry::LLMProviderBackend;
+use super::shared::shared_model_logic::llama_prepare_messages;
 use super::LLMService;
 use crate::llm_provider::execution::chains::inference_chain_trait::LLMInferenceResponse;
-use crate::managers::model_capabilities_manager::{ModelCapabilitiesManager, PromptResultEnum};
+use crate::managers::model_capabilities_manager::{
+    InferenceParamLimits, ModelCapabilitiesManager, ModelCapabilitiesManagerError, ModelCapability, ModelCost,
+    ModelPrivacy, PromptResult, PromptResultEnum,
+};
 use crate::network::ws_manager::WSUpdateHandler;
 use async_trait::async_trait;
 use reqwest::Client;
@@ -12,6 +20,7 @@ use serde_json::json;
 use serde_json::Value as JsonValue;
 use serde_json::{self};
 use shinkai_message_primitives::schemas::inbox_name::InboxName;
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
 use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{Groq, LLMProviderInterface};
 use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
 use tokio::sync::Mutex;
@@ -24,6 +33,7 @@ impl LLMService for Groq {
         url: Option<&String>,
         api_key: Option<&String>,
         prompt: Prompt,
+        inference_params: InferenceParams,
         _model: LLMProviderInterface,
         inbox_name: Option<InboxName>,
         _ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
@@ -63,7 +73,11 @@ impl LLMService for Groq {
                                 }
                             }
                         }
-                        v
+                        // Groq only supports the `tools` API
+                        match v {
+                            JsonValue::Array(messages) => JsonValue::Array(openai_messages_with_tool_calls(messages)),
+                            v => v,
+                        }
                     }
                     _ => {
                         return Err(LLMProviderError::UnexpectedPromptResultVariant(
@@ -72,13 +86,22 @@ impl LLMService for Groq {
                     }
                 };
 
-                let payload = json!({
+                let tools_json = openai_tools_from_functions(result.functions.unwrap_or_default());
+
+                let mut payload = json!({
                     "model": self.model_type,
                     "messages": messages_json,
                     "temperature": 0.7,
                     "max_tokens": result.remaining_tokens,
                 });
 
+                // Conditionally add tools to the payload if tools_json is not empty
+                if !tools_json.is_empty() {
+                    payload["tools"] = JsonValue::Array(tools_json);
+                }
+
+                openai_add_inference_params(&mut payload, &inference_params);
+
                 let payload_log = payload.clone();
                 shinkai_log(
                     ShinkaiLogOption::JobExecution,
@@ -138,7 +161,9 @@ impl LLMService for Groq {
                             })
                             .collect::<Vec<String>>()
                             .join(" ");
-                        Ok(LLMInferenceResponse::new(response_string, json!({}), None))
+                        let function_call = data.choices.iter().find_map(|choice| choice.message.tool_call());
+                        Ok(LLMInferenceResponse::new(response_string, json!({}), function_call)
+                            .with_usage(data.usage.to_token_usage()))
                     }
                     Err(e) => {
                         shinkai_log(
@@ -157,3 +182,81 @@ impl LLMService for Groq {
         }
     }
 }
+
+/// Registry backend for `groq:` models
+pub struct GroqBackend;
+
+#[async_trait]
+impl LLMProviderBackend for GroqBackend {
+    fn provider_prefix(&self) -> &str {
+        "groq"
+    }
+
+    async fn call_api(
+        &self,
+        client: &Client,
+        url: Option<&String>,
+        api_key: Option<&String>,
+        prompt: Prompt,
+        inference_params: InferenceParams,
+        model: LLMProviderInterface,
+        inbox_name: Option<InboxName>,
+        ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> Result<LLMInferenceResponse, LLMProviderError> {
+        match &model {
+            LLMProviderInterface::Groq(groq) => {
+                groq.call_api(
+                    client,
+                    url,
+                    api_key,
+                    prompt,
+                    inference_params,
+                    model.clone(),
+                    inbox_name,
+                    ws_manager_trait,
+                )
+                .await
+            }
+            _ => Err(LLMProviderError::InvalidModelType(format!(
+                "groq backend can't handle {:?}",
+                model
+            ))),
+        }
+    }
+
+    fn prepare_messages(
+        &self,
+        model: &LLMProviderInterface,
+        prompt: Prompt,
+    ) -> Result<PromptResult, ModelCapabilitiesManagerError> {
+        let total_tokens = self.max_tokens(model);
+        Ok(llama_prepare_messages(model, model.model_type(), prompt, total_tokens)?)
+    }
+
+    fn capabilities(&self, model: &LLMProviderInterface) -> Vec<ModelCapability> {
+        ModelCapabilitiesManager::get_capabilities_for_model_type(&model.model_type())
+    }
+
+    fn cost(&self, _model: &LLMProviderInterface) -> ModelCost {
+        ModelCost::VeryCheap
+    }
+
+    fn inference_param_limits(&self, _model: &LLMProviderInterface) -> InferenceParamLimits {
+        InferenceParamLimits {
+            max_temperature: 2.0,
+            max_stop_sequences: Some(4),
+        }
+    }
+
+    fn privacy(&self, _model: &LLMProviderInterface) -> ModelPrivacy {
+        ModelPrivacy::RemoteGreedy
+    }
+
+    fn supports_tool_calls(&self, _model: &LLMProviderInterface) -> bool {
+        true
+    }
+
+    fn max_tokens(&self, model: &LLMProviderInterface) -> usize {
+        ModelCapabilitiesManager::get_max_tokens_for_model_type(&model.model_type())
+    }
+}
@@ -0,0 +1,224 @@
+use std::sync::Arc;
+
+use super::super::{error::LLMProviderError, execution::prompts::prompts::Prompt};
+use super::provider_registry::LLMProviderBackend;
+use super::shared::openai::openai_prepare_messages;
+use super::LLMService;
+use crate::llm_provider::execution::chains::inference_chain_trait::LLMInferenceResponse;
+use crate::managers::model_capabilities_manager::{
+    ModelCapabilitiesManagerError, ModelCost, ModelPrivacy, PromptResult,
+};
+use crate::network::ws_manager::WSUpdateHandler;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use shinkai_message_primitives::schemas::inbox_name::InboxName;
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
+use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{
+    CustomProvider, LLMProviderInterface, SerializedLLMProvider,
+};
+use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
+use tokio::sync::Mutex;
+
+/// Provider prefix of the mock llm (used with `LLMProviderInterface::Custom`)
+pub const MOCK_LLM_PROVIDER_PREFIX: &str = "mock";
+
+/// A scripted answer of the mock llm
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MockLLMResponse {
+    /// The answer is only returned for the prompts containing this text (any prompt if unset)
+    #[serde(default)]
+    pub prompt_contains: Option<String>,
+    pub response: String,
+}
+
+/// LLM answering with scripted responses instead of calling a provider, so inference chains can run
+/// without network (e.g. in CI). The first response whose condition matches the rendered prompt is returned,
+/// which keeps the answers deterministic regardless of the order of the calls.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MockLLM {
+    #[serde(default)]
+    pub responses: Vec<MockLLMResponse>,
+    /// Returned when no response matches. Without it the inference fails.
+    #[serde(default)]
+    pub default_response: Option<String>,
+}
+
+impl MockLLM {
+    pub fn new(responses: Vec<MockLLMResponse>, default_response: Option<String>) -> Self {
+        Self {
+            responses,
+            default_response,
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, LLMProviderError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Returns the scripted response for a rendered prompt
+    pub fn response_for(&self, prompt: &str) -> Option<String> {
+        self.responses
+            .iter()
+            .find(|response| {
+                response
+                    .prompt_contains
+                    .as_ref()
+                    .map_or(true, |text| prompt.contains(text.as_str()))
+            })
+            .map(|response| response.response.clone())
+            .or_else(|| self.default_response.clone())
+    }
+
+    /// Model served by the mock backend once it's registered in the `LLMProviderRegistry`
+    pub fn model(model_type: &str) -> LLMProviderInterface {
+        LLMProviderInterface::Custom(CustomProvider {
+            provider: MOCK_LLM_PROVIDER_PREFIX.to_string(),
+            model_type: model_type.to_string(),
+        })
+    }
+
+    /// LLM provider using the mock model
+    pub fn serialized_llm_provider(id: &str, full_identity_name: ShinkaiName) -> SerializedLLMProvider {
+        SerializedLLMProvider {
+            id: id.to_string(),
+            full_identity_name,
+            perform_locally: false,
+            external_url: None,
+            api_key: None,
+            model: Self::model("scripted"),
+            toolkit_permissions: vec![],
+            storage_bucket_permissions: vec![],
+            allowed_message_senders: vec![],
+            retry_policy: Default::default(),
+            fallback_llm_providers: vec![],
+            usage_quota: None,
+            response_cache: None,
+        }
+    }
+}
+
+#[async_trait]
+impl LLMService for MockLLM {
+    async fn call_api(
+        &self,
+        _client: &Client,
+        _url: Option<&String>,
+        _api_key: Option<&String>,
+        prompt: Prompt,
+        _inference_params: InferenceParams,
+        _model: LLMProviderInterface,
+        _inbox_name: Option<InboxName>,
+        _ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> Result<LLMInferenceResponse, LLMProviderError> {
+        let rendered_prompt = prompt.generate_single_output_string()?;
+        match self.response_for(&rendered_prompt) {
+            Some(response) => Ok(LLMInferenceResponse::new(
+                response.clone(),
+                json!({ "answer": response }),
+                None,
+            )),
+            None => Err(LLMProviderError::LLMServiceUnexpectedError(
+                "The mock llm has no scripted response for the prompt".to_string(),
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl LLMProviderBackend for MockLLM {
+    fn provider_prefix(&self) -> &str {
+        MOCK_LLM_PROVIDER_PREFIX
+    }
+
+    async fn call_api(
+        &self,
+        client: &Client,
+        url: Option<&String>,
+        api_key: Option<&String>,
+        prompt: Prompt,
+        inference_params: InferenceParams,
+        model: LLMProviderInterface,
+        inbox_name: Option<InboxName>,
+        ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> Result<LLMInferenceResponse, LLMProviderError> {
+        LLMService::call_api(
+            self,
+            client,
+            url,
+            api_key,
+            prompt,
+            inference_params,
+            model,
+            inbox_name,
+            ws_manager_trait,
+        )
+        .await
+    }
+
+    fn prepare_messages(
+        &self,
+        model: &LLMProviderInterface,
+        prompt: Prompt,
+    ) -> Result<PromptResult, ModelCapabilitiesManagerError> {
+        Ok(openai_prepare_messages(model, prompt)?)
+    }
+
+    fn cost(&self, _model: &LLMProviderInterface) -> ModelCost {
+        ModelCost::Free
+    }
+
+    fn privacy(&self, _model: &LLMProviderInterface) -> ModelPrivacy {
+        ModelPrivacy::Local
+    }
+
+    fn max_tokens(&self, _model: &LLMProviderInterface) -> usize {
+        32_000
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm_provider::execution::prompts::subprompts::SubPromptType;
+
+    #[tokio::test]
+    async fn test_mock_llm_scripted_responses() {
+        let mock = MockLLM::from_json(
+            r#"{
+                "responses": [
+                    { "prompt_contains": "capital of France", "response": "Paris" },
+                    { "prompt_contains": "capital", "response": "I don't know that capital" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let mut prompt = Prompt::new();
+        prompt.add_content("What is the capital of France?".to_string(), SubPromptType::User, 100);
+        let response = LLMService::call_api(
+            &mock,
+            &Client::new(),
+            None,
+            None,
+            prompt,
+            InferenceParams::default(),
+            MockLLM::model("scripted"),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.response_string, "Paris");
+
+        assert_eq!(
+            mock.response_for("What is the capital of Peru?"),
+            Some("I don't know that capital".to_string())
+        );
+        assert_eq!(mock.response_for("Hello"), None);
+
+        let mock = MockLLM::new(vec![], Some("Hi".to_string()));
+        assert_eq!(mock.response_for("Hello"), Some("Hi".to_string()));
+    }
+}
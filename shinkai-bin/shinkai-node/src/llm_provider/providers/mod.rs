@@ -9,7 +9,10 @@ use super::{
 use async_trait::async_trait;
 use reqwest::Client;
 use serde_json::Value as JsonValue;
-use shinkai_message_primitives::schemas::{inbox_name::InboxName, llm_providers::serialized_llm_provider::LLMProviderInterface};
+use shinkai_message_primitives::schemas::{
+    inbox_name::InboxName,
+    llm_providers::{inference_params::InferenceParams, serialized_llm_provider::LLMProviderInterface},
+};
 use tokio::sync::Mutex;
 
 pub mod genericapi;
@@ -20,6 +23,10 @@ pub mod shared;
 pub mod shinkai_backend;
 pub mod gemini;
 pub mod exo;
+pub mod anthropic;
+pub mod local_llm;
+pub mod mock;
+pub mod provider_registry;
 
 #[async_trait]
 pub trait LLMService {
@@ -32,6 +39,7 @@ pub trait LLMService {
         url: Option<&String>,
         api_key: Option<&String>,
         prompt: Prompt,
+        inference_params: InferenceParams,
         model: LLMProviderInterface,
         inbox_name: Option<InboxName>,
         ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
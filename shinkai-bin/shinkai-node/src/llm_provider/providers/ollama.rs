@@ -2,10 +2,15 @@ use crate::llm_provider::execution::chains::inference_chain_trait::LLMInferenceR
 use crate::llm_provider::providers::shared::ollama::{
     ollama_conversation_prepare_messages, OllamaAPIStreamingResponse,
 };
-use crate::managers::model_capabilities_manager::PromptResultEnum;
+use crate::managers::model_capabilities_manager::{
+    ModelCapabilitiesManager, ModelCapabilitiesManagerError, ModelCapability, ModelCost, ModelPrivacy, PromptResult,
+    PromptResultEnum,
+};
 use crate::network::ws_manager::{WSMessageType, WSMetadata, WSUpdateHandler};
 
 use super::super::{error::LLMProviderError, execution::prompts::prompts::Prompt};
+use super::provider_registry::LLMProviderBackend;
+use super::shared::shared_model_logic::{llama_prepare_messages, llava_prepare_messages};
 use super::LLMService;
 use async_trait::async_trait;
 use futures::StreamExt;
@@ -14,6 +19,7 @@ use serde_json;
 use serde_json::json;
 use serde_json::Value as JsonValue;
 use shinkai_message_primitives::schemas::inbox_name::InboxName;
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
 use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{LLMProviderInterface, Ollama};
 use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::WSTopic;
 use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
@@ -44,6 +50,7 @@ impl LLMService for Ollama {
         url: Option<&String>,
         _api_key: Option<&String>, // Note: not required
         prompt: Prompt,
+        inference_params: InferenceParams,
         model: LLMProviderInterface,
         inbox_name: Option<InboxName>,
         ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
@@ -52,6 +59,7 @@ impl LLMService for Ollama {
         if let Some(base_url) = url {
             let url = format!("{}{}", base_url, "/api/chat");
 
+            let response_schema = prompt.response_schema.clone();
             let messages_result = ollama_conversation_prepare_messages(&model, prompt)?;
             let messages_json = match messages_result.messages {
                 PromptResultEnum::Value(v) => v,
@@ -73,6 +81,8 @@ impl LLMService for Ollama {
             //     Err(e) => eprintln!("Failed to serialize messages_json: {:?}", e),
             // };
 
+            let tools_json = messages_result.functions.unwrap_or_default();
+
             let mut payload = json!({
                 "model": self.model_type,
                 "messages": messages_json,
@@ -81,8 +91,19 @@ impl LLMService for Ollama {
                 // https://github.com/jmorganca/ollama/blob/main/docs/api.md#request-json-mode
             });
 
+            // Ollama doesn't stream the tool calls, so the response comes in a single chunk when tools are sent
+            if !tools_json.is_empty() {
+                payload["tools"] = JsonValue::Array(tools_json);
+                payload["stream"] = json!(false);
+            }
+
+            // Structured outputs: Ollama constrains the response to the JSON schema set as format
+            if let Some(response_schema) = response_schema {
+                payload["format"] = response_schema.schema;
+            }
+
             // Modify payload to add options if needed
-            add_options_to_payload(&mut payload);
+            add_options_to_payload(&mut payload, &inference_params);
 
             let mut payload_log = payload.clone();
             truncate_image_content_in_payload(&mut payload_log);
@@ -103,6 +124,7 @@ impl LLMService for Ollama {
 
             let mut stream = res.bytes_stream();
             let mut response_text = String::new();
+            let mut function_call = None;
             let mut previous_json_chunk: String = String::new();
             while let Some(item) = stream.next().await {
                 match item {
@@ -116,6 +138,9 @@ impl LLMService for Ollama {
                             Ok(data) => {
                                 previous_json_chunk = "".to_string();
                                 response_text.push_str(&data.message.content);
+                                if function_call.is_none() {
+                                    function_call = data.message.tool_call();
+                                }
 
                                 // Note: this is the code for enabling WS
                                 if let Some(ref manager) = ws_manager_trait {
@@ -177,14 +202,14 @@ impl LLMService for Ollama {
             );
 
             // Directly return response_text with an empty JSON object
-            Ok(LLMInferenceResponse::new(response_text, json!({}), None))
+            Ok(LLMInferenceResponse::new(response_text, json!({}), function_call))
         } else {
             Err(LLMProviderError::UrlNotSet)
         }
     }
 }
 
-fn add_options_to_payload(payload: &mut serde_json::Value) {
+fn add_options_to_payload(payload: &mut serde_json::Value, inference_params: &InferenceParams) {
     let mut options = serde_json::Map::new();
 
     // Helper function to read and parse environment variables
@@ -281,8 +306,140 @@ fn add_options_to_payload(payload: &mut serde_json::Value) {
         options.insert("num_thread".to_string(), serde_json::json!(num_thread));
     }
 
+    if let Some(temperature) = inference_params.temperature {
+        options.insert("temperature".to_string(), serde_json::json!(temperature));
+    }
+    if let Some(top_p) = inference_params.top_p {
+        options.insert("top_p".to_string(), serde_json::json!(top_p));
+    }
+    if let Some(max_tokens) = inference_params.max_tokens {
+        options.insert("num_predict".to_string(), serde_json::json!(max_tokens));
+    }
+    if let Some(stop) = &inference_params.stop {
+        options.insert("stop".to_string(), serde_json::json!(stop));
+    }
+    if let Some(seed) = inference_params.seed {
+        options.insert("seed".to_string(), serde_json::json!(seed));
+    }
+
     // Add options to payload if not empty
     if !options.is_empty() {
         payload["options"] = serde_json::Value::Object(options);
     }
 }
+
+/// Registry backend for `ollama:` models
+pub struct OllamaBackend;
+
+#[async_trait]
+impl LLMProviderBackend for OllamaBackend {
+    fn provider_prefix(&self) -> &str {
+        "ollama"
+    }
+
+    async fn call_api(
+        &self,
+        client: &Client,
+        url: Option<&String>,
+        api_key: Option<&String>,
+        prompt: Prompt,
+        inference_params: InferenceParams,
+        model: LLMProviderInterface,
+        inbox_name: Option<InboxName>,
+        ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> Result<LLMInferenceResponse, LLMProviderError> {
+        match &model {
+            LLMProviderInterface::Ollama(ollama) => {
+                ollama
+                    .call_api(
+                        client,
+                        url,
+                        api_key,
+                        prompt,
+                        inference_params,
+                        model.clone(),
+                        inbox_name,
+                        ws_manager_trait,
+                    )
+                    .await
+            }
+            _ => Err(LLMProviderError::InvalidModelType(format!(
+                "ollama backend can't handle {:?}",
+                model
+            ))),
+        }
+    }
+
+    fn prepare_messages(
+        &self,
+        model: &LLMProviderInterface,
+        prompt: Prompt,
+    ) -> Result<PromptResult, ModelCapabilitiesManagerError> {
+        let model_type = model.model_type();
+        let total_tokens = self.max_tokens(model);
+        if model_type.starts_with("mistral")
+            || model_type.starts_with("llama2")
+            || model_type.starts_with("llama3")
+            || model_type.starts_with("gemma2")
+            || model_type.starts_with("wizardlm2")
+            || model_type.starts_with("starling-lm")
+            || model_type.starts_with("neural-chat")
+            || model_type.starts_with("vicuna")
+            || model_type.starts_with("mixtral")
+            || model_type.starts_with("falcon2")
+            || model_type.starts_with("dolphin-llama3")
+            || model_type.starts_with("command-r-plus")
+            || model_type.starts_with("phi3")
+            || model_type.starts_with("aya")
+            || model_type.starts_with("qwen2:0.5b")
+            || model_type.starts_with("qwen2:1.5b")
+            || model_type.starts_with("qwen2:7b")
+            || model_type.starts_with("qwen2:72b")
+            || model_type.starts_with("codestral")
+            || model_type.starts_with("adrienbrault/nous-hermes2theta-llama3-8b")
+            || model_type.contains("minicpm_llama3")
+        {
+            Ok(llama_prepare_messages(model, model_type, prompt, total_tokens)?)
+        } else if model_type.starts_with("llava")
+            || model_type.starts_with("bakllava")
+            || model_type.starts_with("llava-phi3")
+            || model_type.starts_with("moondream")
+        {
+            Ok(llava_prepare_messages(model, model_type, prompt, total_tokens)?)
+        } else {
+            Err(ModelCapabilitiesManagerError::NotImplemented(model_type))
+        }
+    }
+
+    fn capabilities(&self, model: &LLMProviderInterface) -> Vec<ModelCapability> {
+        ModelCapabilitiesManager::get_capabilities_for_model_type(&model.model_type())
+    }
+
+    fn cost(&self, _model: &LLMProviderInterface) -> ModelCost {
+        ModelCost::Free
+    }
+
+    fn privacy(&self, _model: &LLMProviderInterface) -> ModelPrivacy {
+        ModelPrivacy::Local
+    }
+
+    fn supports_tool_calls(&self, _model: &LLMProviderInterface) -> bool {
+        true
+    }
+
+    fn supports_response_schema(&self, _model: &LLMProviderInterface) -> bool {
+        true
+    }
+
+    fn max_tokens(&self, model: &LLMProviderInterface) -> usize {
+        ModelCapabilitiesManager::get_max_tokens_for_model_type(&model.model_type())
+    }
+
+    fn max_output_tokens(&self, model: &LLMProviderInterface) -> usize {
+        if self.max_tokens(model) <= 8000 {
+            2800
+        } else {
+            4096
+        }
+    }
+}
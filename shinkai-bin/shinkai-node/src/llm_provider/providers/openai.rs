@@ -1,10 +1,14 @@
 use std::sync::Arc;
 
 use super::super::{error::LLMProviderError, execution::prompts::prompts::Prompt};
-use super::shared::openai::{openai_prepare_messages, MessageContent, OpenAIResponse};
+use super::shared::openai::{openai_add_inference_params, openai_prepare_messages, MessageContent, OpenAIResponse};
+use super::provider_registry::LLMProviderBackend;
 use super::LLMService;
 use crate::llm_provider::execution::chains::inference_chain_trait::LLMInferenceResponse;
-use crate::managers::model_capabilities_manager::PromptResultEnum;
+use crate::managers::model_capabilities_manager::{
+    InferenceParamLimits, ModelCapabilitiesManagerError, ModelCapability, ModelCost, ModelPrivacy, PromptResult,
+    PromptResultEnum,
+};
 use crate::network::ws_manager::WSUpdateHandler;
 use async_trait::async_trait;
 use reqwest::Client;
@@ -12,6 +16,7 @@ use serde_json::json;
 use serde_json::Value as JsonValue;
 use serde_json::{self};
 use shinkai_message_primitives::schemas::inbox_name::InboxName;
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
 use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{LLMProviderInterface, OpenAI};
 use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
 use tokio::sync::Mutex;
@@ -47,6 +52,7 @@ impl LLMService for OpenAI {
         url: Option<&String>,
         api_key: Option<&String>,
         prompt: Prompt,
+        inference_params: InferenceParams,
         model: LLMProviderInterface,
         inbox_name: Option<InboxName>,
         _ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
@@ -55,6 +61,8 @@ impl LLMService for OpenAI {
             if let Some(key) = api_key {
                 let url = format!("{}{}", base_url, "/v1/chat/completions");
 
+                let response_schema = prompt.response_schema.clone();
+
                 // Note(Nico): we can use prepare_messages directly or we could had called ModelCapabilitiesManager
                 let result = openai_prepare_messages(&model, prompt)?;
                 let messages_json = match result.messages {
@@ -91,8 +99,21 @@ impl LLMService for OpenAI {
                     payload["functions"] = serde_json::Value::Array(tools_json);
                 }
 
+                // Constrain the output to the response schema (not strict, so any schema is accepted)
+                if let Some(response_schema) = response_schema {
+                    payload["response_format"] = json!({
+                        "type": "json_schema",
+                        "json_schema": {
+                            "name": "response",
+                            "schema": response_schema.schema,
+                            "strict": false,
+                        },
+                    });
+                }
+
                 // Add options to payload
                 add_options_to_payload(&mut payload);
+                openai_add_inference_params(&mut payload, &inference_params);
 
                 // Print payload as a pretty JSON string
                 match serde_json::to_string_pretty(&payload) {
@@ -181,7 +202,8 @@ impl LLMService for OpenAI {
                         });
                         eprintln!("Function Call: {:?}", function_call);
                         eprintln!("Response String: {:?}", response_string);
-                        Ok(LLMInferenceResponse::new(response_string, json!({}), function_call))
+                        Ok(LLMInferenceResponse::new(response_string, json!({}), function_call)
+                            .with_usage(data.usage.to_token_usage()))
                     }
                     Err(e) => {
                         shinkai_log(
@@ -216,3 +238,123 @@ fn add_options_to_payload(payload: &mut serde_json::Value) {
     }
     // Add more options as needed...
 }
+
+/// Registry backend for `openai:` models
+pub struct OpenAIBackend;
+
+#[async_trait]
+impl LLMProviderBackend for OpenAIBackend {
+    fn provider_prefix(&self) -> &str {
+        "openai"
+    }
+
+    async fn call_api(
+        &self,
+        client: &Client,
+        url: Option<&String>,
+        api_key: Option<&String>,
+        prompt: Prompt,
+        inference_params: InferenceParams,
+        model: LLMProviderInterface,
+        inbox_name: Option<InboxName>,
+        ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> Result<LLMInferenceResponse, LLMProviderError> {
+        match &model {
+            LLMProviderInterface::OpenAI(openai) => {
+                openai
+                    .call_api(
+                        client,
+                        url,
+                        api_key,
+                        prompt,
+                        inference_params,
+                        model.clone(),
+                        inbox_name,
+                        ws_manager_trait,
+                    )
+                    .await
+            }
+            _ => Err(LLMProviderError::InvalidModelType(format!(
+                "openai backend can't handle {:?}",
+                model
+            ))),
+        }
+    }
+
+    fn prepare_messages(
+        &self,
+        model: &LLMProviderInterface,
+        prompt: Prompt,
+    ) -> Result<PromptResult, ModelCapabilitiesManagerError> {
+        let model_type = model.model_type();
+        if model_type.starts_with("gpt-") {
+            Ok(openai_prepare_messages(model, prompt)?)
+        } else {
+            Err(ModelCapabilitiesManagerError::NotImplemented(model_type))
+        }
+    }
+
+    fn capabilities(&self, model: &LLMProviderInterface) -> Vec<ModelCapability> {
+        match model.model_type().as_str() {
+            "gpt-4o" => vec![ModelCapability::ImageAnalysis, ModelCapability::TextInference],
+            "gpt-4o-mini" => vec![ModelCapability::ImageAnalysis, ModelCapability::TextInference],
+            "gpt-3.5-turbo-1106" => vec![ModelCapability::TextInference],
+            "gpt-4-1106-preview" => vec![ModelCapability::TextInference],
+            "gpt-4-vision-preview" => vec![ModelCapability::ImageAnalysis, ModelCapability::TextInference],
+            "dall-e-3" => vec![ModelCapability::ImageGeneration],
+            model_type if model_type.starts_with("gpt-") => vec![ModelCapability::TextInference],
+            _ => vec![],
+        }
+    }
+
+    fn cost(&self, model: &LLMProviderInterface) -> ModelCost {
+        match model.model_type().as_str() {
+            "gpt-4o" => ModelCost::Cheap,
+            "gpt-3.5-turbo-1106" => ModelCost::VeryCheap,
+            "gpt-4o-mini" => ModelCost::VeryCheap,
+            "gpt-4-1106-preview" => ModelCost::GoodValue,
+            "gpt-4-vision-preview" => ModelCost::GoodValue,
+            "dall-e-3" => ModelCost::GoodValue,
+            _ => ModelCost::Unknown,
+        }
+    }
+
+    fn inference_param_limits(&self, _model: &LLMProviderInterface) -> InferenceParamLimits {
+        InferenceParamLimits {
+            max_temperature: 2.0,
+            max_stop_sequences: Some(4),
+        }
+    }
+
+    fn privacy(&self, _model: &LLMProviderInterface) -> ModelPrivacy {
+        ModelPrivacy::RemoteGreedy
+    }
+
+    fn supports_tool_calls(&self, _model: &LLMProviderInterface) -> bool {
+        true
+    }
+
+    fn supports_response_schema(&self, _model: &LLMProviderInterface) -> bool {
+        true
+    }
+
+    fn max_tokens(&self, model: &LLMProviderInterface) -> usize {
+        let model_type = model.model_type();
+        if model_type == "gpt-4o" || model_type == "gpt-4-1106-preview" || model_type == "gpt-4-vision-preview" {
+            128_000
+        } else {
+            32_000
+        }
+    }
+
+    fn normalize_model(&self, model: &LLMProviderInterface) -> String {
+        let model_type = model.model_type();
+        if model_type.starts_with("gpt-4") {
+            "gpt-4-32k".to_string()
+        } else if model_type.starts_with("gpt-3.5") {
+            "gpt-3.5-turbo-16k".to_string()
+        } else {
+            "gpt-4".to_string()
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::llm_provider::error::LLMProviderError;
+use crate::llm_provider::execution::chains::inference_chain_trait::LLMInferenceResponse;
+use crate::llm_provider::execution::prompts::prompts::Prompt;
+use crate::managers::model_capabilities_manager::{
+    InferenceParamLimits, ModelCapabilitiesManagerError, ModelCapability, ModelCost, ModelPrivacy, PromptResult,
+};
+use crate::network::ws_manager::WSUpdateHandler;
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use reqwest::Client;
+use shinkai_message_primitives::schemas::inbox_name::InboxName;
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
+use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::LLMProviderInterface;
+use tokio::sync::Mutex;
+
+use super::anthropic::AnthropicBackend;
+use super::exo::ExoBackend;
+use super::gemini::GeminiBackend;
+use super::genericapi::GenericAPIBackend;
+use super::groq::GroqBackend;
+use super::local_llm::LocalLLMBackend;
+use super::ollama::OllamaBackend;
+use super::openai::OpenAIBackend;
+use super::shinkai_backend::ShinkaiBackendBackend;
+
+/// Everything the node needs to know about a family of models: how to call them,
+/// how to prepare their prompts and what they are capable of.
+/// One backend is registered per provider prefix (e.g. `openai` for `openai:gpt-4o`).
+#[async_trait]
+pub trait LLMProviderBackend: Send + Sync {
+    /// The provider prefix this backend handles
+    fn provider_prefix(&self) -> &str;
+
+    #[allow(clippy::too_many_arguments)]
+    async fn call_api(
+        &self,
+        client: &Client,
+        url: Option<&String>,
+        api_key: Option<&String>,
+        prompt: Prompt,
+        inference_params: InferenceParams,
+        model: LLMProviderInterface,
+        inbox_name: Option<InboxName>,
+        ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> Result<LLMInferenceResponse, LLMProviderError>;
+
+    /// Converts the prompt into the messages (and functions) expected by the provider
+    fn prepare_messages(
+        &self,
+        model: &LLMProviderInterface,
+        prompt: Prompt,
+    ) -> Result<PromptResult, ModelCapabilitiesManagerError>;
+
+    fn capabilities(&self, _model: &LLMProviderInterface) -> Vec<ModelCapability> {
+        vec![ModelCapability::TextInference]
+    }
+
+    fn cost(&self, _model: &LLMProviderInterface) -> ModelCost {
+        ModelCost::Unknown
+    }
+
+    fn privacy(&self, _model: &LLMProviderInterface) -> ModelPrivacy {
+        ModelPrivacy::Unknown
+    }
+
+    /// Maximum number of tokens (input + output) allowed by the model
+    fn max_tokens(&self, _model: &LLMProviderInterface) -> usize {
+        4096
+    }
+
+    fn max_output_tokens(&self, _model: &LLMProviderInterface) -> usize {
+        4096
+    }
+
+    /// Range of the inference params accepted by the provider
+    fn inference_param_limits(&self, _model: &LLMProviderInterface) -> InferenceParamLimits {
+        InferenceParamLimits::default()
+    }
+
+    /// True if the backend sends the available tools in the provider's native schema and parses
+    /// the tool calls of the responses into `FunctionCall`s
+    fn supports_tool_calls(&self, _model: &LLMProviderInterface) -> bool {
+        false
+    }
+
+    /// True if the backend sends the prompt's response schema to the provider, which then constrains
+    /// its output to it. Responses are still validated locally.
+    fn supports_response_schema(&self, _model: &LLMProviderInterface) -> bool {
+        false
+    }
+
+    /// Name of the model as known by the tokenizer libraries (empty if not applicable)
+    fn normalize_model(&self, _model: &LLMProviderInterface) -> String {
+        "".to_string()
+    }
+}
+
+lazy_static! {
+    static ref LLM_PROVIDER_BACKENDS: RwLock<HashMap<String, Arc<dyn LLMProviderBackend>>> =
+        RwLock::new(LLMProviderRegistry::built_in_backends());
+}
+
+/// Global registry of LLM provider backends, keyed by provider prefix.
+/// Built-in providers are registered on first use. External crates can add their own
+/// backends (used with `LLMProviderInterface::Custom`) or replace a built-in one.
+pub struct LLMProviderRegistry;
+
+impl LLMProviderRegistry {
+    fn built_in_backends() -> HashMap<String, Arc<dyn LLMProviderBackend>> {
+        let backends: Vec<Arc<dyn LLMProviderBackend>> = vec![
+            Arc::new(OpenAIBackend),
+            Arc::new(GenericAPIBackend),
+            Arc::new(OllamaBackend),
+            Arc::new(ShinkaiBackendBackend),
+            Arc::new(LocalLLMBackend),
+            Arc::new(GroqBackend),
+            Arc::new(GeminiBackend),
+            Arc::new(ExoBackend),
+            Arc::new(AnthropicBackend),
+        ];
+
+        backends
+            .into_iter()
+            .map(|backend| (backend.provider_prefix().to_string(), backend))
+            .collect()
+    }
+
+    /// Registers a backend under its provider prefix, returning the backend it replaced (if any)
+    pub fn register(backend: Arc<dyn LLMProviderBackend>) -> Option<Arc<dyn LLMProviderBackend>> {
+        let mut backends = LLM_PROVIDER_BACKENDS.write().unwrap_or_else(|e| e.into_inner());
+        backends.insert(backend.provider_prefix().to_string(), backend)
+    }
+
+    pub fn unregister(provider_prefix: &str) -> Option<Arc<dyn LLMProviderBackend>> {
+        let mut backends = LLM_PROVIDER_BACKENDS.write().unwrap_or_else(|e| e.into_inner());
+        backends.remove(provider_prefix)
+    }
+
+    pub fn get_by_prefix(provider_prefix: &str) -> Option<Arc<dyn LLMProviderBackend>> {
+        // The lock is released before returning so backends can query the registry themselves
+        let backends = LLM_PROVIDER_BACKENDS.read().unwrap_or_else(|e| e.into_inner());
+        backends.get(provider_prefix).cloned()
+    }
+
+    /// Returns the backend in charge of the given model
+    pub fn get(model: &LLMProviderInterface) -> Option<Arc<dyn LLMProviderBackend>> {
+        Self::get_by_prefix(&model.provider_prefix())
+    }
+
+    pub fn provider_prefixes() -> Vec<String> {
+        let backends = LLM_PROVIDER_BACKENDS.read().unwrap_or_else(|e| e.into_inner());
+        let mut prefixes: Vec<String> = backends.keys().cloned().collect();
+        prefixes.sort();
+        prefixes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::managers::model_capabilities_manager::{ModelCapabilitiesManager, PromptResultEnum};
+    use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::CustomProvider;
+
+    struct EchoBackend;
+
+    #[async_trait]
+    impl LLMProviderBackend for EchoBackend {
+        fn provider_prefix(&self) -> &str {
+            "echo"
+        }
+
+        async fn call_api(
+            &self,
+            _client: &Client,
+            _url: Option<&String>,
+            _api_key: Option<&String>,
+            prompt: Prompt,
+            _inference_params: InferenceParams,
+            _model: LLMProviderInterface,
+            _inbox_name: Option<InboxName>,
+            _ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+        ) -> Result<LLMInferenceResponse, LLMProviderError> {
+            Ok(LLMInferenceResponse::new(
+                prompt.generate_single_output_string()?,
+                serde_json::json!({}),
+                None,
+            ))
+        }
+
+        fn prepare_messages(
+            &self,
+            _model: &LLMProviderInterface,
+            prompt: Prompt,
+        ) -> Result<PromptResult, ModelCapabilitiesManagerError> {
+            Ok(PromptResult {
+                messages: PromptResultEnum::Text(prompt.generate_single_output_string()?),
+                functions: None,
+                remaining_tokens: 1000,
+            })
+        }
+
+        fn privacy(&self, _model: &LLMProviderInterface) -> ModelPrivacy {
+            ModelPrivacy::Local
+        }
+
+        fn max_tokens(&self, _model: &LLMProviderInterface) -> usize {
+            42_000
+        }
+    }
+
+    #[test]
+    fn test_built_in_backends_are_registered() {
+        let prefixes = LLMProviderRegistry::provider_prefixes();
+        for prefix in [
+            "openai",
+            "genericapi",
+            "ollama",
+            "shinkai-backend",
+            "local-llm",
+            "groq",
+            "gemini",
+            "exo",
+            "anthropic",
+        ] {
+            assert!(prefixes.contains(&prefix.to_string()), "missing backend {}", prefix);
+        }
+    }
+
+    #[test]
+    fn test_register_custom_backend() {
+        let model = LLMProviderInterface::Custom(CustomProvider {
+            provider: "echo".to_string(),
+            model_type: "echo-1".to_string(),
+        });
+        assert!(LLMProviderRegistry::get(&model).is_none());
+        assert_eq!(
+            ModelCapabilitiesManager::get_llm_provider_privacy(&model),
+            ModelPrivacy::Unknown
+        );
+
+        LLMProviderRegistry::register(Arc::new(EchoBackend));
+
+        assert!(LLMProviderRegistry::get(&model).is_some());
+        assert_eq!(
+            ModelCapabilitiesManager::get_llm_provider_privacy(&model),
+            ModelPrivacy::Local
+        );
+        assert_eq!(ModelCapabilitiesManager::get_max_tokens(&model), 42_000);
+        assert_eq!(
+            ModelCapabilitiesManager::get_llm_provider_capabilities(&model),
+            vec![ModelCapability::TextInference]
+        );
+
+        assert!(LLMProviderRegistry::unregister("echo").is_some());
+        assert!(LLMProviderRegistry::get(&model).is_none());
+    }
+}
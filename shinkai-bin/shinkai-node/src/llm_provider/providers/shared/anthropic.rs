@@ -0,0 +1,373 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use serde_json::Value as JsonValue;
+use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::LLMProviderInterface;
+
+use crate::{
+    llm_provider::{error::LLMProviderError, execution::prompts::prompts::Prompt},
+    managers::model_capabilities_manager::{ModelCapabilitiesManager, PromptResult, PromptResultEnum},
+};
+
+use super::llm_message::LlmMessage;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnthropicContentBlock {
+    Text { text: String },
+    ToolUse { id: String, name: String, input: JsonValue },
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AnthropicUsage {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+}
+
+/// Events sent by the Messages API when `stream` is enabled (one per SSE `data:` line)
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnthropicStreamEvent {
+    MessageStart {
+        message: JsonValue,
+    },
+    ContentBlockStart {
+        index: usize,
+        content_block: AnthropicContentBlock,
+    },
+    ContentBlockDelta {
+        index: usize,
+        delta: AnthropicDelta,
+    },
+    ContentBlockStop {
+        index: usize,
+    },
+    MessageDelta {
+        delta: AnthropicMessageDelta,
+        #[serde(default)]
+        usage: Option<AnthropicUsage>,
+    },
+    MessageStop,
+    Ping,
+    Error {
+        error: AnthropicError,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnthropicDelta {
+    TextDelta { text: String },
+    InputJsonDelta { partial_json: String },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnthropicMessageDelta {
+    pub stop_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnthropicError {
+    #[serde(rename = "type")]
+    pub error_type: String,
+    pub message: String,
+}
+
+/// The Messages API only accepts base64 images with an explicit media type, so we sniff it from the payload header.
+pub fn anthropic_image_media_type(base64_image: &str) -> &'static str {
+    if base64_image.starts_with("iVBORw0KGgo") {
+        "image/png"
+    } else if base64_image.starts_with("R0lGOD") {
+        "image/gif"
+    } else if base64_image.starts_with("UklGR") {
+        "image/webp"
+    } else {
+        "image/jpeg"
+    }
+}
+
+/// Prepares the messages for the Anthropic Messages API.
+/// Returns the PromptResult (messages + tools) and the system prompt, which Anthropic expects as a top level field.
+pub fn anthropic_prepare_messages(
+    model: &LLMProviderInterface,
+    prompt: Prompt,
+) -> Result<(PromptResult, String), LLMProviderError> {
+    let max_input_tokens = ModelCapabilitiesManager::get_max_input_tokens(model);
+
+    let chat_completion_messages = prompt.generate_openai_messages(Some(max_input_tokens))?;
+
+    // Images are not counted as tokens (same as the other providers)
+    let text_messages: Vec<LlmMessage> = chat_completion_messages
+        .iter()
+        .filter(|message| message.name.as_deref() != Some("image"))
+        .cloned()
+        .collect();
+    let used_tokens = ModelCapabilitiesManager::num_tokens_from_messages(&text_messages);
+    let remaining_output_tokens = ModelCapabilitiesManager::get_remaining_output_tokens(model, used_tokens);
+
+    let (system_prompt, messages, tools) = from_chat_completion_messages(chat_completion_messages)?;
+
+    Ok((
+        PromptResult {
+            messages: PromptResultEnum::Value(JsonValue::Array(messages)),
+            functions: Some(tools),
+            remaining_tokens: remaining_output_tokens,
+        },
+        system_prompt,
+    ))
+}
+
+/// Converts LlmMessages into (system prompt, Anthropic messages, Anthropic tools).
+/// Anthropic requires the conversation to alternate between user and assistant, so consecutive
+/// messages with the same role are merged into a single message with multiple content blocks.
+fn from_chat_completion_messages(
+    chat_completion_messages: Vec<LlmMessage>,
+) -> Result<(String, Vec<JsonValue>, Vec<JsonValue>), LLMProviderError> {
+    let mut system_prompt: Vec<String> = Vec::new();
+    let mut tools: Vec<JsonValue> = Vec::new();
+    let mut messages: Vec<(String, Vec<JsonValue>)> = Vec::new();
+    // FunctionCalls in the prompt don't carry an id, so we generate one per call and hand it to the following response
+    let mut tool_use_count = 0;
+    let mut last_tool_use_id: Option<String> = None;
+
+    for message in chat_completion_messages {
+        if let Some(functions) = message.functions {
+            for function in functions {
+                tools.push(json!({
+                    "name": function.name,
+                    "description": function.description,
+                    "input_schema": function.parameters,
+                }));
+            }
+            continue;
+        }
+
+        let role = message.role.unwrap_or_default();
+        let (role, block) = match role.as_str() {
+            "system" => {
+                if let Some(content) = message.content {
+                    system_prompt.push(content);
+                }
+                continue;
+            }
+            "function" => {
+                let tool_use_id = last_tool_use_id.take().unwrap_or_else(|| {
+                    tool_use_count += 1;
+                    format!("toolu_{}", tool_use_count)
+                });
+                (
+                    "user".to_string(),
+                    json!({
+                        "type": "tool_result",
+                        "tool_use_id": tool_use_id,
+                        "content": message.content.unwrap_or_default(),
+                    }),
+                )
+            }
+            _ if message.function_call.is_some() => {
+                let function_call = message.function_call.unwrap_or_default();
+                tool_use_count += 1;
+                let tool_use_id = format!("toolu_{}", tool_use_count);
+                last_tool_use_id = Some(tool_use_id.clone());
+                let input: JsonValue = serde_json::from_str(&function_call.arguments).unwrap_or_else(|_| json!({}));
+                (
+                    "assistant".to_string(),
+                    json!({
+                        "type": "tool_use",
+                        "id": tool_use_id,
+                        "name": function_call.name,
+                        "input": input,
+                    }),
+                )
+            }
+            _ if message.name.as_deref() == Some("image") => {
+                let image = message.content.unwrap_or_default();
+                (
+                    "user".to_string(),
+                    json!({
+                        "type": "image",
+                        "source": {
+                            "type": "base64",
+                            "media_type": anthropic_image_media_type(&image),
+                            "data": image,
+                        }
+                    }),
+                )
+            }
+            _ => {
+                let content = match message.content {
+                    Some(content) if !content.trim().is_empty() => content,
+                    _ => continue,
+                };
+                let role = if role == "assistant" { role } else { "user".to_string() };
+                (role, json!({ "type": "text", "text": content }))
+            }
+        };
+
+        match messages.last_mut() {
+            Some((last_role, blocks)) if *last_role == role => blocks.push(block),
+            _ => messages.push((role, vec![block])),
+        }
+    }
+
+    let messages = messages
+        .into_iter()
+        .map(|(role, content)| json!({ "role": role, "content": content }))
+        .collect();
+
+    Ok((system_prompt.join("\n"), messages, tools))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm_provider::providers::shared::llm_message::{
+        DetailedFunctionCall, FunctionDetails, FunctionParameters,
+    };
+
+    #[test]
+    fn test_from_llm_messages() {
+        let llm_messages = vec![
+            LlmMessage {
+                role: Some("system".to_string()),
+                content: Some("You are a very helpful assistant.".to_string()),
+                name: None,
+                function_call: None,
+                functions: None,
+            },
+            LlmMessage {
+                role: Some("user".to_string()),
+                content: Some("Here is some extra context".to_string()),
+                name: None,
+                function_call: None,
+                functions: None,
+            },
+            LlmMessage {
+                role: Some("user".to_string()),
+                content: Some("describe this".to_string()),
+                name: None,
+                function_call: None,
+                functions: None,
+            },
+            LlmMessage {
+                role: Some("user".to_string()),
+                content: Some("iVBORw0KGgoAAAANSUhEUgAAAlgAAAJYCAYAAAC".to_string()),
+                name: Some("image".to_string()),
+                function_call: None,
+                functions: None,
+            },
+        ];
+
+        let (system, messages, tools) = from_chat_completion_messages(llm_messages).unwrap();
+
+        assert_eq!(system, "You are a very helpful assistant.");
+        assert!(tools.is_empty());
+        assert_eq!(
+            JsonValue::Array(messages),
+            json!([
+                {
+                    "role": "user",
+                    "content": [
+                        { "type": "text", "text": "Here is some extra context" },
+                        { "type": "text", "text": "describe this" },
+                        {
+                            "type": "image",
+                            "source": {
+                                "type": "base64",
+                                "media_type": "image/png",
+                                "data": "iVBORw0KGgoAAAANSUhEUgAAAlgAAAJYCAYAAAC"
+                            }
+                        }
+                    ]
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_from_llm_messages_with_tools() {
+        let llm_messages = vec![
+            LlmMessage {
+                role: Some("user".to_string()),
+                content: Some("concatenate hola and chao".to_string()),
+                name: None,
+                function_call: None,
+                functions: None,
+            },
+            LlmMessage {
+                role: Some("assistant".to_string()),
+                content: None,
+                name: None,
+                function_call: Some(DetailedFunctionCall {
+                    name: "concat_strings".to_string(),
+                    arguments: "{\"first_string\":\"hola\",\"second_string\":\"chao\"}".to_string(),
+                }),
+                functions: None,
+            },
+            LlmMessage {
+                role: Some("function".to_string()),
+                content: Some("holachao".to_string()),
+                name: Some("concat_strings".to_string()),
+                function_call: None,
+                functions: None,
+            },
+            LlmMessage {
+                role: None,
+                content: None,
+                name: None,
+                function_call: None,
+                functions: Some(vec![FunctionDetails {
+                    name: "concat_strings".to_string(),
+                    description: "Concatenates 2 to 4 strings.".to_string(),
+                    parameters: FunctionParameters {
+                        type_: "object".to_string(),
+                        properties: json!({
+                            "first_string": { "type": "string", "description": "The first string" },
+                            "second_string": { "type": "string", "description": "The second string" }
+                        }),
+                        required: vec!["first_string".to_string(), "second_string".to_string()],
+                    },
+                }]),
+            },
+        ];
+
+        let (system, messages, tools) = from_chat_completion_messages(llm_messages).unwrap();
+
+        assert!(system.is_empty());
+        assert_eq!(
+            JsonValue::Array(tools),
+            json!([{
+                "name": "concat_strings",
+                "description": "Concatenates 2 to 4 strings.",
+                "input_schema": {
+                    "type": "object",
+                    "properties": {
+                        "first_string": { "type": "string", "description": "The first string" },
+                        "second_string": { "type": "string", "description": "The second string" }
+                    },
+                    "required": ["first_string", "second_string"]
+                }
+            }])
+        );
+        assert_eq!(
+            JsonValue::Array(messages),
+            json!([
+                { "role": "user", "content": [{ "type": "text", "text": "concatenate hola and chao" }] },
+                {
+                    "role": "assistant",
+                    "content": [{
+                        "type": "tool_use",
+                        "id": "toolu_1",
+                        "name": "concat_strings",
+                        "input": { "first_string": "hola", "second_string": "chao" }
+                    }]
+                },
+                {
+                    "role": "user",
+                    "content": [{ "type": "tool_result", "tool_use_id": "toolu_1", "content": "holachao" }]
+                }
+            ])
+        );
+    }
+}
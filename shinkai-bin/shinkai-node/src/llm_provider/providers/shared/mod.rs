@@ -2,4 +2,5 @@ pub mod openai;
 pub mod togetherai;
 pub mod ollama;
 pub mod shared_model_logic;
-pub mod llm_message;
\ No newline at end of file
+pub mod llm_message;
+pub mod anthropic;
\ No newline at end of file
@@ -8,6 +8,7 @@ use crate::{
 };
 
 use super::llm_message::LlmMessage;
+use super::openai::FunctionCall;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct OllamaAPIResponse {
@@ -41,9 +42,34 @@ pub struct OllamaAPIStreamingResponse {
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct OllamaMessage {
     pub role: String,
+    #[serde(default)]
     pub content: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub images: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<OllamaToolCall>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OllamaToolCall {
+    pub function: OllamaFunctionCall,
+}
+
+/// Unlike OpenAI, Ollama sends and expects the arguments as a JSON object
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct OllamaFunctionCall {
+    pub name: String,
+    pub arguments: Value,
+}
+
+impl OllamaMessage {
+    /// Returns the first tool call of the message as a `FunctionCall`
+    pub fn tool_call(&self) -> Option<FunctionCall> {
+        self.tool_calls.as_ref()?.first().map(|tool_call| FunctionCall {
+            name: tool_call.function.name.clone(),
+            arguments: tool_call.function.arguments.clone(),
+        })
+    }
 }
 
 pub fn ollama_conversation_prepare_messages(
@@ -60,13 +86,23 @@ pub fn ollama_conversation_prepare_messages(
     // Calculate the remaining output tokens available
     let remaining_output_tokens = ModelCapabilitiesManager::get_remaining_output_tokens(model, used_tokens);
 
-    // Converts the ChatCompletionMessages to OpenAIApiMessages
+    // The messages without a role hold the available tools
+    let (chat_completion_messages, tool_messages): (Vec<_>, Vec<_>) = chat_completion_messages
+        .into_iter()
+        .partition(|message| message.role.is_some());
+    let tools = tool_messages
+        .into_iter()
+        .flat_map(|message| message.functions.unwrap_or_default())
+        .map(|function| serde_json::json!({ "type": "function", "function": function }))
+        .collect();
+
+    // Converts the ChatCompletionMessages to OllamaMessages
     let messages = from_chat_completion_messages(chat_completion_messages)?;
 
     let messages_json = serde_json::to_value(messages)?;
     Ok(PromptResult {
         messages: PromptResultEnum::Value(messages_json),
-        functions: None,
+        functions: Some(tools),
         remaining_tokens: remaining_output_tokens,
     })
 }
@@ -79,6 +115,32 @@ fn from_chat_completion_messages(
     let mut iter = chat_completion_messages.into_iter().peekable();
 
     while let Some(message) = iter.next() {
+        // Function calls requested by the assistant and the responses of the tools
+        if let Some(function_call) = message.function_call {
+            messages.push(OllamaMessage {
+                role: "assistant".to_string(),
+                content: String::new(),
+                images: None,
+                tool_calls: Some(vec![OllamaToolCall {
+                    function: OllamaFunctionCall {
+                        name: function_call.name,
+                        arguments: serde_json::from_str(&function_call.arguments)
+                            .unwrap_or_else(|_| serde_json::json!({})),
+                    },
+                }]),
+            });
+            continue;
+        }
+        if message.role.as_deref() == Some("function") {
+            messages.push(OllamaMessage {
+                role: "tool".to_string(),
+                content: message.content.unwrap_or_default(),
+                images: None,
+                tool_calls: None,
+            });
+            continue;
+        }
+
         if let Some(content) = message.content {
             let mut images = None;
 
@@ -97,6 +159,7 @@ fn from_chat_completion_messages(
                 role: message.role.unwrap_or_default(),
                 content,
                 images,
+                tool_calls: None,
             });
         }
     }
@@ -106,8 +169,8 @@ fn from_chat_completion_messages(
 
 #[cfg(test)]
 mod tests {
-    use crate::llm_provider::providers::shared::llm_message::LlmMessage;
     use super::*;
+    use crate::llm_provider::providers::shared::llm_message::{DetailedFunctionCall, LlmMessage};
 
     #[test]
     fn test_from_llm_messages() {
@@ -147,20 +210,63 @@ mod tests {
                 role: "system".to_string(),
                 content: "You are a very helpful assistant that's very good at completing a task.".to_string(),
                 images: None,
+                tool_calls: None,
             },
             OllamaMessage {
                 role: "user".to_string(),
                 content: "The current main task at hand is: `describe this`".to_string(),
                 images: Some(vec!["iVBORw0KGgoAAAANSUhEUgAAAlgAAAJYCAYAAAC".to_string()]),
+                tool_calls: None,
             },
             OllamaMessage {
                 role: "system".to_string(),
                 content: "Make the answer very readable and easy to understand formatted using markdown bulletpoint lists and separated paragraphs.".to_string(),
                 images: None,
+                tool_calls: None,
             },
         ];
 
         let result = from_chat_completion_messages(llm_messages).unwrap();
         assert_eq!(result, expected_messages);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_from_llm_messages_with_function_call() {
+        let llm_messages = vec![
+            LlmMessage {
+                role: Some("user".to_string()),
+                content: Some("concatenate hola and chao".to_string()),
+                name: None,
+                function_call: None,
+                functions: None,
+            },
+            LlmMessage {
+                role: Some("assistant".to_string()),
+                content: None,
+                name: None,
+                function_call: Some(DetailedFunctionCall {
+                    name: "concat_strings".to_string(),
+                    arguments: "{\"first_string\":\"hola\",\"second_string\":\"chao\"}".to_string(),
+                }),
+                functions: None,
+            },
+            LlmMessage {
+                role: Some("function".to_string()),
+                content: Some("holachao".to_string()),
+                name: Some("concat_strings".to_string()),
+                function_call: None,
+                functions: None,
+            },
+        ];
+
+        let result = from_chat_completion_messages(llm_messages).unwrap();
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[1].role, "assistant");
+        assert_eq!(
+            result[1].tool_call().unwrap().arguments,
+            serde_json::json!({"first_string": "hola", "second_string": "chao"})
+        );
+        assert_eq!(result[2].role, "tool");
+        assert_eq!(result[2].content, "holachao");
+    }
+}
@@ -1,4 +1,5 @@
 use crate::llm_provider::error::LLMProviderError;
+use crate::llm_provider::execution::chains::inference_chain_trait::LLMTokenUsage;
 use crate::llm_provider::execution::prompts::prompts::Prompt;
 use crate::managers::model_capabilities_manager::ModelCapabilitiesManager;
 use crate::managers::model_capabilities_manager::PromptResult;
@@ -7,6 +8,7 @@ use serde::ser::{SerializeStruct, Serializer};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use serde_json::{self};
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
 use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::LLMProviderInterface;
 
 #[derive(Debug, Deserialize)]
@@ -15,7 +17,7 @@ pub struct OpenAIResponse {
     object: String,
     created: u64,
     pub choices: Vec<Choice>,
-    usage: Usage,
+    pub usage: Usage,
 }
 
 #[derive(Debug, Deserialize)]
@@ -49,6 +51,32 @@ pub struct OpenAIApiMessage {
     pub role: String,
     pub content: Option<MessageContent>,
     pub function_call: Option<FunctionCall>,
+    pub tool_calls: Option<Vec<OpenAIToolCall>>,
+}
+
+/// A tool call as returned by the providers using the `tools` API (instead of the legacy `functions`)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OpenAIToolCall {
+    #[serde(default)]
+    pub id: String,
+    pub function: OpenAIToolCallFunction,
+}
+
+/// `arguments` is a JSON encoded string
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OpenAIToolCallFunction {
+    pub name: String,
+    pub arguments: String,
+}
+
+impl OpenAIApiMessage {
+    /// Returns the first tool call of the message as a `FunctionCall`
+    pub fn tool_call(&self) -> Option<FunctionCall> {
+        self.tool_calls.as_ref()?.first().map(|tool_call| FunctionCall {
+            name: tool_call.function.name.clone(),
+            arguments: serde_json::from_str(&tool_call.function.arguments).unwrap_or_else(|_| serde_json::json!({})),
+        })
+    }
 }
 
 impl Serialize for OpenAIApiMessage {
@@ -56,7 +84,7 @@ impl Serialize for OpenAIApiMessage {
     where
         S: Serializer,
     {
-        let mut map = serializer.serialize_struct("OpenAIApiMessage", 3)?;
+        let mut map = serializer.serialize_struct("OpenAIApiMessage", 4)?;
         map.serialize_field("role", &self.role)?;
         if let Some(content) = &self.content {
             map.serialize_field("content", content)?;
@@ -64,15 +92,24 @@ impl Serialize for OpenAIApiMessage {
         if let Some(function_call) = &self.function_call {
             map.serialize_field("function_call", function_call)?;
         }
+        if let Some(tool_calls) = &self.tool_calls {
+            map.serialize_field("tool_calls", tool_calls)?;
+        }
         map.end()
     }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Usage {
-    prompt_tokens: i32,
-    completion_tokens: i32,
-    total_tokens: i32,
+    pub prompt_tokens: i32,
+    pub completion_tokens: i32,
+    pub total_tokens: i32,
+}
+
+impl Usage {
+    pub fn to_token_usage(&self) -> LLMTokenUsage {
+        LLMTokenUsage::new(self.prompt_tokens.max(0) as u64, self.completion_tokens.max(0) as u64)
+    }
 }
 
 #[derive(Serialize)]
@@ -144,6 +181,81 @@ pub fn openai_prepare_messages(model: &LLMProviderInterface, prompt: Prompt) ->
     })
 }
 
+/// Sets the inference params of the job on an OpenAI-style payload, replacing the defaults of the provider.
+/// The max tokens stay capped by the ones already in the payload (the room left in the context window).
+pub fn openai_add_inference_params(payload: &mut JsonValue, inference_params: &InferenceParams) {
+    if let Some(temperature) = inference_params.temperature {
+        payload["temperature"] = serde_json::json!(temperature);
+    }
+    if let Some(top_p) = inference_params.top_p {
+        payload["top_p"] = serde_json::json!(top_p);
+    }
+    if let Some(max_tokens) = inference_params.max_tokens {
+        let max_tokens = match payload["max_tokens"].as_u64() {
+            Some(room_left) => max_tokens.min(room_left),
+            None => max_tokens,
+        };
+        payload["max_tokens"] = serde_json::json!(max_tokens);
+    }
+    if let Some(stop) = &inference_params.stop {
+        payload["stop"] = serde_json::json!(stop);
+    }
+    if let Some(seed) = inference_params.seed {
+        payload["seed"] = serde_json::json!(seed);
+    }
+}
+
+/// Wraps the functions returned by `openai_prepare_messages` in the `tools` format
+pub fn openai_tools_from_functions(functions: Vec<JsonValue>) -> Vec<JsonValue> {
+    functions
+        .into_iter()
+        .map(|function| serde_json::json!({ "type": "function", "function": function }))
+        .collect()
+}
+
+/// Converts the legacy `function_call` and `function` messages generated from the prompt into
+/// `tool_calls` and `tool` messages, as required by the providers only supporting the `tools` API.
+/// Each function response is matched with the latest pending call of the same function.
+pub fn openai_messages_with_tool_calls(messages: Vec<JsonValue>) -> Vec<JsonValue> {
+    let mut pending_calls: Vec<(String, String)> = Vec::new();
+
+    messages
+        .into_iter()
+        .enumerate()
+        .map(|(index, mut message)| {
+            let role = message
+                .get("role")
+                .and_then(|r| r.as_str())
+                .unwrap_or_default()
+                .to_string();
+            if role == "assistant" {
+                if let Some(function_call) = message.as_object_mut().and_then(|m| m.remove("function_call")) {
+                    let name = function_call.get("name").and_then(|n| n.as_str()).unwrap_or_default();
+                    let id = format!("call_{}", index);
+                    pending_calls.push((name.to_string(), id.clone()));
+                    message["tool_calls"] = serde_json::json!([{
+                        "id": id,
+                        "type": "function",
+                        "function": function_call,
+                    }]);
+                }
+            } else if role == "function" {
+                let name = message
+                    .get("name")
+                    .and_then(|n| n.as_str())
+                    .unwrap_or_default()
+                    .to_string();
+                message["role"] = serde_json::json!("tool");
+                if let Some(position) = pending_calls.iter().rposition(|(call_name, _)| *call_name == name) {
+                    let (_, id) = pending_calls.remove(position);
+                    message["tool_call_id"] = serde_json::json!(id);
+                }
+            }
+            message
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -251,4 +363,37 @@ mod tests {
             panic!("Expected text content");
         }
     }
+
+    #[test]
+    fn test_openai_messages_with_tool_calls() {
+        let messages = vec![
+            json!({ "role": "user", "content": "concatenate hola and chao" }),
+            json!({
+                "role": "assistant",
+                "function_call": { "name": "concat_strings", "arguments": "{\"first_string\":\"hola\"}" }
+            }),
+            json!({ "role": "function", "name": "concat_strings", "content": "holachao" }),
+        ];
+
+        let messages = openai_messages_with_tool_calls(messages);
+        assert!(messages[1].get("function_call").is_none());
+        assert_eq!(messages[1]["tool_calls"][0]["id"], "call_1");
+        assert_eq!(messages[1]["tool_calls"][0]["function"]["name"], "concat_strings");
+        assert_eq!(messages[2]["role"], "tool");
+        assert_eq!(messages[2]["tool_call_id"], "call_1");
+
+        let message: OpenAIApiMessage = serde_json::from_value(json!({
+            "role": "assistant",
+            "content": null,
+            "tool_calls": [{
+                "id": "call_abc",
+                "type": "function",
+                "function": { "name": "concat_strings", "arguments": "{\"first_string\":\"hola\"}" }
+            }]
+        }))
+        .unwrap();
+        let function_call = message.tool_call().unwrap();
+        assert_eq!(function_call.name, "concat_strings");
+        assert_eq!(function_call.arguments, json!({ "first_string": "hola" }));
+    }
 }
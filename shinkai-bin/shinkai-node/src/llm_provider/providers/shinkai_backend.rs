@@ -1,11 +1,15 @@
 use std::sync::Arc;
 
 use crate::llm_provider::execution::chains::inference_chain_trait::LLMInferenceResponse;
-use crate::managers::model_capabilities_manager::PromptResultEnum;
+use crate::managers::model_capabilities_manager::{
+    InferenceParamLimits, ModelCapabilitiesManagerError, ModelCapability, ModelCost, ModelPrivacy, PromptResult,
+    PromptResultEnum,
+};
 use crate::network::ws_manager::WSUpdateHandler;
 
 use super::super::{error::LLMProviderError, execution::prompts::prompts::Prompt};
-use super::shared::openai::{openai_prepare_messages, MessageContent, OpenAIResponse};
+use super::shared::openai::{openai_add_inference_params, openai_prepare_messages, MessageContent, OpenAIResponse};
+use super::provider_registry::LLMProviderBackend;
 use super::LLMService;
 use async_trait::async_trait;
 use reqwest::Client;
@@ -13,6 +17,7 @@ use serde_json::json;
 use serde_json::Value as JsonValue;
 use serde_json::{self};
 use shinkai_message_primitives::schemas::inbox_name::InboxName;
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
 use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{
     LLMProviderInterface, ShinkaiBackend,
 };
@@ -50,6 +55,7 @@ impl LLMService for ShinkaiBackend {
         url: Option<&String>,
         api_key: Option<&String>,
         prompt: Prompt,
+        inference_params: InferenceParams,
         model: LLMProviderInterface,
         inbox_name: Option<InboxName>,
         _ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
@@ -81,12 +87,13 @@ impl LLMService for ShinkaiBackend {
                 };
                 // eprintln!("Messages JSON: {:?}", messages_json);
 
-                let payload = json!({
+                let mut payload = json!({
                     "model": self.model_type(),
                     "messages": messages_json,
                     "temperature": 0.7,
                     // "max_tokens": result.remaining_tokens, // TODO: Check if this is necessary
                 });
+                openai_add_inference_params(&mut payload, &inference_params);
 
                 let mut payload_log = payload.clone();
                 truncate_image_url_in_payload(&mut payload_log);
@@ -167,7 +174,8 @@ impl LLMService for ShinkaiBackend {
                                 .choices
                                 .iter()
                                 .find_map(|choice| choice.message.function_call.clone());
-                            Ok(LLMInferenceResponse::new(response_string, json!({}), function_call))
+                            Ok(LLMInferenceResponse::new(response_string, json!({}), function_call)
+                                .with_usage(data.usage.to_token_usage()))
                         } else {
                             let data: OpenAIResponse =
                                 serde_json::from_value(value).map_err(LLMProviderError::SerdeError)?;
@@ -184,7 +192,8 @@ impl LLMService for ShinkaiBackend {
                                 .choices
                                 .iter()
                                 .find_map(|choice| choice.message.function_call.clone());
-                            Ok(LLMInferenceResponse::new(response_string, json!({}), function_call))
+                            Ok(LLMInferenceResponse::new(response_string, json!({}), function_call)
+                                .with_usage(data.usage.to_token_usage()))
                         }
                     }
                     Err(e) => {
@@ -204,3 +213,111 @@ impl LLMService for ShinkaiBackend {
         }
     }
 }
+
+/// Registry backend for `shinkai-backend:` models
+pub struct ShinkaiBackendBackend;
+
+#[async_trait]
+impl LLMProviderBackend for ShinkaiBackendBackend {
+    fn provider_prefix(&self) -> &str {
+        "shinkai-backend"
+    }
+
+    async fn call_api(
+        &self,
+        client: &Client,
+        url: Option<&String>,
+        api_key: Option<&String>,
+        prompt: Prompt,
+        inference_params: InferenceParams,
+        model: LLMProviderInterface,
+        inbox_name: Option<InboxName>,
+        ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> Result<LLMInferenceResponse, LLMProviderError> {
+        match &model {
+            LLMProviderInterface::ShinkaiBackend(shinkai_backend) => {
+                shinkai_backend
+                    .call_api(
+                        client,
+                        url,
+                        api_key,
+                        prompt,
+                        inference_params,
+                        model.clone(),
+                        inbox_name,
+                        ws_manager_trait,
+                    )
+                    .await
+            }
+            _ => Err(LLMProviderError::InvalidModelType(format!(
+                "shinkai-backend backend can't handle {:?}",
+                model
+            ))),
+        }
+    }
+
+    fn prepare_messages(
+        &self,
+        model: &LLMProviderInterface,
+        _prompt: Prompt,
+    ) -> Result<PromptResult, ModelCapabilitiesManagerError> {
+        Err(ModelCapabilitiesManagerError::NotImplemented(model.model_type()))
+    }
+
+    fn capabilities(&self, model: &LLMProviderInterface) -> Vec<ModelCapability> {
+        match model.model_type().as_str() {
+            "gpt" | "gpt4" | "gpt-4-1106-preview" | "PREMIUM_TEXT_INFERENCE" | "STANDARD_TEXT_INFERENCE" => {
+                vec![ModelCapability::TextInference]
+            }
+            "gpt-vision" | "gpt-4-vision-preview" | "gp4o" | "gpt-4o" | "PREMIUM_VISION_INFERENCE" | "gpt-4o-mini" => {
+                vec![ModelCapability::ImageAnalysis, ModelCapability::TextInference]
+            }
+            "dall-e" => vec![ModelCapability::ImageGeneration],
+            _ => vec![],
+        }
+    }
+
+    fn cost(&self, model: &LLMProviderInterface) -> ModelCost {
+        match model.model_type().as_str() {
+            "gpt4" | "gpt-4-1106-preview" | "PREMIUM_TEXT_INFERENCE" => ModelCost::Expensive,
+            "gpt-vision" | "gpt-4-vision-preview" | "STANDARD_TEXT_INFERENCE" | "PREMIUM_VISION_INFERENCE" => {
+                ModelCost::GoodValue
+            }
+            "dall-e" => ModelCost::GoodValue,
+            _ => ModelCost::Unknown,
+        }
+    }
+
+    fn inference_param_limits(&self, _model: &LLMProviderInterface) -> InferenceParamLimits {
+        InferenceParamLimits {
+            max_temperature: 2.0,
+            max_stop_sequences: Some(4),
+        }
+    }
+
+    fn privacy(&self, model: &LLMProviderInterface) -> ModelPrivacy {
+        match model.model_type().as_str() {
+            "PREMIUM_TEXT_INFERENCE" => ModelPrivacy::RemoteGreedy,
+            "PREMIUM_VISION_INFERENCE" => ModelPrivacy::RemoteGreedy,
+            "STANDARD_TEXT_INFERENCE" => ModelPrivacy::RemoteGreedy,
+            _ => ModelPrivacy::Unknown,
+        }
+    }
+
+    fn max_tokens(&self, model: &LLMProviderInterface) -> usize {
+        let model_type = model.model_type();
+        if model_type == "PREMIUM_TEXT_INFERENCE" || model_type == "PREMIUM_VISION_INFERENCE" {
+            128_000
+        } else {
+            32_000
+        }
+    }
+
+    fn normalize_model(&self, model: &LLMProviderInterface) -> String {
+        if model.model_type().starts_with("gpt") {
+            "gpt-4-32k".to_string()
+        } else {
+            "gpt-4".to_string()
+        }
+    }
+}
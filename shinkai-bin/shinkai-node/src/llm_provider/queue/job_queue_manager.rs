@@ -4,6 +4,7 @@ use chrono::Utc;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use shinkai_message_primitives::schemas::job_config::JobPriority;
 use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
 use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::JobMessage;
 use std::cmp::Ordering;
@@ -15,33 +16,37 @@ use tokio::sync::{mpsc, Mutex};
 type MutexQueue<T> = Arc<Mutex<Vec<T>>>;
 type Subscriber<T> = mpsc::Sender<T>;
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct JobForProcessing {
     pub job_message: JobMessage,
     pub profile: ShinkaiName,
     pub date_created: String,
+    /// Scheduling class of the message, unless the job config overrides it
+    #[serde(default)]
+    pub priority: JobPriority,
     // TODO: add a new optional field for callbacks
 }
 
 impl JobForProcessing {
     pub fn new(job_message: JobMessage, profile: ShinkaiName) -> Self {
+        let priority = match job_message.sheet_job_data {
+            Some(_) => JobPriority::Batch,
+            None => JobPriority::Interactive,
+        };
+
         JobForProcessing {
             job_message,
             profile,
             date_created: Utc::now().to_rfc3339(),
+            priority,
         }
     }
 }
 
+// Ordered by creation date. Not `Ord`, the job message isn't `Eq` (its inference params hold floats).
 impl PartialOrd for JobForProcessing {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl Ord for JobForProcessing {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.date_created.cmp(&other.date_created)
+        Some(self.date_created.cmp(&other.date_created))
     }
 }
 
@@ -86,7 +91,7 @@ pub struct JobQueueManager<T: Debug> {
 // Note: these are the ones that are kept in memory but the complete list is kept in the database
 static BUFFER_SIZE: usize = 10;
 
-impl<T: Clone + Send + 'static + DeserializeOwned + Serialize + Ord + Debug> JobQueueManager<T> {
+impl<T: Clone + Send + 'static + DeserializeOwned + Serialize + PartialOrd + Debug> JobQueueManager<T> {
     pub async fn new(db: Weak<ShinkaiDB>, cf_name: &str, prefix: Option<String>) -> Result<Self, ShinkaiDBError> {
         // Lock the db for safe access
         let db_arc = db.upgrade().ok_or("Failed to upgrade shinkai_db").unwrap();
@@ -184,6 +189,43 @@ impl<T: Clone + Send + 'static + DeserializeOwned + Serialize + Ord + Debug> Job
         Ok(result)
     }
 
+    /// Removes all the elements of a queue, returning them
+    pub async fn clear(&mut self, key: &str) -> Result<Vec<T>, ShinkaiDBError> {
+        let queues = self.queues.lock().await;
+        let removed = match queues.get(key) {
+            Some(queue) => {
+                let mut guarded_queue = queue.lock().await;
+                let removed = std::mem::take(&mut *guarded_queue);
+
+                // Persist queue to the database
+                let db_arc = self.db.upgrade().ok_or("Failed to upgrade shinkai_db").unwrap();
+                db_arc.persist_queue(&self.cf_name, key, &guarded_queue, self.prefix.clone())?;
+                removed
+            }
+            None => Vec::new(),
+        };
+
+        Ok(removed)
+    }
+
+    /// Notifies the subscribers to all keys about the first element of a queue again
+    /// (e.g. so it gets processed after being on hold)
+    pub async fn notify(&self, key: &str) -> Result<(), ShinkaiDBError> {
+        let first = match self.peek(key).await? {
+            Some(first) => first,
+            None => return Ok(()),
+        };
+
+        let all_subscribers = self.all_subscribers.lock().await;
+        for sub in all_subscribers.iter() {
+            if sub.capacity() > 0 {
+                // Check if there's space in the buffer
+                let _ = sub.send(first.clone()).await;
+            }
+        }
+        Ok(())
+    }
+
     pub async fn peek(&self, key: &str) -> Result<Option<T>, ShinkaiDBError> {
         let queues = self.queues.lock().await;
         if let Some(queue) = queues.get(key) {
@@ -195,6 +237,18 @@ impl<T: Clone + Send + 'static + DeserializeOwned + Serialize + Ord + Debug> Job
         Ok(None)
     }
 
+    /// Returns the first element of every non empty queue along with its key
+    pub async fn get_all_queue_heads(&self) -> Result<Vec<(String, T)>, ShinkaiDBError> {
+        let queues = self.queues.lock().await;
+        let mut heads = Vec::new();
+        for (key, queue) in queues.iter() {
+            if let Some(first) = queue.lock().await.first() {
+                heads.push((key.clone(), first.clone()));
+            }
+        }
+        Ok(heads)
+    }
+
     pub async fn get_all_elements_interleave(&self) -> Result<Vec<T>, ShinkaiDBError> {
         let db_arc = self.db.upgrade().ok_or("Failed to upgrade shinkai_db").unwrap();
         let mut db_queues: HashMap<_, _> = db_arc.get_all_queues::<T>(&self.cf_name, self.prefix.clone())?;
@@ -205,7 +259,7 @@ impl<T: Clone + Send + 'static + DeserializeOwned + Serialize + Ord + Debug> Job
             let a_first = db_queues.get(a).and_then(|q| q.first());
             let b_first = db_queues.get(b).and_then(|q| q.first());
             match (a_first, b_first) {
-                (Some(a), Some(b)) => a.cmp(b),
+                (Some(a), Some(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
                 _ => a.cmp(b),
             }
         });
@@ -322,6 +376,8 @@ mod tests {
                 workflow_name: None,
                 sheet_job_data: None,
                 callback: None,
+                inference_params: None,
+                is_delegation_trace: false,
             },
             ShinkaiName::new("@@node1.shinkai/main".to_string()).unwrap(),
         );
@@ -355,6 +411,8 @@ mod tests {
                 workflow_name: None,
                 sheet_job_data: None,
                 callback: None,
+                inference_params: None,
+                is_delegation_trace: false,
             },
             ShinkaiName::new("@@node1.shinkai/main".to_string()).unwrap(),
         );
@@ -368,6 +426,8 @@ mod tests {
                 workflow_name: None,
                 sheet_job_data: None,
                 callback: None,
+                inference_params: None,
+                is_delegation_trace: false,
             },
             ShinkaiName::new("@@node1.shinkai/main".to_string()).unwrap(),
         );
@@ -471,6 +531,8 @@ mod tests {
                 workflow_name: None,
                 sheet_job_data: None,
                 callback: None,
+                inference_params: None,
+                is_delegation_trace: false,
             },
             ShinkaiName::new("@@node1.shinkai/main".to_string()).unwrap(),
         );
@@ -484,6 +546,8 @@ mod tests {
                 workflow_name: None,
                 sheet_job_data: None,
                 callback: None,
+                inference_params: None,
+                is_delegation_trace: false,
             },
             ShinkaiName::new("@@node1.shinkai/main".to_string()).unwrap(),
         );
@@ -497,6 +561,8 @@ mod tests {
                 workflow_name: None,
                 sheet_job_data: None,
                 callback: None,
+                inference_params: None,
+                is_delegation_trace: false,
             },
             ShinkaiName::new("@@node1.shinkai/main".to_string()).unwrap(),
         );
@@ -511,6 +577,8 @@ mod tests {
                 workflow_name: None,
                 sheet_job_data: None,
                 callback: None,
+                inference_params: None,
+                is_delegation_trace: false,
             },
             ShinkaiName::new("@@node1.shinkai/main".to_string()).unwrap(),
         );
@@ -525,6 +593,8 @@ mod tests {
                 workflow_name: None,
                 sheet_job_data: None,
                 callback: None,
+                inference_params: None,
+                is_delegation_trace: false,
             },
             ShinkaiName::new("@@node1.shinkai/main".to_string()).unwrap(),
         );
@@ -539,6 +609,8 @@ mod tests {
                 workflow_name: None,
                 sheet_job_data: None,
                 callback: None,
+                inference_params: None,
+                is_delegation_trace: false,
             },
             ShinkaiName::new("@@node1.shinkai/main".to_string()).unwrap(),
         );
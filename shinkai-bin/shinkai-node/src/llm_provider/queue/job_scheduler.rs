@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::env;
+
+use shinkai_message_primitives::schemas::job_config::JobPriority;
+
+use super::job_queue_manager::JobForProcessing;
+
+/// How many messages can be processed at once, in total and per llm provider
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobSchedulerLimits {
+    pub max_parallel_jobs: usize,
+    /// Limit of the llm providers without a limit of their own (`None` means only the total limit applies)
+    pub default_provider_limit: Option<usize>,
+    /// Limits by llm provider id
+    pub provider_limits: HashMap<String, usize>,
+}
+
+impl JobSchedulerLimits {
+    pub fn new(max_parallel_jobs: usize) -> Self {
+        JobSchedulerLimits {
+            max_parallel_jobs,
+            default_provider_limit: None,
+            provider_limits: HashMap::new(),
+        }
+    }
+
+    /// Reads the llm provider limits from `JOB_MANAGER_PROVIDER_LIMIT` (default for every provider) and
+    /// `JOB_MANAGER_PROVIDER_LIMITS` (comma separated `llm_provider_id=limit` pairs)
+    pub fn from_env(max_parallel_jobs: usize) -> Self {
+        let mut limits = Self::new(max_parallel_jobs);
+        limits.default_provider_limit = env::var("JOB_MANAGER_PROVIDER_LIMIT")
+            .ok()
+            .and_then(|limit| limit.trim().parse::<usize>().ok());
+        if let Ok(provider_limits) = env::var("JOB_MANAGER_PROVIDER_LIMITS") {
+            limits.provider_limits = Self::parse_provider_limits(&provider_limits);
+        }
+        limits
+    }
+
+    fn parse_provider_limits(value: &str) -> HashMap<String, usize> {
+        value
+            .split(',')
+            .filter_map(|pair| {
+                let (provider, limit) = pair.split_once('=')?;
+                Some((provider.trim().to_string(), limit.trim().parse::<usize>().ok()?))
+            })
+            .collect()
+    }
+
+    pub fn provider_limit(&self, llm_provider_id: &str) -> Option<usize> {
+        self.provider_limits
+            .get(llm_provider_id)
+            .copied()
+            .or(self.default_provider_limit)
+    }
+}
+
+/// The next message of a job waiting to be processed
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedJob {
+    pub job_id: String,
+    pub job: JobForProcessing,
+    /// Set by the job config, if it overrides the priority of the message
+    pub priority_override: Option<JobPriority>,
+    pub llm_provider_id: Option<String>,
+}
+
+impl QueuedJob {
+    pub fn priority(&self) -> JobPriority {
+        self.priority_override.unwrap_or(self.job.priority)
+    }
+
+    pub fn profile(&self) -> String {
+        self.job.profile.get_profile_name_string().unwrap_or_default()
+    }
+}
+
+/// A job with a message being processed
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunningJob {
+    pub profile: String,
+    pub llm_provider_id: Option<String>,
+}
+
+/// Picks the queued jobs to start processing now, in order.
+/// Higher priority classes go first. Within a class, profiles take turns (the one with the fewest running
+/// jobs first, then the one waiting the longest) and each profile's jobs go from the oldest message.
+/// Jobs whose llm provider is at its limit stay queued.
+pub fn select_jobs_to_process(
+    queued: Vec<QueuedJob>,
+    running: &HashMap<String, RunningJob>,
+    limits: &JobSchedulerLimits,
+) -> Vec<QueuedJob> {
+    let mut available = limits.max_parallel_jobs.saturating_sub(running.len());
+    let mut running_by_provider: HashMap<String, usize> = HashMap::new();
+    let mut running_by_profile: HashMap<String, usize> = HashMap::new();
+    for job in running.values() {
+        if let Some(llm_provider_id) = &job.llm_provider_id {
+            *running_by_provider.entry(llm_provider_id.clone()).or_default() += 1;
+        }
+        *running_by_profile.entry(job.profile.clone()).or_default() += 1;
+    }
+
+    let mut candidates: Vec<QueuedJob> = queued
+        .into_iter()
+        .filter(|job| !running.contains_key(&job.job_id))
+        .collect();
+    candidates.sort_by(|a, b| {
+        a.priority()
+            .cmp(&b.priority())
+            .then_with(|| a.job.date_created.cmp(&b.job.date_created))
+    });
+
+    let mut selected = Vec::new();
+    while available > 0 && !candidates.is_empty() {
+        // Candidates are sorted, so the first one has the highest priority and the rest of its class follows
+        let priority = candidates[0].priority();
+        let next = candidates
+            .iter()
+            .enumerate()
+            .take_while(|(_, job)| job.priority() == priority)
+            .min_by_key(|(_, job)| running_by_profile.get(&job.profile()).copied().unwrap_or_default())
+            .map(|(index, _)| index)
+            .unwrap_or_default();
+        let job = candidates.remove(next);
+
+        if let Some(llm_provider_id) = &job.llm_provider_id {
+            let provider_running = running_by_provider.entry(llm_provider_id.clone()).or_default();
+            if limits
+                .provider_limit(llm_provider_id)
+                .is_some_and(|limit| *provider_running >= limit)
+            {
+                continue;
+            }
+            *provider_running += 1;
+        }
+
+        *running_by_profile.entry(job.profile()).or_default() += 1;
+        available -= 1;
+        selected.push(job);
+    }
+
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
+    use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::JobMessage;
+
+    fn queued_job(job_id: &str, profile: &str, priority: JobPriority, date: &str, provider: &str) -> QueuedJob {
+        let mut job = JobForProcessing::new(
+            JobMessage {
+                job_id: job_id.to_string(),
+                content: "content".to_string(),
+                files_inbox: "".to_string(),
+                parent: None,
+                workflow_code: None,
+                workflow_name: None,
+                sheet_job_data: None,
+                callback: None,
+                inference_params: None,
+                is_delegation_trace: false,
+            },
+            ShinkaiName::new(format!("@@node1.shinkai/{}", profile)).unwrap(),
+        );
+        job.date_created = date.to_string();
+        job.priority = priority;
+
+        QueuedJob {
+            job_id: job_id.to_string(),
+            job,
+            priority_override: None,
+            llm_provider_id: Some(provider.to_string()),
+        }
+    }
+
+    fn job_ids(jobs: &[QueuedJob]) -> Vec<&str> {
+        jobs.iter().map(|job| job.job_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_priority_classes() {
+        let queued = vec![
+            queued_job("cell1", "main", JobPriority::Batch, "1", "ollama"),
+            queued_job("cell2", "main", JobPriority::Batch, "2", "ollama"),
+            queued_job("task", "main", JobPriority::Background, "3", "ollama"),
+            queued_job("chat", "main", JobPriority::Interactive, "4", "ollama"),
+        ];
+        let selected = select_jobs_to_process(queued.clone(), &HashMap::new(), &JobSchedulerLimits::new(2));
+        assert_eq!(job_ids(&selected), vec!["chat", "task"]);
+
+        // The job config overrides the priority of the message
+        let mut queued = queued;
+        queued[1].priority_override = Some(JobPriority::Interactive);
+        let selected = select_jobs_to_process(queued, &HashMap::new(), &JobSchedulerLimits::new(2));
+        assert_eq!(job_ids(&selected), vec!["cell2", "chat"]);
+    }
+
+    #[test]
+    fn test_fair_scheduling_across_profiles() {
+        let queued = vec![
+            queued_job("a1", "alice", JobPriority::Batch, "1", "ollama"),
+            queued_job("a2", "alice", JobPriority::Batch, "2", "ollama"),
+            queued_job("a3", "alice", JobPriority::Batch, "3", "ollama"),
+            queued_job("b1", "bob", JobPriority::Batch, "4", "ollama"),
+            queued_job("b2", "bob", JobPriority::Batch, "5", "ollama"),
+        ];
+        let selected = select_jobs_to_process(queued.clone(), &HashMap::new(), &JobSchedulerLimits::new(4));
+        assert_eq!(job_ids(&selected), vec!["a1", "b1", "a2", "b2"]);
+
+        // Profiles with running jobs wait for the others
+        let running = HashMap::from([(
+            "a0".to_string(),
+            RunningJob {
+                profile: "alice".to_string(),
+                llm_provider_id: Some("ollama".to_string()),
+            },
+        )]);
+        let selected = select_jobs_to_process(queued, &running, &JobSchedulerLimits::new(3));
+        assert_eq!(job_ids(&selected), vec!["b1", "a1"]);
+    }
+
+    #[test]
+    fn test_provider_limits() {
+        let queued = vec![
+            queued_job("local1", "main", JobPriority::Interactive, "1", "ollama"),
+            queued_job("local2", "main", JobPriority::Interactive, "2", "ollama"),
+            queued_job("remote1", "main", JobPriority::Batch, "3", "openai"),
+            queued_job("remote2", "main", JobPriority::Batch, "4", "openai"),
+        ];
+        let mut limits = JobSchedulerLimits::new(4);
+        limits.provider_limits = JobSchedulerLimits::parse_provider_limits("ollama=1, openai = 3, broken");
+        assert_eq!(limits.provider_limits.len(), 2);
+
+        let selected = select_jobs_to_process(queued.clone(), &HashMap::new(), &limits);
+        assert_eq!(job_ids(&selected), vec!["local1", "remote1", "remote2"]);
+
+        // Running jobs count towards the limits
+        let running = HashMap::from([(
+            "local0".to_string(),
+            RunningJob {
+                profile: "main".to_string(),
+                llm_provider_id: Some("ollama".to_string()),
+            },
+        )]);
+        let selected = select_jobs_to_process(queued.clone(), &running, &limits);
+        assert_eq!(job_ids(&selected), vec!["remote1", "remote2"]);
+
+        limits.provider_limits.clear();
+        limits.default_provider_limit = Some(1);
+        let selected = select_jobs_to_process(queued, &HashMap::new(), &limits);
+        assert_eq!(job_ids(&selected), vec!["local1", "remote1"]);
+    }
+}
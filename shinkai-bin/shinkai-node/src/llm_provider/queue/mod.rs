@@ -1,2 +1,3 @@
 pub mod job_queue_manager;
-pub mod job_queue_manager_error;
\ No newline at end of file
+pub mod job_queue_manager_error;
+pub mod job_scheduler;
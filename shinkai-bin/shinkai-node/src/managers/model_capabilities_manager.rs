@@ -3,15 +3,14 @@ use crate::{
     llm_provider::{
         error::LLMProviderError,
         execution::prompts::prompts::Prompt,
-        providers::shared::{
-            llm_message::LlmMessage,
-            openai::openai_prepare_messages,
-            shared_model_logic::{llama_prepare_messages, llava_prepare_messages},
-        },
+        providers::{provider_registry::LLMProviderRegistry, shared::llm_message::LlmMessage},
     },
 };
 use shinkai_message_primitives::schemas::{
-    llm_providers::serialized_llm_provider::{LLMProviderInterface, SerializedLLMProvider},
+    llm_providers::{
+        inference_params::InferenceParams,
+        serialized_llm_provider::{LLMProviderInterface, SerializedLLMProvider},
+    },
     shinkai_name::ShinkaiName,
 };
 use std::{
@@ -23,6 +22,7 @@ use std::{
 pub enum ModelCapabilitiesManagerError {
     GeneralError(String),
     NotImplemented(String),
+    InvalidInferenceParams(String),
 }
 
 impl std::fmt::Display for ModelCapabilitiesManagerError {
@@ -30,6 +30,9 @@ impl std::fmt::Display for ModelCapabilitiesManagerError {
         match self {
             ModelCapabilitiesManagerError::GeneralError(err) => write!(f, "General error: {}", err),
             ModelCapabilitiesManagerError::NotImplemented(model) => write!(f, "Model not implemented: {}", model),
+            ModelCapabilitiesManagerError::InvalidInferenceParams(err) => {
+                write!(f, "Invalid inference params: {}", err)
+            }
         }
     }
 }
@@ -93,6 +96,23 @@ pub enum ModelPrivacy {
     RemoteGreedy,
 }
 
+/// Range of the inference params accepted by a model (the max tokens are bound by its max output tokens)
+#[derive(Clone, Debug, PartialEq)]
+pub struct InferenceParamLimits {
+    pub max_temperature: f64,
+    /// Max number of stop sequences (None if unlimited)
+    pub max_stop_sequences: Option<usize>,
+}
+
+impl Default for InferenceParamLimits {
+    fn default() -> Self {
+        InferenceParamLimits {
+            max_temperature: 2.0,
+            max_stop_sequences: None,
+        }
+    }
+}
+
 // Struct for ModelCapabilitiesManager
 pub struct ModelCapabilitiesManager {
     pub db: Weak<ShinkaiDB>,
@@ -128,49 +148,14 @@ impl ModelCapabilitiesManager {
 
     // Static method to get capabilities of an agent model
     pub fn get_llm_provider_capabilities(model: &LLMProviderInterface) -> Vec<ModelCapability> {
-        match model {
-            LLMProviderInterface::OpenAI(openai) => match openai.model_type.as_str() {
-                "gpt-4o" => vec![ModelCapability::ImageAnalysis, ModelCapability::TextInference],
-                "gpt-4o-mini" => vec![ModelCapability::ImageAnalysis, ModelCapability::TextInference],
-                "gpt-3.5-turbo-1106" => vec![ModelCapability::TextInference],
-                "gpt-4-1106-preview" => vec![ModelCapability::TextInference],
-                "gpt-4-vision-preview" => vec![ModelCapability::ImageAnalysis, ModelCapability::TextInference],
-                "dall-e-3" => vec![ModelCapability::ImageGeneration],
-                model_type if model_type.starts_with("gpt-") => vec![ModelCapability::TextInference],
-                _ => vec![],
-            },
-            LLMProviderInterface::GenericAPI(genericapi) => match genericapi.model_type.as_str() {
-                "togethercomputer/llama-2-70b-chat" => vec![ModelCapability::TextInference],
-                "yorickvp/llava-13b" => vec![ModelCapability::ImageAnalysis],
-                model_type if model_type.starts_with("togethercomputer/llama-2") => {
-                    vec![ModelCapability::TextInference]
-                }
-                _ => vec![],
-            },
-            LLMProviderInterface::LocalLLM(_) => vec![],
-            LLMProviderInterface::ShinkaiBackend(shinkai_backend) => match shinkai_backend.model_type().as_str() {
-                "gpt" | "gpt4" | "gpt-4-1106-preview" | "PREMIUM_TEXT_INFERENCE" | "STANDARD_TEXT_INFERENCE" => {
-                    vec![ModelCapability::TextInference]
-                }
-                "gpt-vision"
-                | "gpt-4-vision-preview"
-                | "gp4o"
-                | "gpt-4o"
-                | "PREMIUM_VISION_INFERENCE"
-                | "gpt-4o-mini" => {
-                    vec![ModelCapability::ImageAnalysis, ModelCapability::TextInference]
-                }
-                "dall-e" => vec![ModelCapability::ImageGeneration],
-                _ => vec![],
-            },
-            LLMProviderInterface::Ollama(model) => Self::get_capabilities_for_model_type(model.model_type().as_str()),
-            LLMProviderInterface::Exo(model) => Self::get_capabilities_for_model_type(model.model_type().as_str()),
-            LLMProviderInterface::Groq(model) => Self::get_capabilities_for_model_type(model.model_type().as_str()),
-            LLMProviderInterface::Gemini(_) => vec![ModelCapability::TextInference, ModelCapability::ImageAnalysis],
+        match LLMProviderRegistry::get(model) {
+            Some(backend) => backend.capabilities(model),
+            None => vec![],
         }
     }
 
-    fn get_capabilities_for_model_type(model_type: &str) -> Vec<ModelCapability> {
+    /// Capabilities shared by the providers serving open models (Ollama, Exo, Groq)
+    pub fn get_capabilities_for_model_type(model_type: &str) -> Vec<ModelCapability> {
         match model_type {
             model_type if model_type.starts_with("llama3") => vec![ModelCapability::TextInference],
             model_type if model_type.starts_with("llava") => {
@@ -191,55 +176,34 @@ impl ModelCapabilitiesManager {
 
     // Static method to get cost of an agent model
     pub fn get_llm_provider_cost(model: &LLMProviderInterface) -> ModelCost {
-        match model {
-            LLMProviderInterface::OpenAI(openai) => match openai.model_type.as_str() {
-                "gpt-4o" => ModelCost::Cheap,
-                "gpt-3.5-turbo-1106" => ModelCost::VeryCheap,
-                "gpt-4o-mini" => ModelCost::VeryCheap,
-                "gpt-4-1106-preview" => ModelCost::GoodValue,
-                "gpt-4-vision-preview" => ModelCost::GoodValue,
-                "gpt-4o" => ModelCost::GoodValue,
-                "dall-e-3" => ModelCost::GoodValue,
-                _ => ModelCost::Unknown,
-            },
-            LLMProviderInterface::GenericAPI(genericapi) => match genericapi.model_type.as_str() {
-                "togethercomputer/llama-2-70b-chat" => ModelCost::Cheap,
-                "togethercomputer/llama3" => ModelCost::Cheap,
-                "yorickvp/llava-13b" => ModelCost::Expensive,
-                _ => ModelCost::Unknown,
-            },
-            LLMProviderInterface::LocalLLM(_) => ModelCost::Cheap,
-            LLMProviderInterface::ShinkaiBackend(shinkai_backend) => match shinkai_backend.model_type().as_str() {
-                "gpt4" | "gpt-4-1106-preview" | "PREMIUM_TEXT_INFERENCE" => ModelCost::Expensive,
-                "gpt-vision" | "gpt-4-vision-preview" | "STANDARD_TEXT_INFERENCE" | "PREMIUM_VISION_INFERENCE" => {
-                    ModelCost::GoodValue
-                }
-                "dall-e" => ModelCost::GoodValue,
-                _ => ModelCost::Unknown,
-            },
-            LLMProviderInterface::Ollama(_) => ModelCost::Free,
-            LLMProviderInterface::Groq(_) => ModelCost::VeryCheap,
-            LLMProviderInterface::Gemini(_) => ModelCost::Cheap,
-            LLMProviderInterface::Exo(_) => ModelCost::Cheap,
+        match LLMProviderRegistry::get(model) {
+            Some(backend) => backend.cost(model),
+            None => ModelCost::Unknown,
         }
     }
 
     // Static method to get privacy of an llm provider model
     pub fn get_llm_provider_privacy(model: &LLMProviderInterface) -> ModelPrivacy {
-        match model {
-            LLMProviderInterface::OpenAI(_) => ModelPrivacy::RemoteGreedy,
-            LLMProviderInterface::GenericAPI(_) => ModelPrivacy::RemoteGreedy,
-            LLMProviderInterface::LocalLLM(_) => ModelPrivacy::Local,
-            LLMProviderInterface::ShinkaiBackend(shinkai_backend) => match shinkai_backend.model_type().as_str() {
-                "PREMIUM_TEXT_INFERENCE" => ModelPrivacy::RemoteGreedy,
-                "PREMIUM_VISION_INFERENCE" => ModelPrivacy::RemoteGreedy,
-                "STANDARD_TEXT_INFERENCE" => ModelPrivacy::RemoteGreedy,
-                _ => ModelPrivacy::Unknown,
-            },
-            LLMProviderInterface::Ollama(_) => ModelPrivacy::Local,
-            LLMProviderInterface::Groq(_) => ModelPrivacy::RemoteGreedy,
-            LLMProviderInterface::Gemini(_) => ModelPrivacy::RemoteGreedy,
-            LLMProviderInterface::Exo(_) => ModelPrivacy::Local,
+        match LLMProviderRegistry::get(model) {
+            Some(backend) => backend.privacy(model),
+            None => ModelPrivacy::Unknown,
+        }
+    }
+
+    /// True if tools can be offered to the model (see `LLMProviderBackend::supports_tool_calls`)
+    pub fn supports_tool_calls(model: &LLMProviderInterface) -> bool {
+        match LLMProviderRegistry::get(model) {
+            Some(backend) => backend.supports_tool_calls(model),
+            None => false,
+        }
+    }
+
+    /// True if the model is constrained natively to a response schema (see
+    /// `LLMProviderBackend::supports_response_schema`)
+    pub fn supports_response_schema(model: &LLMProviderInterface) -> bool {
+        match LLMProviderRegistry::get(model) {
+            Some(backend) => backend.supports_response_schema(model),
+            None => false,
         }
     }
 
@@ -274,146 +238,22 @@ impl ModelCapabilitiesManager {
         prompt: Prompt,
         model: &LLMProviderInterface,
     ) -> Result<PromptResult, ModelCapabilitiesManagerError> {
-        match model {
-            LLMProviderInterface::OpenAI(openai) => {
-                if openai.model_type.starts_with("gpt-") {
-                    let tiktoken_messages = openai_prepare_messages(model, prompt)?;
-                    Ok(tiktoken_messages)
-                } else {
-                    Err(ModelCapabilitiesManagerError::NotImplemented(openai.model_type.clone()))
-                }
-            }
-            LLMProviderInterface::GenericAPI(genericapi) => {
-                if genericapi.model_type.starts_with("togethercomputer/llama-2")
-                    || genericapi.model_type.starts_with("meta-llama/Llama-3")
-                {
-                    let total_tokens = Self::get_max_tokens(model);
-                    let messages_string =
-                        llama_prepare_messages(model, genericapi.clone().model_type, prompt, total_tokens)?;
-                    Ok(messages_string)
-                } else {
-                    Err(ModelCapabilitiesManagerError::NotImplemented(
-                        genericapi.model_type.clone(),
-                    ))
-                }
-            }
-            LLMProviderInterface::LocalLLM(_) => {
-                Err(ModelCapabilitiesManagerError::NotImplemented("LocalLLM".to_string()))
-            }
-            LLMProviderInterface::ShinkaiBackend(shinkai_backend) => Err(
-                ModelCapabilitiesManagerError::NotImplemented(shinkai_backend.model_type().clone()),
-            ),
-            LLMProviderInterface::Ollama(ollama) => {
-                if ollama.model_type.starts_with("mistral")
-                    || ollama.model_type.starts_with("llama2")
-                    || ollama.model_type.starts_with("llama3")
-                    || ollama.model_type.starts_with("gemma2")
-                    || ollama.model_type.starts_with("wizardlm2")
-                    || ollama.model_type.starts_with("starling-lm")
-                    || ollama.model_type.starts_with("neural-chat")
-                    || ollama.model_type.starts_with("vicuna")
-                    || ollama.model_type.starts_with("mixtral")
-                    || ollama.model_type.starts_with("falcon2")
-                    || ollama.model_type.starts_with("dolphin-llama3")
-                    || ollama.model_type.starts_with("command-r-plus")
-                    || ollama.model_type.starts_with("wizardlm2")
-                    || ollama.model_type.starts_with("phi3")
-                    || ollama.model_type.starts_with("aya")
-                    || ollama.model_type.starts_with("qwen2:0.5b")
-                    || ollama.model_type.starts_with("qwen2:1.5b")
-                    || ollama.model_type.starts_with("qwen2:7b")
-                    || ollama.model_type.starts_with("qwen2:72b")
-                    || ollama.model_type.starts_with("codestral")
-                    || ollama
-                        .model_type
-                        .starts_with("adrienbrault/nous-hermes2theta-llama3-8b")
-                    || ollama.model_type.contains("minicpm_llama3")
-                {
-                    let total_tokens = Self::get_max_tokens(model);
-                    let messages_string =
-                        llama_prepare_messages(model, ollama.clone().model_type, prompt, total_tokens)?;
-                    Ok(messages_string)
-                } else if ollama.model_type.starts_with("llava")
-                    || ollama.model_type.starts_with("bakllava")
-                    || ollama.model_type.starts_with("llava-phi3")
-                    || ollama.model_type.starts_with("moondream")
-                {
-                    let total_tokens = Self::get_max_tokens(model);
-                    let messages_string =
-                        llava_prepare_messages(model, ollama.clone().model_type.clone(), prompt, total_tokens)?;
-                    Ok(messages_string)
-                } else {
-                    Err(ModelCapabilitiesManagerError::NotImplemented(ollama.model_type.clone()))
-                }
-            }
-            LLMProviderInterface::Groq(groq) => {
-                let total_tokens = Self::get_max_tokens(model);
-                let messages_string = llama_prepare_messages(model, groq.clone().model_type, prompt, total_tokens)?;
-                Ok(messages_string)
-            }
-            LLMProviderInterface::Gemini(gemini) => {
-                let total_tokens = Self::get_max_tokens(model);
-                let messages_string = llama_prepare_messages(model, gemini.clone().model_type, prompt, total_tokens)?;
-                Ok(messages_string)
-            }
-            LLMProviderInterface::Exo(exo) => {
-                let total_tokens = Self::get_max_tokens(model);
-                let messages_string = llama_prepare_messages(model, exo.clone().model_type, prompt, total_tokens)?;
-                Ok(messages_string)
-            }
+        match LLMProviderRegistry::get(model) {
+            Some(backend) => backend.prepare_messages(model, prompt),
+            None => Err(ModelCapabilitiesManagerError::NotImplemented(model.provider_prefix())),
         }
     }
 
     /// Returns the maximum number of tokens allowed for the given model.
     pub fn get_max_tokens(model: &LLMProviderInterface) -> usize {
-        match model {
-            LLMProviderInterface::OpenAI(openai) => {
-                if openai.model_type == "gpt-4o"
-                    || openai.model_type == "gpt-4-1106-preview"
-                    || openai.model_type == "gpt-4-1106-preview"
-                    || openai.model_type == "gpt-4-vision-preview"
-                {
-                    128_000
-                } else {
-                    32_000
-                }
-            }
-            LLMProviderInterface::GenericAPI(genericapi) => {
-                // Fill in the appropriate logic for GenericAPI
-                if genericapi.model_type == "mistralai/Mixtral-8x7B-Instruct-v0.1" {
-                    32_000
-                } else if genericapi.model_type.starts_with("mistralai/Mistral-7B-Instruct-v0.2") {
-                    16_000
-                    //  32_000
-                } else if genericapi.model_type.starts_with("meta-llama/Llama-3") {
-                    8_000
-                } else if genericapi.model_type.starts_with("mistralai/Mixtral-8x22B") {
-                    65_000
-                } else {
-                    4096
-                }
-            }
-            LLMProviderInterface::LocalLLM(_) => {
-                // Fill in the appropriate logic for LocalLLM
-                0
-            }
-            LLMProviderInterface::ShinkaiBackend(shinkai_backend) => {
-                if shinkai_backend.model_type() == "PREMIUM_TEXT_INFERENCE"
-                    || shinkai_backend.model_type() == "PREMIUM_VISION_INFERENCE"
-                {
-                    128_000
-                } else {
-                    32_000
-                }
-            }
-            LLMProviderInterface::Gemini(_) => 1_000_000,
-            LLMProviderInterface::Ollama(ollama) => Self::get_max_tokens_for_model_type(&ollama.model_type),
-            LLMProviderInterface::Exo(exo) => Self::get_max_tokens_for_model_type(&exo.model_type),
-            LLMProviderInterface::Groq(groq) => Self::get_max_tokens_for_model_type(&groq.model_type),
+        match LLMProviderRegistry::get(model) {
+            Some(backend) => backend.max_tokens(model),
+            None => 4096,
         }
     }
 
-    fn get_max_tokens_for_model_type(model_type: &str) -> usize {
+    /// Context window of the open models served by Ollama, Exo and Groq
+    pub fn get_max_tokens_for_model_type(model_type: &str) -> usize {
         match model_type {
             model_type if model_type.starts_with("mistral:7b-instruct-v0.2") => 32_000,
             model_type if model_type.starts_with("mixtral:8x7b-instruct-v0.1") => 16_000,
@@ -453,44 +293,63 @@ impl ModelCapabilitiesManager {
     }
 
     pub fn get_max_output_tokens(model: &LLMProviderInterface) -> usize {
-        match model {
-            LLMProviderInterface::OpenAI(_) => {
-                // Fill in the appropriate logic for OpenAI
-                4096
-            }
-            LLMProviderInterface::GenericAPI(_) => {
-                if Self::get_max_tokens(model) <= 8000 {
-                    2800
-                } else {
-                    4096
-                }
-            }
-            LLMProviderInterface::LocalLLM(_) => {
-                // Fill in the appropriate logic for LocalLLM
-                4096
-            }
-            LLMProviderInterface::ShinkaiBackend(_) => {
-                // Fill in the appropriate logic for ShinkaiBackend
-                4096
+        match LLMProviderRegistry::get(model) {
+            Some(backend) => backend.max_output_tokens(model),
+            None => 4096,
+        }
+    }
+
+    pub fn get_inference_param_limits(model: &LLMProviderInterface) -> InferenceParamLimits {
+        match LLMProviderRegistry::get(model) {
+            Some(backend) => backend.inference_param_limits(model),
+            None => InferenceParamLimits::default(),
+        }
+    }
+
+    /// Checks the inference params are within the limits of the model, so bad values are reported
+    /// before calling the provider
+    pub fn validate_inference_params(
+        model: &LLMProviderInterface,
+        inference_params: &InferenceParams,
+    ) -> Result<(), ModelCapabilitiesManagerError> {
+        let limits = Self::get_inference_param_limits(model);
+
+        if let Some(temperature) = inference_params.temperature {
+            if !(0.0..=limits.max_temperature).contains(&temperature) {
+                return Err(ModelCapabilitiesManagerError::InvalidInferenceParams(format!(
+                    "temperature must be between 0 and {}, got {}",
+                    limits.max_temperature, temperature
+                )));
             }
-            LLMProviderInterface::Ollama(_) => {
-                // Fill in the appropriate logic for Ollama
-                if Self::get_max_tokens(model) <= 8000 {
-                    2800
-                } else {
-                    4096
-                }
+        }
+        if let Some(top_p) = inference_params.top_p {
+            if !(0.0..=1.0).contains(&top_p) {
+                return Err(ModelCapabilitiesManagerError::InvalidInferenceParams(format!(
+                    "top_p must be between 0 and 1, got {}",
+                    top_p
+                )));
             }
-            LLMProviderInterface::Groq(_) => {
-                // Fill in the appropriate logic for Ollama
-                4096
+        }
+        if let Some(max_tokens) = inference_params.max_tokens {
+            let max_output_tokens = Self::get_max_output_tokens(model);
+            if max_tokens == 0 || max_tokens as usize > max_output_tokens {
+                return Err(ModelCapabilitiesManagerError::InvalidInferenceParams(format!(
+                    "max_tokens must be between 1 and {}, got {}",
+                    max_output_tokens, max_tokens
+                )));
             }
-            LLMProviderInterface::Exo(_) => 4096,
-            LLMProviderInterface::Gemini(_) => {
-                // Fill in the appropriate logic for Ollama
-                4096
+        }
+        if let (Some(stop), Some(max_stop_sequences)) = (&inference_params.stop, limits.max_stop_sequences) {
+            if stop.len() > max_stop_sequences {
+                return Err(ModelCapabilitiesManagerError::InvalidInferenceParams(format!(
+                    "at most {} stop sequences are supported, got {}",
+                    max_stop_sequences,
+                    stop.len()
+                )));
             }
         }
+
+        Ok(())
     }
 
     /// Returns the remaining number of output tokens allowed for the LLM to use
@@ -506,47 +365,9 @@ impl ModelCapabilitiesManager {
 
     // Note(Nico): this may be necessary bc some libraries are not caught up with the latest models e.g. tiktoken-rs
     pub fn normalize_model(model: &LLMProviderInterface) -> String {
-        match model {
-            LLMProviderInterface::OpenAI(openai) => {
-                if openai.model_type.starts_with("gpt-4") {
-                    "gpt-4-32k".to_string()
-                } else if openai.model_type.starts_with("gpt-3.5") {
-                    "gpt-3.5-turbo-16k".to_string()
-                } else {
-                    "gpt-4".to_string()
-                }
-            }
-            LLMProviderInterface::GenericAPI(_genericapi) => {
-                // Fill in the appropriate logic for GenericAPI
-                "".to_string()
-            }
-            LLMProviderInterface::LocalLLM(_) => {
-                // Fill in the appropriate logic for LocalLLM
-                "".to_string()
-            }
-            LLMProviderInterface::Groq(_) => {
-                // Fill in the appropriate logic for LocalLLM
-                "".to_string()
-            }
-            LLMProviderInterface::ShinkaiBackend(shinkai_backend) => {
-                if shinkai_backend.model_type().starts_with("gpt") {
-                    "gpt-4-32k".to_string()
-                } else {
-                    "gpt-4".to_string()
-                }
-            }
-            LLMProviderInterface::Ollama(_) => {
-                // Fill in the appropriate logic for Ollama
-                "".to_string()
-            }
-            LLMProviderInterface::Exo(_) => {
-                // Fill in the appropriate logic for Ollama
-                "".to_string()
-            }
-            LLMProviderInterface::Gemini(_) => {
-                // Fill in the appropriate logic for Ollama
-                "".to_string()
-            }
+        match LLMProviderRegistry::get(model) {
+            Some(backend) => backend.normalize_model(model),
+            None => "".to_string(),
         }
     }
 
@@ -1180,4 +1001,33 @@ mod tests {
         assert!(num_tokens > 13000);
         assert!(num_tokens_llama3 > 13000);
     }
+
+    #[test]
+    fn test_validate_inference_params() {
+        use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{Anthropic, OpenAI};
+
+        let openai = LLMProviderInterface::OpenAI(OpenAI {
+            model_type: "gpt-4o".to_string(),
+        });
+        let anthropic = LLMProviderInterface::Anthropic(Anthropic {
+            model_type: "claude-3-5-sonnet-20240620".to_string(),
+        });
+        let params = |temperature: f64| InferenceParams {
+            temperature: Some(temperature),
+            ..Default::default()
+        };
+
+        assert!(ModelCapabilitiesManager::validate_inference_params(&openai, &InferenceParams::default()).is_ok());
+        assert!(ModelCapabilitiesManager::validate_inference_params(&openai, &params(1.5)).is_ok());
+        assert!(ModelCapabilitiesManager::validate_inference_params(&anthropic, &params(1.5)).is_err());
+        assert!(ModelCapabilitiesManager::validate_inference_params(&anthropic, &params(-0.1)).is_err());
+        assert!(ModelCapabilitiesManager::validate_inference_params(&openai, &params(f64::NAN)).is_err());
+
+        let too_many_stop_sequences = InferenceParams {
+            stop: Some((0..5).map(|i| i.to_string()).collect()),
+            ..Default::default()
+        };
+        assert!(ModelCapabilitiesManager::validate_inference_params(&openai, &too_many_stop_sequences).is_err());
+        assert!(ModelCapabilitiesManager::validate_inference_params(&anthropic, &too_many_stop_sequences).is_ok());
+    }
 }
@@ -215,6 +215,7 @@ impl SheetManager {
                 scope: JobScope::new_default(),
                 is_hidden: Some(true),
                 associated_ui: None,
+                preset_id: None,
             };
 
             let mut job_manager = job_manager.lock().await;
@@ -233,6 +234,8 @@ impl SheetManager {
                 workflow_name: None, // it could be in the sheet_job_data
                 sheet_job_data: Some(serde_json::to_string(&job_data).unwrap()),
                 callback: None,
+                inference_params: None,
+                is_delegation_trace: false,
             };
 
             job_messages.push((job_message, job_data));
@@ -2547,6 +2547,141 @@ impl Node {
                     .await;
                 });
             }
+            NodeCommand::V2ApiGetJobConfig { bearer, job_id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_get_job_config(db_clone, bearer, job_id, res).await;
+                });
+            }
+            NodeCommand::V2ApiSetJobConfig { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_set_job_config(db_clone, bearer, payload, res).await;
+                });
+            }
+            NodeCommand::V2ApiCancelJob { bearer, job_id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let job_manager_clone = self.job_manager.clone().unwrap();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_cancel_job(db_clone, job_manager_clone, bearer, job_id, res).await;
+                });
+            }
+            NodeCommand::V2ApiPauseJob { bearer, job_id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let job_manager_clone = self.job_manager.clone().unwrap();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_pause_job(db_clone, job_manager_clone, bearer, job_id, res).await;
+                });
+            }
+            NodeCommand::V2ApiResumeJob { bearer, job_id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let job_manager_clone = self.job_manager.clone().unwrap();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_resume_job(db_clone, job_manager_clone, bearer, job_id, res).await;
+                });
+            }
+            NodeCommand::V2ApiForkJobMessages { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let node_name_clone = self.node_name.clone();
+                let identity_manager_clone = self.identity_manager.clone();
+                let job_manager_clone = self.job_manager.clone().unwrap();
+                let encryption_secret_key_clone = self.encryption_secret_key.clone();
+                let encryption_public_key_clone = self.encryption_public_key;
+                let signing_secret_key_clone = self.identity_secret_key.clone();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_fork_job_messages(
+                        db_clone,
+                        node_name_clone,
+                        identity_manager_clone,
+                        job_manager_clone,
+                        bearer,
+                        payload,
+                        encryption_secret_key_clone,
+                        encryption_public_key_clone,
+                        signing_secret_key_clone,
+                        res,
+                    )
+                    .await;
+                });
+            }
+            NodeCommand::V2ApiGetJobMessageTree { bearer, job_id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_get_job_message_tree(db_clone, bearer, job_id, res).await;
+                });
+            }
+            NodeCommand::V2ApiSwitchJobBranch { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_switch_job_branch(db_clone, bearer, payload, res).await;
+                });
+            }
+            NodeCommand::V2ApiExportJob { bearer, job_id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let vector_fs_clone = self.vector_fs.clone();
+                let node_name_clone = self.node_name.clone();
+                let signing_secret_key_clone = self.identity_secret_key.clone();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_export_job(
+                        db_clone,
+                        vector_fs_clone,
+                        node_name_clone,
+                        bearer,
+                        job_id,
+                        signing_secret_key_clone,
+                        res,
+                    )
+                    .await;
+                });
+            }
+            NodeCommand::V2ApiImportJob { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let vector_fs_clone = self.vector_fs.clone();
+                let identity_manager_clone = self.identity_manager.clone();
+                let node_name_clone = self.node_name.clone();
+                let signing_secret_key_clone = self.identity_secret_key.clone();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_import_job(
+                        db_clone,
+                        vector_fs_clone,
+                        identity_manager_clone,
+                        node_name_clone,
+                        bearer,
+                        payload,
+                        signing_secret_key_clone,
+                        res,
+                    )
+                    .await;
+                });
+            }
+            NodeCommand::V2ApiChatCompletions { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let vector_fs_clone = self.vector_fs.clone();
+                let node_name_clone = self.node_name.clone();
+                let identity_manager_clone = self.identity_manager.clone();
+                let job_manager_clone = self.job_manager.clone().unwrap();
+                let embedding_generator_clone = self.embedding_generator.clone();
+                let encryption_secret_key_clone = self.encryption_secret_key.clone();
+                let encryption_public_key_clone = self.encryption_public_key;
+                let signing_secret_key_clone = self.identity_secret_key.clone();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_chat_completions(
+                        db_clone,
+                        vector_fs_clone,
+                        node_name_clone,
+                        identity_manager_clone,
+                        job_manager_clone,
+                        embedding_generator_clone,
+                        bearer,
+                        payload,
+                        encryption_secret_key_clone,
+                        encryption_public_key_clone,
+                        signing_secret_key_clone,
+                        res,
+                    )
+                    .await;
+                });
+            }
             NodeCommand::V2ApiRemoveLlmProvider { bearer, llm_provider_id, res } => {
                 let db_clone = Arc::clone(&self.db);
                 let identity_manager_clone = self.identity_manager.clone();
@@ -2674,6 +2809,130 @@ impl Node {
                     .await;
                 });
             }
+            NodeCommand::V2ApiGetTokenUsage { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_get_token_usage(db_clone, bearer, payload, res).await;
+                });
+            }
+            NodeCommand::V2ApiGetTokenUsageRecords { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_get_token_usage_records(db_clone, bearer, payload, res).await;
+                });
+            }
+            NodeCommand::V2ApiGetProfileUsageQuota { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_get_profile_usage_quota(db_clone, bearer, payload, res).await;
+                });
+            }
+            NodeCommand::V2ApiSetProfileUsageQuota { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_set_profile_usage_quota(db_clone, bearer, payload, res).await;
+                });
+            }
+            NodeCommand::V2ApiExportMemory { bearer, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let lance_db_clone = self.lance_db.clone();
+                let identity_manager_clone = self.identity_manager.clone();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_export_memory(db_clone, lance_db_clone, identity_manager_clone, bearer, res)
+                        .await;
+                });
+            }
+            NodeCommand::V2ApiForgetMemory { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let lance_db_clone = self.lance_db.clone();
+                let identity_manager_clone = self.identity_manager.clone();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_forget_memory(
+                        db_clone,
+                        lance_db_clone,
+                        identity_manager_clone,
+                        bearer,
+                        payload,
+                        res,
+                    )
+                    .await;
+                });
+            }
+            NodeCommand::V2ApiExcludeJobFromMemory { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let lance_db_clone = self.lance_db.clone();
+                let identity_manager_clone = self.identity_manager.clone();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_exclude_job_from_memory(
+                        db_clone,
+                        lance_db_clone,
+                        identity_manager_clone,
+                        bearer,
+                        payload,
+                        res,
+                    )
+                    .await;
+                });
+            }
+            NodeCommand::V2ApiAddJobPreset { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let node_name_clone = self.node_name.clone();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_add_job_preset(db_clone, node_name_clone, bearer, payload, res).await;
+                });
+            }
+            NodeCommand::V2ApiUpdateJobPreset { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let node_name_clone = self.node_name.clone();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_update_job_preset(db_clone, node_name_clone, bearer, payload, res).await;
+                });
+            }
+            NodeCommand::V2ApiGetJobPreset { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let node_name_clone = self.node_name.clone();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_get_job_preset(db_clone, node_name_clone, bearer, payload, res).await;
+                });
+            }
+            NodeCommand::V2ApiListJobPresets { bearer, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let node_name_clone = self.node_name.clone();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_list_job_presets(db_clone, node_name_clone, bearer, res).await;
+                });
+            }
+            NodeCommand::V2ApiRemoveJobPreset { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let node_name_clone = self.node_name.clone();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_remove_job_preset(db_clone, node_name_clone, bearer, payload, res).await;
+                });
+            }
+            NodeCommand::V2ApiCreateJobFromPreset { bearer, payload, res } => {
+                let job_manager_clone = self.job_manager.clone().unwrap();
+                let node_name_clone = self.node_name.clone();
+                let db_clone = self.db.clone();
+                let identity_manager_clone = self.identity_manager.clone();
+                let encryption_secret_key_clone = self.encryption_secret_key.clone();
+                let encryption_public_key_clone = self.encryption_public_key;
+                let signing_secret_key_clone = self.identity_secret_key.clone();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_create_job_from_preset(
+                        db_clone,
+                        node_name_clone,
+                        identity_manager_clone,
+                        job_manager_clone,
+                        bearer,
+                        payload,
+                        encryption_secret_key_clone,
+                        encryption_public_key_clone,
+                        signing_secret_key_clone,
+                        res,
+                    )
+                    .await;
+                });
+            }
             _ => (),
         }
     }
@@ -6,18 +6,19 @@ use ed25519_dalek::VerifyingKey;
 use serde_json::Value;
 use shinkai_message_primitives::{
     schemas::{
-        llm_providers::serialized_llm_provider::SerializedLLMProvider, shinkai_name::ShinkaiName,
+        job_archive::SignedJobArchive, job_config::JobConfig, job_preset::JobPreset, llm_providers::serialized_llm_provider::SerializedLLMProvider,
+        shinkai_name::ShinkaiName,
         shinkai_subscription::ShinkaiSubscription,
     },
     shinkai_message::{
         shinkai_message::ShinkaiMessage,
         shinkai_message_schemas::{
-            APIAddOllamaModels, APIAvailableSharedItems, APIChangeJobAgentRequest, APIConvertFilesAndSaveToFolder, APICreateShareableFolder, APIGetLastNotifications, APIGetMySubscribers, APIGetNotificationsBeforeTimestamp, APISetWorkflow, APISubscribeToSharedFolder, APIUnshareFolder, APIUnsubscribeToSharedFolder, APIUpdateShareableFolder, APIVecFsCopyFolder, APIVecFsCopyItem, APIVecFsCreateFolder, APIVecFsDeleteFolder, APIVecFsDeleteItem, APIVecFsMoveFolder, APIVecFsMoveItem, APIVecFsRetrievePathSimplifiedJson, APIVecFsSearchItems, APIWorkflowKeyname, IdentityPermissions, JobCreationInfo, JobMessage, RegistrationCodeType, V2ChatMessage
+            APIAddOllamaModels, APIAvailableSharedItems, APICreateJobFromPreset, APIExcludeJobFromMemory, APIForgetMemory, APIChangeJobAgentRequest, APIConvertFilesAndSaveToFolder, APICreateShareableFolder, APIGetLastNotifications, APIGetMySubscribers, APIGetNotificationsBeforeTimestamp, APIGetProfileUsageQuota, APIForkJobMessages, APIGetTokenUsage, APIImportJob, APIJobPresetId, APISetJobConfig, APISwitchJobBranch, APISetProfileUsageQuota, APISetWorkflow, APISubscribeToSharedFolder, APIUnshareFolder, APIUnsubscribeToSharedFolder, APIUpdateShareableFolder, APIVecFsCopyFolder, APIVecFsCopyItem, APIVecFsCreateFolder, APIVecFsDeleteFolder, APIVecFsDeleteItem, APIVecFsMoveFolder, APIVecFsMoveItem, APIVecFsRetrievePathSimplifiedJson, APIVecFsSearchItems, APIWorkflowKeyname, IdentityPermissions, JobCreationInfo, JobMessage, RegistrationCodeType, V2ChatMessage
         },
     },
 };
 
-use crate::{schemas::{
+use crate::{lance_db::shinkai_lance_memory::MemoryEntry, llm_provider::job_archive::JobImportResult, schemas::{
     identity::{Identity, StandardIdentity},
     smart_inbox::{SmartInbox, V2SmartInbox},
 }, tools::shinkai_tool::ShinkaiTool};
@@ -26,7 +27,10 @@ use x25519_dalek::PublicKey as EncryptionPublicKey;
 use super::{
     node_api_router::{APIError, GetPublicKeysResponse, SendResponseBodyData},
     v1_api::api_v1_handlers::APIUseRegistrationCodeSuccessResponse,
-    v2_api::api_v2_handlers_general::InitialRegistrationRequest,
+    v2_api::{
+        api_v2_handlers_general::InitialRegistrationRequest,
+        api_v2_handlers_openai::{ChatCompletionOutput, ChatCompletionRequest},
+    },
 };
 
 pub enum NodeCommand {
@@ -745,6 +749,61 @@ pub enum NodeCommand {
         payload: APIChangeJobAgentRequest,
         res: Sender<Result<String, APIError>>,
     },
+    V2ApiGetJobConfig {
+        bearer: String,
+        job_id: String,
+        res: Sender<Result<JobConfig, APIError>>,
+    },
+    V2ApiSetJobConfig {
+        bearer: String,
+        payload: APISetJobConfig,
+        res: Sender<Result<String, APIError>>,
+    },
+    V2ApiCancelJob {
+        bearer: String,
+        job_id: String,
+        res: Sender<Result<String, APIError>>,
+    },
+    V2ApiPauseJob {
+        bearer: String,
+        job_id: String,
+        res: Sender<Result<String, APIError>>,
+    },
+    V2ApiResumeJob {
+        bearer: String,
+        job_id: String,
+        res: Sender<Result<String, APIError>>,
+    },
+    V2ApiForkJobMessages {
+        bearer: String,
+        payload: APIForkJobMessages,
+        res: Sender<Result<SendResponseBodyData, APIError>>,
+    },
+    V2ApiGetJobMessageTree {
+        bearer: String,
+        job_id: String,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiSwitchJobBranch {
+        bearer: String,
+        payload: APISwitchJobBranch,
+        res: Sender<Result<String, APIError>>,
+    },
+    V2ApiExportJob {
+        bearer: String,
+        job_id: String,
+        res: Sender<Result<SignedJobArchive, APIError>>,
+    },
+    V2ApiImportJob {
+        bearer: String,
+        payload: APIImportJob,
+        res: Sender<Result<JobImportResult, APIError>>,
+    },
+    V2ApiChatCompletions {
+        bearer: String,
+        payload: ChatCompletionRequest,
+        res: Sender<Result<ChatCompletionOutput, APIError>>,
+    },
     V2ApiRemoveLlmProvider {
         bearer: String,
         llm_provider_id: String,
@@ -773,4 +832,67 @@ pub enum NodeCommand {
         payload: APIAddOllamaModels,
         res: Sender<Result<(), APIError>>,
     },
+    V2ApiGetTokenUsage {
+        bearer: String,
+        payload: APIGetTokenUsage,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiGetTokenUsageRecords {
+        bearer: String,
+        payload: APIGetTokenUsage,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiGetProfileUsageQuota {
+        bearer: String,
+        payload: APIGetProfileUsageQuota,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiSetProfileUsageQuota {
+        bearer: String,
+        payload: APISetProfileUsageQuota,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiExportMemory {
+        bearer: String,
+        res: Sender<Result<Vec<MemoryEntry>, APIError>>,
+    },
+    V2ApiForgetMemory {
+        bearer: String,
+        payload: APIForgetMemory,
+        res: Sender<Result<String, APIError>>,
+    },
+    V2ApiExcludeJobFromMemory {
+        bearer: String,
+        payload: APIExcludeJobFromMemory,
+        res: Sender<Result<String, APIError>>,
+    },
+    V2ApiAddJobPreset {
+        bearer: String,
+        payload: JobPreset,
+        res: Sender<Result<JobPreset, APIError>>,
+    },
+    V2ApiUpdateJobPreset {
+        bearer: String,
+        payload: JobPreset,
+        res: Sender<Result<JobPreset, APIError>>,
+    },
+    V2ApiGetJobPreset {
+        bearer: String,
+        payload: APIJobPresetId,
+        res: Sender<Result<JobPreset, APIError>>,
+    },
+    V2ApiListJobPresets {
+        bearer: String,
+        res: Sender<Result<Vec<JobPreset>, APIError>>,
+    },
+    V2ApiRemoveJobPreset {
+        bearer: String,
+        payload: APIJobPresetId,
+        res: Sender<Result<String, APIError>>,
+    },
+    V2ApiCreateJobFromPreset {
+        bearer: String,
+        payload: APICreateJobFromPreset,
+        res: Sender<Result<String, APIError>>,
+    },
 }
@@ -441,7 +441,8 @@ impl Node {
                             let job_creation = JobCreationInfo {
                                 scope: job_scope,
                                 is_hidden: Some(false),
-                                associated_ui: None
+                                associated_ui: None,
+                                preset_id: None,
                             };
 
                             let mut job_manager_locked = job_manager.lock().await;
@@ -677,6 +678,10 @@ impl Node {
                     toolkit_permissions: vec![],
                     storage_bucket_permissions: vec![],
                     allowed_message_senders: vec![],
+                    retry_policy: Default::default(),
+                    fallback_llm_providers: vec![],
+                    usage_quota: None,
+                    response_cache: None,
                 }
             })
             .collect();
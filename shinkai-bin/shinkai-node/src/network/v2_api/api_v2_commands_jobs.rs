@@ -1,24 +1,37 @@
 use std::sync::Arc;
 
 use async_channel::Sender;
-use ed25519_dalek::SigningKey;
+use ed25519_dalek::{SigningKey, VerifyingKey};
 use reqwest::StatusCode;
 use shinkai_message_primitives::{
     schemas::{
         inbox_name::InboxName,
+        job_archive::SignedJobArchive,
+        job_config::JobConfig,
         llm_providers::serialized_llm_provider::SerializedLLMProvider,
         shinkai_name::{ShinkaiName, ShinkaiSubidentityType},
     },
-    shinkai_message::shinkai_message_schemas::{APIChangeJobAgentRequest, JobCreationInfo, JobMessage, MessageSchemaType, V2ChatMessage},
+    shinkai_message::shinkai_message::{
+        shinkai_message::ShinkaiMessage,
+        shinkai_message_schemas::{
+            APIChangeJobAgentRequest, APIForkJobMessages, APIImportJob, APISetJobConfig, APISwitchJobBranch,
+            JobCreationInfo, JobMessage, MessageSchemaType, V2ChatMessage,
+        },
+    },
+    shinkai_utils::signatures::string_to_signature_public_key,
 };
 
+use serde_json::{json, Value};
 use tokio::sync::Mutex;
 use x25519_dalek::PublicKey as EncryptionPublicKey;
 
 use crate::{
-    db::ShinkaiDB,
-    llm_provider::job_manager::JobManager,
-    managers::IdentityManager,
+    db::{db_errors::ShinkaiDBError, ShinkaiDB},
+    llm_provider::{
+        job_archive::{JobArchive, JobImportResult},
+        job_manager::JobManager,
+    },
+    managers::{model_capabilities_manager::ModelCapabilitiesManager, IdentityManager},
     network::{
         node_api_router::{APIError, SendResponseBodyData},
         node_error::NodeError,
@@ -175,6 +188,76 @@ impl Node {
             return Ok(());
         }
 
+        let shinkai_message = match Self::v2_create_job_shinkai_message(
+            db.clone(),
+            node_name,
+            identity_manager,
+            &job_message,
+            node_encryption_sk,
+            node_encryption_pk,
+            node_signing_sk,
+        )
+        .await
+        {
+            Ok(message) => message,
+            Err(api_error) => {
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        // Process the job message
+        match Self::internal_job_message(job_manager, shinkai_message.clone()).await {
+            Ok(_) => {
+                let inbox_name = match InboxName::get_job_inbox_name_from_params(job_message.job_id) {
+                    Ok(inbox) => inbox.to_string(),
+                    Err(_) => "".to_string(),
+                };
+
+                let scheduled_time = shinkai_message.clone().external_metadata.scheduled_time;
+                let message_hash = shinkai_message.calculate_message_hash_for_pagination();
+
+                let parent_key = if !inbox_name.is_empty() {
+                    match db.get_parent_message_hash(&inbox_name, &message_hash) {
+                        Ok(result) => result,
+                        Err(_) => None,
+                    }
+                } else {
+                    None
+                };
+
+                let response = SendResponseBodyData {
+                    message_id: message_hash,
+                    parent_message_id: parent_key,
+                    inbox: inbox_name,
+                    scheduled_time,
+                };
+
+                let _ = res.send(Ok(response)).await;
+                Ok(())
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("{}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+        }
+    }
+
+    /// Creates the message sent by the main identity to the llm provider of the job
+    async fn v2_create_job_shinkai_message(
+        db: Arc<ShinkaiDB>,
+        node_name: ShinkaiName,
+        identity_manager: Arc<Mutex<IdentityManager>>,
+        job_message: &JobMessage,
+        node_encryption_sk: EncryptionStaticKey,
+        node_encryption_pk: EncryptionPublicKey,
+        node_signing_sk: SigningKey,
+    ) -> Result<ShinkaiMessage, APIError> {
         // Get the main identity from the identity manager
         let main_identity = {
             let identity_manager = identity_manager.lock().await;
@@ -186,8 +269,7 @@ impl Node {
                         error: "Internal Server Error".to_string(),
                         message: "Failed to get main identity".to_string(),
                     };
-                    let _ = res.send(Err(api_error)).await;
-                    return Ok(());
+                    return Err(api_error);
                 }
             }
         };
@@ -201,8 +283,7 @@ impl Node {
                     error: "Internal Server Error".to_string(),
                     message: format!("Failed to retrieve job: {}", err),
                 };
-                let _ = res.send(Err(api_error)).await;
-                return Ok(());
+                return Err(api_error);
             }
         };
 
@@ -215,8 +296,7 @@ impl Node {
                     error: "Internal Server Error".to_string(),
                     message: format!("Failed to create sender name: {}", err),
                 };
-                let _ = res.send(Err(api_error)).await;
-                return Ok(());
+                return Err(api_error);
             }
         };
 
@@ -233,12 +313,11 @@ impl Node {
                     error: "Internal Server Error".to_string(),
                     message: format!("Failed to create recipient name: {}", err),
                 };
-                let _ = res.send(Err(api_error)).await;
-                return Ok(());
+                return Err(api_error);
             }
         };
 
-        let shinkai_message = match Self::api_v2_create_shinkai_message(
+        Self::api_v2_create_shinkai_message(
             sender,
             recipient,
             &serde_json::to_string(&job_message).unwrap(),
@@ -247,59 +326,12 @@ impl Node {
             node_signing_sk,
             node_encryption_pk,
             Some(job_message.job_id.clone()),
-        ) {
-            Ok(message) => message,
-            Err(err) => {
-                let api_error = APIError {
-                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-                    error: "Internal Server Error".to_string(),
-                    message: format!("Failed to create Shinkai message: {}", err),
-                };
-                let _ = res.send(Err(api_error)).await;
-                return Ok(());
-            }
-        };
-
-        // Process the job message
-        match Self::internal_job_message(job_manager, shinkai_message.clone()).await {
-            Ok(_) => {
-                let inbox_name = match InboxName::get_job_inbox_name_from_params(job_message.job_id) {
-                    Ok(inbox) => inbox.to_string(),
-                    Err(_) => "".to_string(),
-                };
-
-                let scheduled_time = shinkai_message.clone().external_metadata.scheduled_time;
-                let message_hash = shinkai_message.calculate_message_hash_for_pagination();
-
-                let parent_key = if !inbox_name.is_empty() {
-                    match db.get_parent_message_hash(&inbox_name, &message_hash) {
-                        Ok(result) => result,
-                        Err(_) => None,
-                    }
-                } else {
-                    None
-                };
-
-                let response = SendResponseBodyData {
-                    message_id: message_hash,
-                    parent_message_id: parent_key,
-                    inbox: inbox_name,
-                    scheduled_time,
-                };
-
-                let _ = res.send(Ok(response)).await;
-                Ok(())
-            }
-            Err(err) => {
-                let api_error = APIError {
-                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-                    error: "Internal Server Error".to_string(),
-                    message: format!("{}", err),
-                };
-                let _ = res.send(Err(api_error)).await;
-                Ok(())
-            }
-        }
+        )
+        .map_err(|err| APIError {
+            code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+            error: "Internal Server Error".to_string(),
+            message: format!("Failed to create Shinkai message: {}", err),
+        })
     }
 
     pub async fn v2_get_last_messages_from_inbox(
@@ -581,4 +613,707 @@ impl Node {
             }
         }
     }
+
+    pub async fn v2_api_get_job_config(
+        db: Arc<ShinkaiDB>,
+        bearer: String,
+        job_id: String,
+        res: Sender<Result<JobConfig, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        // Make sure the job exists
+        if let Err(err) = db.get_job_like(&job_id) {
+            let api_error = APIError {
+                code: StatusCode::NOT_FOUND.as_u16(),
+                error: "Not Found".to_string(),
+                message: format!("Job {} not found: {}", job_id, err),
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+
+        match db.get_job_config(&job_id) {
+            Ok(config) => {
+                let _ = res.send(Ok(config)).await;
+                Ok(())
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to get job config: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn v2_api_set_job_config(
+        db: Arc<ShinkaiDB>,
+        bearer: String,
+        payload: APISetJobConfig,
+        res: Sender<Result<String, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        // Reject the inference params the llm provider of the job can't take
+        let job_data = JobManager::fetch_relevant_job_data(&payload.job_id, db.clone()).await;
+        if let Ok((_, Some(llm_provider), _, user_profile)) = job_data {
+            let inference_params = &payload.config.inference_params;
+            if let Err(err) = ModelCapabilitiesManager::validate_inference_params(&llm_provider.model, inference_params) {
+                let api_error = APIError {
+                    code: StatusCode::BAD_REQUEST.as_u16(),
+                    error: "Bad Request".to_string(),
+                    message: err.to_string(),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+
+            // And the agents referring to llm providers the profile doesn't have
+            if let Some(user_profile) = user_profile {
+                let missing_llm_provider = payload
+                    .config
+                    .agents
+                    .iter()
+                    .filter_map(|agent| agent.llm_provider_id.as_ref())
+                    .find(|llm_provider_id| {
+                        !matches!(db.get_llm_provider(llm_provider_id, &user_profile), Ok(Some(_)))
+                    });
+                if let Some(llm_provider_id) = missing_llm_provider {
+                    let api_error = APIError {
+                        code: StatusCode::BAD_REQUEST.as_u16(),
+                        error: "Bad Request".to_string(),
+                        message: format!("LLM provider {} of the agents not found", llm_provider_id),
+                    };
+                    let _ = res.send(Err(api_error)).await;
+                    return Ok(());
+                }
+            }
+        }
+
+        match db.set_job_config(&payload.job_id, &payload.config) {
+            Ok(_) => {
+                let _ = res.send(Ok("Job config updated successfully".to_string())).await;
+                Ok(())
+            }
+            Err(ShinkaiDBError::DataNotFound) => {
+                let api_error = APIError {
+                    code: StatusCode::NOT_FOUND.as_u16(),
+                    error: "Not Found".to_string(),
+                    message: format!("Job {} not found", payload.job_id),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to set job config: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks that the job exists, sending a not found error otherwise
+    async fn v2_api_check_job_exists<T>(
+        db: Arc<ShinkaiDB>,
+        job_id: &str,
+        res: &Sender<Result<T, APIError>>,
+    ) -> Result<(), ()> {
+        if let Err(err) = db.get_job_like(job_id) {
+            let api_error = APIError {
+                code: StatusCode::NOT_FOUND.as_u16(),
+                error: "Not Found".to_string(),
+                message: format!("Job {} not found: {}", job_id, err),
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Err(());
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_cancel_job(
+        db: Arc<ShinkaiDB>,
+        job_manager: Arc<Mutex<JobManager>>,
+        bearer: String,
+        job_id: String,
+        res: Sender<Result<String, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        if Self::v2_api_check_job_exists(db.clone(), &job_id, &res).await.is_err() {
+            return Ok(());
+        }
+
+        let result = job_manager.lock().await.cancel_job(&job_id).await;
+        match result {
+            Ok(true) => {
+                let _ = res.send(Ok("Job cancelled successfully".to_string())).await;
+                Ok(())
+            }
+            Ok(false) => {
+                let _ = res.send(Ok("Job had nothing to cancel".to_string())).await;
+                Ok(())
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to cancel job: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn v2_api_pause_job(
+        db: Arc<ShinkaiDB>,
+        job_manager: Arc<Mutex<JobManager>>,
+        bearer: String,
+        job_id: String,
+        res: Sender<Result<String, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        if Self::v2_api_check_job_exists(db.clone(), &job_id, &res).await.is_err() {
+            return Ok(());
+        }
+
+        let result = job_manager.lock().await.pause_job(&job_id).await;
+        match result {
+            Ok(_) => {
+                let _ = res.send(Ok("Job paused successfully".to_string())).await;
+                Ok(())
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to pause job: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn v2_api_resume_job(
+        db: Arc<ShinkaiDB>,
+        job_manager: Arc<Mutex<JobManager>>,
+        bearer: String,
+        job_id: String,
+        res: Sender<Result<String, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        if Self::v2_api_check_job_exists(db.clone(), &job_id, &res).await.is_err() {
+            return Ok(());
+        }
+
+        let result = job_manager.lock().await.resume_job(&job_id).await;
+        match result {
+            Ok(true) => {
+                let _ = res.send(Ok("Job resumed successfully".to_string())).await;
+                Ok(())
+            }
+            Ok(false) => {
+                let api_error = APIError {
+                    code: StatusCode::BAD_REQUEST.as_u16(),
+                    error: "Bad Request".to_string(),
+                    message: format!("Job {} is not paused", job_id),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to resume job: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the message with the given hash if it belongs to the inbox of the job
+    fn v2_get_job_inbox_message(
+        db: &ShinkaiDB,
+        inbox_name: &InboxName,
+        message_hash: &str,
+    ) -> Result<ShinkaiMessage, APIError> {
+        let not_found = || APIError {
+            code: StatusCode::NOT_FOUND.as_u16(),
+            error: "Not Found".to_string(),
+            message: format!("Message {} not found in {}", message_hash, inbox_name),
+        };
+
+        let (message, _) = db.fetch_message_and_hash(message_hash).map_err(|_| not_found())?;
+        match InboxName::from_message(&message) {
+            Ok(message_inbox) if message_inbox.get_value() == inbox_name.get_value() => Ok(message),
+            _ => Err(not_found()),
+        }
+    }
+
+    pub async fn v2_api_fork_job_messages(
+        db: Arc<ShinkaiDB>,
+        node_name: ShinkaiName,
+        identity_manager: Arc<Mutex<IdentityManager>>,
+        job_manager: Arc<Mutex<JobManager>>,
+        bearer: String,
+        payload: APIForkJobMessages,
+        node_encryption_sk: EncryptionStaticKey,
+        node_encryption_pk: EncryptionPublicKey,
+        node_signing_sk: SigningKey,
+        res: Sender<Result<SendResponseBodyData, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        if Self::v2_api_check_job_exists(db.clone(), &payload.job_id, &res).await.is_err() {
+            return Ok(());
+        }
+
+        let inbox_name = match InboxName::get_job_inbox_name_from_params(payload.job_id.clone()) {
+            Ok(inbox_name) => inbox_name,
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::BAD_REQUEST.as_u16(),
+                    error: "Bad Request".to_string(),
+                    message: format!("Invalid job id: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        let mut user_message = match Self::v2_get_job_inbox_message(&db, &inbox_name, &payload.message_hash) {
+            Ok(message) => message,
+            Err(api_error) => {
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        // Regenerating an answer of the llm provider forks the user message it replies to
+        // (walking up past the delegation messages of the job's agents)
+        while user_message.get_sender_subidentity().is_none() {
+            let parent_hash = db
+                .get_parent_message_hash(
+                    &inbox_name.to_string(),
+                    &user_message.calculate_message_hash_for_pagination(),
+                )
+                .ok()
+                .flatten();
+            user_message = match parent_hash.map(|hash| Self::v2_get_job_inbox_message(&db, &inbox_name, &hash)) {
+                Some(Ok(message)) => message,
+                _ => {
+                    let api_error = APIError {
+                        code: StatusCode::BAD_REQUEST.as_u16(),
+                        error: "Bad Request".to_string(),
+                        message: format!("Message {} doesn't reply to a user message", payload.message_hash),
+                    };
+                    let _ = res.send(Err(api_error)).await;
+                    return Ok(());
+                }
+            };
+        }
+
+        let original_job_message = match user_message
+            .get_message_content()
+            .ok()
+            .and_then(|content| serde_json::from_str::<JobMessage>(&content).ok())
+        {
+            Some(job_message) => job_message,
+            None => {
+                let api_error = APIError {
+                    code: StatusCode::BAD_REQUEST.as_u16(),
+                    error: "Bad Request".to_string(),
+                    message: format!("Message {} is not a job message", payload.message_hash),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        // The new message is a sibling of the user message
+        let parent_hash = match db.get_parent_message_hash(
+            &inbox_name.to_string(),
+            &user_message.calculate_message_hash_for_pagination(),
+        ) {
+            Ok(parent_hash) => parent_hash,
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to get parent message: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        let job_message = JobMessage {
+            job_id: payload.job_id.clone(),
+            content: payload.content.unwrap_or(original_job_message.content),
+            parent: parent_hash.clone(),
+            ..original_job_message
+        };
+
+        let shinkai_message = match Self::v2_create_job_shinkai_message(
+            db.clone(),
+            node_name,
+            identity_manager,
+            &job_message,
+            node_encryption_sk,
+            node_encryption_pk,
+            node_signing_sk,
+        )
+        .await
+        {
+            Ok(message) => message,
+            Err(api_error) => {
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        let result = job_manager
+            .lock()
+            .await
+            .add_branch_to_job_processing_queue(shinkai_message.clone(), job_message)
+            .await;
+        match result {
+            Ok(_) => {
+                let response = SendResponseBodyData {
+                    message_id: shinkai_message.calculate_message_hash_for_pagination(),
+                    parent_message_id: parent_hash,
+                    inbox: inbox_name.to_string(),
+                    scheduled_time: shinkai_message.external_metadata.scheduled_time,
+                };
+                let _ = res.send(Ok(response)).await;
+                Ok(())
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to fork job messages: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn v2_api_get_job_message_tree(
+        db: Arc<ShinkaiDB>,
+        bearer: String,
+        job_id: String,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        if Self::v2_api_check_job_exists(db.clone(), &job_id, &res).await.is_err() {
+            return Ok(());
+        }
+
+        let inbox_name = match InboxName::get_job_inbox_name_from_params(job_id) {
+            Ok(inbox_name) => inbox_name.to_string(),
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::BAD_REQUEST.as_u16(),
+                    error: "Bad Request".to_string(),
+                    message: format!("Invalid job id: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        let nodes = match db.get_inbox_message_tree(&inbox_name) {
+            Ok(nodes) => nodes,
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to get message tree: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        // Messages are sorted by time, so the leaf of the active branch is its most recent message
+        let active_leaf = nodes.iter().rev().find(|node| node.is_active).map(|node| node.hash.clone());
+
+        let mut messages = Vec::new();
+        for node in nodes {
+            let message = match Self::convert_shinkai_message_to_v2_chat_message(node.message) {
+                Ok(message) => message,
+                Err(err) => {
+                    let api_error = APIError {
+                        code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                        error: "Internal Server Error".to_string(),
+                        message: format!("Failed to convert message: {}", err),
+                    };
+                    let _ = res.send(Err(api_error)).await;
+                    return Ok(());
+                }
+            };
+            messages.push(json!({
+                "message": message,
+                "hash": node.hash,
+                "parent_hash": node.parent_hash,
+                "children": node.children,
+                "is_active": node.is_active,
+            }));
+        }
+
+        let _ = res
+            .send(Ok(json!({ "active_leaf": active_leaf, "messages": messages })))
+            .await;
+        Ok(())
+    }
+
+    pub async fn v2_api_switch_job_branch(
+        db: Arc<ShinkaiDB>,
+        bearer: String,
+        payload: APISwitchJobBranch,
+        res: Sender<Result<String, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        if Self::v2_api_check_job_exists(db.clone(), &payload.job_id, &res).await.is_err() {
+            return Ok(());
+        }
+
+        let inbox_name = match InboxName::get_job_inbox_name_from_params(payload.job_id.clone()) {
+            Ok(inbox_name) => inbox_name.to_string(),
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::BAD_REQUEST.as_u16(),
+                    error: "Bad Request".to_string(),
+                    message: format!("Invalid job id: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        match db.set_inbox_active_leaf(&inbox_name, &payload.message_hash) {
+            Ok(leaf) => {
+                let _ = res.send(Ok(leaf)).await;
+                Ok(())
+            }
+            Err(ShinkaiDBError::MessageNotFound) => {
+                let api_error = APIError {
+                    code: StatusCode::NOT_FOUND.as_u16(),
+                    error: "Not Found".to_string(),
+                    message: format!("Message {} not found in {}", payload.message_hash, inbox_name),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to switch branch: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn v2_api_export_job(
+        db: Arc<ShinkaiDB>,
+        vector_fs: Arc<VectorFS>,
+        node_name: ShinkaiName,
+        bearer: String,
+        job_id: String,
+        node_signing_sk: SigningKey,
+        res: Sender<Result<SignedJobArchive, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        if Self::v2_api_check_job_exists(db.clone(), &job_id, &res).await.is_err() {
+            return Ok(());
+        }
+
+        let result = JobArchive::export(&db, &vector_fs, &job_id, &node_name)
+            .and_then(|archive| archive.sign(&node_signing_sk))
+            .map_err(|err| APIError {
+                code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                error: "Internal Server Error".to_string(),
+                message: format!("Failed to export job: {}", err),
+            });
+        let _ = res.send(result).await;
+        Ok(())
+    }
+
+    /// Recreates a job exported by this or another node. The missing VectorFS items of its scope are reported
+    /// (the job is imported anyway).
+    #[allow(clippy::too_many_arguments)]
+    pub async fn v2_api_import_job(
+        db: Arc<ShinkaiDB>,
+        vector_fs: Arc<VectorFS>,
+        identity_manager: Arc<Mutex<IdentityManager>>,
+        node_name: ShinkaiName,
+        bearer: String,
+        payload: APIImportJob,
+        node_signing_sk: SigningKey,
+        res: Sender<Result<JobImportResult, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        // The archive must be signed by the node that exported it, not just by the key stored in it
+        let expected_signer =
+            match Self::job_archive_signer(&identity_manager, &node_name, &node_signing_sk, &payload).await {
+                Ok(key) => key,
+                Err(message) => {
+                    let api_error = APIError {
+                        code: StatusCode::BAD_REQUEST.as_u16(),
+                        error: "Bad Request".to_string(),
+                        message,
+                    };
+                    let _ = res.send(Err(api_error)).await;
+                    return Ok(());
+                }
+            };
+
+        let archive = match JobArchive::from_signed(&payload.archive, &expected_signer) {
+            Ok(archive) => archive,
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::BAD_REQUEST.as_u16(),
+                    error: "Bad Request".to_string(),
+                    message: format!("{}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        let profile = match ShinkaiName::from_node_and_profile_names(node_name.node_name.clone(), "main".to_string()) {
+            Ok(profile) => profile,
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to create profile name: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        let llm_provider_id = payload
+            .llm_provider_id
+            .unwrap_or_else(|| archive.job.parent_llm_provider_id.clone());
+        match db.get_llm_provider(&llm_provider_id, &profile) {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                let api_error = APIError {
+                    code: StatusCode::BAD_REQUEST.as_u16(),
+                    error: "Bad Request".to_string(),
+                    message: format!("LLM provider {} not found", llm_provider_id),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to retrieve LLM provider: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        }
+
+        let result = archive
+            .import(
+                &db,
+                &vector_fs,
+                &node_name,
+                &profile,
+                &llm_provider_id,
+                &payload.profile_mapping,
+                &node_signing_sk,
+            )
+            .await
+            .map_err(|err| APIError {
+                code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                error: "Internal Server Error".to_string(),
+                message: format!("Failed to import job: {}", err),
+            });
+        let _ = res.send(result).await;
+        Ok(())
+    }
+
+    /// Key a job archive must be signed with: the trusted key of the request if set, the identity key of this node
+    /// for its own archives, or else the identity key registered for the exporting node
+    async fn job_archive_signer(
+        identity_manager: &Arc<Mutex<IdentityManager>>,
+        node_name: &ShinkaiName,
+        node_signing_sk: &SigningKey,
+        payload: &APIImportJob,
+    ) -> Result<VerifyingKey, String> {
+        if let Some(trusted_public_key) = &payload.trusted_public_key {
+            return string_to_signature_public_key(trusted_public_key)
+                .map_err(|e| format!("Invalid trusted public key: {}", e));
+        }
+
+        let exporting_node = JobArchive::exporting_node_name(&payload.archive).map_err(|e| e.to_string())?;
+        if exporting_node == node_name.get_node_name_string() {
+            return Ok(node_signing_sk.verifying_key());
+        }
+
+        let identity_manager = identity_manager.lock().await;
+        identity_manager
+            .external_profile_to_global_identity(&exporting_node)
+            .await
+            .map(|identity| identity.node_signature_public_key)
+            .map_err(|e| format!("Unknown exporting node {}: {}", exporting_node, e))
+    }
 }
@@ -0,0 +1,203 @@
+use std::sync::Arc;
+
+use async_channel::Sender;
+use reqwest::StatusCode;
+use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
+use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::{
+    APIExcludeJobFromMemory, APIForgetMemory,
+};
+use tokio::sync::Mutex;
+
+use crate::{
+    db::{db_errors::ShinkaiDBError, ShinkaiDB},
+    lance_db::{shinkai_lance_db::LanceShinkaiDb, shinkai_lance_memory::MemoryEntry},
+    managers::IdentityManager,
+    network::{node_api_router::APIError, node_error::NodeError, Node},
+};
+
+impl Node {
+    /// Returns the profile of the main identity, the one the v2 jobs run with (and so the one their memory belongs to)
+    async fn v2_api_memory_profile<T>(
+        identity_manager: Arc<Mutex<IdentityManager>>,
+        res: &Sender<Result<T, APIError>>,
+    ) -> Result<String, ()> {
+        let main_identity = identity_manager.lock().await.get_main_identity().cloned();
+        let profile = main_identity
+            .and_then(|identity| ShinkaiName::new(identity.get_full_identity_name()).ok())
+            .and_then(|name| name.get_profile_name_string());
+
+        match profile {
+            Some(profile) => Ok(profile),
+            None => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: "Failed to get main identity".to_string(),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Err(())
+            }
+        }
+    }
+
+    pub async fn v2_api_export_memory(
+        db: Arc<ShinkaiDB>,
+        lance_db: Arc<Mutex<LanceShinkaiDb>>,
+        identity_manager: Arc<Mutex<IdentityManager>>,
+        bearer: String,
+        res: Sender<Result<Vec<MemoryEntry>, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let profile = match Self::v2_api_memory_profile(identity_manager, &res).await {
+            Ok(profile) => profile,
+            Err(_) => return Ok(()),
+        };
+
+        let result = lance_db.lock().await.get_memory_entries(&profile).await;
+        match result {
+            Ok(entries) => {
+                let _ = res.send(Ok(entries)).await;
+                Ok(())
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to export memory: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn v2_api_forget_memory(
+        db: Arc<ShinkaiDB>,
+        lance_db: Arc<Mutex<LanceShinkaiDb>>,
+        identity_manager: Arc<Mutex<IdentityManager>>,
+        bearer: String,
+        payload: APIForgetMemory,
+        res: Sender<Result<String, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        if payload.memory_id.is_none() && payload.job_id.is_none() {
+            let api_error = APIError {
+                code: StatusCode::BAD_REQUEST.as_u16(),
+                error: "Bad Request".to_string(),
+                message: "Either memory_id or job_id is required".to_string(),
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+
+        let profile = match Self::v2_api_memory_profile(identity_manager, &res).await {
+            Ok(profile) => profile,
+            Err(_) => return Ok(()),
+        };
+
+        let lance_db = lance_db.lock().await;
+        let mut result = Ok(());
+        if let Some(memory_id) = &payload.memory_id {
+            result = lance_db.forget_memory_entry(&profile, memory_id).await;
+        }
+        if let (Ok(_), Some(job_id)) = (&result, &payload.job_id) {
+            result = lance_db.forget_job_memory(&profile, job_id).await;
+        }
+
+        match result {
+            Ok(_) => {
+                let _ = res.send(Ok("Memory forgotten successfully".to_string())).await;
+                Ok(())
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to forget memory: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn v2_api_exclude_job_from_memory(
+        db: Arc<ShinkaiDB>,
+        lance_db: Arc<Mutex<LanceShinkaiDb>>,
+        identity_manager: Arc<Mutex<IdentityManager>>,
+        bearer: String,
+        payload: APIExcludeJobFromMemory,
+        res: Sender<Result<String, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let mut config = match db.get_job_config(&payload.job_id) {
+            Ok(config) => config,
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to get job config: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        config.exclude_from_memory = payload.exclude;
+        match db.set_job_config(&payload.job_id, &config) {
+            Ok(_) => {}
+            Err(ShinkaiDBError::DataNotFound) => {
+                let api_error = APIError {
+                    code: StatusCode::NOT_FOUND.as_u16(),
+                    error: "Not Found".to_string(),
+                    message: format!("Job {} not found", payload.job_id),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to set job config: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        }
+
+        // The messages remembered before the job was excluded are forgotten
+        if payload.exclude {
+            let profile = match Self::v2_api_memory_profile(identity_manager, &res).await {
+                Ok(profile) => profile,
+                Err(_) => return Ok(()),
+            };
+
+            let result = lance_db.lock().await.forget_job_memory(&profile, &payload.job_id).await;
+            if let Err(err) = result {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to forget the memory of the job: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        }
+
+        let _ = res.send(Ok("Job memory settings updated successfully".to_string())).await;
+        Ok(())
+    }
+}
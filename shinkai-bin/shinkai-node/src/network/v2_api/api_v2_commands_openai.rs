@@ -0,0 +1,579 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use async_channel::Sender;
+use async_trait::async_trait;
+use chrono::Utc;
+use ed25519_dalek::SigningKey;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use shinkai_message_primitives::{
+    schemas::{inbox_name::InboxName, shinkai_name::ShinkaiName},
+    shinkai_message::shinkai_message_schemas::{JobCreationInfo, WSTopic},
+    shinkai_utils::{
+        job_scope::JobScope,
+        shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption},
+    },
+};
+use shinkai_vector_resources::{embedding_generator::RemoteEmbeddingGenerator, vector_resource::RetrievedNode};
+use tokio::sync::Mutex;
+use x25519_dalek::{PublicKey as EncryptionPublicKey, StaticSecret as EncryptionStaticKey};
+
+use crate::{
+    db::ShinkaiDB,
+    llm_provider::{
+        error::LLMProviderError,
+        execution::{
+            chains::inference_chain_trait::LLMInferenceResponse,
+            prompts::{prompts::Prompt, subprompts::SubPromptType},
+        },
+        job_manager::JobManager,
+        providers::shared::openai::{FunctionCall, FunctionCallResponse},
+    },
+    managers::{
+        model_capabilities_manager::{ModelCapabilitiesManager, ModelCapabilitiesManagerError},
+        IdentityManager,
+    },
+    network::{
+        node_api_router::APIError,
+        node_error::NodeError,
+        ws_manager::{WSMessageType, WSUpdateHandler},
+        Node,
+    },
+    vector_fs::vector_fs::VectorFS,
+};
+
+use super::api_v2_handlers_openai::{ChatCompletionMessage, ChatCompletionOutput, ChatCompletionRequest};
+
+impl Node {
+    /// Answers an OpenAI chat completions request with the llm provider named in `model`.
+    /// The inference runs inside a job (a new hidden one unless `shinkai.job_id` is set) so the job scope
+    /// is vector searched, the usage quotas are enforced and the tokens used are attributed to the job.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn v2_api_chat_completions(
+        db: Arc<ShinkaiDB>,
+        vector_fs: Arc<VectorFS>,
+        node_name: ShinkaiName,
+        identity_manager: Arc<Mutex<IdentityManager>>,
+        job_manager: Arc<Mutex<JobManager>>,
+        generator: RemoteEmbeddingGenerator,
+        bearer: String,
+        payload: ChatCompletionRequest,
+        node_encryption_sk: EncryptionStaticKey,
+        node_encryption_pk: EncryptionPublicKey,
+        node_signing_sk: SigningKey,
+        res: Sender<Result<ChatCompletionOutput, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let user_profile =
+            match ShinkaiName::from_node_and_profile_names(node_name.node_name.clone(), "main".to_string()) {
+                Ok(profile) => profile,
+                Err(err) => {
+                    let _ = res
+                        .send(Err(Self::chat_completion_error(
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            format!("Failed to create profile name: {}", err),
+                        )))
+                        .await;
+                    return Ok(());
+                }
+            };
+
+        let llm_provider = match db.get_llm_provider(&payload.model, &user_profile) {
+            Ok(Some(llm_provider)) => llm_provider,
+            Ok(None) => {
+                let _ = res
+                    .send(Err(Self::chat_completion_error(
+                        StatusCode::NOT_FOUND,
+                        format!("Model {} not found", payload.model),
+                    )))
+                    .await;
+                return Ok(());
+            }
+            Err(err) => {
+                let _ = res
+                    .send(Err(Self::chat_completion_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to retrieve model {}: {}", payload.model, err),
+                    )))
+                    .await;
+                return Ok(());
+            }
+        };
+
+        let Some(user_message) = payload.messages.iter().rev().find(|m| m.role == "user") else {
+            let _ = res
+                .send(Err(Self::chat_completion_error(
+                    StatusCode::BAD_REQUEST,
+                    "At least one user message is required".to_string(),
+                )))
+                .await;
+            return Ok(());
+        };
+        let user_message = user_message.content.as_ref().map(|c| c.text()).unwrap_or_default();
+
+        // Reuse the job given by the caller or create a hidden one with the requested scope
+        let options = payload.shinkai.clone().unwrap_or_default();
+        let job_id = match options.job_id {
+            Some(job_id) => job_id,
+            None => {
+                let job_creation_info = JobCreationInfo {
+                    scope: options.job_scope.unwrap_or_else(JobScope::new_default),
+                    is_hidden: Some(true),
+                    associated_ui: None,
+                    preset_id: None,
+                };
+                let (job_sender, job_receiver) = async_channel::bounded(1);
+                Self::v2_create_new_job(
+                    db.clone(),
+                    node_name.clone(),
+                    identity_manager,
+                    job_manager,
+                    bearer,
+                    job_creation_info,
+                    llm_provider.id.clone(),
+                    node_encryption_sk,
+                    node_encryption_pk,
+                    node_signing_sk,
+                    job_sender,
+                )
+                .await?;
+                match job_receiver.recv().await {
+                    Ok(Ok(job_id)) => job_id,
+                    Ok(Err(api_error)) => {
+                        let _ = res.send(Err(api_error)).await;
+                        return Ok(());
+                    }
+                    Err(err) => {
+                        let _ = res
+                            .send(Err(Self::chat_completion_error(
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                format!("Failed to create job: {}", err),
+                            )))
+                            .await;
+                        return Ok(());
+                    }
+                }
+            }
+        };
+        let job = match db.get_job(&job_id) {
+            Ok(job) => job,
+            Err(err) => {
+                let _ = res
+                    .send(Err(Self::chat_completion_error(
+                        StatusCode::NOT_FOUND,
+                        format!("Job {} not found: {}", job_id, err),
+                    )))
+                    .await;
+                return Ok(());
+            }
+        };
+
+        if let Err(err) =
+            JobManager::enforce_usage_quotas(db.clone(), &job_id, &user_profile, Some(&llm_provider), None).await
+        {
+            let code = match err {
+                LLMProviderError::UsageQuotaExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            let _ = res.send(Err(Self::chat_completion_error(code, err.to_string()))).await;
+            return Ok(());
+        }
+
+        // Search the job scope with the last user message
+        let mut ret_nodes = vec![];
+        if !job.scope.is_empty() && !user_message.is_empty() {
+            let max_tokens_in_prompt = ModelCapabilitiesManager::get_max_input_tokens(&llm_provider.model);
+            match JobManager::job_scope_search(
+                db.clone(),
+                vector_fs,
+                &job.scope,
+                user_message.clone(),
+                &user_profile,
+                generator,
+                20,
+                max_tokens_in_prompt,
+                job.config.retrieval_mode,
+            )
+            .await
+            {
+                Ok((nodes, _)) => ret_nodes = nodes,
+                Err(err) => {
+                    let _ = res
+                        .send(Err(Self::chat_completion_error(
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            format!("Failed to search the job scope: {}", err),
+                        )))
+                        .await;
+                    return Ok(());
+                }
+            }
+        }
+
+        let prompt = match chat_completion_prompt(&payload.messages, &payload.tools, ret_nodes) {
+            Ok(prompt) => prompt,
+            Err(message) => {
+                let _ = res
+                    .send(Err(Self::chat_completion_error(StatusCode::BAD_REQUEST, message)))
+                    .await;
+                return Ok(());
+            }
+        };
+
+        let completion = ChatCompletionInfo {
+            id: format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+            model: payload.model.clone(),
+            created: Utc::now().timestamp(),
+            job_id: job_id.clone(),
+        };
+        let inbox_name = InboxName::get_job_inbox_name_from_params(job_id.clone()).ok();
+        let inference_params =
+            JobManager::job_inference_params(&db, &job, &user_profile, Some(&payload.inference_params()));
+
+        if !payload.stream {
+            let response = JobManager::inference_with_llm_provider(
+                llm_provider,
+                prompt,
+                inference_params,
+                inbox_name,
+                None,
+                Some(db.clone()),
+            )
+            .await;
+            let output = match response {
+                Ok(response) => {
+                    Self::add_chat_completion_step(&db, &completion, user_message, &response);
+                    Ok(ChatCompletionOutput::Completion(completion.response(&response)))
+                }
+                Err(err @ LLMProviderError::LLMProviderCapabilitiesManagerError(
+                    ModelCapabilitiesManagerError::InvalidInferenceParams(_),
+                )) => Err(Self::chat_completion_error(StatusCode::BAD_REQUEST, err.to_string())),
+                Err(err) => Err(Self::chat_completion_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    err.to_string(),
+                )),
+            };
+            let _ = res.send(output).await;
+            return Ok(());
+        }
+
+        // Streaming: the chunks streamed by the provider are forwarded as they arrive
+        let (chunk_sender, chunk_receiver) = async_channel::unbounded();
+        let streamed = Arc::new(AtomicBool::new(false));
+        let streamer: Arc<Mutex<dyn WSUpdateHandler + Send>> = Arc::new(Mutex::new(ChatCompletionStreamer {
+            completion: completion.clone(),
+            sender: chunk_sender.clone(),
+            streamed: streamed.clone(),
+        }));
+        let _ = res.send(Ok(ChatCompletionOutput::Stream(chunk_receiver))).await;
+
+        let response = JobManager::inference_with_llm_provider(
+            llm_provider,
+            prompt,
+            inference_params,
+            inbox_name,
+            Some(streamer),
+            Some(db.clone()),
+        )
+        .await;
+        match response {
+            Ok(response) => {
+                Self::add_chat_completion_step(&db, &completion, user_message, &response);
+                // Providers that don't stream send the whole answer at once
+                if !streamed.load(Ordering::SeqCst) && !response.response_string.is_empty() {
+                    let delta = json!({ "role": "assistant", "content": response.response_string });
+                    let _ = chunk_sender.send(completion.chunk(delta, None)).await;
+                }
+                let finish_reason = match &response.function_call {
+                    Some(function_call) => {
+                        let delta = json!({ "tool_calls": tool_calls_json(function_call, true) });
+                        let _ = chunk_sender.send(completion.chunk(delta, None)).await;
+                        "tool_calls"
+                    }
+                    None => "stop",
+                };
+                let _ = chunk_sender
+                    .send(completion.chunk(json!({}), Some(finish_reason)))
+                    .await;
+            }
+            Err(err) => {
+                let _ = chunk_sender
+                    .send(json!({ "error": { "message": err.to_string(), "type": err.error_name() } }))
+                    .await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Keeps the exchange in the job's step history (which also attaches the llm provider attempts to it)
+    fn add_chat_completion_step(
+        db: &Arc<ShinkaiDB>,
+        completion: &ChatCompletionInfo,
+        user_message: String,
+        response: &LLMInferenceResponse,
+    ) {
+        if let Err(err) = db.add_step_history(
+            completion.job_id.clone(),
+            user_message,
+            response.response_string.clone(),
+            Some(completion.id.clone()),
+        ) {
+            shinkai_log(
+                ShinkaiLogOption::JobExecution,
+                ShinkaiLogLevel::Error,
+                &format!("Failed to add chat completion to the step history: {}", err),
+            );
+        }
+    }
+
+    fn chat_completion_error(code: StatusCode, message: String) -> APIError {
+        APIError {
+            code: code.as_u16(),
+            error: code.canonical_reason().unwrap_or("Error").to_string(),
+            message,
+        }
+    }
+}
+
+/// Converts the OpenAI messages and tools into a prompt. The retrieved nodes are added as extra context.
+pub fn chat_completion_prompt(
+    messages: &[ChatCompletionMessage],
+    tools: &[Value],
+    ret_nodes: Vec<RetrievedNode>,
+) -> Result<Prompt, String> {
+    let mut prompt = Prompt::new();
+    let mut function_calls: HashMap<String, FunctionCall> = HashMap::new();
+    let last_index = messages.len().saturating_sub(1);
+
+    for (index, message) in messages.iter().enumerate() {
+        // The latest message is the most important one, the rest of the conversation can be pruned first
+        let priority = if index == last_index { 100 } else { 97 };
+        let content = message.content.as_ref().map(|c| c.text()).unwrap_or_default();
+        match message.role.as_str() {
+            "system" | "developer" => prompt.add_content(content, SubPromptType::System, 98),
+            "user" => prompt.add_content(content, SubPromptType::User, priority),
+            "assistant" => {
+                if !content.is_empty() {
+                    prompt.add_content(content, SubPromptType::Assistant, priority);
+                }
+                for tool_call in &message.tool_calls {
+                    let function_call = FunctionCall {
+                        name: tool_call.function.name.clone(),
+                        arguments: serde_json::from_str(&tool_call.function.arguments).unwrap_or_else(|_| json!({})),
+                    };
+                    function_calls.insert(tool_call.id.clone(), function_call.clone());
+                    prompt.add_function_call(function_call, priority);
+                }
+            }
+            "tool" => {
+                let tool_call_id = message.tool_call_id.clone().unwrap_or_default();
+                let function_call = function_calls
+                    .get(&tool_call_id)
+                    .cloned()
+                    .ok_or_else(|| format!("No tool call found with id {}", tool_call_id))?;
+                prompt.add_function_call_response(
+                    FunctionCallResponse {
+                        response: content,
+                        function_call,
+                    },
+                    priority,
+                );
+            }
+            role => return Err(format!("Unsupported message role: {}", role)),
+        }
+    }
+
+    for tool in tools {
+        prompt.add_tool(tool.clone(), SubPromptType::AvailableTool, 98);
+    }
+
+    if !ret_nodes.is_empty() {
+        prompt.add_content("--- start --- \n".to_string(), SubPromptType::ExtraContext, 97);
+        for node in ret_nodes {
+            prompt.add_ret_node_content(node, SubPromptType::ExtraContext, 96);
+        }
+        prompt.add_content("--- end ---".to_string(), SubPromptType::ExtraContext, 97);
+    }
+
+    Ok(prompt)
+}
+
+/// OpenAI tool calls for a function call (streamed tool calls carry their index)
+fn tool_calls_json(function_call: &FunctionCall, streamed: bool) -> Vec<Value> {
+    let mut tool_call = json!({
+        "id": format!("call_{}", uuid::Uuid::new_v4().simple()),
+        "type": "function",
+        "function": {
+            "name": function_call.name,
+            "arguments": function_call.arguments.to_string(),
+        },
+    });
+    if streamed {
+        tool_call["index"] = json!(0);
+    }
+    vec![tool_call]
+}
+
+#[derive(Debug, Clone)]
+struct ChatCompletionInfo {
+    id: String,
+    model: String,
+    created: i64,
+    job_id: String,
+}
+
+impl ChatCompletionInfo {
+    fn response(&self, response: &LLMInferenceResponse) -> Value {
+        let mut message = json!({ "role": "assistant", "content": response.response_string });
+        let finish_reason = match &response.function_call {
+            Some(function_call) => {
+                message["tool_calls"] = json!(tool_calls_json(function_call, false));
+                "tool_calls"
+            }
+            None => "stop",
+        };
+        let usage = response.usage.clone().unwrap_or_default();
+
+        json!({
+            "id": self.id,
+            "object": "chat.completion",
+            "created": self.created,
+            "model": self.model,
+            "choices": [{ "index": 0, "message": message, "finish_reason": finish_reason }],
+            "usage": {
+                "prompt_tokens": usage.prompt_tokens,
+                "completion_tokens": usage.completion_tokens,
+                "total_tokens": usage.prompt_tokens + usage.completion_tokens,
+            },
+            "shinkai_job_id": self.job_id,
+        })
+    }
+
+    fn chunk(&self, delta: Value, finish_reason: Option<&str>) -> Value {
+        json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+            "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }],
+            "shinkai_job_id": self.job_id,
+        })
+    }
+}
+
+/// Forwards the chunks streamed by the llm provider as chat completion chunks
+struct ChatCompletionStreamer {
+    completion: ChatCompletionInfo,
+    sender: async_channel::Sender<Value>,
+    streamed: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl WSUpdateHandler for ChatCompletionStreamer {
+    async fn queue_message(
+        &self,
+        _topic: WSTopic,
+        _subtopic: String,
+        update: String,
+        _metadata: WSMessageType,
+        is_stream: bool,
+    ) {
+        if !is_stream || update.is_empty() {
+            return;
+        }
+        let delta = if self.streamed.swap(true, Ordering::SeqCst) {
+            json!({ "content": update })
+        } else {
+            json!({ "role": "assistant", "content": update })
+        };
+        let _ = self.sender.send(self.completion.chunk(delta, None)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm_provider::execution::prompts::subprompts::SubPrompt;
+
+    #[test]
+    fn test_chat_completion_prompt_with_tool_calls() {
+        let request: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "my_gpt",
+            "messages": [
+                { "role": "system", "content": "Be brief" },
+                { "role": "user", "content": [{ "type": "text", "text": "Weather in Paris?" }] },
+                {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": { "name": "get_weather", "arguments": "{\"city\":\"Paris\"}" }
+                    }]
+                },
+                { "role": "tool", "tool_call_id": "call_1", "content": "Sunny" }
+            ],
+            "tools": [{ "type": "function", "function": { "name": "get_weather", "parameters": {} } }],
+            "shinkai": { "job_id": "job_1" }
+        }))
+        .unwrap();
+        assert!(!request.stream);
+        assert_eq!(request.shinkai.unwrap().job_id, Some("job_1".to_string()));
+
+        let prompt = chat_completion_prompt(&request.messages, &request.tools, vec![]).unwrap();
+        assert_eq!(prompt.sub_prompts.len(), 5);
+        assert!(
+            matches!(&prompt.sub_prompts[0], SubPrompt::Content(SubPromptType::System, text, 98) if text == "Be brief")
+        );
+        assert!(
+            matches!(&prompt.sub_prompts[1], SubPrompt::Content(SubPromptType::User, text, 97) if text == "Weather in Paris?")
+        );
+        assert!(
+            matches!(&prompt.sub_prompts[2], SubPrompt::FunctionCall(_, call, 97) if call["arguments"]["city"] == "Paris")
+        );
+        assert!(
+            matches!(&prompt.sub_prompts[3], SubPrompt::FunctionCallResponse(_, response, 100) if response["response"] == "Sunny")
+        );
+        assert!(matches!(
+            &prompt.sub_prompts[4],
+            SubPrompt::ToolAvailable(SubPromptType::AvailableTool, _, 98)
+        ));
+
+        // Tool results must answer a previous tool call
+        let messages = vec![ChatCompletionMessage {
+            role: "tool".to_string(),
+            content: None,
+            tool_calls: vec![],
+            tool_call_id: Some("unknown".to_string()),
+        }];
+        assert!(chat_completion_prompt(&messages, &[], vec![]).is_err());
+    }
+
+    #[test]
+    fn test_chat_completion_response() {
+        let completion = ChatCompletionInfo {
+            id: "chatcmpl-1".to_string(),
+            model: "my_gpt".to_string(),
+            created: 0,
+            job_id: "job_1".to_string(),
+        };
+        let function_call = FunctionCall {
+            name: "get_weather".to_string(),
+            arguments: json!({ "city": "Paris" }),
+        };
+        let response = LLMInferenceResponse::new(String::new(), json!({}), Some(function_call));
+
+        let value = completion.response(&response);
+        assert_eq!(value["choices"][0]["finish_reason"], "tool_calls");
+        let tool_call = &value["choices"][0]["message"]["tool_calls"][0];
+        assert_eq!(tool_call["function"]["name"], "get_weather");
+        assert_eq!(tool_call["function"]["arguments"], "{\"city\":\"Paris\"}");
+        assert_eq!(value["shinkai_job_id"], "job_1");
+    }
+}
@@ -0,0 +1,278 @@
+use std::sync::Arc;
+
+use async_channel::Sender;
+use ed25519_dalek::SigningKey;
+use reqwest::StatusCode;
+use shinkai_message_primitives::{
+    schemas::{job_preset::JobPreset, shinkai_name::ShinkaiName},
+    shinkai_message::shinkai_message_schemas::{APICreateJobFromPreset, APIJobPresetId, JobCreationInfo},
+    shinkai_utils::job_scope::JobScope,
+};
+use tokio::sync::Mutex;
+use x25519_dalek::{PublicKey as EncryptionPublicKey, StaticSecret as EncryptionStaticKey};
+
+use crate::{
+    db::{db_errors::ShinkaiDBError, ShinkaiDB},
+    llm_provider::job_manager::JobManager,
+    managers::IdentityManager,
+    network::{node_api_router::APIError, node_error::NodeError, Node},
+};
+
+impl Node {
+    /// Returns the profile the presets of the v2 jobs belong to
+    async fn v2_api_presets_profile<T>(
+        node_name: ShinkaiName,
+        res: &Sender<Result<T, APIError>>,
+    ) -> Result<ShinkaiName, ()> {
+        match ShinkaiName::from_node_and_profile_names(node_name.node_name, "main".to_string()) {
+            Ok(profile) => Ok(profile),
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to create profile name: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Err(())
+            }
+        }
+    }
+
+    fn job_preset_api_error(err: ShinkaiDBError) -> APIError {
+        match err {
+            ShinkaiDBError::JobPresetNotFound(preset_id) => APIError {
+                code: StatusCode::NOT_FOUND.as_u16(),
+                error: "Not Found".to_string(),
+                message: format!("Job preset {} not found", preset_id),
+            },
+            err => APIError {
+                code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                error: "Internal Server Error".to_string(),
+                message: format!("Failed to access job preset: {}", err),
+            },
+        }
+    }
+
+    /// Checks the llm provider of a preset exists and saves the preset
+    async fn v2_api_save_job_preset(
+        db: Arc<ShinkaiDB>,
+        profile: &ShinkaiName,
+        preset: JobPreset,
+        res: Sender<Result<JobPreset, APIError>>,
+    ) -> Result<(), NodeError> {
+        match db.get_llm_provider(&preset.llm_provider_id, profile) {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                let api_error = APIError {
+                    code: StatusCode::BAD_REQUEST.as_u16(),
+                    error: "Bad Request".to_string(),
+                    message: format!("LLM provider {} not found", preset.llm_provider_id),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to retrieve LLM provider: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        }
+
+        match db.save_job_preset(&preset, profile) {
+            Ok(_) => {
+                let _ = res.send(Ok(preset)).await;
+            }
+            Err(err) => {
+                let _ = res.send(Err(Self::job_preset_api_error(err))).await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_add_job_preset(
+        db: Arc<ShinkaiDB>,
+        node_name: ShinkaiName,
+        bearer: String,
+        mut payload: JobPreset,
+        res: Sender<Result<JobPreset, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let profile = match Self::v2_api_presets_profile(node_name, &res).await {
+            Ok(profile) => profile,
+            Err(_) => return Ok(()),
+        };
+
+        if payload.preset_id.is_empty() {
+            payload.preset_id = uuid::Uuid::new_v4().to_string();
+        } else if db.get_job_preset(&payload.preset_id, &profile).is_ok() {
+            let api_error = APIError {
+                code: StatusCode::CONFLICT.as_u16(),
+                error: "Conflict".to_string(),
+                message: format!("Job preset {} already exists", payload.preset_id),
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+
+        Self::v2_api_save_job_preset(db, &profile, payload, res).await
+    }
+
+    pub async fn v2_api_update_job_preset(
+        db: Arc<ShinkaiDB>,
+        node_name: ShinkaiName,
+        bearer: String,
+        payload: JobPreset,
+        res: Sender<Result<JobPreset, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let profile = match Self::v2_api_presets_profile(node_name, &res).await {
+            Ok(profile) => profile,
+            Err(_) => return Ok(()),
+        };
+
+        if let Err(err) = db.get_job_preset(&payload.preset_id, &profile) {
+            let _ = res.send(Err(Self::job_preset_api_error(err))).await;
+            return Ok(());
+        }
+
+        Self::v2_api_save_job_preset(db, &profile, payload, res).await
+    }
+
+    pub async fn v2_api_get_job_preset(
+        db: Arc<ShinkaiDB>,
+        node_name: ShinkaiName,
+        bearer: String,
+        payload: APIJobPresetId,
+        res: Sender<Result<JobPreset, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let profile = match Self::v2_api_presets_profile(node_name, &res).await {
+            Ok(profile) => profile,
+            Err(_) => return Ok(()),
+        };
+
+        let result = db
+            .get_job_preset(&payload.preset_id, &profile)
+            .map_err(Self::job_preset_api_error);
+        let _ = res.send(result).await;
+        Ok(())
+    }
+
+    pub async fn v2_api_list_job_presets(
+        db: Arc<ShinkaiDB>,
+        node_name: ShinkaiName,
+        bearer: String,
+        res: Sender<Result<Vec<JobPreset>, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let profile = match Self::v2_api_presets_profile(node_name, &res).await {
+            Ok(profile) => profile,
+            Err(_) => return Ok(()),
+        };
+
+        let result = db.list_job_presets(&profile).map_err(Self::job_preset_api_error);
+        let _ = res.send(result).await;
+        Ok(())
+    }
+
+    pub async fn v2_api_remove_job_preset(
+        db: Arc<ShinkaiDB>,
+        node_name: ShinkaiName,
+        bearer: String,
+        payload: APIJobPresetId,
+        res: Sender<Result<String, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let profile = match Self::v2_api_presets_profile(node_name, &res).await {
+            Ok(profile) => profile,
+            Err(_) => return Ok(()),
+        };
+
+        let result = db
+            .remove_job_preset(&payload.preset_id, &profile)
+            .map(|_| "Job preset removed successfully".to_string())
+            .map_err(Self::job_preset_api_error);
+        let _ = res.send(result).await;
+        Ok(())
+    }
+
+    /// Creates a job with the llm provider of a preset. The preset's scope, system prompt, tools and limits
+    /// are applied by the job manager and the inference chain.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn v2_api_create_job_from_preset(
+        db: Arc<ShinkaiDB>,
+        node_name: ShinkaiName,
+        identity_manager: Arc<Mutex<IdentityManager>>,
+        job_manager: Arc<Mutex<JobManager>>,
+        bearer: String,
+        payload: APICreateJobFromPreset,
+        node_encryption_sk: EncryptionStaticKey,
+        node_encryption_pk: EncryptionPublicKey,
+        node_signing_sk: SigningKey,
+        res: Sender<Result<String, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let profile = match Self::v2_api_presets_profile(node_name.clone(), &res).await {
+            Ok(profile) => profile,
+            Err(_) => return Ok(()),
+        };
+
+        let preset = match db.get_job_preset(&payload.preset_id, &profile) {
+            Ok(preset) => preset,
+            Err(err) => {
+                let _ = res.send(Err(Self::job_preset_api_error(err))).await;
+                return Ok(());
+            }
+        };
+
+        let job_creation_info = JobCreationInfo {
+            scope: JobScope::new_default(),
+            is_hidden: payload.is_hidden,
+            associated_ui: payload.associated_ui,
+            preset_id: Some(preset.preset_id),
+        };
+
+        Self::v2_create_new_job(
+            db,
+            node_name,
+            identity_manager,
+            job_manager,
+            bearer,
+            job_creation_info,
+            preset.llm_provider_id,
+            node_encryption_sk,
+            node_encryption_pk,
+            node_signing_sk,
+            res,
+        )
+        .await
+    }
+}
@@ -0,0 +1,262 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_channel::Sender;
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
+use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::{
+    APIGetProfileUsageQuota, APIGetTokenUsage, APISetProfileUsageQuota,
+};
+
+use crate::{
+    db::{
+        db_token_usage::{TokenUsageFilter, TokenUsageGroupBy},
+        ShinkaiDB,
+    },
+    network::{node_api_router::APIError, node_error::NodeError, Node},
+};
+
+impl Node {
+    pub async fn v2_api_get_token_usage(
+        db: Arc<ShinkaiDB>,
+        bearer: String,
+        payload: APIGetTokenUsage,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let (start, end, filter) = match Self::parse_token_usage_query(&payload) {
+            Ok(query) => query,
+            Err(api_error) => {
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        let group_by = match payload.group_by.as_deref() {
+            Some(group_by) if !group_by.trim().is_empty() => {
+                match group_by.split(',').map(TokenUsageGroupBy::from_str).collect() {
+                    Ok(group_by) => group_by,
+                    Err(err) => {
+                        let api_error = APIError {
+                            code: StatusCode::BAD_REQUEST.as_u16(),
+                            error: "Bad Request".to_string(),
+                            message: err,
+                        };
+                        let _ = res.send(Err(api_error)).await;
+                        return Ok(());
+                    }
+                }
+            }
+            _ => vec![],
+        };
+
+        match db.get_token_usage_summary(start, end, &filter, &group_by) {
+            Ok(summary) => {
+                let summary_json = serde_json::to_value(summary).map_err(|err| NodeError {
+                    message: format!("Failed to serialize token usage: {}", err),
+                })?;
+                let _ = res.send(Ok(summary_json)).await;
+                Ok(())
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to retrieve token usage: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn v2_api_get_token_usage_records(
+        db: Arc<ShinkaiDB>,
+        bearer: String,
+        payload: APIGetTokenUsage,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let (start, end, filter) = match Self::parse_token_usage_query(&payload) {
+            Ok(query) => query,
+            Err(api_error) => {
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        match db.get_token_usage_records(start, end, &filter) {
+            Ok(records) => {
+                let records_json = serde_json::to_value(records).map_err(|err| NodeError {
+                    message: format!("Failed to serialize token usage records: {}", err),
+                })?;
+                let _ = res.send(Ok(records_json)).await;
+                Ok(())
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to retrieve token usage records: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn v2_api_get_profile_usage_quota(
+        db: Arc<ShinkaiDB>,
+        bearer: String,
+        payload: APIGetProfileUsageQuota,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let profile = match Self::parse_usage_quota_profile(&payload.profile) {
+            Ok(profile) => profile,
+            Err(api_error) => {
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        let filter = TokenUsageFilter {
+            profile: Some(profile.clone()),
+            ..Default::default()
+        };
+        let quota_and_usage = db
+            .get_profile_usage_quota(&profile)
+            .and_then(|quota| Ok((quota, db.get_token_usage_counts(&filter, Utc::now())?)));
+
+        match quota_and_usage {
+            Ok((quota, usage)) => {
+                let (soft_limits_reached, hard_limits_reached) = match &quota {
+                    Some(quota) => (
+                        quota.soft_limits.exceeded_limits(&usage),
+                        quota.hard_limits.exceeded_limits(&usage),
+                    ),
+                    None => (vec![], vec![]),
+                };
+                let _ = res
+                    .send(Ok(json!({
+                        "profile": profile,
+                        "quota": quota,
+                        "usage": usage,
+                        "soft_limits_reached": soft_limits_reached,
+                        "hard_limits_reached": hard_limits_reached,
+                    })))
+                    .await;
+                Ok(())
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to retrieve usage quota: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn v2_api_set_profile_usage_quota(
+        db: Arc<ShinkaiDB>,
+        bearer: String,
+        payload: APISetProfileUsageQuota,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let profile = match Self::parse_usage_quota_profile(&payload.profile) {
+            Ok(profile) => profile,
+            Err(api_error) => {
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        match db.set_profile_usage_quota(&profile, payload.quota.as_ref()) {
+            Ok(_) => {
+                let _ = res
+                    .send(Ok(json!({ "profile": profile, "quota": payload.quota })))
+                    .await;
+                Ok(())
+            }
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to set usage quota: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Ok(())
+            }
+        }
+    }
+
+    /// Normalizes the profile name the same way it's stored in the token usage records
+    fn parse_usage_quota_profile(profile: &str) -> Result<String, APIError> {
+        let bad_request = |message: String| APIError {
+            code: StatusCode::BAD_REQUEST.as_u16(),
+            error: "Bad Request".to_string(),
+            message,
+        };
+
+        let name = ShinkaiName::new(profile.to_string())
+            .map_err(|err| bad_request(format!("Invalid profile name {}: {}", profile, err)))?;
+        let profile_name = name
+            .extract_profile()
+            .map_err(|err| bad_request(format!("Invalid profile name {}: {}", profile, err)))?;
+        if profile_name.get_profile_name_string().is_none() {
+            return Err(bad_request(format!("{} is not a profile", profile)));
+        }
+
+        Ok(profile_name.full_name)
+    }
+
+    /// Parses the date range and the filters of a token usage query
+    #[allow(clippy::type_complexity)]
+    fn parse_token_usage_query(
+        payload: &APIGetTokenUsage,
+    ) -> Result<(Option<DateTime<Utc>>, Option<DateTime<Utc>>, TokenUsageFilter), APIError> {
+        let parse_date = |date: &Option<String>| -> Result<Option<DateTime<Utc>>, APIError> {
+            match date {
+                Some(date) => DateTime::parse_from_rfc3339(date)
+                    .map(|date| Some(date.with_timezone(&Utc)))
+                    .map_err(|err| APIError {
+                        code: StatusCode::BAD_REQUEST.as_u16(),
+                        error: "Bad Request".to_string(),
+                        message: format!("Invalid date {}: {}", date, err),
+                    }),
+                None => Ok(None),
+            }
+        };
+
+        let filter = TokenUsageFilter {
+            job_id: payload.job_id.clone(),
+            inbox_name: payload.inbox_name.clone(),
+            profile: payload.profile.clone(),
+            llm_provider_id: payload.llm_provider_id.clone(),
+        };
+
+        Ok((parse_date(&payload.start_date)?, parse_date(&payload.end_date)?, filter))
+    }
+}
@@ -4,7 +4,10 @@ use futures::StreamExt;
 use reqwest::StatusCode;
 use serde::Deserialize;
 use serde_json::json;
-use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::{APIChangeJobAgentRequest, JobCreationInfo, JobMessage};
+use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::{
+    APIChangeJobAgentRequest, APIForkJobMessages, APIImportJob, APISetJobConfig, APISwitchJobBranch, JobCreationInfo,
+    JobMessage,
+};
 use utoipa::OpenApi;
 use warp::multipart::FormData;
 use warp::Filter;
@@ -80,6 +83,76 @@ pub fn job_routes(
         .and(warp::body::json())
         .and_then(change_job_llm_provider_handler);
 
+    let get_job_config_route = warp::path("job_config")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<GetJobConfigRequest>())
+        .and_then(get_job_config_handler);
+
+    let set_job_config_route = warp::path("set_job_config")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(set_job_config_handler);
+
+    let cancel_job_route = warp::path("cancel_job")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(cancel_job_handler);
+
+    let pause_job_route = warp::path("pause_job")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(pause_job_handler);
+
+    let resume_job_route = warp::path("resume_job")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(resume_job_handler);
+
+    let fork_job_messages_route = warp::path("fork_job_messages")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(fork_job_messages_handler);
+
+    let get_job_message_tree_route = warp::path("job_message_tree")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<GetJobConfigRequest>())
+        .and_then(get_job_message_tree_handler);
+
+    let switch_job_branch_route = warp::path("switch_job_branch")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(switch_job_branch_handler);
+
+    let export_job_route = warp::path("export_job")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<GetJobConfigRequest>())
+        .and_then(export_job_handler);
+
+    let import_job_route = warp::path("import_job")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(import_job_handler);
+
     create_job_route
         .or(job_message_route)
         .or(get_last_messages_route)
@@ -89,6 +162,16 @@ pub fn job_routes(
         .or(create_files_inbox_route)
         .or(add_file_to_inbox_route)
         .or(change_job_llm_provider_route)
+        .or(get_job_config_route)
+        .or(set_job_config_route)
+        .or(cancel_job_route)
+        .or(pause_job_route)
+        .or(resume_job_route)
+        .or(fork_job_messages_route)
+        .or(get_job_message_tree_route)
+        .or(switch_job_branch_route)
+        .or(export_job_route)
+        .or(import_job_route)
 }
 
 #[derive(Deserialize)]
@@ -115,6 +198,16 @@ pub struct UpdateSmartInboxNameRequest {
     pub custom_name: String,
 }
 
+#[derive(Deserialize)]
+pub struct GetJobConfigRequest {
+    pub job_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct JobIdRequest {
+    pub job_id: String,
+}
+
 #[derive(Deserialize)]
 pub struct AddFileToInboxRequest {
     pub file_inbox_name: String,
@@ -574,6 +667,404 @@ pub async fn change_job_llm_provider_handler(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/v2/job_config",
+    params(
+        ("job_id" = String, Query, description = "Job ID to get the config of")
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved job config", body = Value),
+        (status = 404, description = "Job not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn get_job_config_handler(
+    node_commands_sender: Sender<NodeCommand>,
+    authorization: String,
+    query: GetJobConfigRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    node_commands_sender
+        .send(NodeCommand::V2ApiGetJobConfig {
+            bearer,
+            job_id: query.job_id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(config) => {
+            let response = create_success_response(json!(config));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/set_job_config",
+    request_body = APISetJobConfig,
+    responses(
+        (status = 200, description = "Successfully updated job config", body = Value),
+        (status = 404, description = "Job not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn set_job_config_handler(
+    node_commands_sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: APISetJobConfig,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    node_commands_sender
+        .send(NodeCommand::V2ApiSetJobConfig {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(json!({ "result": response }));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/cancel_job",
+    request_body = JobIdRequest,
+    responses(
+        (status = 200, description = "Cancels the message being processed and the queued ones", body = Value),
+        (status = 404, description = "Job not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn cancel_job_handler(
+    node_commands_sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: JobIdRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    node_commands_sender
+        .send(NodeCommand::V2ApiCancelJob {
+            bearer,
+            job_id: payload.job_id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(json!({ "result": response }));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/pause_job",
+    request_body = JobIdRequest,
+    responses(
+        (status = 200, description = "Pauses the job, keeping its messages queued", body = Value),
+        (status = 404, description = "Job not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn pause_job_handler(
+    node_commands_sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: JobIdRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    node_commands_sender
+        .send(NodeCommand::V2ApiPauseJob {
+            bearer,
+            job_id: payload.job_id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(json!({ "result": response }));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/resume_job",
+    request_body = JobIdRequest,
+    responses(
+        (status = 200, description = "Resumes a paused job", body = Value),
+        (status = 400, description = "Job is not paused", body = APIError),
+        (status = 404, description = "Job not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn resume_job_handler(
+    node_commands_sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: JobIdRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    node_commands_sender
+        .send(NodeCommand::V2ApiResumeJob {
+            bearer,
+            job_id: payload.job_id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(json!({ "result": response }));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/fork_job_messages",
+    request_body = APIForkJobMessages,
+    responses(
+        (status = 200, description = "Adds the (edited) message as a new branch and regenerates the answer", body = SendResponseBody),
+        (status = 400, description = "Bad request", body = APIError),
+        (status = 404, description = "Job or message not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn fork_job_messages_handler(
+    node_commands_sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: APIForkJobMessages,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    node_commands_sender
+        .send(NodeCommand::V2ApiForkJobMessages {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(response);
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/job_message_tree",
+    params(
+        ("job_id" = String, Query, description = "Job ID to get the message tree of")
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved every message of the job with its branch metadata", body = Value),
+        (status = 404, description = "Job not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn get_job_message_tree_handler(
+    node_commands_sender: Sender<NodeCommand>,
+    authorization: String,
+    query: GetJobConfigRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    node_commands_sender
+        .send(NodeCommand::V2ApiGetJobMessageTree {
+            bearer,
+            job_id: query.job_id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(tree) => {
+            let response = create_success_response(tree);
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/switch_job_branch",
+    request_body = APISwitchJobBranch,
+    responses(
+        (status = 200, description = "Makes the branch containing the message the active one", body = Value),
+        (status = 404, description = "Job or message not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn switch_job_branch_handler(
+    node_commands_sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: APISwitchJobBranch,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    node_commands_sender
+        .send(NodeCommand::V2ApiSwitchJobBranch {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(active_leaf) => {
+            let response = create_success_response(json!({ "active_leaf": active_leaf }));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/export_job",
+    params(
+        ("job_id" = String, Query, description = "Job ID to export")
+    ),
+    responses(
+        (status = 200, description = "Successfully exported the job as an archive signed by the node", body = Value),
+        (status = 404, description = "Job not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn export_job_handler(
+    node_commands_sender: Sender<NodeCommand>,
+    authorization: String,
+    query: GetJobConfigRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    node_commands_sender
+        .send(NodeCommand::V2ApiExportJob {
+            bearer,
+            job_id: query.job_id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(archive) => {
+            let response = create_success_response(json!(archive));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/import_job",
+    request_body = APIImportJob,
+    responses(
+        (status = 200, description = "Successfully imported the job and listed its missing scope items", body = Value),
+        (status = 400, description = "Invalid archive or LLM provider not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn import_job_handler(
+    node_commands_sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: APIImportJob,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    node_commands_sender
+        .send(NodeCommand::V2ApiImportJob {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(import_result) => {
+            let response = create_success_response(json!(import_result));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
 #[derive(OpenApi)]
 #[openapi(
     paths(
@@ -585,7 +1076,17 @@ pub async fn change_job_llm_provider_handler(
         update_smart_inbox_name_handler,
         create_files_inbox_handler,
         add_file_to_inbox_handler,
-        change_job_llm_provider_handler
+        change_job_llm_provider_handler,
+        get_job_config_handler,
+        set_job_config_handler,
+        cancel_job_handler,
+        pause_job_handler,
+        resume_job_handler,
+        fork_job_messages_handler,
+        get_job_message_tree_handler,
+        switch_job_branch_handler,
+        export_job_handler,
+        import_job_handler,
     ),
     components(
         schemas(SendResponseBody, SendResponseBodyData, APIError)
@@ -0,0 +1,169 @@
+use async_channel::Sender;
+use reqwest::StatusCode;
+use serde_json::json;
+use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::{
+    APIExcludeJobFromMemory, APIForgetMemory,
+};
+use utoipa::OpenApi;
+use warp::Filter;
+
+use crate::network::{node_api_router::APIError, node_commands::NodeCommand};
+
+use super::api_v2_router::{create_success_response, with_sender};
+
+pub fn memory_routes(
+    node_commands_sender: Sender<NodeCommand>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let export_memory_route = warp::path("export_memory")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and_then(export_memory_handler);
+
+    let forget_memory_route = warp::path("forget_memory")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(forget_memory_handler);
+
+    let exclude_job_from_memory_route = warp::path("exclude_job_from_memory")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(exclude_job_from_memory_handler);
+
+    export_memory_route
+        .or(forget_memory_route)
+        .or(exclude_job_from_memory_route)
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/export_memory",
+    responses(
+        (status = 200, description = "Successfully exported the memory of past conversations", body = Value),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn export_memory_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiExportMemory {
+            bearer,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(entries) => {
+            let response = create_success_response(json!(entries));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/forget_memory",
+    request_body = APIForgetMemory,
+    responses(
+        (status = 200, description = "Successfully forgot the memory entries", body = Value),
+        (status = 400, description = "Bad request", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn forget_memory_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: APIForgetMemory,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiForgetMemory {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(json!({ "result": response }));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/exclude_job_from_memory",
+    request_body = APIExcludeJobFromMemory,
+    responses(
+        (status = 200, description = "Successfully updated the memory settings of the job", body = Value),
+        (status = 404, description = "Job not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn exclude_job_from_memory_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: APIExcludeJobFromMemory,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiExcludeJobFromMemory {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(json!({ "result": response }));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        export_memory_handler,
+        forget_memory_handler,
+        exclude_job_from_memory_handler,
+    ),
+    components(
+        schemas(APIError)
+    ),
+    tags(
+        (name = "memory", description = "Memory of past conversations API endpoints")
+    )
+)]
+pub struct MemoryApiDoc;
@@ -0,0 +1,204 @@
+use std::convert::Infallible;
+
+use async_channel::Sender;
+use futures::StreamExt;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
+use shinkai_message_primitives::shinkai_utils::job_scope::JobScope;
+use utoipa::OpenApi;
+use warp::Filter;
+
+use crate::network::{node_api_router::APIError, node_commands::NodeCommand};
+
+use super::api_v2_router::with_sender;
+
+/// OpenAI compatible routes. OpenAI SDKs can use the node by setting their base url to `http://<node>/v2`.
+pub fn openai_routes(
+    node_commands_sender: Sender<NodeCommand>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("chat" / "completions")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(chat_completions_handler)
+}
+
+/// Body of an OpenAI chat completions request. `model` is the id of the llm provider to use.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChatCompletionRequest {
+    pub model: String,
+    pub messages: Vec<ChatCompletionMessage>,
+    #[serde(default)]
+    pub stream: bool,
+    /// Tools in the OpenAI format (`{"type": "function", "function": {...}}`), called by the client
+    #[serde(default)]
+    pub tools: Vec<Value>,
+    /// Sampling, overriding the inference params of the job
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub top_p: Option<f64>,
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+    #[serde(default)]
+    pub stop: Option<ChatCompletionStop>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Shinkai extension fields
+    #[serde(default)]
+    pub shinkai: Option<ChatCompletionShinkaiOptions>,
+}
+
+impl ChatCompletionRequest {
+    pub fn inference_params(&self) -> InferenceParams {
+        InferenceParams {
+            temperature: self.temperature,
+            top_p: self.top_p,
+            max_tokens: self.max_tokens,
+            stop: self.stop.clone().map(|stop| match stop {
+                ChatCompletionStop::One(sequence) => vec![sequence],
+                ChatCompletionStop::Many(sequences) => sequences,
+            }),
+            seed: self.seed,
+        }
+    }
+}
+
+/// `stop` can be a single sequence or a list of them, as in the OpenAI API
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ChatCompletionStop {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Runs the completion in an existing job or, if not set, in a new hidden job created with `job_scope`.
+/// The job scope is vector searched with the last user message (RAG over the VectorFS).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ChatCompletionShinkaiOptions {
+    pub job_id: Option<String>,
+    pub job_scope: Option<JobScope>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChatCompletionMessage {
+    pub role: String,
+    #[serde(default)]
+    pub content: Option<ChatCompletionContent>,
+    #[serde(default)]
+    pub tool_calls: Vec<ChatCompletionToolCall>,
+    #[serde(default)]
+    pub tool_call_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ChatCompletionContent {
+    Text(String),
+    /// Content parts (e.g. `{"type": "text", "text": "..."}`), only the text parts are used
+    Parts(Vec<Value>),
+}
+
+impl ChatCompletionContent {
+    pub fn text(&self) -> String {
+        match self {
+            ChatCompletionContent::Text(text) => text.clone(),
+            ChatCompletionContent::Parts(parts) => parts
+                .iter()
+                .filter_map(|part| part.get("text").and_then(|text| text.as_str()))
+                .collect::<Vec<&str>>()
+                .join("\n"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChatCompletionToolCall {
+    pub id: String,
+    #[serde(rename = "type", default = "default_tool_call_type")]
+    pub call_type: String,
+    pub function: ChatCompletionFunctionCall,
+}
+
+fn default_tool_call_type() -> String {
+    "function".to_string()
+}
+
+/// `arguments` is a JSON encoded string, as in the OpenAI API
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChatCompletionFunctionCall {
+    pub name: String,
+    pub arguments: String,
+}
+
+/// A full completion or, when streaming, the chunks to send as server sent events
+pub enum ChatCompletionOutput {
+    Completion(Value),
+    Stream(async_channel::Receiver<Value>),
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/chat/completions",
+    request_body = Value,
+    responses(
+        (status = 200, description = "Chat completion (or a stream of chunks if `stream` is set)", body = Value),
+        (status = 400, description = "Bad request", body = APIError),
+        (status = 404, description = "Model or job not found", body = APIError),
+        (status = 429, description = "Usage quota exceeded", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn chat_completions_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: ChatCompletionRequest,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiChatCompletions {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(ChatCompletionOutput::Completion(completion)) => Ok(Box::new(warp::reply::with_status(
+            warp::reply::json(&completion),
+            StatusCode::OK,
+        ))),
+        Ok(ChatCompletionOutput::Stream(chunks)) => {
+            let events = chunks
+                .map(|chunk| Ok::<_, Infallible>(warp::sse::Event::default().data(chunk.to_string())))
+                .chain(futures::stream::once(async {
+                    Ok::<_, Infallible>(warp::sse::Event::default().data("[DONE]"))
+                }));
+            Ok(Box::new(warp::sse::reply(warp::sse::keep_alive().stream(events))))
+        }
+        Err(error) => Ok(Box::new(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        ))),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        chat_completions_handler,
+    ),
+    components(
+        schemas(APIError)
+    ),
+    tags(
+        (name = "openai", description = "OpenAI compatible API endpoints")
+    )
+)]
+pub struct OpenAIApiDoc;
@@ -0,0 +1,318 @@
+use async_channel::Sender;
+use reqwest::StatusCode;
+use serde_json::json;
+use shinkai_message_primitives::{
+    schemas::job_preset::JobPreset,
+    shinkai_message::shinkai_message_schemas::{APICreateJobFromPreset, APIJobPresetId},
+};
+use utoipa::OpenApi;
+use warp::Filter;
+
+use crate::network::{node_api_router::APIError, node_commands::NodeCommand};
+
+use super::api_v2_router::{create_success_response, with_sender};
+
+pub fn preset_routes(
+    node_commands_sender: Sender<NodeCommand>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let add_job_preset_route = warp::path("add_job_preset")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(add_job_preset_handler);
+
+    let update_job_preset_route = warp::path("update_job_preset")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(update_job_preset_handler);
+
+    let get_job_preset_route = warp::path("get_job_preset")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<APIJobPresetId>())
+        .and_then(get_job_preset_handler);
+
+    let list_job_presets_route = warp::path("list_job_presets")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and_then(list_job_presets_handler);
+
+    let remove_job_preset_route = warp::path("remove_job_preset")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(remove_job_preset_handler);
+
+    let create_job_from_preset_route = warp::path("create_job_from_preset")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(create_job_from_preset_handler);
+
+    add_job_preset_route
+        .or(update_job_preset_route)
+        .or(get_job_preset_route)
+        .or(list_job_presets_route)
+        .or(remove_job_preset_route)
+        .or(create_job_from_preset_route)
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/add_job_preset",
+    request_body = JobPreset,
+    responses(
+        (status = 200, description = "Successfully added the job preset", body = Value),
+        (status = 400, description = "Bad request", body = APIError),
+        (status = 409, description = "Job preset already exists", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn add_job_preset_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: JobPreset,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiAddJobPreset {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(preset) => {
+            let response = create_success_response(json!(preset));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/update_job_preset",
+    request_body = JobPreset,
+    responses(
+        (status = 200, description = "Successfully updated the job preset", body = Value),
+        (status = 400, description = "Bad request", body = APIError),
+        (status = 404, description = "Job preset not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn update_job_preset_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: JobPreset,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiUpdateJobPreset {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(preset) => {
+            let response = create_success_response(json!(preset));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/get_job_preset",
+    params(
+        ("preset_id" = String, Query, description = "Id of the job preset")
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved the job preset", body = Value),
+        (status = 404, description = "Job preset not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn get_job_preset_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    query: APIJobPresetId,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiGetJobPreset {
+            bearer,
+            payload: query,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(preset) => {
+            let response = create_success_response(json!(preset));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/list_job_presets",
+    responses(
+        (status = 200, description = "Successfully listed the job presets", body = Value),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn list_job_presets_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiListJobPresets {
+            bearer,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(presets) => {
+            let response = create_success_response(json!(presets));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/remove_job_preset",
+    request_body = APIJobPresetId,
+    responses(
+        (status = 200, description = "Successfully removed the job preset", body = Value),
+        (status = 404, description = "Job preset not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn remove_job_preset_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: APIJobPresetId,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiRemoveJobPreset {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(json!({ "result": response }));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/create_job_from_preset",
+    request_body = APICreateJobFromPreset,
+    responses(
+        (status = 200, description = "Successfully created the job", body = Value),
+        (status = 404, description = "Job preset not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn create_job_from_preset_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: APICreateJobFromPreset,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiCreateJobFromPreset {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(job_id) => {
+            let response = create_success_response(json!({ "job_id": job_id }));
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        add_job_preset_handler,
+        update_job_preset_handler,
+        get_job_preset_handler,
+        list_job_presets_handler,
+        remove_job_preset_handler,
+        create_job_from_preset_handler,
+    ),
+    components(
+        schemas(APIError)
+    ),
+    tags(
+        (name = "presets", description = "Job presets API endpoints")
+    )
+)]
+pub struct PresetsApiDoc;
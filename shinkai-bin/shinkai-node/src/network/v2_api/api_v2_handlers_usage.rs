@@ -0,0 +1,238 @@
+use async_channel::Sender;
+use reqwest::StatusCode;
+use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::{
+    APIGetProfileUsageQuota, APIGetTokenUsage, APISetProfileUsageQuota,
+};
+use utoipa::OpenApi;
+use warp::Filter;
+
+use crate::network::{node_api_router::APIError, node_commands::NodeCommand};
+
+use super::api_v2_router::{create_success_response, with_sender};
+
+pub fn usage_routes(
+    node_commands_sender: Sender<NodeCommand>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let get_token_usage_route = warp::path("token_usage")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<APIGetTokenUsage>())
+        .and_then(get_token_usage_handler);
+
+    let get_token_usage_records_route = warp::path("token_usage_records")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<APIGetTokenUsage>())
+        .and_then(get_token_usage_records_handler);
+
+    let get_profile_usage_quota_route = warp::path("profile_usage_quota")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<APIGetProfileUsageQuota>())
+        .and_then(get_profile_usage_quota_handler);
+
+    let set_profile_usage_quota_route = warp::path("set_profile_usage_quota")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(set_profile_usage_quota_handler);
+
+    get_token_usage_route
+        .or(get_token_usage_records_route)
+        .or(get_profile_usage_quota_route)
+        .or(set_profile_usage_quota_route)
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/token_usage",
+    params(
+        ("start_date" = Option<String>, Query, description = "RFC3339 start date (inclusive)"),
+        ("end_date" = Option<String>, Query, description = "RFC3339 end date (exclusive)"),
+        ("group_by" = Option<String>, Query, description = "Comma separated list of: job, inbox, profile, llm_provider, model, day, month"),
+        ("job_id" = Option<String>, Query, description = "Only count the usage of this job"),
+        ("inbox_name" = Option<String>, Query, description = "Only count the usage of this inbox"),
+        ("profile" = Option<String>, Query, description = "Only count the usage of this profile"),
+        ("llm_provider_id" = Option<String>, Query, description = "Only count the usage of this llm provider")
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved token usage totals", body = Value),
+        (status = 400, description = "Bad request", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn get_token_usage_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    query: APIGetTokenUsage,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiGetTokenUsage {
+            bearer,
+            payload: query,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(response);
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/token_usage_records",
+    params(
+        ("start_date" = Option<String>, Query, description = "RFC3339 start date (inclusive)"),
+        ("end_date" = Option<String>, Query, description = "RFC3339 end date (exclusive)"),
+        ("job_id" = Option<String>, Query, description = "Only return the records of this job"),
+        ("inbox_name" = Option<String>, Query, description = "Only return the records of this inbox"),
+        ("profile" = Option<String>, Query, description = "Only return the records of this profile"),
+        ("llm_provider_id" = Option<String>, Query, description = "Only return the records of this llm provider")
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved token usage records", body = Value),
+        (status = 400, description = "Bad request", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn get_token_usage_records_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    query: APIGetTokenUsage,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiGetTokenUsageRecords {
+            bearer,
+            payload: query,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(response);
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/profile_usage_quota",
+    params(
+        ("profile" = String, Query, description = "Full name of the profile (e.g. @@node.shinkai/main)")
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved the usage quota of the profile", body = Value),
+        (status = 400, description = "Bad request", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn get_profile_usage_quota_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    query: APIGetProfileUsageQuota,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiGetProfileUsageQuota {
+            bearer,
+            payload: query,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(response);
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/set_profile_usage_quota",
+    request_body = APISetProfileUsageQuota,
+    responses(
+        (status = 200, description = "Successfully set the usage quota of the profile", body = Value),
+        (status = 400, description = "Bad request", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn set_profile_usage_quota_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: APISetProfileUsageQuota,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiSetProfileUsageQuota {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(response);
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        get_token_usage_handler,
+        get_token_usage_records_handler,
+        get_profile_usage_quota_handler,
+        set_profile_usage_quota_handler,
+    ),
+    components(
+        schemas(APIError)
+    ),
+    tags(
+        (name = "usage", description = "LLM Usage API endpoints")
+    )
+)]
+pub struct UsageApiDoc;
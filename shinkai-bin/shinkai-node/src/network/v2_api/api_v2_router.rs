@@ -1,6 +1,10 @@
 use crate::network::node_commands::NodeCommand;
 
 use super::api_v2_handlers_jobs::job_routes;
+use super::api_v2_handlers_memory::memory_routes;
+use super::api_v2_handlers_openai::openai_routes;
+use super::api_v2_handlers_presets::preset_routes;
+use super::api_v2_handlers_usage::usage_routes;
 use super::api_v2_handlers_vecfs::vecfs_routes;
 use super::api_v2_handlers_workflows::workflows_routes;
 use super::{api_v2_handlers_general::general_routes, api_v2_handlers_subscriptions::subscriptions_routes};
@@ -19,12 +23,20 @@ pub fn v2_routes(
     let job_routes = job_routes(node_commands_sender.clone(), node_name.clone());
     let subscriptions_routes = subscriptions_routes(node_commands_sender.clone());
     let workflows_routes = workflows_routes(node_commands_sender.clone());
+    let usage_routes = usage_routes(node_commands_sender.clone());
+    let openai_routes = openai_routes(node_commands_sender.clone());
+    let memory_routes = memory_routes(node_commands_sender.clone());
+    let preset_routes = preset_routes(node_commands_sender.clone());
 
     general_routes
         .or(vecfs_routes)
         .or(job_routes)
         .or(subscriptions_routes)
         .or(workflows_routes)
+        .or(usage_routes)
+        .or(openai_routes)
+        .or(memory_routes)
+        .or(preset_routes)
 }
 
 pub fn with_sender(
@@ -4,8 +4,16 @@ pub mod api_v2_commands_jobs;
 pub mod api_v2_commands_vecfs;
 pub mod api_v2_commands_subscriptions;
 pub mod api_v2_commands_workflows;
+pub mod api_v2_commands_usage;
+pub mod api_v2_commands_openai;
+pub mod api_v2_commands_memory;
+pub mod api_v2_commands_presets;
 pub mod api_v2_handlers_general;
 pub mod api_v2_handlers_vecfs;
 pub mod api_v2_handlers_jobs;
 pub mod api_v2_handlers_subscriptions;
-pub mod api_v2_handlers_workflows;
\ No newline at end of file
+pub mod api_v2_handlers_workflows;
+pub mod api_v2_handlers_usage;
+pub mod api_v2_handlers_openai;
+pub mod api_v2_handlers_memory;
+pub mod api_v2_handlers_presets;
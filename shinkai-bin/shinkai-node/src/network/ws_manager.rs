@@ -39,6 +39,7 @@ pub enum MessageType {
     ShinkaiMessage,
     Stream,
     Sheet,
+    Notification,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -105,6 +106,7 @@ pub trait WSUpdateHandler {
 pub enum WSMessageType {
     Metadata(WSMetadata),
     Sheet(CellUpdateInfo),
+    Notification,
     None,
 }
 
@@ -466,6 +468,7 @@ impl WebSocketManager {
         // Determine the message type
         let message_type = match metadata {
             WSMessageType::Sheet(_) => MessageType::Sheet,
+            WSMessageType::Notification => MessageType::Notification,
             _ => {
                 if is_stream {
                     MessageType::Stream
@@ -259,6 +259,7 @@ impl ToolRouter {
 
                 dsl_inference.add_inference_function();
                 dsl_inference.add_inference_no_ws_function();
+                dsl_inference.add_structured_inference_function();
                 dsl_inference.add_opinionated_inference_function();
                 dsl_inference.add_opinionated_inference_no_ws_function();
                 dsl_inference.add_multi_inference_function();
@@ -83,6 +83,10 @@ pub fn fetch_llm_provider_env(global_identity: String) -> Vec<SerializedLLMProvi
             toolkit_permissions: vec![],
             storage_bucket_permissions: vec![],
             allowed_message_senders: vec![],
+            retry_policy: Default::default(),
+            fallback_llm_providers: vec![],
+            usage_quota: None,
+            response_cache: None,
         };
 
         llm_providers.push(agent);
@@ -3,13 +3,22 @@ use std::fs::File;
 use std::io;
 use std::io::Write;
 use std::pin::Pin;
+use std::time::Duration;
 use std::{any::Any, fmt};
 
 use async_trait::async_trait;
 use chrono::Utc;
 use dashmap::DashMap;
-use futures::Future;
-use shinkai_dsl::dsl_schemas::{Action, ComparisonOperator, Expression, ForLoopExpression, FunctionCall, Param, StepBody, Workflow, WorkflowValue};
+use futures::{Future, StreamExt};
+use serde_json::Value;
+use shinkai_dsl::dsl_schemas::{
+    Accessor, Action, Expression, ForLoopExpression, FunctionCall, ParallelOptions, Param, RetryPolicy, StepBody,
+    Workflow, WorkflowValue,
+};
+use shinkai_dsl::register_value::{
+    access_field, access_index, apply_operator, compare, from_function_output, is_truthy, to_register_string,
+};
+use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
 use tokio::runtime::Runtime;
 use tokio::task;
 
@@ -43,6 +52,27 @@ impl fmt::Display for WorkflowError {
 
 impl std::error::Error for WorkflowError {}
 
+/// Upper bound of iterations of a `while` loop, so a condition that never turns false can't hang the job
+pub const MAX_WHILE_ITERATIONS: usize = 100;
+
+/// Upper bound of retries of a function call, whatever its `retry` modifier asks for
+pub const MAX_CALL_RETRIES: u32 = 10;
+
+/// Upper bound of the delay before retrying a function call
+pub const MAX_RETRY_BACKOFF_MS: u64 = 60_000;
+
+/// Register holding the value of a `return` statement
+pub const RESULT_REGISTER: &str = "$RESULT";
+
+/// How the execution goes on after a step body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepFlow {
+    Next,
+    Break,
+    Continue,
+    Return,
+}
+
 #[async_trait]
 pub trait AsyncFunction: Send + Sync {
     async fn call(&self, args: Vec<Box<dyn Any + Send>>) -> Result<Box<dyn Any + Send>, WorkflowError>;
@@ -50,6 +80,25 @@ pub trait AsyncFunction: Send + Sync {
 
 pub type FunctionMap<'a> = HashMap<String, Box<dyn AsyncFunction + 'a>>;
 
+/// Registers hold typed values (strings, numbers, booleans, lists and maps)
+pub type Registers = DashMap<String, Value>;
+
+/// Position of a parallel branch and the registers it ended with
+type BranchFuture<'b> = Pin<Box<dyn Future<Output = (usize, Result<Registers, WorkflowError>)> + Send + 'b>>;
+
+/// Functions receive every argument as a String, whatever the type of the value
+pub fn function_arg(value: &Value) -> Box<dyn Any + Send> {
+    Box::new(to_register_string(value))
+}
+
+/// Functions return a String, parsed when it holds a JSON list or map, or directly a `Value`
+pub fn function_output(output: Box<dyn Any + Send>) -> Option<Value> {
+    match output.downcast::<String>() {
+        Ok(output) => Some(from_function_output(*output)),
+        Err(output) => output.downcast::<Value>().ok().map(|value| *value),
+    }
+}
+
 pub struct WorkflowEngine<'a> {
     functions: &'a FunctionMap<'a>,
 }
@@ -58,7 +107,7 @@ pub struct StepExecutor<'a> {
     engine: &'a WorkflowEngine<'a>,
     workflow: &'a Workflow,
     pub current_step: usize,
-    pub registers: DashMap<String, String>,
+    pub registers: Registers,
     pub logs: DashMap<String, Vec<String>>,
 }
 
@@ -67,25 +116,47 @@ impl<'a> WorkflowEngine<'a> {
         WorkflowEngine { functions }
     }
 
-    pub async fn execute_workflow(&self, workflow: &Workflow) -> Result<DashMap<String, String>, WorkflowError> {
+    pub async fn execute_workflow(&self, workflow: &Workflow) -> Result<Registers, WorkflowError> {
         let registers = DashMap::new();
         let logs = DashMap::new();
         for step in &workflow.steps {
-            for body in &step.body {
-                self.execute_step_body(&step.name.clone(), body, &registers, &logs)
-                    .await?;
+            if self.execute_step(&step.name, &step.body, &registers, &logs).await? == StepFlow::Return {
+                break;
             }
         }
         Ok(registers)
     }
 
+    /// Executes the bodies of a step. Returns `StepFlow::Return` if the workflow has to end there.
+    pub async fn execute_step(
+        &self,
+        step_name: &str,
+        bodies: &[StepBody],
+        registers: &Registers,
+        logs: &DashMap<String, Vec<String>>,
+    ) -> Result<StepFlow, WorkflowError> {
+        for body in bodies {
+            match self.execute_step_body(step_name, body, registers, logs).await? {
+                StepFlow::Next => {}
+                StepFlow::Return => return Ok(StepFlow::Return),
+                StepFlow::Break | StepFlow::Continue => {
+                    return Err(WorkflowError::ExecutionError(format!(
+                        "break or continue outside of a loop in step {}",
+                        step_name
+                    )))
+                }
+            }
+        }
+        Ok(StepFlow::Next)
+    }
+
     pub fn execute_step_body<'b>(
         &'b self,
         step_name: &'b str,
         step_body: &'b StepBody,
-        registers: &'b DashMap<String, String>,
+        registers: &'b Registers,
         logs: &'b DashMap<String, Vec<String>>,
-    ) -> Pin<Box<dyn Future<Output = Result<(), WorkflowError>> + Send + 'b>> {
+    ) -> Pin<Box<dyn Future<Output = Result<StepFlow, WorkflowError>> + Send + 'b>> {
         Box::pin(async move {
             match step_body {
                 StepBody::Action(action) => {
@@ -93,50 +164,36 @@ impl<'a> WorkflowEngine<'a> {
                     logs.entry(step_name.to_string())
                         .or_default()
                         .push(format!("Executing action: {:?}, Result: {:?}", action, result));
-                    result
+                    result.map(|_| StepFlow::Next)
                 }
-                StepBody::Condition { condition, body } => {
+                StepBody::Condition {
+                    condition,
+                    body,
+                    else_body,
+                } => {
                     let condition_result = self.evaluate_condition(condition, registers).await;
                     logs.entry(step_name.to_string()).or_default().push(format!(
                         "Evaluating condition: {:?}, Result: {:?}",
                         condition, condition_result
                     ));
                     if condition_result? {
-                        self.execute_step_body(step_name, body, registers, logs).await?;
+                        self.execute_step_body(step_name, body, registers, logs).await
+                    } else if let Some(else_body) = else_body {
+                        self.execute_step_body(step_name, else_body, registers, logs).await
+                    } else {
+                        Ok(StepFlow::Next)
                     }
-                    Ok(())
                 }
                 StepBody::ForLoop { var, in_expr, body } => {
-                    match in_expr {
-                        ForLoopExpression::Range { start, end } => {
-                            let start = self
-                                .evaluate_param(start.as_ref(), registers)
-                                .await?
-                                .parse::<i32>()
-                                .unwrap_or(0);
-                            let end = self
-                                .evaluate_param(end.as_ref(), registers)
-                                .await?
-                                .parse::<i32>()
-                                .unwrap_or(0);
-                            for i in start..=end {
-                                registers.insert(var.clone(), i.to_string());
-                                logs.entry(step_name.to_string())
-                                    .or_default()
-                                    .push(format!("ForLoop iteration: {} = {}", var, i));
-                                self.execute_step_body(step_name, body, registers, logs).await?;
-                            }
-                        }
-                        ForLoopExpression::Split { source, delimiter } => {
-                            let source_value = self.evaluate_param(source, registers).await?;
-                            let parts: Vec<&str> = source_value.split(delimiter).collect();
-                            for part in parts {
-                                registers.insert(var.clone(), part.to_string());
-                                logs.entry(step_name.to_string())
-                                    .or_default()
-                                    .push(format!("ForLoop iteration: {} = {}", var, part));
-                                self.execute_step_body(step_name, body, registers, logs).await?;
-                            }
+                    for item in self.for_loop_items(in_expr, registers).await? {
+                        logs.entry(step_name.to_string())
+                            .or_default()
+                            .push(format!("ForLoop iteration: {} = {}", var, to_register_string(&item)));
+                        registers.insert(var.clone(), item);
+                        match self.execute_step_body(step_name, body, registers, logs).await? {
+                            StepFlow::Break => break,
+                            StepFlow::Return => return Ok(StepFlow::Return),
+                            StepFlow::Next | StepFlow::Continue => {}
                         }
                     }
                     logs.entry(step_name.to_string()).or_default().push(format!(
@@ -144,38 +201,203 @@ impl<'a> WorkflowEngine<'a> {
                         in_expr,
                         registers.clone()
                     ));
-                    Ok(())
+                    Ok(StepFlow::Next)
+                }
+                StepBody::Parallel { branches, options } => {
+                    let branches = branches.iter().map(|branch| (None, branch)).collect();
+                    self.execute_parallel(step_name, branches, options, registers, logs).await
+                }
+                StepBody::ParallelForLoop {
+                    var,
+                    in_expr,
+                    body,
+                    options,
+                } => {
+                    let branches = self
+                        .for_loop_items(in_expr, registers)
+                        .await?
+                        .into_iter()
+                        .map(|item| (Some((var.clone(), item)), body.as_ref()))
+                        .collect();
+                    self.execute_parallel(step_name, branches, options, registers, logs).await
+                }
+                StepBody::WhileLoop { condition, body } => {
+                    let mut iterations = 0;
+                    while self.evaluate_condition(condition, registers).await? {
+                        if iterations == MAX_WHILE_ITERATIONS {
+                            return Err(WorkflowError::ExecutionError(format!(
+                                "While loop in step {} exceeded {} iterations",
+                                step_name, MAX_WHILE_ITERATIONS
+                            )));
+                        }
+                        iterations += 1;
+                        logs.entry(step_name.to_string())
+                            .or_default()
+                            .push(format!("WhileLoop iteration: {}", iterations));
+                        match self.execute_step_body(step_name, body, registers, logs).await? {
+                            StepFlow::Break => break,
+                            StepFlow::Return => return Ok(StepFlow::Return),
+                            StepFlow::Next | StepFlow::Continue => {}
+                        }
+                    }
+                    Ok(StepFlow::Next)
+                }
+                StepBody::TryCatch {
+                    body,
+                    error_register,
+                    catch_body,
+                } => match self.execute_step_body(step_name, body, registers, logs).await {
+                    Ok(flow) => Ok(flow),
+                    Err(e) => {
+                        logs.entry(step_name.to_string())
+                            .or_default()
+                            .push(format!("Caught error: {}", e));
+                        if let Some(error_register) = error_register {
+                            registers.insert(error_register.clone(), Value::String(e.to_string()));
+                        }
+                        self.execute_step_body(step_name, catch_body, registers, logs).await
+                    }
+                },
+                StepBody::Break => Ok(StepFlow::Break),
+                StepBody::Continue => Ok(StepFlow::Continue),
+                StepBody::Return(value) => {
+                    if let Some(value) = value {
+                        let value = self.evaluate_param(value, registers).await?;
+                        registers.insert(RESULT_REGISTER.to_string(), value);
+                    }
+                    logs.entry(step_name.to_string())
+                        .or_default()
+                        .push(format!("Returning: {:?}", value));
+                    Ok(StepFlow::Return)
                 }
                 StepBody::RegisterOperation { register, value } => {
                     let value = self.evaluate_workflow_value(value, registers).await?;
                     registers.insert(register.clone(), value.clone());
                     logs.entry(step_name.to_string())
                         .or_default()
-                        .push(format!("Setting register {} to {:?}", register, value));
-                    Ok(())
+                        .push(format!("Setting register {} to {}", register, value));
+                    Ok(StepFlow::Next)
                 }
                 StepBody::Composite(bodies) => {
                     for (index, body) in bodies.iter().enumerate() {
                         let step_body_str = format!("{:?}", body);
-                        self.execute_step_body(step_name, body, registers, logs).await?;
+                        let flow = self.execute_step_body(step_name, body, registers, logs).await?;
                         logs.entry(step_name.to_string())
                             .or_default()
                             .push(format!("Composite body {}: {:?}", index, step_body_str));
+                        if flow != StepFlow::Next {
+                            return Ok(flow);
+                        }
                     }
-                    Ok(())
+                    Ok(StepFlow::Next)
                 }
             }
         })
     }
 
+    /// Values taken by the variable of a for loop
+    async fn for_loop_items(
+        &self,
+        in_expr: &ForLoopExpression,
+        registers: &Registers,
+    ) -> Result<Vec<Value>, WorkflowError> {
+        match in_expr {
+            ForLoopExpression::Range { start, end } => {
+                let start = to_register_string(&self.evaluate_param(start.as_ref(), registers).await?)
+                    .parse::<i32>()
+                    .unwrap_or(0);
+                let end = to_register_string(&self.evaluate_param(end.as_ref(), registers).await?)
+                    .parse::<i32>()
+                    .unwrap_or(0);
+                Ok((start..=end).map(Value::from).collect())
+            }
+            ForLoopExpression::Split { source, delimiter } => {
+                let source_value = to_register_string(&self.evaluate_param(source, registers).await?);
+                Ok(source_value.split(delimiter.as_str()).map(Value::from).collect())
+            }
+        }
+    }
+
+    /// Runs the branches concurrently, each on its own copy of the registers with its loop variable (if any) set.
+    /// A `continue` ends its branch, `break` and `return` can't leave it. Writes of the successful branches are
+    /// merged once they are all done, see `merge_branch_registers`.
+    async fn execute_parallel(
+        &self,
+        step_name: &str,
+        branches: Vec<(Option<(String, Value)>, &StepBody)>,
+        options: &ParallelOptions,
+        registers: &Registers,
+        logs: &DashMap<String, Vec<String>>,
+    ) -> Result<StepFlow, WorkflowError> {
+        let num_branches = branches.len();
+        let max_concurrency = options.max_concurrency.unwrap_or(num_branches).max(1);
+        logs.entry(step_name.to_string()).or_default().push(format!(
+            "Running {} branches in parallel, {} at once",
+            num_branches, max_concurrency
+        ));
+
+        let mut branch_futures: Vec<BranchFuture<'_>> = Vec::new();
+        for (index, (variable, body)) in branches.into_iter().enumerate() {
+            let branch_registers = registers.clone();
+            if let Some((var, value)) = variable {
+                branch_registers.insert(var, value);
+            }
+            branch_futures.push(Box::pin(async move {
+                let result = match self.execute_step_body(step_name, body, &branch_registers, logs).await {
+                    Ok(StepFlow::Next | StepFlow::Continue) => Ok(branch_registers),
+                    Ok(_) => Err(WorkflowError::ExecutionError(format!(
+                        "break or return inside a parallel branch in step {}",
+                        step_name
+                    ))),
+                    Err(e) => Err(e),
+                };
+                (index, result)
+            }));
+        }
+        let mut results = futures::stream::iter(branch_futures).buffer_unordered(max_concurrency);
+
+        let mut outcomes: Vec<Option<Result<Registers, WorkflowError>>> = (0..num_branches).map(|_| None).collect();
+        while let Some((index, result)) = results.next().await {
+            if let Err(e) = result {
+                if !options.collect_errors {
+                    return Err(e);
+                }
+                outcomes[index] = Some(Err(e));
+            } else {
+                outcomes[index] = Some(result);
+            }
+        }
+
+        let snapshot = registers.clone();
+        let mut errors = Vec::new();
+        for (index, outcome) in outcomes.into_iter().enumerate() {
+            match outcome {
+                Some(Ok(branch_registers)) => merge_branch_registers(registers, &snapshot, branch_registers),
+                Some(Err(e)) => errors.push(format!("branch {}: {}", index, e)),
+                None => {}
+            }
+        }
+        if errors.is_empty() {
+            Ok(StepFlow::Next)
+        } else {
+            Err(WorkflowError::ExecutionError(format!(
+                "{} of {} parallel branches failed in step {}: {}",
+                errors.len(),
+                num_branches,
+                step_name,
+                errors.join("; ")
+            )))
+        }
+    }
+
     pub async fn execute_action(
         &self,
         action: &Action,
-        registers: &DashMap<String, String>,
+        registers: &Registers,
     ) -> Result<(), WorkflowError> {
         println!("Executing action: {:?}", action);
         match action {
-            Action::ExternalFnCall(FunctionCall { name, args }) => {
+            Action::ExternalFnCall(FunctionCall { name, args, retry }) => {
                 println!("Function call: {}", name);
                 if let Some(func) = self.functions.get(name) {
                     let arg_values =
@@ -186,7 +408,7 @@ impl<'a> WorkflowEngine<'a> {
                         match arg {
                             Ok(value) => {
                                 println!("Argument {}: {:?}", i, value);
-                                resolved_args.push(Box::new(value) as Box<dyn Any + Send>);
+                                resolved_args.push(value);
                             }
                             Err(e) => {
                                 println!("Failed to evaluate argument {}: {:?}", i, e);
@@ -197,19 +419,15 @@ impl<'a> WorkflowEngine<'a> {
 
                     // Log the resolved arguments before calling the function
                     for (i, arg) in resolved_args.iter().enumerate() {
-                        if let Some(value) = arg.downcast_ref::<String>() {
-                            println!("Resolved Argument {}: {:?}", i, value);
-                        } else {
-                            println!("Resolved Argument {}: Failed to downcast", i);
-                        }
+                        println!("Resolved Argument {}: {:?}", i, to_register_string(arg));
                     }
 
                     eprintln!("Resolved args: {:?}", resolved_args);
-                    let result = func.call(resolved_args).await?;
-                    if let Some(result) = result.downcast_ref::<String>() {
+                    let result = self.call_function(name, func.as_ref(), &resolved_args, retry.as_ref()).await?;
+                    if let Some(result) = function_output(result) {
                         if let Some(Param::Identifier(register_name)) = args.first() {
                             println!("Storing result in register {}: {:?}", register_name, result);
-                            registers.insert(register_name.clone(), result.clone());
+                            registers.insert(register_name.clone(), result);
                         }
                     } else {
                         return Err(WorkflowError::FunctionError("Failed to downcast result".to_string()));
@@ -223,71 +441,153 @@ impl<'a> WorkflowEngine<'a> {
         }
     }
 
-    pub async fn evaluate_condition(
+    /// Calls a function with the given arguments. Failed calls are retried as set by the retry policy,
+    /// doubling the delay before each retry.
+    async fn call_function(
         &self,
-        expression: &Expression,
-        registers: &DashMap<String, String>,
-    ) -> Result<bool, WorkflowError> {
-        match expression {
-            Expression::Binary { left, operator, right } => {
-                let left_val = self.evaluate_param(left, registers).await?.parse::<i32>().unwrap_or(0);
-                let right_val = self.evaluate_param(right, registers).await?.parse::<i32>().unwrap_or(0);
-                let result = match operator {
-                    ComparisonOperator::Less => left_val < right_val,
-                    ComparisonOperator::Greater => left_val > right_val,
-                    ComparisonOperator::Equal => left_val == right_val,
-                    ComparisonOperator::NotEqual => left_val != right_val,
-                    ComparisonOperator::LessEqual => left_val <= right_val,
-                    ComparisonOperator::GreaterEqual => left_val >= right_val,
-                };
-                Ok(result)
+        name: &str,
+        func: &(dyn AsyncFunction + 'a),
+        args: &[Value],
+        retry: Option<&RetryPolicy>,
+    ) -> Result<Box<dyn Any + Send>, WorkflowError> {
+        let (retries, backoff_ms) = retry.map_or((0, 0), |retry| {
+            (
+                retry.retries.min(MAX_CALL_RETRIES),
+                retry.backoff_ms.min(MAX_RETRY_BACKOFF_MS),
+            )
+        });
+        let mut attempt = 0;
+        loop {
+            match func.call(args.iter().map(function_arg).collect()).await {
+                Err(e) if attempt < retries => {
+                    let delay = backoff_ms
+                        .saturating_mul(1 << attempt.min(16))
+                        .min(MAX_RETRY_BACKOFF_MS);
+                    shinkai_log(
+                        ShinkaiLogOption::JobExecution,
+                        ShinkaiLogLevel::Info,
+                        &format!(
+                            "Call to {} failed ({}), retry {} of {} in {}ms",
+                            name,
+                            e,
+                            attempt + 1,
+                            retries,
+                            delay
+                        ),
+                    );
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    attempt += 1;
+                }
+                result => return result,
             }
-            _ => Err(WorkflowError::EvaluationError(
-                "Unsupported expression type".to_string(),
-            )),
         }
     }
 
-    async fn evaluate_param(
-        &self,
-        param: &Param,
-        registers: &DashMap<String, String>,
-    ) -> Result<String, WorkflowError> {
-        eprintln!("\n\nEvaluating param: {:?}", param);
-        eprintln!("Registers: {:?}", registers);
-        let value = match param {
-            Param::String(s) => s.clone(),
-            Param::Number(n) => n.to_string(),
-            Param::Boolean(b) => b.to_string(),
-            Param::Identifier(id) => registers.get(id).map(|v| v.clone()).unwrap_or_default(),
-            Param::Register(reg) => registers.get(reg).map(|v| v.clone()).unwrap_or_default(),
-            Param::Range(start, end) => format!("{}..{}", start, end),
-        };
-        Ok(value)
+    pub fn evaluate_condition<'b>(
+        &'b self,
+        expression: &'b Expression,
+        registers: &'b Registers,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, WorkflowError>> + Send + 'b>> {
+        Box::pin(async move {
+            match expression {
+                Expression::Binary { left, operator, right } => {
+                    let left_val = self.evaluate_param(left, registers).await?;
+                    let right_val = self.evaluate_param(right, registers).await?;
+                    Ok(compare(&left_val, operator, &right_val))
+                }
+                Expression::Simple(param) => Ok(is_truthy(&self.evaluate_param(param, registers).await?)),
+                Expression::And { left, right } => {
+                    Ok(self.evaluate_condition(left, registers).await?
+                        && self.evaluate_condition(right, registers).await?)
+                }
+                Expression::Or { left, right } => {
+                    Ok(self.evaluate_condition(left, registers).await?
+                        || self.evaluate_condition(right, registers).await?)
+                }
+                Expression::Not(inner) => Ok(!self.evaluate_condition(inner, registers).await?),
+                _ => Err(WorkflowError::EvaluationError(
+                    "Unsupported expression type".to_string(),
+                )),
+            }
+        })
+    }
+
+    fn evaluate_param<'b>(
+        &'b self,
+        param: &'b Param,
+        registers: &'b Registers,
+    ) -> Pin<Box<dyn Future<Output = Result<Value, WorkflowError>> + Send + 'b>> {
+        Box::pin(async move {
+            eprintln!("\n\nEvaluating param: {:?}", param);
+            eprintln!("Registers: {:?}", registers);
+            let value = match param {
+                Param::String(s) => Value::from(s.clone()),
+                Param::Number(n) => Value::from(*n),
+                Param::Float(f) => Value::from(*f),
+                Param::Boolean(b) => Value::from(*b),
+                Param::Identifier(id) => registers.get(id).map(|v| v.clone()).unwrap_or_default(),
+                Param::Register(reg) => registers.get(reg).map(|v| v.clone()).unwrap_or_default(),
+                Param::Range(start, end) => Value::from(format!("{}..{}", start, end)),
+                Param::List(items) => {
+                    let mut values = Vec::new();
+                    for item in items {
+                        values.push(self.evaluate_param(item, registers).await?);
+                    }
+                    Value::Array(values)
+                }
+                Param::Map(entries) => {
+                    let mut map = serde_json::Map::new();
+                    for (key, item) in entries {
+                        map.insert(key.clone(), self.evaluate_param(item, registers).await?);
+                    }
+                    Value::Object(map)
+                }
+                Param::Access { base, path } => {
+                    let mut value = self.evaluate_param(base, registers).await?;
+                    for accessor in path {
+                        value = match accessor {
+                            Accessor::Field(field) => access_field(&value, field),
+                            Accessor::Index(index) => {
+                                access_index(&value, &self.evaluate_param(index, registers).await?)
+                            }
+                        }
+                        .map_err(WorkflowError::EvaluationError)?;
+                    }
+                    value
+                }
+                Param::Operation { left, operator, right } => {
+                    let left_val = self.evaluate_param(left, registers).await?;
+                    let right_val = self.evaluate_param(right, registers).await?;
+                    apply_operator(&left_val, operator, &right_val).map_err(WorkflowError::EvaluationError)?
+                }
+            };
+            Ok(value)
+        })
     }
 
     pub async fn evaluate_workflow_value(
         &self,
         value: &WorkflowValue,
-        registers: &DashMap<String, String>,
-    ) -> Result<String, WorkflowError> {
+        registers: &Registers,
+    ) -> Result<Value, WorkflowError> {
         eprintln!("Evaluating workflow value: {:?}", value);
         match value {
-            WorkflowValue::String(s) => Ok(s.clone()),
-            WorkflowValue::Number(n) => Ok(n.to_string()),
-            WorkflowValue::Boolean(b) => Ok(b.to_string()),
+            WorkflowValue::String(s) => Ok(Value::from(s.clone())),
+            WorkflowValue::Number(n) => Ok(Value::from(*n)),
+            WorkflowValue::Boolean(b) => Ok(Value::from(*b)),
             WorkflowValue::Identifier(id) | WorkflowValue::Register(id) => registers
                 .get(id)
                 .map(|v| Ok(v.value().clone()))
                 .unwrap_or_else(|| Err(WorkflowError::InvalidArgument(format!("Identifier {} not found", id)))),
-            WorkflowValue::FunctionCall(FunctionCall { name, args }) => {
+            WorkflowValue::Param(param) => self.evaluate_param(param, registers).await,
+            WorkflowValue::FunctionCall(FunctionCall { name, args, retry }) => {
                 if let Some(func) = self.functions.get(name) {
                     let mut arg_values = Vec::new();
                     for arg in args {
                         let evaluated_arg = self.evaluate_param(arg, registers).await;
                         // eprintln!("Evaluated arg: {:?}", evaluated_arg);
                         match evaluated_arg {
-                            Ok(value) => arg_values.push(Box::new(value) as Box<dyn Any + Send>),
+                            Ok(value) => arg_values.push(value),
                             Err(e) => {
                                 eprintln!("Error evaluating argument: {}", e);
                                 return Err(e);
@@ -295,15 +595,15 @@ impl<'a> WorkflowEngine<'a> {
                         }
                     }
 
-                    let result = func.call(arg_values).await;
+                    let result = self.call_function(name, func.as_ref(), &arg_values, retry.as_ref()).await;
                     match result {
                         Ok(result) => {
-                            if let Ok(result) = result.downcast::<String>() {
-                                Ok((*result).clone())
+                            if let Some(result) = function_output(result) {
+                                Ok(result)
                             } else {
-                                eprintln!("Function call to '{}' did not return a String.", name);
+                                eprintln!("Function call to '{}' did not return a String or a JSON value.", name);
                                 Err(WorkflowError::FunctionError(format!(
-                                    "Function call to '{}' did not return a String",
+                                    "Function call to '{}' did not return a String or a JSON value",
                                     name
                                 )))
                             }
@@ -327,7 +627,7 @@ impl<'a> WorkflowEngine<'a> {
     pub fn iter(
         &'a self,
         workflow: &'a Workflow,
-        initial_registers: Option<DashMap<String, String>>,
+        initial_registers: Option<Registers>,
         logs: Option<DashMap<String, Vec<String>>>,
     ) -> StepExecutor<'a> {
         StepExecutor {
@@ -371,8 +671,32 @@ impl<'a> WorkflowEngine<'a> {
     }
 }
 
+/// Applies the writes of a parallel branch, called in branch order so the result doesn't depend on which branch
+/// finished first. Items a branch appended to a list that existed before the block (e.g. `$ALL = $ALL + [$R1]`)
+/// are appended after those of the previous branches. Any other write replaces the value.
+fn merge_branch_registers(registers: &Registers, snapshot: &Registers, branch_registers: Registers) {
+    for (register, value) in branch_registers {
+        let before = snapshot.get(&register).map(|v| v.clone());
+        if before.as_ref() == Some(&value) {
+            continue;
+        }
+        match (before, value) {
+            (Some(Value::Array(old_items)), Value::Array(new_items)) if new_items.starts_with(&old_items) => {
+                let mut merged = registers.entry(register).or_insert_with(|| Value::Array(Vec::new()));
+                match merged.value_mut() {
+                    Value::Array(items) => items.extend(new_items.into_iter().skip(old_items.len())),
+                    other => *other = Value::Array(new_items),
+                }
+            }
+            (_, value) => {
+                registers.insert(register, value);
+            }
+        }
+    }
+}
+
 impl<'a> Iterator for StepExecutor<'a> {
-    type Item = Result<DashMap<String, String>, WorkflowError>;
+    type Item = Result<Registers, WorkflowError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.current_step < self.workflow.steps.len() {
@@ -380,27 +704,31 @@ impl<'a> Iterator for StepExecutor<'a> {
             let step_name = step.name.clone();
             eprintln!("Executing step: {:?}", step);
             let mut result = Ok(self.registers.clone());
+            let mut returned = false;
 
             task::block_in_place(|| {
                 let rt = Runtime::new().unwrap();
                 rt.block_on(async {
-                    for body in step.body.iter() {
-                        if let Err(e) = self
-                            .engine
-                            .execute_step_body(&step_name, body, &self.registers, &self.logs)
-                            .await
-                        {
-                            result = Err(e);
-                            break;
+                    match self
+                        .engine
+                        .execute_step(&step_name, &step.body, &self.registers, &self.logs)
+                        .await
+                    {
+                        Ok(flow) => {
+                            returned = flow == StepFlow::Return;
+                            result = Ok(self.registers.clone());
                         }
-                    }
-                    if result.is_ok() {
-                        result = Ok(self.registers.clone());
+                        Err(e) => result = Err(e),
                     }
                 });
             });
 
-            self.current_step += 1;
+            // A return skips the remaining steps
+            self.current_step = if returned {
+                self.workflow.steps.len()
+            } else {
+                self.current_step + 1
+            };
             Some(result)
         } else {
             None
@@ -2,9 +2,11 @@
 mod tests {
     use std::any::Any;
     use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     use async_trait::async_trait;
     use dashmap::DashMap;
+    use serde_json::json;
     use shinkai_dsl::{
         dsl_schemas::{
             Action, ComparisonOperator, Expression, ForLoopExpression, FunctionCall, Param, StepBody, WorkflowValue,
@@ -14,7 +16,7 @@ mod tests {
 
     use tokio::time::{sleep, Duration};
 
-    use crate::workflows::sm_executor::{AsyncFunction, FunctionMap, WorkflowEngine, WorkflowError};
+    use crate::workflows::sm_executor::{AsyncFunction, FunctionMap, WorkflowEngine, WorkflowError, MAX_CALL_RETRIES};
     struct SumFunction;
 
     #[async_trait]
@@ -201,9 +203,9 @@ mod tests {
         eprintln!("Registers: {:?}", registers);
 
         // Check the results
-        assert_eq!(registers.get("$R1").unwrap().as_str(), "5");
-        assert_eq!(registers.get("$R2").unwrap().as_str(), "10");
-        assert_eq!(registers.get("$R3").unwrap().as_str(), "20");
+        assert_eq!(*registers.get("$R1").unwrap(), json!(5));
+        assert_eq!(*registers.get("$R2").unwrap(), json!(10));
+        assert_eq!(*registers.get("$R3").unwrap(), json!("20"));
     }
 
     #[tokio::test]
@@ -216,8 +218,8 @@ mod tests {
 
         let executor = WorkflowEngine::new(&functions);
         let registers = DashMap::new();
-        registers.insert("$R1".to_string(), "5".to_string());
-        registers.insert("$R2".to_string(), "10".to_string());
+        registers.insert("$R1".to_string(), json!("5"));
+        registers.insert("$R2".to_string(), json!("10"));
 
         let action = Action::ExternalFnCall(FunctionCall {
             name: "multiply".to_string(),
@@ -225,6 +227,7 @@ mod tests {
                 Param::Identifier("$R1".to_string()),
                 Param::Identifier("$R2".to_string()),
             ],
+            retry: None,
         });
 
         executor
@@ -232,7 +235,7 @@ mod tests {
             .await
             .expect("Failed to execute action");
 
-        assert_eq!(*registers.get("$R1").unwrap(), "50"); // Assuming the result is stored back in "$R1"
+        assert_eq!(*registers.get("$R1").unwrap(), json!("50")); // Assuming the result is stored back in "$R1"
     }
 
     #[tokio::test]
@@ -240,8 +243,8 @@ mod tests {
         let functions = HashMap::new();
         let executor = WorkflowEngine::new(&functions);
         let registers = DashMap::new();
-        registers.insert("$R1".to_string(), "5".to_string());
-        registers.insert("$R2".to_string(), "10".to_string());
+        registers.insert("$R1".to_string(), json!("5"));
+        registers.insert("$R2".to_string(), json!("10"));
 
         let condition = Expression::Binary {
             left: Box::new(Param::Identifier("$R1".to_string())),
@@ -255,6 +258,337 @@ mod tests {
             .expect("Failed to evaluate condition"));
     }
 
+    #[tokio::test]
+    async fn test_execute_else_if_branches() {
+        let dsl_input = r#"
+        workflow Grading v0.1 {
+            step Initialize {
+                $SCORE = 72
+                $PASSED = "true"
+            }
+            step Grade {
+                if $SCORE >= 90 && $PASSED {
+                    $GRADE = "A"
+                } else if ($SCORE >= 70 && $SCORE < 90) || !$PASSED {
+                    $GRADE = "B"
+                } else {
+                    $GRADE = "C"
+                }
+            }
+        }
+        "#;
+        let workflow = parse_workflow(dsl_input).expect("Failed to parse workflow");
+
+        let functions = HashMap::new();
+        let executor = WorkflowEngine::new(&functions);
+        let registers = executor
+            .execute_workflow(&workflow)
+            .await
+            .expect("Failed to execute workflow");
+
+        assert_eq!(*registers.get("$GRADE").unwrap(), json!("B"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_while_loop_with_break_and_return() {
+        let dsl_input = r#"
+        workflow Retry v0.1 {
+            step Initialize {
+                $ATTEMPTS = 0
+                $SKIPPED = 0
+            }
+            step Loop {
+                while $ATTEMPTS < 10 {
+                    $ATTEMPTS = call sum($ATTEMPTS, 1)
+                    if $ATTEMPTS == 2 {
+                        continue
+                    }
+                    if $ATTEMPTS == 4 {
+                        break
+                    }
+                    $SKIPPED = call sum($SKIPPED, 1)
+                }
+                return $ATTEMPTS
+            }
+            step Unreachable {
+                $RESULT = "not returned"
+            }
+        }
+        "#;
+        let workflow = parse_workflow(dsl_input).expect("Failed to parse workflow");
+
+        let mut functions = HashMap::new();
+        functions.insert("sum".to_string(), Box::new(SumFunction) as Box<dyn AsyncFunction>);
+        let executor = WorkflowEngine::new(&functions);
+        let registers = executor
+            .execute_workflow(&workflow)
+            .await
+            .expect("Failed to execute workflow");
+
+        assert_eq!(*registers.get("$ATTEMPTS").unwrap(), json!("4"));
+        assert_eq!(*registers.get("$SKIPPED").unwrap(), json!("2"));
+        assert_eq!(*registers.get("$RESULT").unwrap(), json!("4"));
+    }
+
+    #[tokio::test]
+    async fn test_while_loop_iteration_cap() {
+        let dsl_input = r#"
+        workflow Endless v0.1 {
+            step Loop {
+                while true {
+                    $R1 = "again"
+                }
+            }
+        }
+        "#;
+        let workflow = parse_workflow(dsl_input).expect("Failed to parse workflow");
+
+        let functions = HashMap::new();
+        let executor = WorkflowEngine::new(&functions);
+        let result = executor.execute_workflow(&workflow).await;
+
+        assert!(matches!(result, Err(WorkflowError::ExecutionError(_))));
+    }
+
+    struct SearchFunction;
+
+    #[async_trait]
+    impl AsyncFunction for SearchFunction {
+        async fn call(&self, _args: Vec<Box<dyn Any + Send>>) -> Result<Box<dyn Any + Send>, WorkflowError> {
+            Ok(Box::new(
+                r#"{"items": [{"name": "Lima", "population": 9.7}, {"name": "Cusco", "population": 0.4}]}"#
+                    .to_string(),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_structured_registers() {
+        let dsl_input = r#"
+        workflow Cities v0.1 {
+            step Search {
+                $R1 = call search("cities of Peru")
+                $FIRST = $R1.items[0]
+                $TOTAL = ($FIRST.population + $R1.items[1].population) * 10
+                $NAMES = [$FIRST.name, $R1.items[1]["name"]]
+                $LABEL = call concat("Biggest: ", $FIRST.name)
+                $SUMMARY = { "label": $LABEL, "count": 1 + 1 }
+            }
+        }
+        "#;
+        let workflow = parse_workflow(dsl_input).expect("Failed to parse workflow");
+
+        let mut functions: FunctionMap = HashMap::new();
+        functions.insert("search".to_string(), Box::new(SearchFunction) as Box<dyn AsyncFunction>);
+        functions.insert("concat".to_string(), Box::new(ConcatFunction) as Box<dyn AsyncFunction>);
+        let executor = WorkflowEngine::new(&functions);
+        let registers = executor
+            .execute_workflow(&workflow)
+            .await
+            .expect("Failed to execute workflow");
+
+        assert_eq!(*registers.get("$FIRST").unwrap(), json!({"name": "Lima", "population": 9.7}));
+        assert_eq!(*registers.get("$TOTAL").unwrap(), json!(101.0));
+        assert_eq!(*registers.get("$NAMES").unwrap(), json!(["Lima", "Cusco"]));
+        // Functions taking strings still get them
+        assert_eq!(*registers.get("$LABEL").unwrap(), json!("Biggest: Lima"));
+        assert_eq!(
+            *registers.get("$SUMMARY").unwrap(),
+            json!({"label": "Biggest: Lima", "count": 2})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parallel_execution() {
+        let dsl_input = r#"
+        workflow FanOut v0.1 {
+            step Initialize {
+                $CHUNKS = "a:::b:::c:::d"
+                $ALL = []
+            }
+            step Summarize {
+                parallel for chunk in $CHUNKS.split(":::") {
+                    $R1 = call clone(chunk)
+                    $ALL = $ALL + [$R1]
+                }
+            }
+            step Limited {
+                parallel(max: 2) {
+                    $A = call clone("first")
+                    $B = call clone("second")
+                    $C = call clone("third")
+                }
+            }
+        }
+        "#;
+        let workflow = parse_workflow(dsl_input).expect("Failed to parse workflow");
+
+        let mut functions: FunctionMap = HashMap::new();
+        functions.insert("clone".to_string(), Box::new(CloneWithDelayFunction) as Box<dyn AsyncFunction>);
+        let executor = WorkflowEngine::new(&functions);
+
+        let start = std::time::Instant::now();
+        let registers = executor
+            .execute_workflow(&workflow)
+            .await
+            .expect("Failed to execute workflow");
+        let elapsed = start.elapsed();
+
+        // 4 branches at once, then 3 branches 2 at a time, of 100ms each
+        assert!(elapsed >= Duration::from_millis(300), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(600), "{:?}", elapsed);
+        // Writes are merged in branch order, whichever finished first
+        assert_eq!(*registers.get("$ALL").unwrap(), json!(["a", "b", "c", "d"]));
+        assert_eq!(*registers.get("$R1").unwrap(), json!("d"));
+        assert_eq!(*registers.get("$C").unwrap(), json!("third"));
+    }
+
+    #[tokio::test]
+    async fn test_parallel_errors() {
+        let collect_input = r#"
+        workflow Collect v0.1 {
+            step Run {
+                parallel(on_error: collect) {
+                    $A = call missing()
+                    $B = call clone("kept")
+                }
+            }
+        }
+        "#;
+        let mut functions: FunctionMap = HashMap::new();
+        functions.insert("clone".to_string(), Box::new(CloneWithDelayFunction) as Box<dyn AsyncFunction>);
+        let executor = WorkflowEngine::new(&functions);
+
+        let workflow = parse_workflow(collect_input).expect("Failed to parse workflow");
+        let registers = DashMap::new();
+        let logs = DashMap::new();
+        let result = executor
+            .execute_step("Run", &workflow.steps[0].body, &registers, &logs)
+            .await;
+        match result {
+            Err(WorkflowError::ExecutionError(message)) => assert!(message.contains("1 of 2 parallel branches failed")),
+            _ => panic!("Expected the collected errors"),
+        }
+        assert_eq!(*registers.get("$B").unwrap(), json!("kept"));
+
+        let fail_fast_input = collect_input.replace("(on_error: collect)", "");
+        let workflow = parse_workflow(&fail_fast_input).expect("Failed to parse workflow");
+        let registers = DashMap::new();
+        let result = executor
+            .execute_step("Run", &workflow.steps[0].body, &registers, &logs)
+            .await;
+        assert!(matches!(result, Err(WorkflowError::FunctionError(_))));
+        assert!(registers.get("$B").is_none());
+    }
+
+    /// Fails the first calls, like a tool hitting a flaky network
+    struct FlakyFunction {
+        failures: usize,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl AsyncFunction for FlakyFunction {
+        async fn call(&self, _args: Vec<Box<dyn Any + Send>>) -> Result<Box<dyn Any + Send>, WorkflowError> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if call <= self.failures {
+                Err(WorkflowError::FunctionError(format!("Connection reset on call {}", call)))
+            } else {
+                Ok(Box::new("downloaded".to_string()))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_try_catch_and_retry() {
+        let dsl_input = r#"
+        workflow Recover v0.1 {
+            step Download {
+                $PAGE = call flaky("https://shinkai.com") retry(2, 50)
+            }
+            step GiveUp {
+                try {
+                    $OTHER = call unstable("https://shinkai.com") retry(1)
+                    $OTHER = "not reached"
+                } catch $ERR {
+                    $OTHER = "fallback"
+                }
+            }
+            step Divide {
+                try {
+                    call divide("1", "0")
+                } catch {
+                    $DIVIDED = false
+                }
+            }
+        }
+        "#;
+        let workflow = parse_workflow(dsl_input).expect("Failed to parse workflow");
+
+        let mut functions: FunctionMap = HashMap::new();
+        functions.insert(
+            "flaky".to_string(),
+            Box::new(FlakyFunction {
+                failures: 2,
+                calls: AtomicUsize::new(0),
+            }) as Box<dyn AsyncFunction>,
+        );
+        functions.insert(
+            "unstable".to_string(),
+            Box::new(FlakyFunction {
+                failures: 5,
+                calls: AtomicUsize::new(0),
+            }) as Box<dyn AsyncFunction>,
+        );
+        functions.insert("divide".to_string(), Box::new(DivideFunction) as Box<dyn AsyncFunction>);
+        let executor = WorkflowEngine::new(&functions);
+
+        let start = std::time::Instant::now();
+        let registers = executor
+            .execute_workflow(&workflow)
+            .await
+            .expect("Failed to execute workflow");
+
+        // Two failed calls, retried after 50ms then 100ms
+        assert!(start.elapsed() >= Duration::from_millis(150), "{:?}", start.elapsed());
+        assert_eq!(*registers.get("$PAGE").unwrap(), json!("downloaded"));
+        assert_eq!(*registers.get("$OTHER").unwrap(), json!("fallback"));
+        let error = registers.get("$ERR").unwrap().value().clone();
+        assert!(error.as_str().unwrap().contains("Connection reset on call 2"), "{}", error);
+        assert_eq!(*registers.get("$DIVIDED").unwrap(), json!(false));
+
+        // Without a try, the error still ends the workflow
+        let workflow = parse_workflow(
+            r#"workflow Fail v0.1 { step Divide { call divide("1", "0") retry(1) } }"#,
+        )
+        .expect("Failed to parse workflow");
+        let result = executor.execute_workflow(&workflow).await;
+        assert!(matches!(result, Err(WorkflowError::FunctionError(message)) if message.contains("Division by zero")));
+    }
+
+    #[tokio::test]
+    async fn test_retry_clamped() {
+        let workflow = parse_workflow(
+            r#"workflow Clamp v0.1 { step Download { call unstable("https://shinkai.com") retry(4294967295) } }"#,
+        )
+        .expect("Failed to parse workflow");
+
+        let mut functions: FunctionMap = HashMap::new();
+        functions.insert(
+            "unstable".to_string(),
+            Box::new(FlakyFunction {
+                failures: usize::MAX,
+                calls: AtomicUsize::new(0),
+            }) as Box<dyn AsyncFunction>,
+        );
+        let executor = WorkflowEngine::new(&functions);
+
+        // The call is retried MAX_CALL_RETRIES times, then its last error ends the workflow
+        let result = executor.execute_workflow(&workflow).await;
+        let last_call = format!("Connection reset on call {}", MAX_CALL_RETRIES + 1);
+        assert!(matches!(result, Err(WorkflowError::FunctionError(message)) if message.contains(&last_call)));
+    }
+
     #[tokio::test]
     async fn test_for_loop_execution() {
         let functions = HashMap::new();
@@ -281,7 +615,7 @@ mod tests {
             .await
             .expect("Failed to execute for loop");
 
-        assert_eq!(registers.get("$Last").unwrap().as_str(), "3"); // Assuming $Sum accumulates values of "$i"
+        assert_eq!(*registers.get("$Last").unwrap(), json!(3)); // Assuming $Sum accumulates values of "$i"
     }
 
     #[tokio::test]
@@ -310,7 +644,7 @@ mod tests {
             .await
             .expect("Failed to execute for loop");
 
-        assert_eq!(registers.get("$Last").unwrap().as_str(), "3"); // Assuming $Sum accumulates values of "$item"
+        assert_eq!(*registers.get("$Last").unwrap(), json!("3")); // Split parts are strings
     }
 
     #[test]
@@ -358,39 +692,39 @@ mod tests {
                 println!("Iteration {}: {:?}", i, registers);
                 match i {
                     0 => {
-                        assert_eq!(registers.get("$R1").unwrap().as_str(), "5");
-                        assert_eq!(registers.get("$R2").unwrap().as_str(), "10");
-                        assert_eq!(registers.get("$R3").unwrap().as_str(), "0");
-                        assert_eq!(registers.get("$R4").unwrap().as_str(), "20");
+                        assert_eq!(*registers.get("$R1").unwrap(), json!(5));
+                        assert_eq!(*registers.get("$R2").unwrap(), json!(10));
+                        assert_eq!(*registers.get("$R3").unwrap(), json!(0));
+                        assert_eq!(*registers.get("$R4").unwrap(), json!(20));
                     }
                     1 => {
-                        assert_eq!(registers.get("$R1").unwrap().as_str(), "5");
-                        assert_eq!(registers.get("$R2").unwrap().as_str(), "10");
-                        assert_eq!(registers.get("$R3").unwrap().as_str(), "15"); // 10 + 5
-                        assert_eq!(registers.get("$R4").unwrap().as_str(), "20");
+                        assert_eq!(*registers.get("$R1").unwrap(), json!(5));
+                        assert_eq!(*registers.get("$R2").unwrap(), json!(10));
+                        assert_eq!(*registers.get("$R3").unwrap(), json!("15")); // 10 + 5
+                        assert_eq!(*registers.get("$R4").unwrap(), json!(20));
                     }
                     2 => {
-                        assert_eq!(registers.get("$R1").unwrap().as_str(), "5");
-                        assert_eq!(registers.get("$R2").unwrap().as_str(), "10");
-                        assert_eq!(registers.get("$R3").unwrap().as_str(), "15");
-                        assert_eq!(registers.get("$R4").unwrap().as_str(), "4");
+                        assert_eq!(*registers.get("$R1").unwrap(), json!(5));
+                        assert_eq!(*registers.get("$R2").unwrap(), json!(10));
+                        assert_eq!(*registers.get("$R3").unwrap(), json!("15"));
+                        assert_eq!(*registers.get("$R4").unwrap(), json!("4"));
                         // 20 / 5
                     }
                     3 => {
-                        assert_eq!(registers.get("$R1").unwrap().as_str(), "5");
-                        assert_eq!(registers.get("$R2").unwrap().as_str(), "10");
-                        assert_eq!(registers.get("$R3").unwrap().as_str(), "20"); // 15 + 5
-                        assert_eq!(registers.get("$R4").unwrap().as_str(), "4");
+                        assert_eq!(*registers.get("$R1").unwrap(), json!(5));
+                        assert_eq!(*registers.get("$R2").unwrap(), json!(10));
+                        assert_eq!(*registers.get("$R3").unwrap(), json!("20")); // 15 + 5
+                        assert_eq!(*registers.get("$R4").unwrap(), json!("4"));
                     }
                     _ => panic!("Unexpected iteration"),
                 }
             }
             // Check the final results
             let final_registers = step_executor.registers;
-            assert_eq!(final_registers.get("$R1").unwrap().as_str(), "5");
-            assert_eq!(final_registers.get("$R2").unwrap().as_str(), "10");
-            assert_eq!(final_registers.get("$R3").unwrap().as_str(), "20");
-            assert_eq!(final_registers.get("$R4").unwrap().as_str(), "4"); // 20 / 5 = 4
+            assert_eq!(*final_registers.get("$R1").unwrap(), json!(5));
+            assert_eq!(*final_registers.get("$R2").unwrap(), json!(10));
+            assert_eq!(*final_registers.get("$R3").unwrap(), json!("20"));
+            assert_eq!(*final_registers.get("$R4").unwrap(), json!("4")); // 20 / 5 = 4
         });
     }
 
@@ -401,8 +735,8 @@ mod tests {
 
         let executor = WorkflowEngine::new(&functions);
         let registers = DashMap::new();
-        registers.insert("$S1".to_string(), "Hello".to_string());
-        registers.insert("$S2".to_string(), "World".to_string());
+        registers.insert("$S1".to_string(), json!("Hello"));
+        registers.insert("$S2".to_string(), json!("World"));
 
         let action = Action::ExternalFnCall(FunctionCall {
             name: "concat".to_string(),
@@ -410,6 +744,7 @@ mod tests {
                 Param::Identifier("$S1".to_string()),
                 Param::Identifier("$S2".to_string()),
             ],
+            retry: None,
         });
 
         executor
@@ -417,7 +752,7 @@ mod tests {
             .await
             .expect("Failed to execute action");
 
-        assert_eq!(registers.get("$S1").unwrap().as_str(), "HelloWorld"); // Assuming the result is stored back in "$S1"
+        assert_eq!(*registers.get("$S1").unwrap(), json!("HelloWorld")); // Assuming the result is stored back in "$S1"
     }
 
     #[tokio::test]
@@ -454,12 +789,12 @@ mod tests {
 
         // Check the results
         assert_eq!(
-            registers.get("$R1").unwrap().as_str(),
-            "Inference result for: Tell me about the Economy of the Roman Empire"
+            *registers.get("$R1").unwrap(),
+            json!("Inference result for: Tell me about the Economy of the Roman Empire")
         );
         assert_eq!(
-            registers.get("$R2").unwrap().as_str(),
-            "Tell me about the Economy of the Roman Empire"
+            *registers.get("$R2").unwrap(),
+            json!("Tell me about the Economy of the Roman Empire")
         );
     }
 
@@ -496,8 +831,8 @@ mod tests {
             .expect("Failed to execute workflow");
 
         // Check the results
-        assert_eq!(registers.get("$R1").unwrap().as_str(), "Clone this string");
-        assert_eq!(registers.get("$R2").unwrap().as_str(), "Clone this string");
+        assert_eq!(*registers.get("$R1").unwrap(), json!("Clone this string"));
+        assert_eq!(*registers.get("$R2").unwrap(), json!("Clone this string"));
     }
 
     #[tokio::test]
@@ -533,8 +868,8 @@ mod tests {
             .expect("Failed to execute workflow");
 
         // Check the results
-        assert_eq!(registers.get("$R1").unwrap().as_str(), "red,blue,green");
-        assert_eq!(registers.get("$R2").unwrap().as_str(), "redbluegreen");
+        assert_eq!(*registers.get("$R1").unwrap(), json!("red,blue,green"));
+        assert_eq!(*registers.get("$R2").unwrap(), json!("redbluegreen"));
     }
 
     #[tokio::test]
@@ -563,8 +898,8 @@ mod tests {
             .expect("Failed to execute workflow");
 
         // Check the results
-        assert_eq!(registers.get("$R1").unwrap().as_str(), "red,blue,green");
-        assert_eq!(registers.get("$R2").unwrap().as_str(), "red,blue,green");
+        assert_eq!(*registers.get("$R1").unwrap(), json!("red,blue,green"));
+        assert_eq!(*registers.get("$R2").unwrap(), json!("red,blue,green"));
     }
 
     #[test]
@@ -597,7 +932,7 @@ mod tests {
 
             // Create initial registers with $R0 value
             let registers = DashMap::new();
-            registers.insert("$R0".to_string(), "hello".to_string());
+            registers.insert("$R0".to_string(), json!("hello"));
 
             // Create the StepExecutor iterator
             let mut step_executor = engine.iter(&workflow, Some(registers), None);
@@ -608,20 +943,20 @@ mod tests {
                 println!("Iteration {}: {:?}", i, registers);
                 match i {
                     0 => {
-                        assert_eq!(registers.get("$R1").unwrap().as_str(), "red,blue,green");
-                        assert_eq!(registers.get("$R2").unwrap().as_str(), "");
+                        assert_eq!(*registers.get("$R1").unwrap(), json!("red,blue,green"));
+                        assert_eq!(*registers.get("$R2").unwrap(), json!(""));
                     }
                     1 => {
-                        assert_eq!(registers.get("$R1").unwrap().as_str(), "redbluegreenhello");
-                        assert_eq!(registers.get("$R2").unwrap().as_str(), "redbluegreen");
+                        assert_eq!(*registers.get("$R1").unwrap(), json!("redbluegreenhello"));
+                        assert_eq!(*registers.get("$R2").unwrap(), json!("redbluegreen"));
                     }
                     _ => panic!("Unexpected iteration"),
                 }
             }
             // Check the final results
             let final_registers = step_executor.registers;
-            assert_eq!(final_registers.get("$R1").unwrap().as_str(), "redbluegreenhello");
-            assert_eq!(final_registers.get("$R2").unwrap().as_str(), "redbluegreen");
+            assert_eq!(*final_registers.get("$R1").unwrap(), json!("redbluegreenhello"));
+            assert_eq!(*final_registers.get("$R2").unwrap(), json!("redbluegreen"));
         });
     }
 
@@ -649,7 +984,7 @@ mod tests {
 
         // Create initial registers with $R0 value
         let registers = DashMap::new();
-        registers.insert("$R0".to_string(), "about Rust programming".to_string());
+        registers.insert("$R0".to_string(), json!("about Rust programming"));
 
         // Create the StepExecutor iterator
         let mut step_executor = engine.iter(&workflow, Some(registers), None);
@@ -663,18 +998,18 @@ mod tests {
         // Check the results
         // Check the final results
         let final_registers = step_executor.registers;
-        assert_eq!(final_registers.get("$R0").unwrap().as_str(), "about Rust programming");
+        assert_eq!(*final_registers.get("$R0").unwrap(), json!("about Rust programming"));
         assert_eq!(
-            final_registers.get("$R1").unwrap().as_str(),
-            "Create an outline for a blog post about the topic of the user's message "
+            *final_registers.get("$R1").unwrap(),
+            json!("Create an outline for a blog post about the topic of the user's message ")
         );
         assert_eq!(
-            final_registers.get("$R2").unwrap().as_str(),
-            r"\n separate the sections using a comma e.g. red,green,blue"
+            *final_registers.get("$R2").unwrap(),
+            json!(r"\n separate the sections using a comma e.g. red,green,blue")
         );
         assert_eq!(
-            final_registers.get("$R3").unwrap().as_str(),
-            r"Create an outline for a blog post about the topic of the user's message about Rust programming\n separate the sections using a comma e.g. red,green,blue"
+            *final_registers.get("$R3").unwrap(),
+            json!(r"Create an outline for a blog post about the topic of the user's message about Rust programming\n separate the sections using a comma e.g. red,green,blue")
         );
 
         // Assert logs
@@ -684,9 +1019,9 @@ mod tests {
             r#"Setting register $R2 to "\\n separate the sections using a comma e.g. red,green,blue""#,
             r#"Composite body 1: "RegisterOperation { register: \"$R2\", value: String(\"\\\\n separate the sections using a comma e.g. red,green,blue\") }""#,
             r#"Setting register $R3 to "Create an outline for a blog post about the topic of the user's message about Rust programming""#,
-            r#"Composite body 2: "RegisterOperation { register: \"$R3\", value: FunctionCall(FunctionCall { name: \"concat\", args: [Register(\"$R1\"), Register(\"$R0\")] }) }""#,
+            r#"Composite body 2: "RegisterOperation { register: \"$R3\", value: FunctionCall(FunctionCall { name: \"concat\", args: [Register(\"$R1\"), Register(\"$R0\")], retry: None }) }""#,
             r#"Setting register $R3 to "Create an outline for a blog post about the topic of the user's message about Rust programming\\n separate the sections using a comma e.g. red,green,blue""#,
-            r#"Composite body 3: "RegisterOperation { register: \"$R3\", value: FunctionCall(FunctionCall { name: \"concat\", args: [Register(\"$R3\"), Register(\"$R2\")] }) }""#,
+            r#"Composite body 3: "RegisterOperation { register: \"$R3\", value: FunctionCall(FunctionCall { name: \"concat\", args: [Register(\"$R3\"), Register(\"$R2\")], retry: None }) }""#,
         ];
         let log_values: Vec<String> = step_executor
             .logs
@@ -753,7 +1088,7 @@ mod tests {
         eprintln!("Registers: {:?}", registers);
 
         // Check the results
-        assert_eq!(registers.get("$WEBPAGE").unwrap().as_str(), "http://quotes.toscrape.com");
-        assert!(registers.get("$RESULT").unwrap().as_str().contains("<html"));
+        assert_eq!(*registers.get("$WEBPAGE").unwrap(), json!("http://quotes.toscrape.com"));
+        assert!(registers.get("$RESULT").unwrap().as_str().unwrap().contains("<html"));
     }
 }
@@ -107,6 +107,10 @@ fn create_a_sheet_and_check_workflows() {
                     toolkit_permissions: vec![],
                     storage_bucket_permissions: vec![],
                     allowed_message_senders: vec![],
+                    retry_policy: Default::default(),
+                    fallback_llm_providers: vec![],
+                    usage_quota: None,
+                    response_cache: None,
                 };
                 api_llm_provider_registration(
                     node1_commands_sender.clone(),
@@ -169,6 +173,8 @@ fn create_a_sheet_and_check_workflows() {
                         workflow_name: None,
                         llm_provider_name: node1_agent.clone(),
                         input_hash: None,
+                        response_schema: None,
+                        response_schema_max_retries: None,
                     },
                 };
 
@@ -78,6 +78,10 @@ mod tests {
                 toolkit_permissions: vec![],
                 storage_bucket_permissions: vec![],
                 allowed_message_senders: vec![],
+                retry_policy: Default::default(),
+                fallback_llm_providers: vec![],
+                usage_quota: None,
+                response_cache: None,
             };
 
             let profile = agent_name.clone().extract_profile().unwrap();
@@ -64,7 +64,7 @@ mod tests {
     use std::collections::{HashMap, HashSet};
 
     use shinkai_message_primitives::{
-        schemas::inbox_name::InboxName,
+        schemas::{inbox_name::InboxName, job_preset::JobPreset, shinkai_name::ShinkaiName},
         shinkai_message::shinkai_message_schemas::JobMessage,
         shinkai_utils::signatures::clone_signature_secret_key,
         shinkai_utils::{
@@ -74,6 +74,9 @@ mod tests {
         },
     };
     use shinkai_node::{db::db_errors::ShinkaiDBError, llm_provider::execution::prompts::subprompts::SubPrompt};
+    use shinkai_node::llm_provider::execution::prompts::prompts::Prompt;
+    use shinkai_node::llm_provider::job::JobStepResult;
+    use shinkai_node::llm_provider::llm_provider::LLMProviderAttempt;
     use shinkai_vector_resources::utils::hash_string;
 
     use super::*;
@@ -191,6 +194,75 @@ mod tests {
         assert!(job.is_finished);
     }
 
+    #[test]
+    fn test_set_job_paused() {
+        init_default_tracing();
+        setup();
+        let job_id = "job_paused".to_string();
+        let agent_id = "agent_paused".to_string();
+        let scope = JobScope::new_default();
+        let db_path = format!("db_tests/{}", hash_string(&agent_id.clone()));
+        let mut shinkai_db = ShinkaiDB::new(&db_path).unwrap();
+
+        create_new_job(&mut shinkai_db, job_id.clone(), agent_id.clone(), scope);
+        assert!(!shinkai_db.is_job_paused(&job_id).unwrap());
+
+        shinkai_db.set_job_paused(&job_id, true).unwrap();
+        assert!(shinkai_db.is_job_paused(&job_id).unwrap());
+
+        shinkai_db.set_job_paused(&job_id, false).unwrap();
+        assert!(!shinkai_db.is_job_paused(&job_id).unwrap());
+
+        // Unknown jobs can't be paused
+        assert!(matches!(
+            shinkai_db.set_job_paused("unknown_job", true),
+            Err(ShinkaiDBError::DataNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_job_presets() {
+        init_default_tracing();
+        setup();
+        let db_path = format!("db_tests/{}", hash_string("job_presets"));
+        let shinkai_db = ShinkaiDB::new(&db_path).unwrap();
+        let profile = ShinkaiName::new("@@node1.shinkai/main".to_string()).unwrap();
+        let other_profile = ShinkaiName::new("@@node1.shinkai/other".to_string()).unwrap();
+
+        let mut preset = JobPreset {
+            preset_id: "researcher".to_string(),
+            name: "Researcher".to_string(),
+            system_prompt: Some("Answer with sources".to_string()),
+            llm_provider_id: "my_gpt".to_string(),
+            scope: JobScope::new_default(),
+            tools: vec!["local:::shinkai-tool-web-search:::shinkai__web_search".to_string()],
+            temperature: Some(0.2),
+            max_tokens: None,
+            max_iterations: Some(5),
+        };
+        shinkai_db.save_job_preset(&preset, &profile).unwrap();
+        assert_eq!(shinkai_db.get_job_preset("researcher", &profile).unwrap(), preset);
+
+        // Presets belong to a profile
+        assert!(matches!(
+            shinkai_db.get_job_preset("researcher", &other_profile),
+            Err(ShinkaiDBError::JobPresetNotFound(_))
+        ));
+        assert!(shinkai_db.list_job_presets(&other_profile).unwrap().is_empty());
+
+        preset.system_prompt = None;
+        shinkai_db.save_job_preset(&preset, &profile).unwrap();
+        let presets = shinkai_db.list_job_presets(&profile).unwrap();
+        assert_eq!(presets, vec![preset]);
+
+        shinkai_db.remove_job_preset("researcher", &profile).unwrap();
+        assert!(shinkai_db.list_job_presets(&profile).unwrap().is_empty());
+        assert!(matches!(
+            shinkai_db.remove_job_preset("researcher", &profile),
+            Err(ShinkaiDBError::JobPresetNotFound(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_update_step_history() {
         init_default_tracing();
@@ -248,6 +320,127 @@ mod tests {
         assert_eq!(job.step_history.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_step_history_records_llm_provider_attempts() {
+        init_default_tracing();
+        setup();
+        let job_id = "test_job".to_string();
+        let agent_id = "agent_attempts".to_string();
+        let db_path = format!("db_tests/{}", hash_string(&agent_id.clone()));
+        let mut shinkai_db = ShinkaiDB::new(&db_path).unwrap();
+
+        let node1_identity_name = "@@node1.shinkai";
+        let node1_subidentity_name = "main_profile_node1";
+        let (node1_identity_sk, _) = unsafe_deterministic_signature_keypair(0);
+        let (node1_encryption_sk, node1_encryption_pk) = unsafe_deterministic_encryption_keypair(0);
+
+        create_new_job(&mut shinkai_db, job_id.clone(), agent_id.clone(), JobScope::new_default());
+
+        let message = generate_message_with_text(
+            "Hello World".to_string(),
+            node1_encryption_sk.clone(),
+            clone_signature_secret_key(&node1_identity_sk),
+            node1_encryption_pk,
+            node1_subidentity_name.to_string(),
+            node1_identity_name.to_string(),
+            "2023-07-02T20:53:34.810Z".to_string(),
+        );
+        shinkai_db.unsafe_insert_inbox_message(&message, None, None).await.unwrap();
+
+        let attempts = vec![
+            LLMProviderAttempt {
+                llm_provider_id: "my_openai".to_string(),
+                model: "openai:gpt-4o".to_string(),
+                attempt: 1,
+                error: Some("NetworkError".to_string()),
+                cached: false,
+                datetime: "2023-07-02T20:53:35.810Z".to_string(),
+            },
+            LLMProviderAttempt {
+                llm_provider_id: "my_ollama".to_string(),
+                model: "ollama:llama3".to_string(),
+                attempt: 1,
+                error: None,
+                cached: false,
+                datetime: "2023-07-02T20:53:36.810Z".to_string(),
+            },
+        ];
+        shinkai_db
+            .add_pending_llm_provider_attempts(&job_id, attempts[..1].to_vec())
+            .unwrap();
+        shinkai_db
+            .add_pending_llm_provider_attempts(&job_id, attempts[1..].to_vec())
+            .unwrap();
+        assert_eq!(shinkai_db.get_pending_llm_provider_attempts(&job_id).unwrap(), attempts);
+
+        shinkai_db
+            .add_step_history(job_id.clone(), "Hi".to_string(), "Hello!".to_string(), None)
+            .unwrap();
+
+        // The attempts are moved to the step history entry
+        assert!(shinkai_db.get_pending_llm_provider_attempts(&job_id).unwrap().is_empty());
+        let job = shinkai_db.get_job(&job_id.clone()).unwrap();
+        assert_eq!(job.step_history.len(), 1);
+        assert_eq!(job.step_history[0].llm_provider_attempts, attempts);
+    }
+
+    #[tokio::test]
+    async fn test_add_job_step_results() {
+        init_default_tracing();
+        setup();
+        let job_id = "test_job".to_string();
+        let agent_id = "agent_step_results".to_string();
+        let db_path = format!("db_tests/{}", hash_string(&agent_id.clone()));
+        let mut shinkai_db = ShinkaiDB::new(&db_path).unwrap();
+
+        let node1_identity_name = "@@node1.shinkai";
+        let node1_subidentity_name = "main_profile_node1";
+        let (node1_identity_sk, _) = unsafe_deterministic_signature_keypair(0);
+        let (node1_encryption_sk, node1_encryption_pk) = unsafe_deterministic_encryption_keypair(0);
+
+        create_new_job(&mut shinkai_db, job_id.clone(), agent_id.clone(), JobScope::new_default());
+
+        let message = generate_message_with_text(
+            "Hello World".to_string(),
+            node1_encryption_sk.clone(),
+            clone_signature_secret_key(&node1_identity_sk),
+            node1_encryption_pk,
+            node1_subidentity_name.to_string(),
+            node1_identity_name.to_string(),
+            "2023-07-02T20:53:34.810Z".to_string(),
+        );
+        shinkai_db.unsafe_insert_inbox_message(&message, None, None).await.unwrap();
+        let message_key = message.calculate_message_hash_for_pagination();
+
+        // Entries of an imported job are added as they were, in the same order
+        let step_results: Vec<JobStepResult> = ["First", "Second"]
+            .iter()
+            .map(|answer| {
+                let mut prompt = Prompt::new();
+                prompt.add_content("Question".to_string(), User, 100);
+                prompt.add_content(answer.to_string(), Assistant, 100);
+                let mut step_result = JobStepResult::new();
+                step_result.add_new_step_revision(prompt);
+                step_result
+            })
+            .collect();
+        shinkai_db
+            .add_job_step_results(&job_id, message_key.clone(), &step_results)
+            .unwrap();
+
+        assert_eq!(shinkai_db.get_message_step_history(&message_key).unwrap(), step_results);
+        let job = shinkai_db.get_job(&job_id).unwrap();
+        assert_eq!(job.step_history, step_results);
+
+        // Custom name of the job inbox (exported with the job)
+        let inbox_name = job.conversation_inbox_name.to_string();
+        shinkai_db.update_smart_inbox_name(&inbox_name, "Greetings").unwrap();
+        assert_eq!(
+            shinkai_db.get_smart_inbox_name(&inbox_name).unwrap(),
+            Some("Greetings".to_string())
+        );
+    }
+
     #[test]
     fn test_get_non_existent_job() {
         init_default_tracing();
@@ -464,6 +657,117 @@ mod tests {
         assert_eq!(job_message_4.content, "Hello World 4".to_string());
     }
 
+    #[tokio::test]
+    async fn test_job_inbox_branches() {
+        init_default_tracing();
+        setup();
+        let job_id = "job_branches".to_string();
+        let agent_id = "agent_branches".to_string();
+        let scope = JobScope::new_default();
+        let db_path = format!("db_tests/{}", hash_string(&agent_id.clone().to_string()));
+        let mut shinkai_db = ShinkaiDB::new(&db_path).unwrap();
+
+        create_new_job(&mut shinkai_db, job_id.clone(), agent_id.clone(), scope);
+        let inbox_name = InboxName::get_job_inbox_name_from_params(job_id.clone())
+            .unwrap()
+            .to_string();
+
+        let (placeholder_signature_sk, _) = unsafe_deterministic_signature_keypair(0);
+        let new_message = |content: &str| {
+            ShinkaiMessageBuilder::job_message_from_llm_provider(
+                job_id.clone(),
+                content.to_string(),
+                "".to_string(),
+                placeholder_signature_sk.clone(),
+                "@@node1.shinkai".to_string(),
+                "@@node1.shinkai".to_string(),
+            )
+            .unwrap()
+        };
+        let contents = |paths: Vec<Vec<ShinkaiMessage>>| {
+            paths
+                .iter()
+                .map(|path| {
+                    let job_message: JobMessage =
+                        serde_json::from_str(&path[0].get_message_content().unwrap()).unwrap();
+                    job_message.content
+                })
+                .collect::<Vec<String>>()
+        };
+
+        /*
+        The tree that we are creating looks like:
+            1
+            ├── 2
+            │   └── 3
+            └── 4
+         */
+        let mut hashes = Vec::new();
+        for i in 1..=3 {
+            let message = new_message(&format!("Hello World {}", i));
+            shinkai_db.unsafe_insert_inbox_message(&message, None, None).await.unwrap();
+            hashes.push(message.calculate_message_hash_for_pagination());
+            sleep(Duration::from_millis(10)).await;
+        }
+
+        // Forking the second message makes the new branch the active one
+        let message_4 = new_message("Hello World 4");
+        shinkai_db
+            .insert_inbox_message_as_branch(&message_4, Some(hashes[0].clone()), None)
+            .await
+            .unwrap();
+        hashes.push(message_4.calculate_message_hash_for_pagination());
+        sleep(Duration::from_millis(10)).await;
+
+        let last_messages = shinkai_db.get_last_messages_from_inbox(inbox_name.clone(), 10, None).unwrap();
+        assert_eq!(contents(last_messages), vec!["Hello World 1", "Hello World 4"]);
+
+        let tree = shinkai_db.get_inbox_message_tree(&inbox_name).unwrap();
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree[0].children, vec![hashes[3].clone(), hashes[1].clone()]);
+        assert_eq!(tree[3].parent_hash, Some(hashes[0].clone()));
+        let active: Vec<bool> = tree.iter().map(|node| node.is_active).collect();
+        assert_eq!(active, vec![true, false, false, true]);
+
+        // Switching to the second message activates its branch up to its leaf
+        let leaf = shinkai_db.set_inbox_active_leaf(&inbox_name, &hashes[1]).unwrap();
+        assert_eq!(leaf, hashes[2]);
+        let last_messages = shinkai_db.get_last_messages_from_inbox(inbox_name.clone(), 10, None).unwrap();
+        assert_eq!(
+            contents(last_messages),
+            vec!["Hello World 1", "Hello World 2", "Hello World 3"]
+        );
+        let tree = shinkai_db.get_inbox_message_tree(&inbox_name).unwrap();
+        let active: Vec<bool> = tree.iter().map(|node| node.is_active).collect();
+        assert_eq!(active, vec![true, true, true, false]);
+
+        // New messages continue the active branch
+        let message_5 = new_message("Hello World 5");
+        shinkai_db.unsafe_insert_inbox_message(&message_5, None, None).await.unwrap();
+        assert_eq!(shinkai_db.get_inbox_active_leaf(&inbox_name).unwrap(), None);
+        let last_messages = shinkai_db.get_last_messages_from_inbox(inbox_name.clone(), 10, None).unwrap();
+        assert_eq!(
+            contents(last_messages),
+            vec!["Hello World 1", "Hello World 2", "Hello World 3", "Hello World 5"]
+        );
+        sleep(Duration::from_millis(10)).await;
+
+        // Forking the first message starts a conversation without the previous messages
+        let message_6 = new_message("Hello World 6");
+        shinkai_db
+            .insert_inbox_message_as_branch(&message_6, None, None)
+            .await
+            .unwrap();
+        let last_messages = shinkai_db.get_last_messages_from_inbox(inbox_name.clone(), 10, None).unwrap();
+        assert_eq!(contents(last_messages), vec!["Hello World 6"]);
+
+        // Unknown messages can't be selected
+        assert!(matches!(
+            shinkai_db.set_inbox_active_leaf(&inbox_name, "unknown_hash"),
+            Err(ShinkaiDBError::MessageNotFound)
+        ));
+    }
+
     #[tokio::test]
     async fn test_job_inbox_tree_structure_with_step_history_and_execution_context() {
         init_default_tracing();
@@ -46,6 +46,10 @@ mod tests {
             toolkit_permissions: vec!["toolkit1".to_string(), "toolkit2".to_string()],
             storage_bucket_permissions: vec!["storage1".to_string(), "storage2".to_string()],
             allowed_message_senders: vec!["sender1".to_string(), "sender2".to_string()],
+            retry_policy: Default::default(),
+            fallback_llm_providers: vec![],
+            usage_quota: None,
+            response_cache: None,
         };
 
         // Add a new agent
@@ -118,6 +122,10 @@ mod tests {
             toolkit_permissions: vec!["toolkit1".to_string(), "toolkit2".to_string()],
             storage_bucket_permissions: vec!["storage1".to_string(), "storage2".to_string()],
             allowed_message_senders: vec!["sender1".to_string(), "sender2".to_string()],
+            retry_policy: Default::default(),
+            fallback_llm_providers: vec![],
+            usage_quota: None,
+            response_cache: None,
         };
 
         // Add a new agent
@@ -169,6 +177,10 @@ mod tests {
             toolkit_permissions: vec!["toolkit1".to_string(), "toolkit2".to_string()],
             storage_bucket_permissions: vec!["storage1".to_string(), "storage2".to_string()],
             allowed_message_senders: vec!["sender1".to_string(), "sender2".to_string()],
+            retry_policy: Default::default(),
+            fallback_llm_providers: vec![],
+            usage_quota: None,
+            response_cache: None,
         };
 
         // Add a new agent
@@ -209,6 +221,10 @@ mod tests {
             toolkit_permissions: vec!["toolkit1".to_string(), "toolkit2".to_string()],
             storage_bucket_permissions: vec!["storage1".to_string(), "storage2".to_string()],
             allowed_message_senders: vec!["sender1".to_string(), "sender2".to_string()],
+            retry_policy: Default::default(),
+            fallback_llm_providers: vec![],
+            usage_quota: None,
+            response_cache: None,
         };
 
         // Add a new agent
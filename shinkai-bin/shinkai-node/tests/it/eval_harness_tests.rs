@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
+use shinkai_node::db::ShinkaiDB;
+use shinkai_node::llm_provider::error::LLMProviderError;
+use shinkai_node::llm_provider::eval::eval_dataset::{EvalCase, EvalDataset};
+use shinkai_node::llm_provider::eval::eval_graders::{EvalGrade, EvalGrader};
+use shinkai_node::llm_provider::eval::eval_harness::EvalHarness;
+use shinkai_node::llm_provider::providers::mock::{MockLLM, MockLLMResponse};
+use shinkai_node::llm_provider::providers::provider_registry::LLMProviderRegistry;
+use shinkai_node::vector_fs::vector_fs::VectorFS;
+use shinkai_vector_resources::embedding_generator::RemoteEmbeddingGenerator;
+
+use super::utils::db_handlers::setup;
+
+/// Custom grader, passing if the answer is short
+struct ShortAnswerGrader;
+
+#[async_trait]
+impl EvalGrader for ShortAnswerGrader {
+    fn name(&self) -> String {
+        "short_answer".to_string()
+    }
+
+    async fn grade(&self, _case: &EvalCase, output: &str) -> Result<EvalGrade, LLMProviderError> {
+        let passed = output.len() < 20;
+        Ok(EvalGrade {
+            grader: self.name(),
+            passed,
+            score: if passed { 1.0 } else { 0.0 },
+            reason: None,
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_eval_harness_with_mock_llm() {
+    setup();
+    let db = Arc::new(ShinkaiDB::new("db_tests/").unwrap());
+    let vector_fs = Arc::new(VectorFS::new_empty().unwrap());
+    let profile = ShinkaiName::new("@@localhost.shinkai/main".to_string()).unwrap();
+
+    LLMProviderRegistry::register(Arc::new(MockLLM::new(
+        vec![
+            MockLLMResponse {
+                prompt_contains: Some("Grading criteria".to_string()),
+                response: r#"{"score": 9, "reason": "Correct"}"#.to_string(),
+            },
+            MockLLMResponse {
+                prompt_contains: Some("capital of France".to_string()),
+                response: "Paris".to_string(),
+            },
+            MockLLMResponse {
+                prompt_contains: Some("capital of Peru".to_string()),
+                response: "The capital of Peru is Lima, a city on the coast".to_string(),
+            },
+            MockLLMResponse {
+                prompt_contains: Some("as JSON".to_string()),
+                response: r#"{"capital": "Rome"}"#.to_string(),
+            },
+        ],
+        None,
+    )));
+    let llm_provider = MockLLM::serialized_llm_provider("mock", profile.clone());
+
+    let dataset = EvalDataset::from_jsonl(
+        r#"{"id": "france", "message": "What is the capital of France?", "graders": [{"type": "exact_match", "expected": "Paris"}, {"type": "llm_judge", "criteria": "Names the capital"}]}
+{"id": "peru", "message": "What is the capital of Peru?", "graders": [{"type": "regex", "pattern": "Lima"}]}
+{"id": "italy", "message": "Give me the capital of Italy as JSON", "graders": [{"type": "json_schema", "schema": {"type": "object", "required": ["capital"]}}]}
+{"id": "unscripted", "message": "Hello"}"#,
+    )
+    .unwrap();
+
+    let harness = EvalHarness::new(
+        db,
+        vector_fs,
+        llm_provider.clone(),
+        RemoteEmbeddingGenerator::new_default(),
+        profile,
+    )
+    .with_judge(llm_provider)
+    .with_grader(Arc::new(ShortAnswerGrader));
+    let report = harness.run(&dataset).await;
+    LLMProviderRegistry::unregister("mock");
+
+    assert_eq!(report.model, "mock:scripted");
+    assert_eq!(report.summary.total, 4);
+    assert_eq!(report.summary.passed, 2);
+    assert_eq!(report.summary.errored, 1);
+
+    let france = &report.cases[0];
+    assert_eq!(france.output.as_deref(), Some("Paris"));
+    assert!(france.passed());
+    assert_eq!(france.grades.len(), 3);
+    assert_eq!(france.grades[1].grader, "llm_judge");
+    assert_eq!(france.grades[1].score, 0.9);
+
+    // The answer matches the regex but it's too long for the custom grader
+    let peru = &report.cases[1];
+    assert!(!peru.passed());
+    assert!(peru.grades[0].passed);
+    assert!(!peru.grades[1].passed);
+
+    assert!(report.cases[2].passed());
+    assert!(report.cases[3].error.is_some());
+    assert_eq!(report.summary.graders["short_answer"].total, 3);
+}
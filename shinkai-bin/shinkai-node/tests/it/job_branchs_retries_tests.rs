@@ -144,6 +144,10 @@ fn job_branchs_retries_tests() {
                     toolkit_permissions: vec![],
                     storage_bucket_permissions: vec![],
                     allowed_message_senders: vec![],
+                    retry_policy: Default::default(),
+                    fallback_llm_providers: vec![],
+                    usage_quota: None,
+                    response_cache: None,
                 };
                 api_llm_provider_registration(
                     node1_commands_sender.clone(),
@@ -17,8 +17,10 @@ use shinkai_message_primitives::{
 };
 use shinkai_node::db::{ShinkaiDB, Topic};
 use shinkai_node::llm_provider::job_callback_manager::JobCallbackManager;
+use shinkai_node::llm_provider::job_control::JobControl;
 use shinkai_node::llm_provider::job_manager::JobManager;
 use shinkai_node::llm_provider::queue::job_queue_manager::{JobForProcessing, JobQueueManager};
+use shinkai_node::llm_provider::queue::job_scheduler::JobSchedulerLimits;
 use shinkai_node::managers::sheet_manager::SheetManager;
 use shinkai_node::vector_fs::vector_fs::VectorFS;
 use shinkai_vector_resources::embedding_generator::RemoteEmbeddingGenerator;
@@ -173,7 +175,7 @@ async fn test_process_job_queue_concurrency() {
         db_weak.clone(),
         vector_fs_weak.clone(),
         node_name.clone(),
-        num_threads,
+        JobSchedulerLimits::new(num_threads),
         clone_signature_secret_key(&node_identity_sk),
         RemoteEmbeddingGenerator::new_default(),
         UnstructuredAPI::new_default(),
@@ -181,6 +183,7 @@ async fn test_process_job_queue_concurrency() {
         None,
         sheet_manager.clone(),
         callback_manager.clone(),
+        Arc::new(Mutex::new(JobControl::default())),
         move |job,
               _db,
               _vector_fs,
@@ -221,6 +224,8 @@ async fn test_process_job_queue_concurrency() {
                 workflow_name: None,
                 sheet_job_data: None,
                 callback: None,
+                inference_params: None,
+                is_delegation_trace: false,
             },
             ShinkaiName::new("@@node1.shinkai/main".to_string()).unwrap(),
         );
@@ -324,7 +329,7 @@ async fn test_sequential_process_for_same_job_id() {
         db_weak.clone(),
         vector_fs_weak.clone(),
         node_name.clone(),
-        num_threads,
+        JobSchedulerLimits::new(num_threads),
         clone_signature_secret_key(&node_identity_sk),
         RemoteEmbeddingGenerator::new_default(),
         UnstructuredAPI::new_default(),
@@ -332,6 +337,7 @@ async fn test_sequential_process_for_same_job_id() {
         None,
         sheet_manager.clone(),
         callback_manager.clone(),
+        Arc::new(Mutex::new(JobControl::default())),
         move |job,
               _db,
               _vector_fs,
@@ -371,6 +377,8 @@ async fn test_sequential_process_for_same_job_id() {
                 workflow_name: None,
                 sheet_job_data: None,
                 callback: None,
+                inference_params: None,
+                is_delegation_trace: false,
             },
             ShinkaiName::new("@@node1.shinkai/main".to_string()).unwrap(),
         );
@@ -128,6 +128,10 @@ fn job_tree_usage_tests() {
                     toolkit_permissions: vec![],
                     storage_bucket_permissions: vec![],
                     allowed_message_senders: vec![],
+                    retry_policy: Default::default(),
+                    fallback_llm_providers: vec![],
+                    usage_quota: None,
+                    response_cache: None,
                 };
                 api_agent_registration(
                     node1_commands_sender.clone(),
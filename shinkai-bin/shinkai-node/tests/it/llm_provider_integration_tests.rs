@@ -123,6 +123,10 @@ fn node_llm_provider_registration() {
             toolkit_permissions: vec![],
             storage_bucket_permissions: vec![],
             allowed_message_senders: vec![],
+            retry_policy: Default::default(),
+            fallback_llm_providers: vec![],
+            usage_quota: None,
+            response_cache: None,
         };
 
         // Create node1 and node2
@@ -464,6 +468,8 @@ fn node_llm_provider_registration() {
                     workflow_name: None,
                     sheet_job_data: None,
                     callback: None,
+                    inference_params: None,
+                    is_delegation_trace: false,
                 };
                 let body = serde_json::to_string(&job_message)
                     .map_err(|_| "Failed to serialize job message to JSON")
@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod tests {
-    use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{LLMProviderInterface, OpenAI, SerializedLLMProvider};
+    use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{
+        Anthropic, LLMProviderInterface, OpenAI, SerializedLLMProvider,
+    };
     use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
     use shinkai_message_primitives::shinkai_utils::shinkai_logging::init_default_tracing;
     use shinkai_node::db::ShinkaiDB;
@@ -42,6 +44,10 @@ mod tests {
             toolkit_permissions: vec![],
             storage_bucket_permissions: vec![],
             allowed_message_senders: vec![],
+            retry_policy: Default::default(),
+            fallback_llm_providers: vec![],
+            usage_quota: None,
+            response_cache: None,
         };
 
         let manager = ModelCapabilitiesManager {
@@ -84,6 +90,10 @@ mod tests {
             toolkit_permissions: vec![],
             storage_bucket_permissions: vec![],
             allowed_message_senders: vec![],
+            retry_policy: Default::default(),
+            fallback_llm_providers: vec![],
+            usage_quota: None,
+            response_cache: None,
         };
 
         let manager = ModelCapabilitiesManager {
@@ -122,6 +132,10 @@ mod tests {
             toolkit_permissions: vec![],
             storage_bucket_permissions: vec![],
             allowed_message_senders: vec![],
+            retry_policy: Default::default(),
+            fallback_llm_providers: vec![],
+            usage_quota: None,
+            response_cache: None,
         };
 
         let manager = ModelCapabilitiesManager {
@@ -134,4 +148,41 @@ mod tests {
         assert!(!manager.has_capability(ModelCapability::ImageAnalysis).await);
         assert!(!manager.has_capability(ModelCapability::ImageGeneration).await);
     }
+
+    #[test]
+    fn test_anthropic_model_capabilities() {
+        let agent_id = "agent_id4".to_string();
+        let agent_name =
+            ShinkaiName::new(format!("@@localhost.shinkai/main/agent/{}", agent_id.clone()).to_string()).unwrap();
+
+        let anthropic = Anthropic {
+            model_type: "claude-3-5-sonnet-20240620".to_string(),
+        };
+
+        let claude_agent = SerializedLLMProvider {
+            id: agent_id.clone(),
+            full_identity_name: agent_name,
+            perform_locally: false,
+            external_url: Some("https://api.anthropic.com".to_string()),
+            api_key: env::var("INITIAL_AGENT_API_KEY").ok(),
+            model: LLMProviderInterface::Anthropic(anthropic),
+            toolkit_permissions: vec![],
+            storage_bucket_permissions: vec![],
+            allowed_message_senders: vec![],
+            retry_policy: Default::default(),
+            fallback_llm_providers: vec![],
+            usage_quota: None,
+            response_cache: None,
+        };
+
+        let capabilities = ModelCapabilitiesManager::get_capability(&claude_agent);
+        assert_eq!(
+            capabilities.0,
+            vec![ModelCapability::ImageAnalysis, ModelCapability::TextInference]
+        );
+        assert_eq!(capabilities.1, ModelCost::GoodValue);
+        assert_eq!(capabilities.2, ModelPrivacy::RemoteGreedy);
+        assert_eq!(ModelCapabilitiesManager::get_max_tokens(&claude_agent.model), 200_000);
+        assert_eq!(ModelCapabilitiesManager::get_max_output_tokens(&claude_agent.model), 8192);
+    }
 }
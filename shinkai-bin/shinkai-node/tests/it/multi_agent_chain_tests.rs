@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use shinkai_message_primitives::schemas::inbox_name::InboxName;
+use shinkai_message_primitives::schemas::job_config::JobConfig;
+use shinkai_message_primitives::schemas::llm_providers::customized_agent::CustomizedAgent;
+use shinkai_message_primitives::schemas::llm_providers::inference_params::InferenceParams;
+use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{
+    CustomProvider, LLMProviderInterface,
+};
+use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
+use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::JobMessage;
+use shinkai_message_primitives::shinkai_utils::job_scope::JobScope;
+use shinkai_node::db::ShinkaiDB;
+use shinkai_node::llm_provider::error::LLMProviderError;
+use shinkai_node::llm_provider::execution::chains::inference_chain_trait::LLMInferenceResponse;
+use shinkai_node::llm_provider::execution::chains::multi_agent_chain::multi_agent_inference_chain::DelegationStep;
+use shinkai_node::llm_provider::execution::prompts::prompts::Prompt;
+use shinkai_node::llm_provider::job_manager::JobManager;
+use shinkai_node::llm_provider::providers::mock::{MockLLM, MockLLMResponse};
+use shinkai_node::llm_provider::providers::provider_registry::{LLMProviderBackend, LLMProviderRegistry};
+use shinkai_node::llm_provider::providers::LLMService;
+use shinkai_node::managers::model_capabilities_manager::{ModelCapabilitiesManagerError, PromptResult};
+use shinkai_node::network::ws_manager::WSUpdateHandler;
+use shinkai_node::vector_fs::vector_fs::VectorFS;
+use shinkai_vector_resources::embedding_generator::RemoteEmbeddingGenerator;
+use tokio::sync::Mutex;
+
+use super::utils::db_handlers::setup;
+
+const PROVIDER_PREFIX: &str = "mock-agents";
+
+/// The mock llm under its own provider prefix, so it doesn't clash with the other tests using the mock
+struct AgentsMockLLM(MockLLM);
+
+#[async_trait]
+impl LLMProviderBackend for AgentsMockLLM {
+    fn provider_prefix(&self) -> &str {
+        PROVIDER_PREFIX
+    }
+
+    async fn call_api(
+        &self,
+        client: &Client,
+        url: Option<&String>,
+        api_key: Option<&String>,
+        prompt: Prompt,
+        inference_params: InferenceParams,
+        model: LLMProviderInterface,
+        inbox_name: Option<InboxName>,
+        ws_manager_trait: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> Result<LLMInferenceResponse, LLMProviderError> {
+        LLMService::call_api(
+            &self.0,
+            client,
+            url,
+            api_key,
+            prompt,
+            inference_params,
+            model,
+            inbox_name,
+            ws_manager_trait,
+        )
+        .await
+    }
+
+    fn prepare_messages(
+        &self,
+        model: &LLMProviderInterface,
+        prompt: Prompt,
+    ) -> Result<PromptResult, ModelCapabilitiesManagerError> {
+        self.0.prepare_messages(model, prompt)
+    }
+}
+
+fn agent(role: &str, goal: &str, allow_delegation: bool) -> CustomizedAgent {
+    CustomizedAgent::new(
+        role.to_string(),
+        goal.to_string(),
+        format!("You have been a {} for years.", role.to_lowercase()),
+        None,
+        vec![],
+        None,
+        None,
+        None,
+        None,
+        false,
+        allow_delegation,
+        None,
+    )
+}
+
+#[tokio::test]
+async fn test_multi_agent_chain_delegation() {
+    setup();
+    let db = Arc::new(ShinkaiDB::new("db_tests/multi_agent_chain").unwrap());
+    let vector_fs = Arc::new(VectorFS::new_empty().unwrap());
+    let profile = ShinkaiName::new("@@localhost.shinkai/main".to_string()).unwrap();
+
+    LLMProviderRegistry::register(Arc::new(AgentsMockLLM(MockLLM::new(
+        vec![
+            MockLLMResponse {
+                prompt_contains: Some("Researcher answered".to_string()),
+                response: r#"{"action": "final_answer", "answer": "The capital of Peru is Lima."}"#.to_string(),
+            },
+            MockLLMResponse {
+                prompt_contains: Some("You are Researcher".to_string()),
+                response: "Lima".to_string(),
+            },
+            MockLLMResponse {
+                prompt_contains: Some("You are Writer".to_string()),
+                response: r#"{"action": "delegate", "coworker": "researcher", "task": "Find the capital of Peru"}"#
+                    .to_string(),
+            },
+        ],
+        None,
+    ))));
+    let mut llm_provider = MockLLM::serialized_llm_provider("mock-agents", profile.clone());
+    llm_provider.model = LLMProviderInterface::Custom(CustomProvider {
+        provider: PROVIDER_PREFIX.to_string(),
+        model_type: "scripted".to_string(),
+    });
+
+    let job_id = "jobid_multi_agent".to_string();
+    db.create_new_job(
+        job_id.clone(),
+        llm_provider.id.clone(),
+        JobScope::new_default(),
+        false,
+        None,
+    )
+    .unwrap();
+    let config = JobConfig {
+        agents: vec![
+            agent("Writer", "Write short answers", true),
+            agent("Researcher", "Find facts", false),
+        ],
+        ..Default::default()
+    };
+    db.set_job_config(&job_id, &config).unwrap();
+    let full_job = db.get_job(&job_id).unwrap();
+
+    let job_message = JobMessage {
+        job_id,
+        content: "What is the capital of Peru?".to_string(),
+        files_inbox: "".to_string(),
+        parent: None,
+        workflow_code: None,
+        workflow_name: None,
+        sheet_job_data: None,
+        callback: None,
+        inference_params: None,
+        is_delegation_trace: false,
+    };
+    let result = JobManager::inference_chain_router(
+        db,
+        vector_fs,
+        Some(llm_provider),
+        full_job,
+        job_message,
+        HashMap::new(),
+        RemoteEmbeddingGenerator::new_default(),
+        profile,
+        None,
+        None,
+        None,
+    )
+    .await;
+    LLMProviderRegistry::unregister(PROVIDER_PREFIX);
+    let result = result.unwrap();
+
+    assert_eq!(result.response, "The capital of Peru is Lima.");
+    assert_eq!(result.delegation_trace.len(), 1);
+    let step = &result.delegation_trace[0];
+    assert_eq!(step.agent, "Researcher");
+    assert_eq!(step.delegated_by, "Writer");
+    assert_eq!(step.task, "Find the capital of Peru");
+    assert_eq!(step.answer.as_deref(), Some("Lima"));
+    assert!(step.steps.is_empty());
+    assert_eq!(
+        DelegationStep::inbox_messages(&result.delegation_trace),
+        vec!["> **Writer** delegated to **Researcher**: Find the capital of Peru\n>\n> Lima".to_string()]
+    );
+}
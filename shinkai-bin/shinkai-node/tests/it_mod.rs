@@ -13,6 +13,7 @@ mod it {
     mod db_restore_tests;
     mod db_tests;
     mod encrypted_files_tests;
+    mod eval_harness_tests;
     mod get_onchain_identity_tests;
     mod job_branchs_retries_tests;
     mod job_concurrency_in_seq_tests;
@@ -22,6 +23,7 @@ mod it {
     mod job_one_page_cron_tests;
     mod llm_provider_integration_tests;
     mod model_capabilities_manager_tests;
+    mod multi_agent_chain_tests;
     mod node_integration_tests;
     mod node_retrying_tests;
     mod node_simple_ux_tests;
@@ -8,7 +8,7 @@ use crate::parser::parse_workflow;
 #[grammar = "workflow.pest"]
 pub struct WorkflowParser;
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Workflow {
     pub name: String,
     pub version: String,
@@ -44,25 +44,55 @@ impl Workflow {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Step {
     pub name: String,
     pub body: Vec<StepBody>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(tag = "type", content = "value", rename_all = "lowercase")]
 pub enum StepBody {
     Action(Action),
     Condition {
         condition: Expression,
         body: Box<StepBody>,
+        /// Runs when the condition is false. An `else if` is a Condition here.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        else_body: Option<Box<StepBody>>,
     },
     ForLoop {
         var: String,
         in_expr: ForLoopExpression,
         body: Box<StepBody>,
     },
+    WhileLoop {
+        condition: Expression,
+        body: Box<StepBody>,
+    },
+    /// Runs each branch concurrently on its own copy of the registers, then merges their writes in branch order
+    Parallel {
+        branches: Vec<StepBody>,
+        options: ParallelOptions,
+    },
+    /// A for loop running its iterations as parallel branches
+    ParallelForLoop {
+        var: String,
+        in_expr: ForLoopExpression,
+        body: Box<StepBody>,
+        options: ParallelOptions,
+    },
+    /// Runs the catch body if the body fails, with the error message in the error register.
+    /// Registers written by the body before the error keep their values.
+    TryCatch {
+        body: Box<StepBody>,
+        error_register: Option<String>,
+        catch_body: Box<StepBody>,
+    },
+    Break,
+    Continue,
+    /// Ends the workflow. The value, if any, becomes the `$RESULT` register.
+    Return(Option<Param>),
     RegisterOperation {
         register: String,
         value: WorkflowValue,
@@ -70,31 +100,78 @@ pub enum StepBody {
     Composite(Vec<StepBody>),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct ParallelOptions {
+    /// Branches running at once, all of them if not set
+    pub max_concurrency: Option<usize>,
+    /// Lets every branch finish and reports all their errors instead of stopping at the first one
+    pub collect_errors: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum Action {
     ExternalFnCall(FunctionCall),
     Command { command: String, params: Vec<Param> },
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct FunctionCall {
     pub name: String,
     pub args: Vec<Param>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+/// Retries of a failed function call. The delay before each retry doubles, starting at the backoff.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    pub backoff_ms: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(tag = "type", content = "value", rename_all = "lowercase")]
 pub enum Param {
     String(String),
     Number(i64),
+    Float(f64),
     Boolean(bool),
     Identifier(String),
     Register(String),
     Range(i32, i32),
+    List(Vec<Param>),
+    Map(Vec<(String, Param)>),
+    /// Field and index access, e.g. `$R1.items[0].name`
+    Access {
+        base: Box<Param>,
+        path: Vec<Accessor>,
+    },
+    Operation {
+        left: Box<Param>,
+        operator: ArithmeticOperator,
+        right: Box<Param>,
+    },
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum Accessor {
+    Field(String),
+    Index(Param),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ArithmeticOperator {
+    /// Adds numbers, concatenates strings and lists, merges maps
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum Expression {
     Binary {
         left: Box<Param>,
@@ -106,9 +183,18 @@ pub enum Expression {
         end: Box<Param>,
     },
     Simple(Box<Param>),
+    And {
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+    Or {
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+    Not(Box<Expression>),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum ForLoopExpression {
     Split { source: Param, delimiter: String },
     Range { start: Box<Param>, end: Box<Param> },
@@ -124,7 +210,7 @@ pub enum ComparisonOperator {
     LessEqual,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum WorkflowValue {
     String(String),
@@ -133,4 +219,6 @@ pub enum WorkflowValue {
     Identifier(String),
     Register(String),
     FunctionCall(FunctionCall),
+    /// Lists, maps, field access and operations
+    Param(Param),
 }
@@ -1,2 +1,3 @@
 pub mod parser;
-pub mod dsl_schemas;
\ No newline at end of file
+pub mod dsl_schemas;
+pub mod register_value;
\ No newline at end of file
@@ -1,11 +1,11 @@
 use pest::Parser;
 
 use crate::dsl_schemas::{
-    Action, ComparisonOperator, Expression, ForLoopExpression, FunctionCall, Param, Rule, Step, StepBody, Workflow,
-    WorkflowParser, WorkflowValue,
+    Accessor, Action, ArithmeticOperator, ComparisonOperator, Expression, ForLoopExpression, FunctionCall,
+    ParallelOptions, Param, RetryPolicy, Rule, Step, StepBody, Workflow, WorkflowParser, WorkflowValue,
 };
 
-pub fn parse_step_body(pair: pest::iterators::Pair<Rule>) -> StepBody {
+pub fn parse_step_body(pair: pest::iterators::Pair<Rule>) -> Result<StepBody, String> {
     if pair.as_rule() != Rule::step_body {
         panic!("Expected 'step_body' rule, found {:?}", pair.as_rule());
     }
@@ -14,29 +14,31 @@ pub fn parse_step_body(pair: pest::iterators::Pair<Rule>) -> StepBody {
     let mut bodies = Vec::new();
 
     for inner_pair in inner_pairs {
-        bodies.push(parse_step_body_item(inner_pair));
+        bodies.push(parse_step_body_item(inner_pair)?);
     }
 
     if bodies.len() == 1 {
-        bodies.remove(0)
+        Ok(bodies.remove(0))
     } else {
-        StepBody::Composite(bodies) // Assuming there is a Composite variant to handle multiple bodies
+        Ok(StepBody::Composite(bodies)) // Assuming there is a Composite variant to handle multiple bodies
     }
 }
 
-pub fn parse_step_body_item(pair: pest::iterators::Pair<Rule>) -> StepBody {
-    match pair.as_rule() {
+pub fn parse_step_body_item(pair: pest::iterators::Pair<Rule>) -> Result<StepBody, String> {
+    let step_body = match pair.as_rule() {
         Rule::action => {
-            StepBody::Action(parse_action(pair))
+            StepBody::Action(parse_action(pair)?)
         }
         Rule::condition => {
             let mut inner_pairs = pair.into_inner();
             let expression = parse_expression(inner_pairs.next().expect("Expected expression in condition"));
-            let body = parse_step_body(inner_pairs.next().expect("Expected step body in condition"));
+            let body = parse_step_body(inner_pairs.next().expect("Expected step body in condition"))?;
+            let else_body = inner_pairs.next().map(parse_else_branch).transpose()?;
 
             StepBody::Condition {
                 condition: expression,
                 body: Box::new(body),
+                else_body: else_body.map(Box::new),
             }
         }
         Rule::for_loop => {
@@ -73,9 +75,79 @@ pub fn parse_step_body_item(pair: pest::iterators::Pair<Rule>) -> StepBody {
             StepBody::ForLoop {
                 var: var_pair.as_str().to_string(),
                 in_expr,
-                body: Box::new(parse_step_body(body_pair)),
+                body: Box::new(parse_step_body(body_pair)?),
+            }
+        }
+        Rule::while_loop => {
+            let mut inner_pairs = pair.into_inner();
+            let expression = parse_expression(inner_pairs.next().expect("Expected expression in while loop"));
+            let body = parse_step_body(inner_pairs.next().expect("Expected step body in while loop"))?;
+
+            StepBody::WhileLoop {
+                condition: expression,
+                body: Box::new(body),
+            }
+        }
+        Rule::parallel_block => {
+            let mut options = ParallelOptions::default();
+            let mut branches = Vec::new();
+            for inner_pair in pair.into_inner() {
+                match inner_pair.as_rule() {
+                    Rule::parallel_options => options = parse_parallel_options(inner_pair)?,
+                    _ => {
+                        let branch_pair = inner_pair.into_inner().next().expect("Expected content in parallel branch");
+                        branches.push(match branch_pair.as_rule() {
+                            Rule::step_body => parse_step_body(branch_pair)?,
+                            _ => parse_step_body_item(branch_pair)?,
+                        });
+                    }
+                }
+            }
+
+            StepBody::Parallel { branches, options }
+        }
+        Rule::parallel_for_loop => {
+            let mut options = ParallelOptions::default();
+            let mut for_loop = None;
+            for inner_pair in pair.into_inner() {
+                match inner_pair.as_rule() {
+                    Rule::parallel_options => options = parse_parallel_options(inner_pair)?,
+                    _ => for_loop = Some(parse_step_body_item(inner_pair)?),
+                }
+            }
+
+            match for_loop {
+                Some(StepBody::ForLoop { var, in_expr, body }) => StepBody::ParallelForLoop {
+                    var,
+                    in_expr,
+                    body,
+                    options,
+                },
+                _ => panic!("Expected for loop in parallel for loop"),
             }
         }
+        Rule::try_catch => {
+            let mut body = None;
+            let mut error_register = None;
+            let mut catch_body = None;
+            for inner_pair in pair.into_inner() {
+                match inner_pair.as_rule() {
+                    Rule::register => error_register = Some(inner_pair.as_str().trim().to_string()),
+                    Rule::step_body if body.is_none() => body = Some(parse_step_body(inner_pair)?),
+                    Rule::step_body => catch_body = Some(parse_step_body(inner_pair)?),
+                    _ => panic!("Unexpected rule in try catch: {:?}", inner_pair.as_rule()),
+                }
+            }
+
+            StepBody::TryCatch {
+                body: Box::new(body.expect("Expected body in try catch")),
+                error_register,
+                catch_body: Box::new(catch_body.expect("Expected catch body in try catch")),
+            }
+        }
+        Rule::break_statement => StepBody::Break,
+        Rule::continue_statement => StepBody::Continue,
+        Rule::return_statement => StepBody::Return(pair.into_inner().next().map(parse_param)),
         Rule::register_operation => {
             let mut register_inner_pairs = pair.into_inner();
             let register_pair = register_inner_pairs
@@ -86,51 +158,99 @@ pub fn parse_step_body_item(pair: pest::iterators::Pair<Rule>) -> StepBody {
                 .expect("Expected value in register operation");
             StepBody::RegisterOperation {
                 register: register_pair.as_str().trim().to_string(),
-                value: parse_workflow_value(value_pair),
+                value: parse_workflow_value(value_pair)?,
             }
         }
         _ => panic!("Unexpected rule in step body item: {:?}", pair.as_rule()),
+    };
+    Ok(step_body)
+}
+
+pub fn parse_parallel_options(pair: pest::iterators::Pair<Rule>) -> Result<ParallelOptions, String> {
+    let mut options = ParallelOptions::default();
+    for option_pair in pair.into_inner() {
+        let value = option_pair
+            .clone()
+            .into_inner()
+            .next()
+            .expect("Expected value in parallel option");
+        match option_pair.as_rule() {
+            Rule::max_concurrency => {
+                let max_concurrency = value
+                    .as_str()
+                    .parse()
+                    .map_err(|_| format!("Invalid max concurrency: {}", value.as_str()))?;
+                options.max_concurrency = Some(max_concurrency)
+            }
+            Rule::on_error => options.collect_errors = value.as_str() == "collect",
+            _ => panic!("Unexpected parallel option: {:?}", option_pair.as_rule()),
+        }
+    }
+    Ok(options)
+}
+
+/// Parses the branch after `else`: either a body or, for `else if`, another condition
+pub fn parse_else_branch(pair: pest::iterators::Pair<Rule>) -> Result<StepBody, String> {
+    let inner_pair = pair.into_inner().next().expect("Expected condition or step body in else branch");
+    match inner_pair.as_rule() {
+        Rule::condition => parse_step_body_item(inner_pair),
+        Rule::step_body => parse_step_body(inner_pair),
+        _ => panic!("Unexpected rule in else branch: {:?}", inner_pair.as_rule()),
     }
 }
 
-pub fn parse_value_or_call(pair: pest::iterators::Pair<Rule>) -> WorkflowValue {
+pub fn parse_value_or_call(pair: pest::iterators::Pair<Rule>) -> Result<WorkflowValue, String> {
     match pair.as_rule() {
-        Rule::value => parse_workflow_value(pair),
-        Rule::external_fn_call => WorkflowValue::FunctionCall(parse_external_fn_call(pair)),
+        Rule::param => parse_workflow_value(pair),
+        Rule::external_fn_call => Ok(WorkflowValue::FunctionCall(parse_external_fn_call(pair)?)),
         _ => panic!("Expected value or external function call, found {:?}", pair.as_rule()),
     }
 }
 
-pub fn parse_external_fn_call(pair: pest::iterators::Pair<Rule>) -> FunctionCall {
+pub fn parse_external_fn_call(pair: pest::iterators::Pair<Rule>) -> Result<FunctionCall, String> {
     let mut inner_pairs = pair.into_inner();
     let name_pair = inner_pairs
         .next()
         .expect("Expected function name in external function call");
-    let args = inner_pairs.map(parse_param).collect();
+    let mut args = Vec::new();
+    let mut retry = None;
+    for inner_pair in inner_pairs {
+        match inner_pair.as_rule() {
+            Rule::retry_modifier => retry = Some(parse_retry_modifier(inner_pair)?),
+            _ => args.push(parse_param(inner_pair)),
+        }
+    }
 
-    FunctionCall {
+    Ok(FunctionCall {
         name: name_pair.as_str().to_string(),
         args,
-    }
+        retry,
+    })
+}
+
+pub fn parse_retry_modifier(pair: pest::iterators::Pair<Rule>) -> Result<RetryPolicy, String> {
+    let mut inner_pairs = pair.into_inner();
+    let retries_pair = inner_pairs.next().expect("Expected retries in retry modifier");
+    let retries = retries_pair
+        .as_str()
+        .parse()
+        .map_err(|_| format!("Invalid number of retries: {}", retries_pair.as_str()))?;
+    let backoff_ms = match inner_pairs.next() {
+        Some(backoff) => backoff
+            .as_str()
+            .parse()
+            .map_err(|_| format!("Invalid retry backoff: {}", backoff.as_str()))?,
+        None => 0,
+    };
+    Ok(RetryPolicy { retries, backoff_ms })
 }
 
-pub fn parse_action(pair: pest::iterators::Pair<Rule>) -> Action {
+pub fn parse_action(pair: pest::iterators::Pair<Rule>) -> Result<Action, String> {
     let mut inner_pairs = pair.into_inner();
     let first_pair = inner_pairs.next().expect("Expected content in action");
 
     match first_pair.as_rule() {
-        Rule::external_fn_call => {
-            let mut fn_call_inner_pairs = first_pair.into_inner();
-            let name_pair = fn_call_inner_pairs
-                .next()
-                .expect("Expected function name in external function call");
-            let args = fn_call_inner_pairs.map(parse_param).collect();
-
-            Action::ExternalFnCall(FunctionCall {
-                name: name_pair.as_str().to_string(),
-                args,
-            })
-        }
+        Rule::external_fn_call => Ok(Action::ExternalFnCall(parse_external_fn_call(first_pair)?)),
         Rule::command => {
             let command = first_pair.as_str().to_string();
             let params = inner_pairs
@@ -142,7 +262,7 @@ pub fn parse_action(pair: pest::iterators::Pair<Rule>) -> Action {
                 })
                 .collect::<Vec<_>>();
 
-            Action::Command { command, params }
+            Ok(Action::Command { command, params })
         }
         _ => panic!("Unexpected rule in action: {:?}", first_pair.as_rule()),
     }
@@ -159,7 +279,24 @@ pub fn parse_expression(pair: pest::iterators::Pair<Rule>) -> Expression {
                 end: Box::new(end),
             }
         }
-        Rule::expression => {
+        Rule::expression | Rule::primary_expression => {
+            parse_expression(pair.into_inner().next().expect("Expected content in expression"))
+        }
+        Rule::or_expression => parse_boolean_chain(pair, |left, right| Expression::Or { left, right }),
+        Rule::and_expression => parse_boolean_chain(pair, |left, right| Expression::And { left, right }),
+        Rule::unary_expression => {
+            let mut negations = 0;
+            let mut expression = None;
+            for inner_pair in pair.into_inner() {
+                match inner_pair.as_rule() {
+                    Rule::not_operator => negations += 1,
+                    _ => expression = Some(parse_expression(inner_pair)),
+                }
+            }
+            let expression = expression.expect("Expected expression after negations");
+            (0..negations).fold(expression, |expression, _| Expression::Not(Box::new(expression)))
+        }
+        Rule::comparison => {
             let mut inner_pairs = pair.into_inner();
             let first_expr = parse_param(
                 inner_pairs
@@ -189,8 +326,74 @@ pub fn parse_expression(pair: pest::iterators::Pair<Rule>) -> Expression {
     }
 }
 
+/// Folds the operands of a `||` or `&&` chain from the left
+fn parse_boolean_chain(
+    pair: pest::iterators::Pair<Rule>,
+    combine: fn(Box<Expression>, Box<Expression>) -> Expression,
+) -> Expression {
+    let mut operands = pair.into_inner().map(parse_expression);
+    let first = operands.next().expect("Expected operand in boolean expression");
+    operands.fold(first, |left, right| combine(Box::new(left), Box::new(right)))
+}
+
 pub fn parse_param(pair: pest::iterators::Pair<Rule>) -> Param {
-    let input = pair.as_str().trim();
+    match pair.as_rule() {
+        Rule::param => parse_param(pair.into_inner().next().expect("Expected content in param")),
+        Rule::additive_expression | Rule::multiplicative_expression => {
+            let mut inner_pairs = pair.into_inner();
+            let mut param = parse_param(inner_pairs.next().expect("Expected operand in operation"));
+            while let Some(operator_pair) = inner_pairs.next() {
+                let operator = parse_arithmetic_operator(operator_pair);
+                let right = parse_param(inner_pairs.next().expect("Expected operand after operator"));
+                param = Param::Operation {
+                    left: Box::new(param),
+                    operator,
+                    right: Box::new(right),
+                };
+            }
+            param
+        }
+        Rule::access_expression => {
+            let mut inner_pairs = pair.into_inner();
+            let base = parse_param(inner_pairs.next().expect("Expected value in access expression"));
+            let path: Vec<Accessor> = inner_pairs
+                .map(|accessor_pair| {
+                    let is_field = accessor_pair.as_rule() == Rule::field_access;
+                    let inner_pair = accessor_pair.into_inner().next().expect("Expected field or index");
+                    if is_field {
+                        Accessor::Field(inner_pair.as_str().to_string())
+                    } else {
+                        Accessor::Index(parse_param(inner_pair))
+                    }
+                })
+                .collect();
+
+            if path.is_empty() {
+                base
+            } else {
+                Param::Access {
+                    base: Box::new(base),
+                    path,
+                }
+            }
+        }
+        Rule::list_literal => Param::List(pair.into_inner().map(parse_param).collect()),
+        Rule::map_literal => Param::Map(
+            pair.into_inner()
+                .map(|entry_pair| {
+                    let mut inner_pairs = entry_pair.into_inner();
+                    let key = inner_pairs.next().expect("Expected key in map entry");
+                    let value = parse_param(inner_pairs.next().expect("Expected value in map entry"));
+                    (key.as_str().trim_matches('"').to_string(), value)
+                })
+                .collect(),
+        ),
+        _ => parse_literal(pair.as_str()),
+    }
+}
+
+fn parse_literal(input: &str) -> Param {
+    let input = input.trim();
 
     match identify_param_type(input) {
         "string" => {
@@ -199,9 +402,11 @@ pub fn parse_param(pair: pest::iterators::Pair<Rule>) -> Param {
             Param::String(stripped_string)
         }
         "number" => {
-            // Parse the string as a number, assuming it's valid since it matched the number rule
-            let number = input.parse().expect("Failed to parse number");
-            Param::Number(number)
+            // Parse the string as a number, assuming it's valid since it matched the number or float rule
+            match input.parse::<i64>() {
+                Ok(number) => Param::Number(number),
+                Err(_) => Param::Float(input.parse().expect("Failed to parse number")),
+            }
         }
         "boolean" => {
             // Parse the string as a boolean, assuming it's valid since it matched the boolean rule
@@ -236,7 +441,7 @@ fn identify_param_type(input: &str) -> &str {
         "string"
     } else if input.contains("..") {
         "range"
-    } else if input.parse::<f64>().is_ok() {
+    } else if is_number_literal(input) {
         "number"
     } else if input == "true" || input == "false" {
         "boolean"
@@ -249,6 +454,17 @@ fn identify_param_type(input: &str) -> &str {
     }
 }
 
+/// Digits with an optional sign and decimal part. Not `str::parse::<f64>`, which takes `nan` and `inf`.
+fn is_number_literal(input: &str) -> bool {
+    let digits = input.strip_prefix('-').unwrap_or(input);
+    let (integer, decimals) = match digits.split_once('.') {
+        Some((integer, decimals)) => (integer, Some(decimals)),
+        None => (digits, None),
+    };
+    let all_digits = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+    all_digits(integer) && decimals.is_none_or(all_digits)
+}
+
 pub fn parse_comparison_operator(pair: pest::iterators::Pair<Rule>) -> ComparisonOperator {
     match pair.as_str() {
         "==" => ComparisonOperator::Equal,
@@ -261,30 +477,32 @@ pub fn parse_comparison_operator(pair: pest::iterators::Pair<Rule>) -> Compariso
     }
 }
 
-pub fn parse_workflow_value(pair: pest::iterators::Pair<Rule>) -> WorkflowValue {
-    let input = pair.as_str().trim(); // Trim leading and trailing spaces
+pub fn parse_arithmetic_operator(pair: pest::iterators::Pair<Rule>) -> ArithmeticOperator {
+    match pair.as_str() {
+        "+" => ArithmeticOperator::Add,
+        "-" => ArithmeticOperator::Subtract,
+        "*" => ArithmeticOperator::Multiply,
+        "/" => ArithmeticOperator::Divide,
+        "%" => ArithmeticOperator::Modulo,
+        _ => panic!("Unexpected arithmetic operator: {}", pair.as_str()),
+    }
+}
 
-    match pair.as_rule() {
-        Rule::value => {
-            // Directly parse the value based on its content
-            if input.starts_with('"') && input.ends_with('"') {
-                let stripped_string = input.trim_matches('"').to_string();
-                WorkflowValue::String(stripped_string)
-            } else if input.parse::<i64>().is_ok() {
-                let number = input.parse::<i64>().expect("Failed to parse number");
-                WorkflowValue::Number(number)
-            } else if input == "true" || input == "false" {
-                let boolean = input.parse::<bool>().expect("Failed to parse boolean");
-                WorkflowValue::Boolean(boolean)
-            } else if input.starts_with('$') {
-                WorkflowValue::Register(input.to_string())
-            } else {
-                WorkflowValue::Identifier(input.to_string())
-            }
-        }
-        Rule::external_fn_call => WorkflowValue::FunctionCall(parse_external_fn_call(pair)),
+pub fn parse_workflow_value(pair: pest::iterators::Pair<Rule>) -> Result<WorkflowValue, String> {
+    let value = match pair.as_rule() {
+        // Plain values keep their own variants, anything else is evaluated as a param
+        Rule::param => match parse_param(pair) {
+            Param::String(s) => WorkflowValue::String(s),
+            Param::Number(n) => WorkflowValue::Number(n),
+            Param::Boolean(b) => WorkflowValue::Boolean(b),
+            Param::Register(register) => WorkflowValue::Register(register),
+            Param::Identifier(id) => WorkflowValue::Identifier(id),
+            param => WorkflowValue::Param(param),
+        },
+        Rule::external_fn_call => WorkflowValue::FunctionCall(parse_external_fn_call(pair)?),
         _ => panic!("Unexpected rule in parse_workflow_value: {:?}", pair.as_rule()),
-    }
+    };
+    Ok(value)
 }
 
 pub fn parse_workflow(dsl_input: &str) -> Result<Workflow, String> {
@@ -348,7 +566,7 @@ pub fn parse_step(pair: pest::iterators::Pair<Rule>) -> Result<Step, String> {
             }
             Rule::step_body => {
                 // Assuming step_body can directly contain action, condition, etc.
-                bodies.push(parse_step_body(inner_pair));
+                bodies.push(parse_step_body(inner_pair)?);
             }
             _ => return Err("Unexpected rule in step parsing".to_string()),
         }
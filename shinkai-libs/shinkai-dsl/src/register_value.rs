@@ -0,0 +1,163 @@
+use std::cmp::Ordering;
+
+use serde_json::{Map, Number, Value};
+
+use crate::dsl_schemas::{ArithmeticOperator, ComparisonOperator};
+
+// Registers of a running workflow hold JSON values: strings, numbers (integers and floats), booleans,
+// lists and maps. Missing registers and fields are `Null`.
+
+/// Renders a value the way functions receive it: strings as they are, `Null` as an empty string
+/// and everything else as JSON.
+pub fn to_register_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        _ => value.to_string(),
+    }
+}
+
+/// Converts the output of a function into a value. Outputs holding a JSON list or map become that value,
+/// so tool results don't have to be parsed again at every step. Anything else stays the string it was
+/// (numbers included, parsing them would rewrite "1.50" as "1.5").
+pub fn from_function_output(output: String) -> Value {
+    match serde_json::from_str::<Value>(output.trim()) {
+        Ok(value @ (Value::Array(_) | Value::Object(_))) => value,
+        _ => Value::String(output),
+    }
+}
+
+/// A value is false if it's `Null`, `false`, `0`, empty, or the string "false" or "0"
+pub fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !matches!(s.trim(), "" | "false" | "0"),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+/// Reads a field of a map. Strings holding JSON are parsed first.
+pub fn access_field(value: &Value, field: &str) -> Result<Value, String> {
+    match parse_json_string(value) {
+        Value::Object(map) => Ok(map.get(field).cloned().unwrap_or(Value::Null)),
+        Value::Null => Ok(Value::Null),
+        other => Err(format!("Cannot read field {} of {}", field, other)),
+    }
+}
+
+/// Reads an item of a list by position, or a field of a map by name. Strings holding JSON are parsed first.
+pub fn access_index(value: &Value, index: &Value) -> Result<Value, String> {
+    match (parse_json_string(value), index) {
+        (Value::Array(items), index) => match as_number(index).filter(|n| *n >= 0.0 && n.fract() == 0.0) {
+            Some(position) => Ok(items.get(position as usize).cloned().unwrap_or(Value::Null)),
+            None => Err(format!("Invalid list index {}", index)),
+        },
+        (Value::Object(map), index) => Ok(map.get(&to_register_string(index)).cloned().unwrap_or(Value::Null)),
+        (Value::Null, _) => Ok(Value::Null),
+        (other, index) => Err(format!("Cannot index {} with {}", other, index)),
+    }
+}
+
+/// Compares numbers (or strings holding numbers) numerically and anything else by its string form
+pub fn compare(left: &Value, operator: &ComparisonOperator, right: &Value) -> bool {
+    let ordering = match (as_number(left), as_number(right)) {
+        (Some(left), Some(right)) => left.partial_cmp(&right),
+        _ => Some(to_register_string(left).cmp(&to_register_string(right))),
+    };
+    match ordering {
+        Some(ordering) => match operator {
+            ComparisonOperator::Equal => ordering == Ordering::Equal,
+            ComparisonOperator::NotEqual => ordering != Ordering::Equal,
+            ComparisonOperator::Greater => ordering == Ordering::Greater,
+            ComparisonOperator::Less => ordering == Ordering::Less,
+            ComparisonOperator::GreaterEqual => ordering != Ordering::Less,
+            ComparisonOperator::LessEqual => ordering != Ordering::Greater,
+        },
+        None => *operator == ComparisonOperator::NotEqual,
+    }
+}
+
+/// Applies an arithmetic operator. `+` adds numbers, concatenates lists, merges maps (the right one wins)
+/// and otherwise concatenates the string forms when one side is a string. The other operators take
+/// numbers or strings holding numbers. Integers stay integers unless the result needs a fraction.
+pub fn apply_operator(left: &Value, operator: &ArithmeticOperator, right: &Value) -> Result<Value, String> {
+    if *operator == ArithmeticOperator::Add {
+        match (left, right) {
+            (Value::Number(_), Value::Number(_)) => {}
+            (Value::Array(left_items), Value::Array(right_items)) => {
+                return Ok(Value::Array(left_items.iter().chain(right_items).cloned().collect()));
+            }
+            (Value::Object(left_map), Value::Object(right_map)) => {
+                let mut merged: Map<String, Value> = left_map.clone();
+                merged.extend(right_map.clone());
+                return Ok(Value::Object(merged));
+            }
+            (Value::String(_), _) | (_, Value::String(_)) => {
+                return Ok(Value::String(to_register_string(left) + &to_register_string(right)));
+            }
+            _ => return Err(format!("Cannot add {} and {}", left, right)),
+        }
+    }
+
+    let (left_number, right_number) = match (as_number(left), as_number(right)) {
+        (Some(left_number), Some(right_number)) => (left_number, right_number),
+        _ => return Err(format!("{:?} needs numbers, got {} and {}", operator, left, right)),
+    };
+    if matches!(operator, ArithmeticOperator::Divide | ArithmeticOperator::Modulo) && right_number == 0.0 {
+        return Err("Division by zero".to_string());
+    }
+
+    if let (Some(left_int), Some(right_int)) = (as_integer(left), as_integer(right)) {
+        let result = match operator {
+            ArithmeticOperator::Add => left_int.checked_add(right_int),
+            ArithmeticOperator::Subtract => left_int.checked_sub(right_int),
+            ArithmeticOperator::Multiply => left_int.checked_mul(right_int),
+            ArithmeticOperator::Divide if left_int.checked_rem(right_int) == Some(0) => left_int.checked_div(right_int),
+            ArithmeticOperator::Divide => None,
+            ArithmeticOperator::Modulo => left_int.checked_rem(right_int),
+        };
+        if let Some(result) = result {
+            return Ok(Value::from(result));
+        }
+    }
+
+    let result = match operator {
+        ArithmeticOperator::Add => left_number + right_number,
+        ArithmeticOperator::Subtract => left_number - right_number,
+        ArithmeticOperator::Multiply => left_number * right_number,
+        ArithmeticOperator::Divide => left_number / right_number,
+        ArithmeticOperator::Modulo => left_number % right_number,
+    };
+    Number::from_f64(result)
+        .map(Value::Number)
+        .ok_or_else(|| format!("{:?} of {} and {} is not a number", operator, left, right))
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok().filter(|n| n.is_finite()),
+        _ => None,
+    }
+}
+
+fn as_integer(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.trim().parse::<i64>().ok(),
+        _ => None,
+    }
+}
+
+fn parse_json_string(value: &Value) -> Value {
+    match value {
+        Value::String(s) => match serde_json::from_str::<Value>(s.trim()) {
+            Ok(parsed @ (Value::Array(_) | Value::Object(_))) => parsed,
+            _ => value.clone(),
+        },
+        _ => value.clone(),
+    }
+}
@@ -3,8 +3,8 @@ mod tests {
     use pest::Parser;
     use shinkai_dsl::{
         dsl_schemas::{
-            Action, ComparisonOperator, Expression, ForLoopExpression, Param, Rule, StepBody, Workflow, WorkflowParser,
-            WorkflowValue,
+            Accessor, Action, ArithmeticOperator, ComparisonOperator, Expression, ForLoopExpression, ParallelOptions,
+            Param, RetryPolicy, Rule, StepBody, Workflow, WorkflowParser, WorkflowValue,
         },
         parser::{parse_action, parse_expression, parse_step, parse_step_body, parse_step_body_item, parse_workflow},
     };
@@ -81,7 +81,7 @@ mod tests {
     fn test_parse_action_command() {
         let input = r#"command("param1", "param2")"#;
         let pair = WorkflowParser::parse(Rule::action, input).unwrap().next().unwrap();
-        let action = parse_action(pair);
+        let action = parse_action(pair).unwrap();
         match action {
             Action::Command { command, params } => {
                 assert_eq!(command, "command");
@@ -95,7 +95,7 @@ mod tests {
     fn test_parse_action_external_fn_call() {
         let input = r#"call functionName("param1", "param2")"#;
         let pair = WorkflowParser::parse(Rule::action, input).unwrap().next().unwrap();
-        let action = parse_action(pair);
+        let action = parse_action(pair).unwrap();
         match action {
             Action::ExternalFnCall(fn_call) => {
                 assert_eq!(fn_call.name, "functionName");
@@ -156,12 +156,13 @@ mod tests {
         let input = r#"if param1 > 10 { command("doSomething") }"#;
         let pair = WorkflowParser::parse(Rule::step_body, input).unwrap().next().unwrap();
         eprintln!("{:?}", pair);
-        let step_body = parse_step_body(pair);
+        let step_body = parse_step_body(pair).unwrap();
         println!("{:?}", step_body);
         match step_body {
             StepBody::Condition {
                 condition,
                 body: action,
+                else_body: None,
             } => {
                 match condition {
                     Expression::Binary { left, operator, right } => {
@@ -186,6 +187,354 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_condition_with_else_if() {
+        let input = r#"if $R1 >= 10 { $R2 = "big" } else if $R1 <= 0 { $R2 = "none" } else { $R2 = "small" }"#;
+        let pair = WorkflowParser::parse(Rule::step_body, input).unwrap().next().unwrap();
+        let step_body = parse_step_body(pair).unwrap();
+
+        match step_body {
+            StepBody::Condition {
+                condition,
+                else_body: Some(else_body),
+                ..
+            } => {
+                assert!(matches!(
+                    condition,
+                    Expression::Binary {
+                        operator: ComparisonOperator::GreaterEqual,
+                        ..
+                    }
+                ));
+                match *else_body {
+                    StepBody::Condition {
+                        condition,
+                        else_body: Some(else_body),
+                        ..
+                    } => {
+                        assert!(matches!(
+                            condition,
+                            Expression::Binary {
+                                operator: ComparisonOperator::LessEqual,
+                                ..
+                            }
+                        ));
+                        assert!(matches!(*else_body, StepBody::RegisterOperation { .. }));
+                    }
+                    _ => panic!("Expected Condition for else if"),
+                }
+            }
+            _ => panic!("Expected Condition with an else branch"),
+        }
+    }
+
+    #[test]
+    fn test_parse_boolean_expression() {
+        // && binds tighter than ||, parentheses and ! override it
+        let input = r#"$A == 1 || $B == 2 && !($C || $D)"#;
+        let pair = WorkflowParser::parse(Rule::expression, input).unwrap().next().unwrap();
+        let expression = parse_expression(pair);
+
+        match expression {
+            Expression::Or { left, right } => {
+                assert!(matches!(*left, Expression::Binary { .. }));
+                match *right {
+                    Expression::And { left, right } => {
+                        assert!(matches!(*left, Expression::Binary { .. }));
+                        match *right {
+                            Expression::Not(inner) => match *inner {
+                                Expression::Or { left, right } => {
+                                    assert!(matches!(*left, Expression::Simple(_)));
+                                    assert!(matches!(*right, Expression::Simple(_)));
+                                }
+                                _ => panic!("Expected Or inside the parentheses"),
+                            },
+                            _ => panic!("Expected Not"),
+                        }
+                    }
+                    _ => panic!("Expected And on the right of Or"),
+                }
+            }
+            _ => panic!("Expected Or expression"),
+        }
+    }
+
+    #[test]
+    fn test_parse_while_loop() {
+        let input = r#"while $R1 < 3 {
+            $R1 = call sum($R1, 1)
+            breakdown()
+            break
+        }
+        return
+        return $R1"#;
+        let pair = WorkflowParser::parse(Rule::step_body, input).unwrap().next().unwrap();
+        let step_body = parse_step_body(pair).unwrap();
+
+        match step_body {
+            StepBody::Composite(bodies) => {
+                assert_eq!(bodies.len(), 3);
+                match &bodies[0] {
+                    StepBody::WhileLoop { condition, body } => {
+                        assert!(matches!(
+                            condition,
+                            Expression::Binary {
+                                operator: ComparisonOperator::Less,
+                                ..
+                            }
+                        ));
+                        match body.as_ref() {
+                            StepBody::Composite(loop_bodies) => {
+                                assert!(matches!(loop_bodies[1], StepBody::Action(Action::Command { .. })));
+                                assert_eq!(loop_bodies[2], StepBody::Break);
+                            }
+                            _ => panic!("Expected Composite body in while loop"),
+                        }
+                    }
+                    _ => panic!("Expected WhileLoop"),
+                }
+                assert_eq!(bodies[1], StepBody::Return(None));
+                assert_eq!(bodies[2], StepBody::Return(Some(Param::Register("$R1".to_string()))));
+            }
+            _ => panic!("Expected Composite step body"),
+        }
+    }
+
+    #[test]
+    fn test_parse_non_digit_numbers_are_identifiers() {
+        let pair = WorkflowParser::parse(Rule::register_operation, "$R1 = [nan, inf, infinity, 2.5]")
+            .unwrap()
+            .next()
+            .unwrap();
+        let step_body = parse_step_body_item(pair).unwrap();
+
+        assert_eq!(
+            step_body,
+            StepBody::RegisterOperation {
+                register: "$R1".to_string(),
+                value: WorkflowValue::Param(Param::List(vec![
+                    Param::Identifier("nan".to_string()),
+                    Param::Identifier("inf".to_string()),
+                    Param::Identifier("infinity".to_string()),
+                    Param::Float(2.5),
+                ])),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_structured_values() {
+        let input = r#"$R2 = { "total": $R1.items[0].price * 2 + 1.5, tags: ["a", $TAG] }"#;
+        let pair = WorkflowParser::parse(Rule::register_operation, input)
+            .unwrap()
+            .next()
+            .unwrap();
+        let step_body = parse_step_body_item(pair).unwrap();
+
+        let access = Param::Access {
+            base: Box::new(Param::Register("$R1".to_string())),
+            path: vec![
+                Accessor::Field("items".to_string()),
+                Accessor::Index(Param::Number(0)),
+                Accessor::Field("price".to_string()),
+            ],
+        };
+        let total = Param::Operation {
+            left: Box::new(Param::Operation {
+                left: Box::new(access),
+                operator: ArithmeticOperator::Multiply,
+                right: Box::new(Param::Number(2)),
+            }),
+            operator: ArithmeticOperator::Add,
+            right: Box::new(Param::Float(1.5)),
+        };
+        let tags = Param::List(vec![
+            Param::String("a".to_string()),
+            Param::Register("$TAG".to_string()),
+        ]);
+        assert_eq!(
+            step_body,
+            StepBody::RegisterOperation {
+                register: "$R2".to_string(),
+                value: WorkflowValue::Param(Param::Map(vec![
+                    ("total".to_string(), total),
+                    ("tags".to_string(), tags)
+                ])),
+            }
+        );
+
+        // Parentheses group values as well as conditions
+        let pair = WorkflowParser::parse(Rule::expression, "($A + 1) * 2 > $B.count && ($C || $D)")
+            .unwrap()
+            .next()
+            .unwrap();
+        match parse_expression(pair) {
+            Expression::And { left, right } => {
+                assert!(matches!(
+                    *left,
+                    Expression::Binary {
+                        operator: ComparisonOperator::Greater,
+                        ..
+                    }
+                ));
+                assert!(matches!(*right, Expression::Or { .. }));
+            }
+            _ => panic!("Expected And expression"),
+        }
+    }
+
+    #[test]
+    fn test_parse_parallel() {
+        let input = r#"parallel(max: 2, on_error: collect) {
+            $R1 = call summarize($A)
+            {
+                $R2 = call summarize($B)
+                $R3 = $R2 + "!"
+            }
+        }
+        parallel for chunk in $CHUNKS.split(":::") {
+            $ALL = $ALL + [chunk]
+        }"#;
+        let pair = WorkflowParser::parse(Rule::step_body, input).unwrap().next().unwrap();
+        let step_body = parse_step_body(pair).unwrap();
+
+        match step_body {
+            StepBody::Composite(bodies) => {
+                match &bodies[0] {
+                    StepBody::Parallel { branches, options } => {
+                        assert_eq!(
+                            *options,
+                            ParallelOptions {
+                                max_concurrency: Some(2),
+                                collect_errors: true,
+                            }
+                        );
+                        assert_eq!(branches.len(), 2);
+                        assert!(matches!(branches[0], StepBody::RegisterOperation { .. }));
+                        assert!(matches!(branches[1], StepBody::Composite(ref group) if group.len() == 2));
+                    }
+                    _ => panic!("Expected Parallel"),
+                }
+                match &bodies[1] {
+                    StepBody::ParallelForLoop {
+                        var, in_expr, options, ..
+                    } => {
+                        assert_eq!(var, "chunk");
+                        assert!(matches!(in_expr, ForLoopExpression::Split { delimiter, .. } if delimiter == ":::"));
+                        assert_eq!(*options, ParallelOptions::default());
+                    }
+                    _ => panic!("Expected ParallelForLoop"),
+                }
+            }
+            _ => panic!("Expected Composite step body"),
+        }
+    }
+
+    #[test]
+    fn test_parse_parallel_invalid_max() {
+        let dsl_input = r#"
+        workflow Overflow v0.1 {
+            step Fetch {
+                parallel(max: 99999999999999999999999) {
+                    $R1 = call summarize($A)
+                }
+            }
+        }
+        "#;
+        let error = parse_workflow(dsl_input).unwrap_err();
+        assert!(error.contains("max concurrency"));
+    }
+
+    #[test]
+    fn test_parse_retry_invalid_count() {
+        let error = parse_workflow(r#"workflow Overflow v0.1 { step Fetch { call fetch($URL) retry(99999999999) } }"#)
+            .unwrap_err();
+        assert!(error.contains("retries"));
+
+        let error = parse_workflow(
+            r#"workflow Overflow v0.1 { step Fetch { $PAGE = call fetch($URL) retry(1, 99999999999999999999) } }"#,
+        )
+        .unwrap_err();
+        assert!(error.contains("backoff"));
+    }
+
+    #[test]
+    fn test_parse_try_catch_and_retry() {
+        let input = r#"try {
+            $PAGE = call download_webpage($URL) retry(3, 500)
+            call log($PAGE) retry(2)
+        } catch $ERR {
+            $PAGE = ""
+        }
+        try { call notify($PAGE) } catch { break }"#;
+        let pair = WorkflowParser::parse(Rule::step_body, input).unwrap().next().unwrap();
+        let step_body = parse_step_body(pair).unwrap();
+
+        match step_body {
+            StepBody::Composite(bodies) => {
+                match &bodies[0] {
+                    StepBody::TryCatch {
+                        body,
+                        error_register,
+                        catch_body,
+                    } => {
+                        assert_eq!(error_register.as_deref(), Some("$ERR"));
+                        match body.as_ref() {
+                            StepBody::Composite(try_bodies) => {
+                                match &try_bodies[0] {
+                                    StepBody::RegisterOperation {
+                                        value: WorkflowValue::FunctionCall(call),
+                                        ..
+                                    } => {
+                                        assert_eq!(call.name, "download_webpage");
+                                        assert_eq!(call.args, vec![Param::Register("$URL".to_string())]);
+                                        assert_eq!(
+                                            call.retry,
+                                            Some(RetryPolicy {
+                                                retries: 3,
+                                                backoff_ms: 500,
+                                            })
+                                        );
+                                    }
+                                    _ => panic!("Expected function call register operation"),
+                                }
+                                match &try_bodies[1] {
+                                    StepBody::Action(Action::ExternalFnCall(call)) => assert_eq!(
+                                        call.retry,
+                                        Some(RetryPolicy {
+                                            retries: 2,
+                                            backoff_ms: 0,
+                                        })
+                                    ),
+                                    _ => panic!("Expected external function call"),
+                                }
+                            }
+                            _ => panic!("Expected Composite try body"),
+                        }
+                        assert!(matches!(catch_body.as_ref(), StepBody::RegisterOperation { .. }));
+                    }
+                    _ => panic!("Expected TryCatch"),
+                }
+                match &bodies[1] {
+                    StepBody::TryCatch {
+                        body,
+                        error_register,
+                        catch_body,
+                    } => {
+                        assert_eq!(*error_register, None);
+                        assert!(matches!(
+                            body.as_ref(),
+                            StepBody::Action(Action::ExternalFnCall(call)) if call.retry.is_none()
+                        ));
+                        assert_eq!(**catch_body, StepBody::Break);
+                    }
+                    _ => panic!("Expected TryCatch"),
+                }
+            }
+            _ => panic!("Expected Composite step body"),
+        }
+    }
+
     #[test]
     fn test_parse_register_operation() {
         let input = r#"$R1 = 42"#;
@@ -193,7 +542,7 @@ mod tests {
             .unwrap()
             .next()
             .unwrap();
-        let step_body = parse_step_body_item(pair);
+        let step_body = parse_step_body_item(pair).unwrap();
         match step_body {
             StepBody::RegisterOperation { register, value } => {
                 assert_eq!(register, "$R1");
@@ -262,6 +611,7 @@ mod tests {
             StepBody::Condition {
                 condition,
                 body: action,
+                ..
             } => {
                 match condition {
                     Expression::Binary { left, operator, right } => {
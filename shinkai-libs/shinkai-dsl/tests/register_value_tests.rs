@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use shinkai_dsl::{
+        dsl_schemas::{ArithmeticOperator, ComparisonOperator},
+        register_value::{
+            access_field, access_index, apply_operator, compare, from_function_output, is_truthy, to_register_string,
+        },
+    };
+
+    #[test]
+    fn test_function_output_round_trip() {
+        assert_eq!(
+            from_function_output(r#"{"items": [1, 2]}"#.to_string()),
+            json!({"items": [1, 2]})
+        );
+        assert_eq!(from_function_output("[1, 2]".to_string()), json!([1, 2]));
+        // Scalars keep their exact text
+        assert_eq!(from_function_output("1.50".to_string()), json!("1.50"));
+        assert_eq!(from_function_output("1e3".to_string()), json!("1e3"));
+        assert_eq!(
+            from_function_output("123456789012345678901234567890".to_string()),
+            json!("123456789012345678901234567890")
+        );
+        assert_eq!(from_function_output("true".to_string()), json!("true"));
+        assert_eq!(from_function_output("Hello World".to_string()), json!("Hello World"));
+        // JSON strings keep their quotes, the output is not a JSON document for the workflow
+        assert_eq!(from_function_output(r#""quoted""#.to_string()), json!(r#""quoted""#));
+
+        assert_eq!(to_register_string(&json!("Hello World")), "Hello World");
+        assert_eq!(to_register_string(&json!({"items": [1, 2]})), r#"{"items":[1,2]}"#);
+        assert_eq!(to_register_string(&json!(null)), "");
+    }
+
+    #[test]
+    fn test_access() {
+        let value = json!({"items": [{"name": "first"}, {"name": "second"}]});
+        let items = access_field(&value, "items").unwrap();
+        let item = access_index(&items, &json!(1)).unwrap();
+        assert_eq!(access_field(&item, "name").unwrap(), json!("second"));
+
+        // Strings holding JSON, like the output of older functions, are parsed
+        let raw = json!(r#"{"answer": {"score": 0.5}}"#);
+        let answer = access_field(&raw, "answer").unwrap();
+        assert_eq!(access_index(&answer, &json!("score")).unwrap(), json!(0.5));
+
+        assert_eq!(access_field(&value, "missing").unwrap(), json!(null));
+        assert_eq!(access_index(&items, &json!(5)).unwrap(), json!(null));
+        assert!(access_field(&json!(3), "name").is_err());
+        assert!(access_index(&items, &json!("name")).is_err());
+    }
+
+    #[test]
+    fn test_apply_operator() {
+        assert_eq!(
+            apply_operator(&json!(7), &ArithmeticOperator::Add, &json!(3)).unwrap(),
+            json!(10)
+        );
+        assert_eq!(
+            apply_operator(&json!(7), &ArithmeticOperator::Divide, &json!(2)).unwrap(),
+            json!(3.5)
+        );
+        assert_eq!(
+            apply_operator(&json!(8), &ArithmeticOperator::Divide, &json!(2)).unwrap(),
+            json!(4)
+        );
+        assert_eq!(
+            apply_operator(&json!(7), &ArithmeticOperator::Modulo, &json!(3)).unwrap(),
+            json!(1)
+        );
+        assert_eq!(
+            apply_operator(&json!(1.5), &ArithmeticOperator::Multiply, &json!("2")).unwrap(),
+            json!(3.0)
+        );
+        assert_eq!(
+            apply_operator(&json!("Step "), &ArithmeticOperator::Add, &json!(2)).unwrap(),
+            json!("Step 2")
+        );
+        assert_eq!(
+            apply_operator(&json!([1]), &ArithmeticOperator::Add, &json!([2])).unwrap(),
+            json!([1, 2])
+        );
+        assert_eq!(
+            apply_operator(&json!({"a": 1, "b": 1}), &ArithmeticOperator::Add, &json!({"b": 2})).unwrap(),
+            json!({"a": 1, "b": 2})
+        );
+        assert!(apply_operator(&json!(1), &ArithmeticOperator::Divide, &json!(0)).is_err());
+        assert!(apply_operator(&json!("a"), &ArithmeticOperator::Subtract, &json!(1)).is_err());
+        assert!(apply_operator(&json!(true), &ArithmeticOperator::Add, &json!(1)).is_err());
+    }
+
+    #[test]
+    fn test_compare_and_truthiness() {
+        assert!(compare(&json!(10), &ComparisonOperator::Greater, &json!("9")));
+        assert!(compare(&json!(2.5), &ComparisonOperator::LessEqual, &json!(2.5)));
+        assert!(compare(&json!("apple"), &ComparisonOperator::Less, &json!("banana")));
+        assert!(compare(&json!("abc"), &ComparisonOperator::NotEqual, &json!("xyz")));
+        assert!(compare(&json!(true), &ComparisonOperator::Equal, &json!("true")));
+
+        assert!(is_truthy(&json!([0])));
+        assert!(is_truthy(&json!("no")));
+        assert!(!is_truthy(&json!({})));
+        assert!(!is_truthy(&json!(0.0)));
+        assert!(!is_truthy(&json!("false")));
+        assert!(!is_truthy(&json!(null)));
+    }
+}
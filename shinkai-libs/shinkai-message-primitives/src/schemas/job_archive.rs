@@ -0,0 +1,82 @@
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+
+use crate::shinkai_utils::signatures::{signature_public_key_to_string, string_to_signature_public_key};
+
+/// A job exported by a node, in a single file that can be moved to another node or archived.
+/// `archive` is the JSON of the job (messages, step history, execution context, scope and attached files)
+/// and it's signed by the node that exported it, so the node importing it can check it wasn't modified and
+/// that it comes from that node.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SignedJobArchive {
+    pub archive: String,
+    /// Hex encoded ed25519 signature of `archive`
+    pub signature: String,
+    /// Hex encoded identity public key of the node that exported the job
+    pub signer_public_key: String,
+}
+
+impl SignedJobArchive {
+    pub fn sign(archive: String, signing_key: &SigningKey) -> Self {
+        let signature = signing_key.sign(archive.as_bytes());
+        Self {
+            archive,
+            signature: hex::encode(signature.to_bytes()),
+            signer_public_key: signature_public_key_to_string(signing_key.verifying_key()),
+        }
+    }
+
+    /// Checks that `archive` was signed by `expected_signer`, the identity key of the node that exported it.
+    /// The key stored in `signer_public_key` is only informative: anyone editing the archive can re-sign it
+    /// with their own key and replace it.
+    pub fn verify(&self, expected_signer: &VerifyingKey) -> Result<(), &'static str> {
+        let public_key = string_to_signature_public_key(&self.signer_public_key)?;
+        if public_key != *expected_signer {
+            return Err("Archive not signed by the node that exported it");
+        }
+        let signature_bytes: [u8; 64] = hex::decode(&self.signature)
+            .map_err(|_| "Failed to decode signature")?
+            .try_into()
+            .map_err(|_| "Invalid signature length")?;
+        let signature = Signature::from_bytes(&signature_bytes);
+
+        public_key
+            .verify(self.archive.as_bytes(), &signature)
+            .map_err(|_| "Invalid archive signature")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shinkai_utils::signatures::unsafe_deterministic_signature_keypair;
+
+    #[test]
+    fn test_sign_and_verify_job_archive() {
+        let (signing_key, verifying_key) = unsafe_deterministic_signature_keypair(0);
+        let signed = SignedJobArchive::sign(r#"{"job":"jobid_1"}"#.to_string(), &signing_key);
+        assert_eq!(signed.signer_public_key, signature_public_key_to_string(verifying_key));
+        assert!(signed.verify(&verifying_key).is_ok());
+
+        let tampered = SignedJobArchive {
+            archive: r#"{"job":"jobid_2"}"#.to_string(),
+            ..signed.clone()
+        };
+        assert!(tampered.verify(&verifying_key).is_err());
+
+        // Swapping the key doesn't make the original signature valid
+        let (other_key, other_verifying_key) = unsafe_deterministic_signature_keypair(1);
+        let forged = SignedJobArchive {
+            signer_public_key: signature_public_key_to_string(other_verifying_key),
+            ..signed
+        };
+        assert!(forged.verify(&verifying_key).is_err());
+        assert!(forged.verify(&other_verifying_key).is_err());
+
+        // An archive edited and re-signed with another key is valid on its own, but not from the exporting node
+        let resigned = SignedJobArchive::sign(r#"{"job":"jobid_2"}"#.to_string(), &other_key);
+        assert!(resigned.verify(&other_verifying_key).is_ok());
+        assert!(resigned.verify(&verifying_key).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+
+use super::llm_providers::{customized_agent::CustomizedAgent, inference_params::InferenceParams};
+
+/// How the conversation history of a job is kept within the context window of its llm provider
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryStrategy {
+    /// The oldest messages are dropped when the prompt doesn't fit
+    Truncate,
+    /// The oldest messages are summarized by the llm provider and the summary is added to the prompt
+    #[default]
+    Summarize,
+    /// The recent messages are kept along with the older ones most relevant to the new message
+    Retrieval,
+}
+
+/// Scheduling class of the messages of a job. Queued messages of a higher class are always processed first.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPriority {
+    /// Chats waiting for an answer
+    #[default]
+    Interactive,
+    /// Work nobody is waiting on (e.g. workflows and scheduled tasks)
+    Background,
+    /// Large fan-outs (e.g. sheet cells)
+    Batch,
+}
+
+fn default_history_threshold() -> f32 {
+    0.6
+}
+
+fn default_retrieval_weight() -> f32 {
+    1.0
+}
+
+/// How the content relevant to a message is retrieved from the scope of a job
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum RetrievalMode {
+    /// Embedding similarity
+    #[default]
+    Vector,
+    /// BM25 over the text of the scope, for exact identifiers, error codes and names
+    Keyword,
+    /// Both, merged with reciprocal rank fusion. The weights scale the contribution of each ranking.
+    Hybrid {
+        #[serde(default = "default_retrieval_weight")]
+        vector_weight: f32,
+        #[serde(default = "default_retrieval_weight")]
+        keyword_weight: f32,
+    },
+}
+
+/// Settings of a single job. Every field has a default so jobs created before a setting existed keep working.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JobConfig {
+    /// Skips the llm provider's response cache (cached responses are neither read nor stored)
+    #[serde(default)]
+    pub bypass_response_cache: bool,
+    /// What to do once the history gets too long
+    #[serde(default)]
+    pub history_strategy: HistoryStrategy,
+    /// Fraction (0 to 1) of the llm provider's max input tokens the history can take before the strategy applies
+    #[serde(default = "default_history_threshold")]
+    pub history_threshold: f32,
+    /// Adds the most relevant entries of the profile's memory of past conversations to the prompt
+    #[serde(default)]
+    pub use_memory: bool,
+    /// Keeps the messages of the job out of the profile's memory
+    #[serde(default)]
+    pub exclude_from_memory: bool,
+    /// Overrides the scheduling class of the messages of the job (by default sheet jobs are batch and the rest
+    /// interactive)
+    #[serde(default)]
+    pub priority: Option<JobPriority>,
+    /// Job preset the job was created from (its system prompt, tools and limits apply to the job)
+    #[serde(default)]
+    pub preset_id: Option<String>,
+    /// Sampling settings of the llm provider calls (each message can override them)
+    #[serde(default)]
+    pub inference_params: InferenceParams,
+    /// Agents answering the messages of the job. The first one leads: it answers each message and, if it allows
+    /// delegation, hands sub-tasks to the others.
+    #[serde(default)]
+    pub agents: Vec<CustomizedAgent>,
+    /// How the scope of the job is searched
+    #[serde(default)]
+    pub retrieval_mode: RetrievalMode,
+}
+
+impl Default for JobConfig {
+    fn default() -> Self {
+        Self {
+            bypass_response_cache: false,
+            history_strategy: HistoryStrategy::default(),
+            history_threshold: default_history_threshold(),
+            use_memory: false,
+            exclude_from_memory: false,
+            priority: None,
+            preset_id: None,
+            inference_params: InferenceParams::default(),
+            agents: vec![],
+            retrieval_mode: RetrievalMode::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_config_defaults() {
+        let config: JobConfig = serde_json::from_str(r#"{"bypass_response_cache": true}"#).unwrap();
+        assert!(config.bypass_response_cache);
+        assert_eq!(config.history_strategy, HistoryStrategy::Summarize);
+        assert_eq!(config.history_threshold, 0.6);
+        assert!(!config.use_memory);
+        assert!(!config.exclude_from_memory);
+        assert_eq!(config.priority, None);
+        assert_eq!(config.preset_id, None);
+        assert!(config.inference_params.is_empty());
+        assert!(config.agents.is_empty());
+        assert_eq!(config.retrieval_mode, RetrievalMode::Vector);
+        assert_eq!(
+            serde_json::from_str::<JobConfig>("{}").unwrap(),
+            JobConfig::default()
+        );
+
+        let config: JobConfig = serde_json::from_str(r#"{"history_strategy": "retrieval"}"#).unwrap();
+        assert_eq!(config.history_strategy, HistoryStrategy::Retrieval);
+
+        let config: JobConfig = serde_json::from_str(r#"{"priority": "batch"}"#).unwrap();
+        assert_eq!(config.priority, Some(JobPriority::Batch));
+        assert!(JobPriority::Interactive < JobPriority::Background && JobPriority::Background < JobPriority::Batch);
+
+        let config: JobConfig =
+            serde_json::from_str(r#"{"inference_params": {"temperature": 0.0, "seed": 7}}"#).unwrap();
+        assert_eq!(config.inference_params.temperature, Some(0.0));
+        assert_eq!(config.inference_params.seed, Some(7));
+
+        let config: JobConfig = serde_json::from_str(
+            r#"{"agents": [
+                {"role": "Lead", "goal": "Answer the user", "backstory": "Manages the team", "allow_delegation": true},
+                {"role": "Researcher", "goal": "Find facts", "backstory": "Knows where to look", "max_rpm": 10}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.agents.len(), 2);
+        assert!(config.agents[0].allow_delegation);
+        assert!(config.agents[0].tools.is_empty());
+        assert_eq!(config.agents[1].llm_provider_id, None);
+        assert_eq!(config.agents[1].max_rpm, Some(10));
+
+        // Agents of older configs embedded their llm provider, api key included: it's dropped when read
+        let config: JobConfig = serde_json::from_str(
+            r#"{"agents": [{"role": "Lead", "goal": "Answer", "backstory": "Manager", "llm": {"api_key": "sk-1"}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.agents[0].llm_provider_id, None);
+        assert!(!serde_json::to_string(&config).unwrap().contains("sk-1"));
+
+        let config: JobConfig =
+            serde_json::from_str(r#"{"retrieval_mode": {"mode": "hybrid", "keyword_weight": 0.5}}"#).unwrap();
+        assert_eq!(
+            config.retrieval_mode,
+            RetrievalMode::Hybrid {
+                vector_weight: 1.0,
+                keyword_weight: 0.5
+            }
+        );
+    }
+}
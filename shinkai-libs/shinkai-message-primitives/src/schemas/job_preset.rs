@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use crate::shinkai_utils::job_scope::JobScope;
+
+/// Template for new jobs of a profile: the llm provider they talk to, what they know and how they answer.
+/// Jobs created from a preset keep a reference to it (`JobConfig::preset_id`), so editing the preset
+/// changes how their next messages are processed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JobPreset {
+    /// Generated by the node when the preset is added without one
+    #[serde(default)]
+    pub preset_id: String,
+    pub name: String,
+    /// Replaces the default system prompt of the jobs
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Id of the default llm provider of the jobs
+    pub llm_provider_id: String,
+    /// Default scope of the jobs, used when the job is created with an empty scope
+    #[serde(default = "JobScope::new_default")]
+    pub scope: JobScope,
+    /// Tool router keys of the tools the jobs can call (if empty, the enabled tools most relevant to each message)
+    #[serde(default)]
+    pub tools: Vec<String>,
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+    /// Max number of tool calls answering a single message
+    #[serde(default)]
+    pub max_iterations: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_preset_defaults() {
+        let preset: JobPreset =
+            serde_json::from_str(r#"{"name": "Researcher", "llm_provider_id": "my_gpt"}"#).unwrap();
+        assert_eq!(preset.preset_id, "");
+        assert_eq!(preset.system_prompt, None);
+        assert!(preset.scope.is_empty());
+        assert!(preset.tools.is_empty());
+        assert_eq!(preset.max_iterations, None);
+    }
+}
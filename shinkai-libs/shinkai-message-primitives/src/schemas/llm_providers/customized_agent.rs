@@ -1,21 +1,38 @@
-use crate::schemas::llm_providers::serialized_llm_provider::SerializedLLMProvider;
 use serde::{Deserialize, Serialize};
 
 // Based on the great job by crewai (mostly for for compatibility) https://docs.crewai.com
 
+/// An agent working on a job (see `JobConfig::agents`). The role identifies it among its coworkers.
+/// LLM providers are referenced by id, so their api keys stay out of the job config (and out of exported jobs).
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct CustomizedAgent {
     pub role: String,
     pub goal: String,
     pub backstory: String,
-    pub llm: Option<SerializedLLMProvider>,
+    /// Id of the LLM provider of the agent, among the ones of the profile (the one of the job if unset)
+    #[serde(default)]
+    pub llm_provider_id: Option<String>,
+    /// Tool router keys of the tools the agent can call
+    #[serde(default)]
     pub tools: Vec<String>,
-    pub function_calling_llm: Option<SerializedLLMProvider>,
+    #[serde(default)]
+    pub function_calling_llm_provider_id: Option<String>,
+    /// Max number of llm calls (tool calls and delegations included) to complete a task
+    #[serde(default)]
     pub max_iter: Option<u32>,
+    /// Max number of llm calls per minute, the agent waits when it's reached
+    #[serde(default)]
     pub max_rpm: Option<u32>,
+    /// Max seconds to complete a task
+    #[serde(default)]
     pub max_execution_time: Option<u32>,
+    /// Logs every step of the agent
+    #[serde(default)]
     pub verbose: bool,
+    /// Allows the agent to hand sub-tasks to its coworkers
+    #[serde(default)]
     pub allow_delegation: bool,
+    #[serde(default)]
     pub step_callback: Option<String>,
 }
 
@@ -25,9 +42,9 @@ impl CustomizedAgent {
         role: String,
         goal: String,
         backstory: String,
-        llm: Option<SerializedLLMProvider>,
+        llm_provider_id: Option<String>,
         tools: Vec<String>,
-        function_calling_llm: Option<SerializedLLMProvider>,
+        function_calling_llm_provider_id: Option<String>,
         max_iter: Option<u32>,
         max_rpm: Option<u32>,
         max_execution_time: Option<u32>,
@@ -39,9 +56,9 @@ impl CustomizedAgent {
             role,
             goal,
             backstory,
-            llm,
+            llm_provider_id,
             tools,
-            function_calling_llm,
+            function_calling_llm_provider_id,
             max_iter,
             max_rpm,
             max_execution_time,
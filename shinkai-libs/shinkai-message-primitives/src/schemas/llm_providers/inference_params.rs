@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+/// Sampling settings sent to the llm provider. Set values take precedence over the `LLM_*` environment
+/// variables of the node, unset ones are left to those variables or to the provider. Each provider maps them
+/// to its own API and ignores the ones it doesn't support (e.g. Anthropic has no seed).
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct InferenceParams {
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub top_p: Option<f64>,
+    /// Max number of tokens of the answer (capped by the room left in the context window)
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+    /// Sequences that end the answer when generated
+    #[serde(default)]
+    pub stop: Option<Vec<String>>,
+    /// Makes the sampling reproducible, for the providers that support it
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+impl InferenceParams {
+    pub fn is_empty(&self) -> bool {
+        self == &InferenceParams::default()
+    }
+
+    /// Returns these params with the ones set in `overrides` replaced
+    pub fn merged_with(&self, overrides: &InferenceParams) -> InferenceParams {
+        InferenceParams {
+            temperature: overrides.temperature.or(self.temperature),
+            top_p: overrides.top_p.or(self.top_p),
+            max_tokens: overrides.max_tokens.or(self.max_tokens),
+            stop: overrides.stop.clone().or_else(|| self.stop.clone()),
+            seed: overrides.seed.or(self.seed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merged_with() {
+        let job_params = InferenceParams {
+            temperature: Some(0.2),
+            max_tokens: Some(500),
+            seed: Some(42),
+            ..Default::default()
+        };
+        let message_params = InferenceParams {
+            temperature: Some(0.9),
+            stop: Some(vec!["\n\n".to_string()]),
+            ..Default::default()
+        };
+
+        let params = job_params.merged_with(&message_params);
+        assert_eq!(params.temperature, Some(0.9));
+        assert_eq!(params.max_tokens, Some(500));
+        assert_eq!(params.seed, Some(42));
+        assert_eq!(params.stop, Some(vec!["\n\n".to_string()]));
+        assert_eq!(params.top_p, None);
+
+        assert!(InferenceParams::default().is_empty());
+        assert_eq!(job_params.merged_with(&InferenceParams::default()), job_params);
+    }
+}
@@ -1,2 +1,3 @@
 pub mod serialized_llm_provider;
-pub mod customized_agent;
\ No newline at end of file
+pub mod customized_agent;
+pub mod inference_params;
\ No newline at end of file
@@ -16,6 +16,121 @@ pub struct SerializedLLMProvider {
     pub toolkit_permissions: Vec<String>,
     pub storage_bucket_permissions: Vec<String>,
     pub allowed_message_senders: Vec<String>,
+    #[serde(default)]
+    pub retry_policy: LLMProviderRetryPolicy,
+    /// Ids of the llm providers (in order) to try when this one keeps failing
+    #[serde(default)]
+    pub fallback_llm_providers: Vec<String>,
+    /// Limits on how much this llm provider can be used (on top of the limits of each profile)
+    #[serde(default)]
+    pub usage_quota: Option<LLMUsageQuota>,
+    /// Caches the responses of identical prompts sent to this llm provider (no caching if unset)
+    #[serde(default)]
+    pub response_cache: Option<LLMResponseCacheConfig>,
+}
+
+/// How many times an llm provider is called before giving up (and moving on to its fallbacks).
+/// The default policy doesn't retry.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LLMProviderRetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub backoff_multiplier: u32,
+    pub max_backoff_ms: u64,
+    /// Names of the errors worth retrying (e.g. `NetworkError`, `LLMServiceInferenceLimitReached`)
+    pub retryable_errors: Vec<String>,
+}
+
+impl Default for LLMProviderRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff_ms: 1000,
+            backoff_multiplier: 2,
+            max_backoff_ms: 30_000,
+            retryable_errors: vec![
+                "NetworkError".to_string(),
+                "ReqwestError".to_string(),
+                "LLMServiceInferenceLimitReached".to_string(),
+            ],
+        }
+    }
+}
+
+impl LLMProviderRetryPolicy {
+    pub fn is_retryable(&self, error_name: &str) -> bool {
+        self.retryable_errors.iter().any(|e| e == error_name)
+    }
+
+    /// Time to wait after the given failed attempt (starting at 1)
+    pub fn backoff_ms(&self, attempt: u32) -> u64 {
+        let factor = (self.backoff_multiplier.max(1) as u64).saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff_ms.saturating_mul(factor).min(self.max_backoff_ms)
+    }
+}
+
+/// How long and how many responses of an llm provider are kept in the node's response cache.
+/// Once `max_entries` is reached the oldest responses are evicted first.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LLMResponseCacheConfig {
+    pub ttl_secs: u64,
+    pub max_entries: u64,
+}
+
+impl Default for LLMResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 24 * 60 * 60,
+            max_entries: 1000,
+        }
+    }
+}
+
+/// Token and request limits per calendar day and month (UTC). Unset limits are unlimited.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct LLMUsageLimits {
+    pub max_tokens_per_day: Option<u64>,
+    pub max_tokens_per_month: Option<u64>,
+    pub max_requests_per_day: Option<u64>,
+    pub max_requests_per_month: Option<u64>,
+}
+
+/// Going over a soft limit only notifies the user while going over a hard limit stops new job steps
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct LLMUsageQuota {
+    #[serde(default)]
+    pub soft_limits: LLMUsageLimits,
+    #[serde(default)]
+    pub hard_limits: LLMUsageLimits,
+}
+
+/// Tokens and requests already consumed in the current day and month
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct LLMUsageCounts {
+    pub tokens_today: u64,
+    pub requests_today: u64,
+    pub tokens_this_month: u64,
+    pub requests_this_month: u64,
+}
+
+impl LLMUsageLimits {
+    /// Returns a description of every limit reached by the given usage
+    pub fn exceeded_limits(&self, usage: &LLMUsageCounts) -> Vec<String> {
+        let checks = [
+            (self.max_tokens_per_day, usage.tokens_today, "tokens per day"),
+            (self.max_tokens_per_month, usage.tokens_this_month, "tokens per month"),
+            (self.max_requests_per_day, usage.requests_today, "requests per day"),
+            (self.max_requests_per_month, usage.requests_this_month, "requests per month"),
+        ];
+
+        checks
+            .iter()
+            .filter_map(|(limit, used, unit)| match limit {
+                Some(limit) if used >= limit => Some(format!("{} {} (used {})", limit, unit, used)),
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -28,6 +143,42 @@ pub enum LLMProviderInterface {
     Groq(Groq),
     Gemini(Gemini),
     Exo(Exo),
+    Anthropic(Anthropic),
+    Custom(CustomProvider),
+}
+
+impl LLMProviderInterface {
+    /// Returns the provider prefix used in the serialized form (e.g. `openai` in `openai:gpt-4o`)
+    pub fn provider_prefix(&self) -> String {
+        match self {
+            LLMProviderInterface::OpenAI(_) => "openai".to_string(),
+            LLMProviderInterface::GenericAPI(_) => "genericapi".to_string(),
+            LLMProviderInterface::Ollama(_) => "ollama".to_string(),
+            LLMProviderInterface::ShinkaiBackend(_) => "shinkai-backend".to_string(),
+            LLMProviderInterface::LocalLLM(_) => "local-llm".to_string(),
+            LLMProviderInterface::Groq(_) => "groq".to_string(),
+            LLMProviderInterface::Gemini(_) => "gemini".to_string(),
+            LLMProviderInterface::Exo(_) => "exo".to_string(),
+            LLMProviderInterface::Anthropic(_) => "anthropic".to_string(),
+            LLMProviderInterface::Custom(custom) => custom.provider.clone(),
+        }
+    }
+
+    /// Returns the model type without the provider prefix
+    pub fn model_type(&self) -> String {
+        match self {
+            LLMProviderInterface::OpenAI(openai) => openai.model_type.clone(),
+            LLMProviderInterface::GenericAPI(genericapi) => genericapi.model_type.clone(),
+            LLMProviderInterface::Ollama(ollama) => ollama.model_type(),
+            LLMProviderInterface::ShinkaiBackend(shinkai_backend) => shinkai_backend.model_type(),
+            LLMProviderInterface::LocalLLM(_) => "".to_string(),
+            LLMProviderInterface::Groq(groq) => groq.model_type(),
+            LLMProviderInterface::Gemini(gemini) => gemini.model_type(),
+            LLMProviderInterface::Exo(exo) => exo.model_type(),
+            LLMProviderInterface::Anthropic(anthropic) => anthropic.model_type(),
+            LLMProviderInterface::Custom(custom) => custom.model_type(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -77,6 +228,31 @@ impl Gemini {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Anthropic {
+    pub model_type: String,
+}
+
+impl Anthropic {
+    pub fn model_type(&self) -> String {
+        self.model_type.to_string()
+    }
+}
+
+/// A provider that is not built into the node (e.g. shipped by a separate crate).
+/// The node looks up the backend registered under `provider` to handle it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CustomProvider {
+    pub provider: String,
+    pub model_type: String,
+}
+
+impl CustomProvider {
+    pub fn model_type(&self) -> String {
+        self.model_type.to_string()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ShinkaiBackend {
     pub model_type: String,
@@ -134,8 +310,19 @@ impl FromStr for LLMProviderInterface {
         } else if s.starts_with("exo:") {
             let model_type = s.strip_prefix("exo:").unwrap_or("").to_string();
             Ok(LLMProviderInterface::Exo(Exo { model_type }))
+        } else if s.starts_with("anthropic:") {
+            let model_type = s.strip_prefix("anthropic:").unwrap_or("").to_string();
+            Ok(LLMProviderInterface::Anthropic(Anthropic { model_type }))
         } else {
-            Err(())
+            match s.split_once(':') {
+                Some((provider, model_type)) if !provider.is_empty() => {
+                    Ok(LLMProviderInterface::Custom(CustomProvider {
+                        provider: provider.to_string(),
+                        model_type: model_type.to_string(),
+                    }))
+                }
+                _ => Err(()),
+            }
         }
     }
 }
@@ -174,6 +361,14 @@ impl Serialize for LLMProviderInterface {
                 let model_type = format!("exo:{}", exo.model_type);
                 serializer.serialize_str(&model_type)
             }
+            LLMProviderInterface::Anthropic(anthropic) => {
+                let model_type = format!("anthropic:{}", anthropic.model_type);
+                serializer.serialize_str(&model_type)
+            }
+            LLMProviderInterface::Custom(custom) => {
+                let model_type = format!("{}:{}", custom.provider, custom.model_type);
+                serializer.serialize_str(&model_type)
+            }
             LLMProviderInterface::LocalLLM(_) => serializer.serialize_str("local-llm"),
         }
     }
@@ -215,10 +410,27 @@ impl<'de> Visitor<'de> for LLMProviderInterfaceVisitor {
             "exo" => Ok(LLMProviderInterface::Exo(Exo {
                 model_type: parts.get(1).unwrap_or(&"").to_string(),
             })),
+            "anthropic" => Ok(LLMProviderInterface::Anthropic(Anthropic {
+                model_type: parts.get(1).unwrap_or(&"").to_string(),
+            })),
             "local-llm" => Ok(LLMProviderInterface::LocalLLM(LocalLLM {})),
+            provider if !provider.is_empty() && parts.len() == 2 => Ok(LLMProviderInterface::Custom(CustomProvider {
+                provider: provider.to_string(),
+                model_type: parts[1].to_string(),
+            })),
             _ => Err(de::Error::unknown_variant(
                 value,
-                &["openai", "genericapi", "ollama", "shinkai-backend", "local-llm", "groq", "exo", "gemini"],
+                &[
+                    "openai",
+                    "genericapi",
+                    "ollama",
+                    "shinkai-backend",
+                    "local-llm",
+                    "groq",
+                    "exo",
+                    "gemini",
+                    "anthropic",
+                ],
             )),
         }
     }
@@ -232,3 +444,88 @@ impl<'de> Deserialize<'de> for LLMProviderInterface {
         deserializer.deserialize_str(LLMProviderInterfaceVisitor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_provider_round_trip() {
+        let model: LLMProviderInterface = serde_json::from_str("\"acme:acme-large-2\"").unwrap();
+        assert_eq!(
+            model,
+            LLMProviderInterface::Custom(CustomProvider {
+                provider: "acme".to_string(),
+                model_type: "acme-large-2".to_string(),
+            })
+        );
+        assert_eq!(model.provider_prefix(), "acme");
+        assert_eq!(serde_json::to_string(&model).unwrap(), "\"acme:acme-large-2\"");
+        assert_eq!(LLMProviderInterface::from_str("acme:acme-large-2"), Ok(model));
+
+        // Built-in prefixes still map to their own variant
+        let model = LLMProviderInterface::from_str("anthropic:claude-3-haiku-20240307").unwrap();
+        assert_eq!(model.provider_prefix(), "anthropic");
+        assert_eq!(model.model_type(), "claude-3-haiku-20240307");
+
+        assert!(serde_json::from_str::<LLMProviderInterface>("\"no-prefix\"").is_err());
+    }
+
+    #[test]
+    fn test_usage_limits_exceeded() {
+        let limits = LLMUsageLimits {
+            max_tokens_per_day: Some(1000),
+            max_requests_per_month: Some(10),
+            ..Default::default()
+        };
+        let usage = LLMUsageCounts {
+            tokens_today: 999,
+            requests_today: 10,
+            tokens_this_month: 50_000,
+            requests_this_month: 10,
+        };
+        assert_eq!(limits.exceeded_limits(&usage), vec!["10 requests per month (used 10)"]);
+
+        let usage = LLMUsageCounts {
+            tokens_today: 1000,
+            ..Default::default()
+        };
+        assert_eq!(limits.exceeded_limits(&usage), vec!["1000 tokens per day (used 1000)"]);
+        assert!(LLMUsageLimits::default().exceeded_limits(&usage).is_empty());
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = LLMProviderRetryPolicy {
+            max_attempts: 5,
+            initial_backoff_ms: 100,
+            backoff_multiplier: 3,
+            max_backoff_ms: 1000,
+            retryable_errors: vec!["NetworkError".to_string()],
+        };
+        assert_eq!(policy.backoff_ms(1), 100);
+        assert_eq!(policy.backoff_ms(2), 300);
+        assert_eq!(policy.backoff_ms(3), 900);
+        assert_eq!(policy.backoff_ms(4), 1000);
+        assert!(policy.is_retryable("NetworkError"));
+        assert!(!policy.is_retryable("ApiKeyNotSet"));
+    }
+
+    #[test]
+    fn test_llm_provider_without_retry_fields_deserializes() {
+        let json = r#"{
+            "id": "my_gpt",
+            "full_identity_name": "@@node.shinkai/main/agent/my_gpt",
+            "perform_locally": false,
+            "external_url": "https://api.openai.com",
+            "api_key": null,
+            "model": "openai:gpt-4o",
+            "toolkit_permissions": [],
+            "storage_bucket_permissions": [],
+            "allowed_message_senders": []
+        }"#;
+        let llm_provider: SerializedLLMProvider = serde_json::from_str(json).unwrap();
+        assert_eq!(llm_provider.retry_policy, LLMProviderRetryPolicy::default());
+        assert!(llm_provider.fallback_llm_providers.is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 pub mod inbox_name;
+pub mod job_config;
 pub mod registration_code;
 pub mod shinkai_name;
 pub mod shinkai_time;
@@ -7,4 +8,6 @@ pub mod shinkai_subscription;
 pub mod shinkai_subscription_req;
 pub mod shinkai_network;
 pub mod shinkai_proxy_builder_info;
-pub mod sheet;
\ No newline at end of file
+pub mod sheet;
+pub mod job_preset;
+pub mod job_archive;
@@ -11,21 +11,21 @@ pub type FilePath = String;
 pub type FileName = String;
 pub type UuidString = String;
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct APIColumnDefinition {
     pub id: Option<UuidString>,
     pub name: Option<String>,
     pub behavior: ColumnBehavior,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct ColumnDefinition {
     pub id: UuidString,
     pub name: String,
     pub behavior: ColumnBehavior,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum ColumnBehavior {
     Text,
     Number,
@@ -36,6 +36,10 @@ pub enum ColumnBehavior {
         workflow_name: Option<String>,
         llm_provider_name: String, // Note: maybe we want a duality: specific model or some rules that pick a model e.g. Cheap + Private
         input_hash: Option<String>, // New parameter to store the hash of inputs (avoid recomputation)
+        #[serde(default)]
+        response_schema: Option<serde_json::Value>, // JSON Schema the LLM's answer must follow
+        #[serde(default)]
+        response_schema_max_retries: Option<u32>, // Attempts to fix an answer not following the schema
     },
     MultipleVRFiles {
         files: Vec<(FilePath, FileName)>,
@@ -60,6 +60,18 @@ impl ShinkaiMessage {
         }
     }
 
+    /// True for the job messages showing a delegation between the agents of a job (see `JobMessage`)
+    pub fn is_delegation_trace(&self) -> bool {
+        match self.get_message_content_schema() {
+            Ok(MessageSchemaType::JobMessageSchema) => self
+                .get_message_content()
+                .ok()
+                .and_then(|content| serde_json::from_str::<JobMessage>(&content).ok())
+                .is_some_and(|job_message| job_message.is_delegation_trace),
+            _ => false,
+        }
+    }
+
     pub fn get_message_content_schema(&self) -> Result<MessageSchemaType, ShinkaiMessageError> {
         match &self.body {
             MessageBody::Unencrypted(body) => match &body.message_data {
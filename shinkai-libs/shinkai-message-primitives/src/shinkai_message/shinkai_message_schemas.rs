@@ -1,6 +1,14 @@
 use crate::schemas::sheet::{APIColumnDefinition, ColumnUuid, RowUuid, UuidString};
 use crate::schemas::shinkai_subscription_req::{FolderSubscription, SubscriptionPayment};
-use crate::schemas::{inbox_name::InboxName, llm_providers::serialized_llm_provider::SerializedLLMProvider};
+use crate::schemas::{
+    inbox_name::InboxName,
+    job_archive::SignedJobArchive,
+    job_config::JobConfig,
+    llm_providers::{
+        inference_params::InferenceParams,
+        serialized_llm_provider::{LLMUsageQuota, SerializedLLMProvider},
+    },
+};
 use crate::shinkai_utils::job_scope::JobScope;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -275,16 +283,19 @@ pub struct JobCreationInfo {
     pub scope: JobScope,
     pub is_hidden: Option<bool>,
     pub associated_ui: Option<AssociatedUI>,
+    /// Job preset the job is created from (its scope is used if `scope` is empty)
+    #[serde(default)]
+    pub preset_id: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum CallbackAction {
     Job(JobMessage),
     Sheet(SheetManagerAction),
     // Cron(CronManagerAction),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct JobMessage {
     pub job_id: String,
     pub content: String,
@@ -296,6 +307,12 @@ pub struct JobMessage {
     pub workflow_name: Option<String>,
     pub sheet_job_data: Option<String>,
     pub callback: Option<Box<CallbackAction>>,
+    /// Overrides the inference params of the job for this message
+    #[serde(default)]
+    pub inference_params: Option<InferenceParams>,
+    /// Shows a delegation between the agents of the job: not part of the conversation history
+    #[serde(default)]
+    pub is_delegation_trace: bool,
 }
 
 fn deserialize_workflow_name<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
@@ -311,7 +328,7 @@ where
     Ok(s)
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct V2ChatMessage {
     pub job_message: JobMessage,
     pub sender: String,
@@ -322,7 +339,7 @@ pub struct V2ChatMessage {
     pub inbox: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct SheetManagerAction {
     pub job_message_next: Option<JobMessage>,
     // TODO: should this be m0re complex and have the actual desired action?
@@ -586,6 +603,104 @@ pub struct APIChangeJobAgentRequest {
     pub new_agent_id: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct APISetJobConfig {
+    pub job_id: String,
+    pub config: JobConfig,
+}
+
+/// Forks the conversation of a job at a message: a new user message is added as a sibling of the given message
+/// (or of the user message it replies to, if it's an llm provider message) and the job answers it in a new branch.
+/// `content` edits the message, if `None` the original content is used to regenerate the answer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct APIForkJobMessages {
+    pub job_id: String,
+    pub message_hash: String,
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct APISwitchJobBranch {
+    pub job_id: String,
+    pub message_hash: String,
+}
+
+/// Recreates an exported job (and its inbox) on this node.
+/// `llm_provider_id` replaces the llm provider of the job, which is kept if `None` (it must exist on this node), and
+/// `profile_mapping` renames the profiles of the exporting node to profiles of this node (unmapped ones are kept).
+/// The archive must be signed by `trusted_public_key` (hex encoded) if set, otherwise by the identity key
+/// registered for the exporting node.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct APIImportJob {
+    pub archive: SignedJobArchive,
+    #[serde(default)]
+    pub llm_provider_id: Option<String>,
+    #[serde(default)]
+    pub profile_mapping: HashMap<String, String>,
+    #[serde(default)]
+    pub trusted_public_key: Option<String>,
+}
+
+/// Forgets a single entry of the memory of past conversations (`memory_id`) or all the entries of a job (`job_id`)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct APIForgetMemory {
+    #[serde(default)]
+    pub memory_id: Option<String>,
+    #[serde(default)]
+    pub job_id: Option<String>,
+}
+
+/// Keeps the messages of a job out of the memory of past conversations (forgetting the ones already remembered),
+/// or lets them back in
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct APIExcludeJobFromMemory {
+    pub job_id: String,
+    pub exclude: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct APIJobPresetId {
+    pub preset_id: String,
+}
+
+/// Creates a job with the llm provider and scope of a job preset
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct APICreateJobFromPreset {
+    pub preset_id: String,
+    #[serde(default)]
+    pub is_hidden: Option<bool>,
+    #[serde(default)]
+    pub associated_ui: Option<AssociatedUI>,
+}
+
+/// Query for the LLM token usage recorded by the node.
+/// Dates are RFC3339 (start inclusive, end exclusive) and `group_by` is a comma separated list
+/// of `job`, `inbox`, `profile`, `llm_provider`, `model`, `day` and `month`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct APIGetTokenUsage {
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub group_by: Option<String>,
+    pub job_id: Option<String>,
+    pub inbox_name: Option<String>,
+    pub profile: Option<String>,
+    pub llm_provider_id: Option<String>,
+}
+
+/// Profiles are identified by their full name (e.g. `@@node.shinkai/main`)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct APIGetProfileUsageQuota {
+    pub profile: String,
+}
+
+/// Sets the usage quota of a profile, a `None` quota removes it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct APISetProfileUsageQuota {
+    pub profile: String,
+    pub quota: Option<LLMUsageQuota>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct TopicSubscription {
     pub topic: WSTopic,
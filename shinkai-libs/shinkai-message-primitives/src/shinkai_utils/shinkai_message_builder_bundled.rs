@@ -106,6 +106,7 @@ impl ShinkaiMessageBuilder {
             scope,
             is_hidden: Some(is_hidden),
             associated_ui: None,
+            preset_id: None,
         };
         let body = serde_json::to_string(&job_creation).map_err(|_| "Failed to serialize job creation to JSON")?;
 
@@ -151,6 +152,8 @@ impl ShinkaiMessageBuilder {
             workflow_name,
             sheet_job_data: None,
             callback: None,
+            inference_params: None,
+            is_delegation_trace: false,
         };
         let body = serde_json::to_string(&job_message).map_err(|_| "Failed to serialize job message to JSON")?;
 
@@ -182,7 +185,6 @@ impl ShinkaiMessageBuilder {
         node_sender: ShinkaiNameString,
         node_receiver: ShinkaiNameString,
     ) -> Result<ShinkaiMessage, &'static str> {
-        let job_id_clone = job_id.clone();
         let job_message = JobMessage {
             job_id,
             content,
@@ -192,7 +194,42 @@ impl ShinkaiMessageBuilder {
             workflow_name: None, // the agent wont be sending you a workflow
             sheet_job_data: None,
             callback: None,
+            inference_params: None,
+            is_delegation_trace: false,
+        };
+        Self::llm_provider_job_message(job_message, my_signature_secret_key, node_sender, node_receiver)
+    }
+
+    /// Message showing a delegation between the agents of a job, kept out of the conversation history
+    pub fn job_delegation_trace_from_llm_provider(
+        job_id: String,
+        content: String,
+        my_signature_secret_key: SigningKey,
+        node_sender: ShinkaiNameString,
+        node_receiver: ShinkaiNameString,
+    ) -> Result<ShinkaiMessage, &'static str> {
+        let job_message = JobMessage {
+            job_id,
+            content,
+            files_inbox: "".to_string(),
+            parent: None,
+            workflow_code: None,
+            workflow_name: None,
+            sheet_job_data: None,
+            callback: None,
+            inference_params: None,
+            is_delegation_trace: true,
         };
+        Self::llm_provider_job_message(job_message, my_signature_secret_key, node_sender, node_receiver)
+    }
+
+    fn llm_provider_job_message(
+        job_message: JobMessage,
+        my_signature_secret_key: SigningKey,
+        node_sender: ShinkaiNameString,
+        node_receiver: ShinkaiNameString,
+    ) -> Result<ShinkaiMessage, &'static str> {
+        let job_id_clone = job_message.job_id.clone();
         let body = serde_json::to_string(&job_message).map_err(|_| "Failed to serialize job message to JSON")?;
 
         let inbox = InboxName::get_job_inbox_name_from_params(job_id_clone)
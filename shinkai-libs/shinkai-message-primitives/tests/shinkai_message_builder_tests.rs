@@ -342,6 +342,35 @@ mod tests {
         assert_eq!(external_metadata.sender, recipient);
     }
 
+    #[test]
+    fn test_job_delegation_trace_from_llm_provider() {
+        let (my_identity_sk, _my_identity_pk) = unsafe_deterministic_signature_keypair(0);
+        let node = "@@localhost.shinkai".to_string();
+        let job_id = "jobid_399c5571-3504-4aa7-a291-b1e086c1440c".to_string();
+
+        let trace = ShinkaiMessageBuilder::job_delegation_trace_from_llm_provider(
+            job_id.clone(),
+            "> **Writer** delegated to **Researcher**".to_string(),
+            my_identity_sk.clone(),
+            node.clone(),
+            node.clone(),
+        )
+        .unwrap();
+        assert!(trace.is_delegation_trace());
+        assert_eq!(trace.get_message_content_schema().unwrap(), MessageSchemaType::JobMessageSchema);
+
+        let answer = ShinkaiMessageBuilder::job_message_from_llm_provider(
+            job_id,
+            "Lima".to_string(),
+            "".to_string(),
+            my_identity_sk,
+            node.clone(),
+            node,
+        )
+        .unwrap();
+        assert!(!answer.is_delegation_trace());
+    }
+
     #[test]
     fn test_builder_missing_fields() {
         let (my_identity_sk, _my_identity_pk) = unsafe_deterministic_signature_keypair(0);
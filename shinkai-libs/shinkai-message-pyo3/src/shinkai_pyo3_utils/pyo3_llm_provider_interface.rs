@@ -1,5 +1,6 @@
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
+use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::Anthropic;
 use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::GenericAPI;
 use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::Groq;
 use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::LLMProviderInterface;
@@ -55,6 +56,11 @@ impl PyLLMProviderInterface {
             Ok(Self {
                 inner: LLMProviderInterface::Exo(Exo { model_type }),
             })
+        } else if s.starts_with("anthropic:") {
+            let model_type = s.strip_prefix("anthropic:").unwrap_or("").to_string();
+            Ok(Self {
+                inner: LLMProviderInterface::Anthropic(Anthropic { model_type }),
+            })
         } else {
             Ok(Self {
                 inner: LLMProviderInterface::LocalLLM(LocalLLM {}),
@@ -93,9 +99,11 @@ impl PyLLMProviderInterface {
             LLMProviderInterface::Groq(groq) => Ok(format!("groq:{}", groq.model_type)),
             LLMProviderInterface::Gemini(gemini) => Ok(format!("gemini:{}", gemini.model_type)),
             LLMProviderInterface::Exo(exo) => Ok(format!("exo:{}", exo.model_type)),
+            LLMProviderInterface::Anthropic(anthropic) => Ok(format!("anthropic:{}", anthropic.model_type)),
             LLMProviderInterface::ShinkaiBackend(shinkai_backend) => {
                 Ok(format!("shinkai-backend:{}", shinkai_backend.model_type()))
             }
+            LLMProviderInterface::Custom(custom) => Ok(format!("{}:{}", custom.provider, custom.model_type)),
             LLMProviderInterface::LocalLLM(_) => Ok("LocalLLM".to_string()),
         }
     }
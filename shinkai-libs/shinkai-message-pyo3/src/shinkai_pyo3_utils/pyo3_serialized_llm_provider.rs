@@ -70,6 +70,10 @@ impl PySerializedLLMProvider {
                 toolkit_permissions,
                 storage_bucket_permissions,
                 allowed_message_senders,
+                retry_policy: Default::default(),
+                fallback_llm_providers: Vec::new(),
+                usage_quota: None,
+                response_cache: None,
             },
         })
     }
@@ -98,6 +102,10 @@ impl PySerializedLLMProvider {
                 toolkit_permissions: Vec::new(),
                 storage_bucket_permissions: Vec::new(),
                 allowed_message_senders: Vec::new(),
+                retry_policy: Default::default(),
+                fallback_llm_providers: Vec::new(),
+                usage_quota: None,
+                response_cache: None,
             },
         })
     }
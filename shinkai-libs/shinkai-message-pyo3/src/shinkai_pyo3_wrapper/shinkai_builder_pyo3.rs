@@ -1172,6 +1172,7 @@ impl PyShinkaiMessageBuilder {
                 scope: scope.inner.clone(),
                 is_hidden: Some(is_hidden),
                 associated_ui: None,
+                preset_id: None,
             };
 
             let body = match serde_json::to_string(&job_creation) {
@@ -1249,6 +1250,8 @@ impl PyShinkaiMessageBuilder {
                 workflow_name,
                 sheet_job_data: None,
                 callback: None,
+                inference_params: None,
+                is_delegation_trace: false,
             };
 
             let body = match serde_json::to_string(&job_message) {
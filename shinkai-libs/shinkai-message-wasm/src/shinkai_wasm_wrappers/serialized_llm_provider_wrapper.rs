@@ -99,6 +99,10 @@ impl SerializedLLMProviderJsValueConversion for SerializedLLMProvider {
             toolkit_permissions,
             storage_bucket_permissions,
             allowed_message_senders,
+            retry_policy: Default::default(),
+            fallback_llm_providers: Vec::new(),
+            usage_quota: None,
+            response_cache: None,
         })
     }
 }
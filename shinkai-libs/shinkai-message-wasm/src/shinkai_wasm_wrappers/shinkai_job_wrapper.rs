@@ -56,6 +56,7 @@ impl JobCreationWrapper {
             scope,
             is_hidden: Some(is_hidden),
             associated_ui,
+            preset_id: None,
         };
         Ok(JobCreationWrapper { inner: job_creation })
     }
@@ -96,6 +97,7 @@ impl JobCreationWrapper {
                 scope: job_scope,
                 is_hidden: Some(false),
                 associated_ui: None,
+                preset_id: None,
             },
         })
     }
@@ -145,6 +147,8 @@ impl JobMessageWrapper {
             workflow_name,
             sheet_job_data: None,
             callback: None,
+            inference_params: None,
+            is_delegation_trace: false,
         };
         Ok(JobMessageWrapper { inner: job_message })
     }
@@ -190,6 +194,8 @@ impl JobMessageWrapper {
             workflow_name,
             sheet_job_data: None,
             callback: None,
+            inference_params: None,
+            is_delegation_trace: false,
         };
         JobMessageWrapper { inner: job_message }
     }
@@ -836,6 +836,7 @@ impl ShinkaiMessageBuilderWrapper {
             scope,
             is_hidden: Some(is_hidden),
             associated_ui,
+            preset_id: None,
         };
         let body = serde_json::to_string(&job_creation).map_err(|e| JsValue::from_str(&e.to_string()))?;
 
@@ -883,6 +884,8 @@ impl ShinkaiMessageBuilderWrapper {
             workflow_name,
             sheet_job_data: None,
             callback: None,
+            inference_params: None,
+            is_delegation_trace: false,
         };
 
         let body = serde_json::to_string(&job_message).map_err(|e| JsValue::from_str(&e.to_string()))?;
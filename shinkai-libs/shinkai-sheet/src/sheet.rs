@@ -825,6 +825,8 @@ pub async fn sheet_reducer(mut state: Sheet, action: SheetAction) -> (Sheet, Vec
                             workflow_name,
                             llm_provider_name,
                             input_hash,
+                            response_schema: _,
+                            response_schema_max_retries: _,
                         } => {
                             // Check if input_hash is present and matches the blake3 hash of the current input cells values
                             let input_cells =
@@ -908,6 +910,8 @@ pub async fn sheet_reducer(mut state: Sheet, action: SheetAction) -> (Sheet, Vec
                         workflow_name,
                         llm_provider_name,
                         input_hash: _,
+                        response_schema: _,
+                        response_schema_max_retries: _,
                     } = &column_definition.behavior
                     {
                         let dependencies = state.parse_formula_dependencies(input);
@@ -38,6 +38,8 @@ mod tests {
                 workflow_name: None,
                 llm_provider_name: "MockProvider".to_string(),
                 input_hash: None,
+                response_schema: None,
+                response_schema_max_retries: None,
             },
         };
 
@@ -172,6 +174,8 @@ mod tests {
                 workflow_name: None,
                 llm_provider_name: "MockProvider".to_string(),
                 input_hash: None,
+                response_schema: None,
+                response_schema_max_retries: None,
             },
         };
 
@@ -267,6 +271,8 @@ mod tests {
                 workflow_name: None,
                 llm_provider_name: "MockProvider".to_string(),
                 input_hash: None,
+                response_schema: None,
+                response_schema_max_retries: None,
             },
         };
 
@@ -500,6 +506,8 @@ mod tests {
                 workflow_name: None,
                 llm_provider_name: "MockProvider".to_string(),
                 input_hash: None,
+                response_schema: None,
+                response_schema_max_retries: None,
             },
         };
 
@@ -630,6 +638,8 @@ mod tests {
                 workflow_name: None,
                 llm_provider_name: "MockProvider".to_string(),
                 input_hash: None,
+                response_schema: None,
+                response_schema_max_retries: None,
             },
         };
 
@@ -735,6 +745,8 @@ mod tests {
                 workflow_name: None,
                 llm_provider_name: "MockProvider".to_string(),
                 input_hash: None,
+                response_schema: None,
+                response_schema_max_retries: None,
             },
         };
 
@@ -802,6 +814,8 @@ mod tests {
                 workflow_name: None,
                 llm_provider_name: "MockProvider".to_string(),
                 input_hash: None,
+                response_schema: None,
+                response_schema_max_retries: None,
             },
         };
 
@@ -910,6 +924,8 @@ mod tests {
                 workflow_name: None,
                 llm_provider_name: "MockProvider".to_string(),
                 input_hash: None,
+                response_schema: None,
+                response_schema_max_retries: None,
             },
         };
         let jobs = sheet.set_column(column_llm.clone()).await.unwrap();
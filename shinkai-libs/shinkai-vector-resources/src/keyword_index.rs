@@ -0,0 +1,180 @@
+use crate::vector_resource::{BaseVectorResource, NodeContent, RetrievedNode};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// BM25 term frequency saturation
+const BM25_K1: f32 = 1.2;
+/// BM25 length normalization (0 ignores the length of the nodes, 1 fully normalizes it)
+const BM25_B: f32 = 0.75;
+/// Reciprocal rank fusion constant, damping the weight of the top ranks
+pub const RRF_K: f32 = 60.0;
+
+/// Inverted index over the text nodes of Vector Resources, scoring them with BM25.
+/// Finds the exact terms (identifiers, error codes, names) that embedding similarity tends to miss.
+#[derive(Debug, Clone, Default)]
+pub struct KeywordIndex {
+    nodes: Vec<RetrievedNode>,
+    node_lengths: Vec<usize>,
+    total_length: usize,
+    /// Term -> (position of the node, frequency of the term in the node)
+    postings: HashMap<String, Vec<(usize, u32)>>,
+}
+
+impl KeywordIndex {
+    /// Initializes an empty KeywordIndex
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds every text node of the resource, at any depth
+    pub fn add_resource(&mut self, resource: &BaseVectorResource) {
+        for ret_node in resource.as_trait_object().retrieve_text_nodes_exhaustive(None) {
+            self.add_node(ret_node);
+        }
+    }
+
+    /// Adds a retrieved node to the index. Nodes that don't hold text are skipped.
+    pub fn add_node(&mut self, ret_node: RetrievedNode) {
+        let terms = match &ret_node.node.content {
+            NodeContent::Text(text) => Self::tokenize(text),
+            _ => return,
+        };
+        if terms.is_empty() {
+            return;
+        }
+
+        let position = self.nodes.len();
+        let mut frequencies: HashMap<&str, u32> = HashMap::new();
+        for term in &terms {
+            *frequencies.entry(term.as_str()).or_insert(0) += 1;
+        }
+        for (term, frequency) in frequencies {
+            self.postings
+                .entry(term.to_string())
+                .or_default()
+                .push((position, frequency));
+        }
+        self.node_lengths.push(terms.len());
+        self.total_length += terms.len();
+        self.nodes.push(ret_node);
+    }
+
+    /// Number of nodes in the index
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the nodes matching the query sorted by their BM25 score (set as the score of the returned nodes).
+    /// Nodes sharing no term with the query are not returned.
+    pub fn search(&self, query: &str, num_of_results: u64) -> Vec<RetrievedNode> {
+        Self::search_all(&[self], query, num_of_results)
+    }
+
+    /// Searches several indexes as a single one: the BM25 statistics (number of nodes, average length and
+    /// frequency of the terms across nodes) cover all of them. Lets indexes be built once per resource
+    /// and combined at query time.
+    pub fn search_all(indexes: &[&KeywordIndex], query: &str, num_of_results: u64) -> Vec<RetrievedNode> {
+        let num_of_nodes: usize = indexes.iter().map(|index| index.len()).sum();
+        if num_of_nodes == 0 {
+            return vec![];
+        }
+        let num_of_nodes = num_of_nodes as f32;
+        let average_length = indexes.iter().map(|index| index.total_length).sum::<usize>() as f32 / num_of_nodes;
+
+        let mut query_terms = Self::tokenize(query);
+        query_terms.sort();
+        query_terms.dedup();
+
+        // (position of the index, position of the node in it) -> score
+        let mut scores: HashMap<(usize, usize), f32> = HashMap::new();
+        for term in query_terms {
+            let term_postings: Vec<(usize, &Vec<(usize, u32)>)> = indexes
+                .iter()
+                .enumerate()
+                .filter_map(|(index_position, index)| {
+                    index.postings.get(&term).map(|postings| (index_position, postings))
+                })
+                .collect();
+            if term_postings.is_empty() {
+                continue;
+            }
+            let document_frequency = term_postings.iter().map(|(_, postings)| postings.len()).sum::<usize>() as f32;
+            let idf = ((num_of_nodes - document_frequency + 0.5) / (document_frequency + 0.5) + 1.0).ln();
+            for (index_position, postings) in term_postings {
+                for (position, frequency) in postings {
+                    let frequency = *frequency as f32;
+                    let length_ratio = indexes[index_position].node_lengths[*position] as f32 / average_length;
+                    let score = idf * frequency * (BM25_K1 + 1.0)
+                        / (frequency + BM25_K1 * (1.0 - BM25_B + BM25_B * length_ratio));
+                    *scores.entry((index_position, *position)).or_insert(0.0) += score;
+                }
+            }
+        }
+
+        let mut scored_positions: Vec<((usize, usize), f32)> = scores.into_iter().collect();
+        // Ties keep the order the nodes were added in, so results are deterministic
+        scored_positions.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal).then(a.0.cmp(&b.0)));
+        scored_positions
+            .into_iter()
+            .take(num_of_results as usize)
+            .map(|((index_position, position), score)| {
+                let mut ret_node = indexes[index_position].nodes[position].clone();
+                ret_node.score = score;
+                ret_node
+            })
+            .collect()
+    }
+
+    /// Splits text into lowercased terms. Underscores are kept inside terms so identifiers
+    /// like `ERR_CONN_RESET` or `max_tokens` are matched whole.
+    pub fn tokenize(text: &str) -> Vec<String> {
+        text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .filter(|term| !term.is_empty())
+            .map(|term| term.to_lowercase())
+            .collect()
+    }
+
+    /// Identifies a retrieved node by its resource and path
+    pub fn node_key(ret_node: &RetrievedNode) -> String {
+        format!(
+            "{}{}",
+            ret_node.resource_header.reference_string(),
+            ret_node.retrieval_path.format_to_string()
+        )
+    }
+
+    /// Merges rankings of retrieved nodes with weighted reciprocal rank fusion: each node scores the sum
+    /// of `weight / (RRF_K + rank)` over the rankings it appears in. Nodes are identified by their resource
+    /// and path (see `node_key`), the first occurrence is the one kept. Returns the nodes sorted by their fused score
+    /// (set as the score of the returned nodes).
+    pub fn reciprocal_rank_fusion(rankings: Vec<(Vec<RetrievedNode>, f32)>) -> Vec<RetrievedNode> {
+        let mut fused: Vec<(RetrievedNode, f32)> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for (ranking, weight) in rankings {
+            for (rank, ret_node) in ranking.into_iter().enumerate() {
+                let key = Self::node_key(&ret_node);
+                let score = weight / (RRF_K + rank as f32 + 1.0);
+                match positions.get(&key) {
+                    Some(position) => fused[*position].1 += score,
+                    None => {
+                        positions.insert(key, fused.len());
+                        fused.push((ret_node, score));
+                    }
+                }
+            }
+        }
+
+        fused.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        fused
+            .into_iter()
+            .map(|(mut ret_node, score)| {
+                ret_node.score = score;
+                ret_node
+            })
+            .collect()
+    }
+}
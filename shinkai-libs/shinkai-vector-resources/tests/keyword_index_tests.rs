@@ -0,0 +1,119 @@
+use shinkai_vector_resources::embeddings::Embedding;
+use shinkai_vector_resources::keyword_index::KeywordIndex;
+use shinkai_vector_resources::source::VRSourceReference;
+use shinkai_vector_resources::vector_resource::document_resource::DocumentVectorResource;
+use shinkai_vector_resources::vector_resource::{BaseVectorResource, RetrievedNode};
+
+fn troubleshooting_doc() -> BaseVectorResource {
+    let mut doc = DocumentVectorResource::new_empty(
+        "Troubleshooting",
+        Some("Errors of the node and how to fix them"),
+        VRSourceReference::new_uri_ref("docs.shinkai.com"),
+        true,
+    );
+    let texts = [
+        "The node fails to start when the database is locked by another process.",
+        "ERR_CONN_RESET means the peer closed the connection, check the proxy settings.",
+        "Embeddings are generated by the embedding generator configured for the node.",
+        "Set max_tokens to limit the length of the answers of the llm provider.",
+    ];
+    for text in texts {
+        doc.append_text_node(text, None, Embedding::new_empty(), &vec![])
+            .unwrap();
+    }
+    BaseVectorResource::Document(doc)
+}
+
+fn texts(ret_nodes: &[RetrievedNode]) -> Vec<String> {
+    ret_nodes
+        .iter()
+        .map(|ret_node| ret_node.node.get_text_content().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_keyword_index_exact_terms() {
+    let mut index = KeywordIndex::new();
+    index.add_resource(&troubleshooting_doc());
+    assert_eq!(index.len(), 4);
+
+    let results = index.search("What does ERR_CONN_RESET mean?", 10);
+    assert!(!results.is_empty());
+    assert!(texts(&results)[0].starts_with("ERR_CONN_RESET"));
+
+    let results = index.search("max_tokens", 10);
+    assert_eq!(results.len(), 1);
+    assert!(texts(&results)[0].contains("max_tokens"));
+    assert!(results[0].score > 0.0);
+
+    assert!(index.search("kubernetes", 10).is_empty());
+    assert_eq!(index.search("the node", 2).len(), 2);
+}
+
+#[test]
+fn test_keyword_index_tokenize() {
+    assert_eq!(
+        KeywordIndex::tokenize("Error E1234: ERR_CONN_RESET (node-v2)"),
+        vec!["error", "e1234", "err_conn_reset", "node", "v2"]
+    );
+}
+
+#[test]
+fn test_reciprocal_rank_fusion() {
+    let mut index = KeywordIndex::new();
+    index.add_resource(&troubleshooting_doc());
+    let nodes = index.search("node connection proxy database max_tokens", 10);
+    assert_eq!(nodes.len(), 4);
+
+    // The second node of the first ranking is the only one in both rankings
+    let first_ranking = vec![nodes[0].clone(), nodes[1].clone()];
+    let second_ranking = vec![nodes[2].clone(), nodes[3].clone(), nodes[1].clone()];
+
+    let fused = KeywordIndex::reciprocal_rank_fusion(vec![(first_ranking.clone(), 1.0), (second_ranking.clone(), 1.0)]);
+    assert_eq!(fused.len(), 4);
+    assert_eq!(texts(&fused[..1]), texts(&nodes[1..2]));
+
+    // Without weight, the first ranking doesn't count
+    let fused = KeywordIndex::reciprocal_rank_fusion(vec![(first_ranking, 0.0), (second_ranking, 1.0)]);
+    assert_eq!(texts(&fused[..2]), texts(&nodes[2..4]));
+    assert!(fused[0].score > fused[1].score);
+}
+
+#[test]
+fn test_keyword_index_search_all() {
+    let mut release_notes = DocumentVectorResource::new_empty(
+        "Release notes",
+        None,
+        VRSourceReference::new_uri_ref("docs.shinkai.com"),
+        true,
+    );
+    release_notes
+        .append_text_node(
+            "Fixed ERR_CONN_RESET when the node runs behind a proxy.",
+            None,
+            Embedding::new_empty(),
+            &vec![],
+        )
+        .unwrap();
+    let release_notes = BaseVectorResource::Document(release_notes);
+
+    // One index per resource, searched together, ranks and scores like a single index over both
+    let mut troubleshooting_index = KeywordIndex::new();
+    troubleshooting_index.add_resource(&troubleshooting_doc());
+    let mut release_notes_index = KeywordIndex::new();
+    release_notes_index.add_resource(&release_notes);
+    let mut combined_index = KeywordIndex::new();
+    combined_index.add_resource(&troubleshooting_doc());
+    combined_index.add_resource(&release_notes);
+
+    let query = "ERR_CONN_RESET proxy";
+    let results = KeywordIndex::search_all(&[&troubleshooting_index, &release_notes_index], query, 10);
+    let combined_results = combined_index.search(query, 10);
+    assert_eq!(results.len(), 2);
+    assert_eq!(texts(&results), texts(&combined_results));
+    for (result, combined_result) in results.iter().zip(&combined_results) {
+        assert!((result.score - combined_result.score).abs() < 1e-6);
+    }
+
+    assert!(KeywordIndex::search_all(&[], query, 10).is_empty());
+}